@@ -1,4 +1,7 @@
 pub mod cells;
 pub mod excel;
 pub mod file_operator;
+pub mod formulas;
+pub mod number_formats;
+pub mod records;
 pub mod xmls;
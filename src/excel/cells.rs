@@ -1,2 +1,8 @@
 pub mod cell;
 pub mod cell_node;
+pub mod cell_range;
+pub mod cell_value;
+pub mod date_time;
+pub mod formula;
+pub mod line_shift;
+pub mod merged_range;
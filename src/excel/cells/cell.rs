@@ -88,6 +88,20 @@ impl<'a> ColumnAlphabet<'a> {
                 sum + this_digit_number
             })
     }
+    /// Column letters of a 1-based column number.
+    ///
+    ///     assert_eq!(ColumnAlphabet::from_number(28), "AB");
+    ///
+    pub fn from_number(number: usize) -> String {
+        let mut number = number;
+        let mut alphabet = String::new();
+        while number > 0 {
+            let rest = (number - 1) % 26;
+            alphabet.insert(0, (b'A' + rest as u8) as char);
+            number = (number - 1) / 26;
+        }
+        alphabet
+    }
 }
 
 #[cfg(test)]
@@ -116,7 +130,11 @@ mod cell_index_tests {
             }
         );
         let alphabet = ColumnAlphabet::new("A");
-        assert_eq!(alphabet.to_number(), 1)
+        assert_eq!(alphabet.to_number(), 1);
+        assert_eq!(ColumnAlphabet::from_number(1), "A");
+        assert_eq!(ColumnAlphabet::from_number(26), "Z");
+        assert_eq!(ColumnAlphabet::from_number(28), "AB");
+        assert_eq!(ColumnAlphabet::from_number(16384), "XFD");
     }
 }
 #[cfg(test)]
@@ -1,9 +1,17 @@
 use crate::{
     excel::xmls::shared_strings::SharedStringsInterface,
-    xml::nodes::{node::XMLNode, node_type::NodeType},
+    xml::{
+        escape::unescape,
+        nodes::{node::XMLNode, node_type::NodeType},
+    },
 };
 
-use super::cell::CellIndex;
+use super::{
+    cell::CellIndex,
+    cell_value::{CellErrorKind, CellValue},
+    date_time::ExcelDateTime,
+    formula::CellFormula,
+};
 #[derive(Debug, PartialEq, Eq)]
 pub enum CellType {
     Str,
@@ -50,6 +58,66 @@ impl<'a> CellNode<'a> {
         }
         None
     }
+    /// Returns the `<f>` as written, a shared formula child has an empty text.
+    pub fn get_formula(&self) -> Option<CellFormula> {
+        self.node.search_node("f").map(CellFormula::from)
+    }
+    /// Index into cellXfs of the styles part, cells without `s` use the first one.
+    pub fn get_style_index(&self) -> usize {
+        self.node
+            .search_element("s")
+            .and_then(|s| s.parse().ok())
+            .unwrap_or_default()
+    }
+    /// Returns the value typed by the `t` attribute.
+    pub fn get_value(&self) -> CellValue {
+        let v_text = self
+            .node
+            .search_node("v")
+            .and_then(|v_node| v_node.get_text());
+        match (self.node.search_element("t"), v_text) {
+            (Some("inlineStr"), _) => self
+                .get_inline_string()
+                .map(CellValue::InlineString)
+                .unwrap_or(CellValue::Empty),
+            (_, None) => CellValue::Empty,
+            (Some("s"), Some(text)) => text
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| self.shared_strings.get(index))
+                .map(|s| CellValue::String(s.to_string()))
+                .unwrap_or(CellValue::Error(CellErrorKind::Ref)),
+            (Some("str"), Some(text)) => CellValue::String(unescape(&text)),
+            (Some("b"), Some(text)) => CellValue::Bool(text.trim() == "1"),
+            (Some("e"), Some(text)) => CellValue::Error(
+                CellErrorKind::new(&unescape(&text)).unwrap_or(CellErrorKind::Value),
+            ),
+            (Some("d"), Some(text)) => ExcelDateTime::parse_iso(&text)
+                .map(CellValue::DateTime)
+                .unwrap_or(CellValue::String(text)),
+            (_, Some(text)) => text
+                .trim()
+                .parse::<f64>()
+                .map(CellValue::Number)
+                .unwrap_or_else(|_| CellValue::String(unescape(&text))),
+        }
+    }
+    /// `<is>` holds either a single `<t>` or rich text runs `<r><t/></r>`.
+    fn get_inline_string(&self) -> Option<String> {
+        let is_node = self.node.search_node("is")?;
+        if let Some(t_node) = is_node.search_node("t") {
+            return Some(unescape(&t_node.get_text().unwrap_or_default()));
+        }
+        let runs = is_node.search_all_nodes("r")?;
+        Some(
+            runs.iter()
+                .filter_map(|run| run.search_node("t"))
+                .filter_map(|t_node| t_node.get_text())
+                .map(|text| unescape(&text))
+                .collect(),
+        )
+    }
     fn is_use_shared_strings(&self) -> bool {
         self.node.is_containe_key_value("t", "s")
     }
@@ -100,3 +168,68 @@ impl<'a, T: SharedStringsInterface<'a>> MutCellNode<'a, T> {
         self.node.change_text(text)
     }
 }
+
+#[cfg(test)]
+mod cell_node_test {
+    use crate::{
+        excel::cells::{
+            cell_value::{CellErrorKind, CellValue},
+            date_time::ExcelDateTime,
+        },
+        xml::nodes::node::XMLNode,
+    };
+
+    use super::CellNode;
+
+    fn get_value(source: &str) -> CellValue {
+        let shared_strings = vec!["zero".to_string(), "one".to_string()];
+        let node = XMLNode::from(source);
+        let cell_node = CellNode::new(&node, &shared_strings);
+        cell_node.get_value()
+    }
+    #[test]
+    fn get_value_test() {
+        assert_eq!(
+            get_value(r#"<c r="B2" s="15" t="s"><v>1</v></c>"#),
+            CellValue::String("one".to_string())
+        );
+        assert_eq!(
+            get_value(r#"<c r="L3"><v>10</v></c>"#),
+            CellValue::Number(10.0)
+        );
+        assert_eq!(
+            get_value(r#"<c r="L3" t="n"><v>-1.5E-3</v></c>"#),
+            CellValue::Number(-0.0015)
+        );
+        assert_eq!(
+            get_value(r#"<c r="A1" t="b"><v>1</v></c>"#),
+            CellValue::Bool(true)
+        );
+        assert_eq!(
+            get_value(r#"<c r="A1" t="e"><v>#DIV/0!</v></c>"#),
+            CellValue::Error(CellErrorKind::Div0)
+        );
+        assert_eq!(
+            get_value(r#"<c r="H4" t="str"><f>$E$3&amp;G4</f><v>a&amp;b</v></c>"#),
+            CellValue::String("a&b".to_string())
+        );
+        assert_eq!(
+            get_value(r#"<c r="A1" t="d"><v>2023-01-01T09:00:00</v></c>"#),
+            CellValue::DateTime(ExcelDateTime::new(2023, 1, 1, 9, 0, 0))
+        );
+        assert_eq!(get_value(r#"<c r="C2" s="12"></c>"#), CellValue::Empty);
+    }
+    #[test]
+    fn get_value_inline_string_test() {
+        assert_eq!(
+            get_value(r#"<c r="A1" t="inlineStr"><is><t>hello world</t></is></c>"#),
+            CellValue::InlineString("hello world".to_string())
+        );
+        assert_eq!(
+            get_value(
+                r#"<c r="A1" t="inlineStr"><is><r><t>会社</t></r><r><rPr><b/></rPr><t>名</t></r></is></c>"#
+            ),
+            CellValue::InlineString("会社名".to_string())
+        );
+    }
+}
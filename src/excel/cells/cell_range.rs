@@ -0,0 +1,422 @@
+use std::fmt::Display;
+
+use crate::excel::formulas::ast::{CellReference, LineReference, MAX_COLUMN, MAX_ROW};
+
+use super::{
+    cell::{CellIndex, ColumnAlphabet},
+    cell_value::CellValue,
+};
+
+/// Cells of one or more rectangles, `B2:C5,E1` or `A:A`.
+#[derive(Debug, PartialEq, Eq, Clone, Hash)]
+pub struct CellRange {
+    areas: Vec<RangeArea>,
+}
+impl CellRange {
+    /// Reads areas separated by `,`, each written as `<dimension ref>` or a formula reference is.
+    ///
+    ///     let range = CellRange::new("$A$1:$B$2, D:D").unwrap();
+    ///     assert_eq!(range.to_string(), "A1:B2,D:D");
+    ///
+    pub fn new(range: &str) -> Option<Self> {
+        let areas = range
+            .split(',')
+            .map(RangeArea::new)
+            .collect::<Option<Vec<_>>>()?;
+        Some(CellRange { areas })
+    }
+    pub fn get_areas(&self) -> &[RangeArea] {
+        &self.areas
+    }
+    pub fn contains(&self, cell_index: &str) -> bool {
+        let index = CellIndex::new(cell_index);
+        self.contains_position(index.get_row_index(), index.get_column_index())
+    }
+    pub fn contains_position(&self, row: usize, column: usize) -> bool {
+        self.areas
+            .iter()
+            .any(|area| area.contains_position(row, column))
+    }
+    /// Whether every cell of `other` is in this range.
+    pub fn contains_range(&self, other: &CellRange) -> bool {
+        other.areas.iter().all(|other| {
+            self.areas.iter().any(|area| area.contains_area(other))
+                || other
+                    .positions()
+                    .all(|(row, column)| self.contains_position(row, column))
+        })
+    }
+    /// Cells in both ranges, the space operator of formulas.
+    ///
+    ///     let range = CellRange::new("A1:C3,E1:E9").unwrap();
+    ///     let other = CellRange::new("B2:E2").unwrap();
+    ///     assert_eq!(range.intersect(&other).unwrap().to_string(), "B2:C2,E2");
+    ///
+    pub fn intersect(&self, other: &CellRange) -> Option<CellRange> {
+        let areas = self
+            .areas
+            .iter()
+            .flat_map(|area| other.areas.iter().filter_map(|other| area.intersect(other)))
+            .collect::<Vec<_>>();
+        (!areas.is_empty()).then_some(CellRange { areas })
+    }
+    /// Areas of both ranges, leaving out those another area already covers.
+    pub fn union(&self, other: &CellRange) -> CellRange {
+        let mut areas: Vec<RangeArea> = Vec::new();
+        for area in self.areas.iter().chain(other.areas.iter()) {
+            if areas.iter().any(|existing| existing.contains_area(area)) {
+                continue;
+            }
+            areas.retain(|existing| !area.contains_area(existing));
+            areas.push(*area);
+        }
+        CellRange { areas }
+    }
+    /// Moves every area, `None` when one leaves the sheet.
+    pub fn offset(&self, rows: isize, columns: isize) -> Option<CellRange> {
+        let areas = self
+            .areas
+            .iter()
+            .map(|area| area.offset(rows, columns))
+            .collect::<Option<Vec<_>>>()?;
+        Some(CellRange { areas })
+    }
+    /// Gives every area `rows` x `columns` cells keeping its top-left cell.
+    pub fn resize(&self, rows: usize, columns: usize) -> Option<CellRange> {
+        let areas = self
+            .areas
+            .iter()
+            .map(|area| area.resize(rows, columns))
+            .collect::<Option<Vec<_>>>()?;
+        Some(CellRange { areas })
+    }
+    /// Row and column of each cell, area by area and row by row.
+    /// A cell shared by areas comes once for each of them.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.areas.iter().flat_map(|area| area.positions())
+    }
+    /// Index of each cell in the order of `positions`.
+    pub fn cell_indexes(&self) -> impl Iterator<Item = String> + '_ {
+        self.positions()
+            .map(|(row, column)| to_cell_index(row, column))
+    }
+}
+impl From<RangeArea> for CellRange {
+    fn from(area: RangeArea) -> Self {
+        CellRange { areas: vec![area] }
+    }
+}
+impl Display for CellRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let areas = self
+            .areas
+            .iter()
+            .map(|area| area.to_string())
+            .collect::<Vec<_>>();
+        write!(f, "{}", areas.join(","))
+    }
+}
+
+/// One rectangle of a `CellRange`.
+/// `A:B` spans every row and `3:7` every column of the sheet.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct RangeArea {
+    first_row: usize,
+    first_column: usize,
+    last_row: usize,
+    last_column: usize,
+}
+impl RangeArea {
+    /// Reads `B2:S50`, `$A$1`, `A:C` or `3:7`, in any corner order.
+    pub fn new(area: &str) -> Option<Self> {
+        let area = area.trim();
+        let (start, end) = area.split_once(':').unwrap_or((area, area));
+        if let (Some(start), Some(end)) = (CellReference::new(start), CellReference::new(end)) {
+            return Some(RangeArea::from_bounds(
+                (start.row.index, start.column.index),
+                (end.row.index, end.column.index),
+            ));
+        }
+        if !area.contains(':') {
+            return None;
+        }
+        if let (Some(start), Some(end)) = (
+            LineReference::from_column(start),
+            LineReference::from_column(end),
+        ) {
+            return Some(RangeArea::from_bounds(
+                (1, start.index),
+                (MAX_ROW, end.index),
+            ));
+        }
+        let (start, end) = (
+            LineReference::from_row(start)?,
+            LineReference::from_row(end)?,
+        );
+        Some(RangeArea::from_bounds(
+            (start.index, 1),
+            (end.index, MAX_COLUMN),
+        ))
+    }
+    /// Area between two `(row, column)` corners.
+    pub fn from_bounds(start: (usize, usize), end: (usize, usize)) -> Self {
+        RangeArea {
+            first_row: start.0.min(end.0),
+            first_column: start.1.min(end.1),
+            last_row: start.0.max(end.0),
+            last_column: start.1.max(end.1),
+        }
+    }
+    pub fn get_first_row(&self) -> usize {
+        self.first_row
+    }
+    pub fn get_first_column(&self) -> usize {
+        self.first_column
+    }
+    pub fn get_last_row(&self) -> usize {
+        self.last_row
+    }
+    pub fn get_last_column(&self) -> usize {
+        self.last_column
+    }
+    pub fn get_row_count(&self) -> usize {
+        self.last_row - self.first_row + 1
+    }
+    pub fn get_column_count(&self) -> usize {
+        self.last_column - self.first_column + 1
+    }
+    pub fn get_top_left(&self) -> String {
+        to_cell_index(self.first_row, self.first_column)
+    }
+    pub fn get_bottom_right(&self) -> String {
+        to_cell_index(self.last_row, self.last_column)
+    }
+    pub fn is_whole_columns(&self) -> bool {
+        self.first_row == 1 && self.last_row == MAX_ROW
+    }
+    pub fn is_whole_rows(&self) -> bool {
+        self.first_column == 1 && self.last_column == MAX_COLUMN
+    }
+    pub fn contains(&self, cell_index: &str) -> bool {
+        let index = CellIndex::new(cell_index);
+        self.contains_position(index.get_row_index(), index.get_column_index())
+    }
+    pub fn contains_position(&self, row: usize, column: usize) -> bool {
+        (self.first_row..=self.last_row).contains(&row)
+            && (self.first_column..=self.last_column).contains(&column)
+    }
+    pub fn contains_area(&self, other: &RangeArea) -> bool {
+        self.contains_position(other.first_row, other.first_column)
+            && self.contains_position(other.last_row, other.last_column)
+    }
+    pub fn intersect(&self, other: &RangeArea) -> Option<RangeArea> {
+        let area = RangeArea {
+            first_row: self.first_row.max(other.first_row),
+            first_column: self.first_column.max(other.first_column),
+            last_row: self.last_row.min(other.last_row),
+            last_column: self.last_column.min(other.last_column),
+        };
+        (area.first_row <= area.last_row && area.first_column <= area.last_column).then_some(area)
+    }
+    /// The area moved by `rows` and `columns`, `None` when it leaves the sheet.
+    /// Whole columns stay whole when moved sideways, as do whole rows moved up or down.
+    ///
+    ///     let area = RangeArea::new("B2:C3").unwrap();
+    ///     assert_eq!(area.offset(-1, 2).unwrap().to_string(), "D1:E2");
+    ///
+    pub fn offset(&self, rows: isize, columns: isize) -> Option<RangeArea> {
+        let (first_row, last_row) = if self.is_whole_columns() {
+            (self.first_row, self.last_row)
+        } else {
+            (
+                move_by(self.first_row, rows, MAX_ROW)?,
+                move_by(self.last_row, rows, MAX_ROW)?,
+            )
+        };
+        let (first_column, last_column) = if self.is_whole_rows() {
+            (self.first_column, self.last_column)
+        } else {
+            (
+                move_by(self.first_column, columns, MAX_COLUMN)?,
+                move_by(self.last_column, columns, MAX_COLUMN)?,
+            )
+        };
+        Some(RangeArea {
+            first_row,
+            first_column,
+            last_row,
+            last_column,
+        })
+    }
+    /// The area of `rows` x `columns` cells from the same top-left cell.
+    pub fn resize(&self, rows: usize, columns: usize) -> Option<RangeArea> {
+        if rows == 0 || columns == 0 {
+            return None;
+        }
+        let last_row = self.first_row + rows - 1;
+        let last_column = self.first_column + columns - 1;
+        (last_row <= MAX_ROW && last_column <= MAX_COLUMN).then_some(RangeArea {
+            last_row,
+            last_column,
+            ..*self
+        })
+    }
+    /// Row and column of each cell, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let (first_column, last_column) = (self.first_column, self.last_column);
+        (self.first_row..=self.last_row)
+            .flat_map(move |row| (first_column..=last_column).map(move |column| (row, column)))
+    }
+}
+impl Display for RangeArea {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_whole_columns() {
+            return write!(
+                f,
+                "{}:{}",
+                ColumnAlphabet::from_number(self.first_column),
+                ColumnAlphabet::from_number(self.last_column)
+            );
+        }
+        if self.is_whole_rows() {
+            return write!(f, "{}:{}", self.first_row, self.last_row);
+        }
+        if self.first_row == self.last_row && self.first_column == self.last_column {
+            return write!(f, "{}", self.get_top_left());
+        }
+        write!(f, "{}:{}", self.get_top_left(), self.get_bottom_right())
+    }
+}
+
+/// Values of a `RangeArea`, row by row from its top-left cell.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RangeValues {
+    area: RangeArea,
+    values: Vec<Vec<CellValue>>,
+}
+impl RangeValues {
+    pub fn new(area: RangeArea, values: Vec<Vec<CellValue>>) -> Self {
+        RangeValues { area, values }
+    }
+    pub fn get_area(&self) -> &RangeArea {
+        &self.area
+    }
+    /// Index of the cell of `values[0][0]`.
+    pub fn get_origin(&self) -> String {
+        self.area.get_top_left()
+    }
+    pub fn get_values(&self) -> &Vec<Vec<CellValue>> {
+        &self.values
+    }
+    pub fn into_values(self) -> Vec<Vec<CellValue>> {
+        self.values
+    }
+    /// Value of a cell by its index in the sheet, `None` outside the block.
+    ///
+    ///     // block read from B2:C3
+    ///     assert_eq!(block.get_value("C3"), Some(&block.get_values()[1][1]));
+    ///
+    pub fn get_value(&self, cell_index: &str) -> Option<&CellValue> {
+        let index = CellIndex::new(cell_index);
+        let row = index.get_row_index().checked_sub(self.area.first_row)?;
+        let column = index
+            .get_column_index()
+            .checked_sub(self.area.first_column)?;
+        self.values.get(row)?.get(column)
+    }
+}
+
+fn to_cell_index(row: usize, column: usize) -> String {
+    format!("{}{}", ColumnAlphabet::from_number(column), row)
+}
+fn move_by(index: usize, offset: isize, max: usize) -> Option<usize> {
+    let index = index.checked_add_signed(offset)?;
+    (1..=max).contains(&index).then_some(index)
+}
+
+#[cfg(test)]
+mod cell_range_test {
+    use crate::excel::formulas::ast::{MAX_COLUMN, MAX_ROW};
+
+    use super::{CellRange, RangeArea};
+
+    #[test]
+    fn new_test() {
+        let area = RangeArea::new("S50:B2").unwrap();
+        assert_eq!(area.to_string(), "B2:S50");
+        assert_eq!((area.get_row_count(), area.get_column_count()), (49, 18));
+        let area = RangeArea::new("$C:A").unwrap();
+        assert_eq!((area.get_first_column(), area.get_last_column()), (1, 3));
+        assert_eq!((area.get_first_row(), area.get_last_row()), (1, MAX_ROW));
+        assert_eq!(area.to_string(), "A:C");
+        let area = RangeArea::new("3:7").unwrap();
+        assert_eq!(
+            (area.get_first_row(), area.get_last_column()),
+            (3, MAX_COLUMN)
+        );
+        assert_eq!(area.to_string(), "3:7");
+        assert_eq!(RangeArea::new("$A$1").unwrap().to_string(), "A1");
+        assert_eq!(RangeArea::new("A"), None);
+        assert_eq!(RangeArea::new("A1:B"), None);
+        let range = CellRange::new("$A$1:$B$2,C:C, 4:4").unwrap();
+        assert_eq!(range.get_areas().len(), 3);
+        assert_eq!(range.to_string(), "A1:B2,C:C,4:4");
+        assert_eq!(CellRange::new("A1:B2,"), None);
+    }
+    #[test]
+    fn contains_test() {
+        let range = CellRange::new("A1:B2,D:D").unwrap();
+        assert!(range.contains("B2"));
+        assert!(range.contains("D1048576"));
+        assert!(!range.contains("C1"));
+        assert!(range.contains_range(&CellRange::new("A1,D5:D9").unwrap()));
+        assert!(!range.contains_range(&CellRange::new("B2:C2").unwrap()));
+    }
+    #[test]
+    fn intersect_and_union_test() {
+        let range = CellRange::new("A1:C3,E1:E9").unwrap();
+        let other = CellRange::new("B2:E2").unwrap();
+        assert_eq!(range.intersect(&other).unwrap().to_string(), "B2:C2,E2");
+        assert_eq!(
+            CellRange::new("A:A")
+                .unwrap()
+                .intersect(&CellRange::new("2:3").unwrap())
+                .unwrap()
+                .to_string(),
+            "A2:A3"
+        );
+        assert_eq!(range.intersect(&CellRange::new("D1").unwrap()), None);
+        let union = CellRange::new("B2:C3,F1")
+            .unwrap()
+            .union(&CellRange::new("A1:D4,C3").unwrap());
+        assert_eq!(union.to_string(), "F1,A1:D4");
+    }
+    #[test]
+    fn offset_and_resize_test() {
+        let range = CellRange::new("B2:C3,E:E").unwrap();
+        assert_eq!(range.offset(1, -1).unwrap().to_string(), "A3:B4,D:D");
+        assert_eq!(range.offset(0, -5), None);
+        assert_eq!(
+            RangeArea::new("2:3")
+                .unwrap()
+                .offset(-1, 5)
+                .unwrap()
+                .to_string(),
+            "1:2"
+        );
+        assert_eq!(range.resize(1, 3).unwrap().to_string(), "B2:D2,E1:G1");
+        assert_eq!(range.resize(0, 3), None);
+    }
+    #[test]
+    fn positions_test() {
+        let range = CellRange::new("B2:C3,A1").unwrap();
+        assert_eq!(
+            range.cell_indexes().collect::<Vec<_>>(),
+            vec!["B2", "C2", "B3", "C3", "A1"]
+        );
+        assert_eq!(
+            RangeArea::new("A:A").unwrap().positions().nth(4),
+            Some((5, 1))
+        );
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt::Display;
+
+use crate::excel::number_formats::format_code::{get_number_format_kind, NumberFormatKind};
+
+use super::date_time::{ExcelDateTime, ExcelDuration};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum CellErrorKind {
+    Null,
+    Div0,
+    Value,
+    Ref,
+    Name,
+    Num,
+    NA,
+    GettingData,
+    Spill,
+    Calc,
+}
+impl CellErrorKind {
+    pub fn new(s: &str) -> Option<Self> {
+        match s.trim() {
+            "#NULL!" => Some(CellErrorKind::Null),
+            "#DIV/0!" => Some(CellErrorKind::Div0),
+            "#VALUE!" => Some(CellErrorKind::Value),
+            "#REF!" => Some(CellErrorKind::Ref),
+            "#NAME?" => Some(CellErrorKind::Name),
+            "#NUM!" => Some(CellErrorKind::Num),
+            "#N/A" => Some(CellErrorKind::NA),
+            "#GETTING_DATA" => Some(CellErrorKind::GettingData),
+            "#SPILL!" => Some(CellErrorKind::Spill),
+            "#CALC!" => Some(CellErrorKind::Calc),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            CellErrorKind::Null => "#NULL!",
+            CellErrorKind::Div0 => "#DIV/0!",
+            CellErrorKind::Value => "#VALUE!",
+            CellErrorKind::Ref => "#REF!",
+            CellErrorKind::Name => "#NAME?",
+            CellErrorKind::Num => "#NUM!",
+            CellErrorKind::NA => "#N/A",
+            CellErrorKind::GettingData => "#GETTING_DATA",
+            CellErrorKind::Spill => "#SPILL!",
+            CellErrorKind::Calc => "#CALC!",
+        }
+    }
+}
+impl Display for CellErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+/// Value of a `<c>` typed by its `t` attribute.
+///
+/// | t         | value        |
+/// |-----------|--------------|
+/// | s         | String       |
+/// | str       | String       |
+/// | inlineStr | InlineString |
+/// | b         | Bool         |
+/// | e         | Error        |
+/// | d         | DateTime     |
+/// | n or none | Number       |
+///
+/// Numbers become DateTime or Duration by `with_number_format` when the cell's
+/// number format shows a date, time or elapsed time.
+#[derive(Debug, PartialEq, Clone)]
+pub enum CellValue {
+    Empty,
+    Number(f64),
+    String(String),
+    Bool(bool),
+    Error(CellErrorKind),
+    InlineString(String),
+    DateTime(ExcelDateTime),
+    Duration(ExcelDuration),
+}
+impl CellValue {
+    pub fn is_empty(&self) -> bool {
+        *self == CellValue::Empty
+    }
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CellValue::String(s) | CellValue::InlineString(s) => Some(s),
+            _ => None,
+        }
+    }
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            CellValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            CellValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+    pub fn as_date_time(&self) -> Option<&ExcelDateTime> {
+        match self {
+            CellValue::DateTime(date_time) => Some(date_time),
+            _ => None,
+        }
+    }
+    pub fn as_duration(&self) -> Option<&ExcelDuration> {
+        match self {
+            CellValue::Duration(duration) => Some(duration),
+            _ => None,
+        }
+    }
+    /// Reads a number as the date, time or duration its number format shows.
+    /// Time only values are placed on 1899-12-31 whatever the date system is,
+    /// the same day `t="d"` cells use.
+    ///
+    ///     let value = CellValue::Number(44927.0).with_number_format("yyyy/mm/dd", false);
+    ///     assert_eq!(value, CellValue::DateTime(ExcelDateTime::new(2023, 1, 1, 0, 0, 0)));
+    ///
+    pub fn with_number_format(self, format_code: &str, is_date1904: bool) -> Self {
+        let CellValue::Number(serial) = self else {
+            return self;
+        };
+        let converted = match get_number_format_kind(format_code) {
+            NumberFormatKind::Time => {
+                ExcelDateTime::from_serial(serial, false).map(CellValue::DateTime)
+            }
+            NumberFormatKind::Date | NumberFormatKind::DateTime => {
+                ExcelDateTime::from_serial(serial, is_date1904).map(CellValue::DateTime)
+            }
+            NumberFormatKind::Duration => {
+                ExcelDuration::from_serial(serial).map(CellValue::Duration)
+            }
+            _ => None,
+        };
+        converted.unwrap_or(self)
+    }
+}
+impl Display for CellValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CellValue::Empty => Ok(()),
+            CellValue::Number(n) => write!(f, "{}", n),
+            CellValue::String(s) | CellValue::InlineString(s) => write!(f, "{}", s),
+            CellValue::Bool(true) => write!(f, "TRUE"),
+            CellValue::Bool(false) => write!(f, "FALSE"),
+            CellValue::Error(kind) => write!(f, "{}", kind),
+            CellValue::DateTime(date_time) => write!(f, "{}", date_time),
+            CellValue::Duration(duration) => write!(f, "{}", duration),
+        }
+    }
+}
+
+#[cfg(test)]
+mod cell_value_test {
+    use crate::excel::cells::date_time::ExcelDateTime;
+
+    use super::{CellErrorKind, CellValue};
+
+    #[test]
+    fn error_kind_test() {
+        assert_eq!(CellErrorKind::new("#DIV/0!"), Some(CellErrorKind::Div0));
+        assert_eq!(CellErrorKind::new("#N/A"), Some(CellErrorKind::NA));
+        assert_eq!(CellErrorKind::new("#REF!").unwrap().as_str(), "#REF!");
+        assert_eq!(CellErrorKind::new("DIV/0"), None);
+    }
+    #[test]
+    fn with_number_format_test() {
+        assert_eq!(
+            CellValue::Number(44927.0).with_number_format("yyyy/mm/dd", false),
+            CellValue::DateTime(ExcelDateTime::new(2023, 1, 1, 0, 0, 0))
+        );
+        assert_eq!(
+            CellValue::Number(43465.0).with_number_format("mm-dd-yy", true),
+            CellValue::DateTime(ExcelDateTime::new(2023, 1, 1, 0, 0, 0))
+        );
+        assert_eq!(
+            CellValue::Number(0.375).with_number_format("h:mm", true),
+            CellValue::DateTime(ExcelDateTime::new(1899, 12, 31, 9, 0, 0))
+        );
+        assert_eq!(
+            CellValue::Number(1.25)
+                .with_number_format("[h]:mm:ss", false)
+                .to_string(),
+            "30:00:00"
+        );
+        assert_eq!(
+            CellValue::Number(44927.0).with_number_format("#,##0", false),
+            CellValue::Number(44927.0)
+        );
+        assert_eq!(
+            CellValue::Number(-1.0).with_number_format("yyyy/mm/dd", false),
+            CellValue::Number(-1.0)
+        );
+        assert_eq!(
+            CellValue::String("44927".to_string()).with_number_format("yyyy/mm/dd", false),
+            CellValue::String("44927".to_string())
+        );
+    }
+    #[test]
+    fn to_string_test() {
+        assert_eq!(CellValue::Number(10.0).to_string(), "10");
+        assert_eq!(CellValue::Number(0.25).to_string(), "0.25");
+        assert_eq!(CellValue::Bool(true).to_string(), "TRUE");
+        assert_eq!(
+            CellValue::Error(CellErrorKind::Value).to_string(),
+            "#VALUE!"
+        );
+        assert_eq!(
+            CellValue::String("会社名".to_string()).to_string(),
+            "会社名"
+        );
+        assert_eq!(CellValue::Empty.to_string(), "");
+        assert_eq!(
+            CellValue::DateTime(ExcelDateTime::new(2023, 1, 1, 0, 0, 0)).to_string(),
+            "2023-01-01T00:00:00"
+        );
+    }
+}
@@ -0,0 +1,387 @@
+use std::fmt::Display;
+
+use serde::{
+    de::{self, Unexpected, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+const MILLISECONDS_PER_DAY: i64 = 86_400_000;
+/// Newtype name `Serialize` gives the ISO text, for the record serializer to write a date instead of text.
+pub const DATE_TIME_NEWTYPE: &str = "$ExcelDateTime";
+
+/// Calendar date and time of day as Excel shows it, without time zone.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub struct ExcelDateTime {
+    year: i32,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    millisecond: u32,
+}
+impl ExcelDateTime {
+    pub fn new(year: i32, month: u32, day: u32, hour: u32, minute: u32, second: u32) -> Self {
+        ExcelDateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+            millisecond: 0,
+        }
+    }
+    pub fn with_millisecond(mut self, millisecond: u32) -> Self {
+        self.millisecond = millisecond;
+        self
+    }
+    /// Parses the ISO 8601 text used by `t="d"` cells,
+    /// e.g. `2023-01-01`, `2023-01-01T09:30:00Z` or `T09:30:00`.
+    pub fn parse_iso(s: &str) -> Option<Self> {
+        let s = s.trim().trim_end_matches('Z');
+        let (date, time) = match s.split_once('T') {
+            Some((date, time)) => (date, Some(time)),
+            None => (s, None),
+        };
+        let (year, month, day) = if date.is_empty() {
+            // time only values are stored on the 1899-12-31 epoch
+            (1899, 12, 31)
+        } else {
+            let mut parts = date.split('-');
+            let year = parts.next()?.parse().ok()?;
+            let month = parts.next()?.parse().ok()?;
+            let day = parts.next()?.parse().ok()?;
+            (year, month, day)
+        };
+        let mut date_time = ExcelDateTime::new(year, month, day, 0, 0, 0);
+        if let Some(time) = time {
+            let mut parts = time.split(':');
+            date_time.hour = parts.next()?.parse().ok()?;
+            date_time.minute = parts.next()?.parse().ok()?;
+            if let Some(second) = parts.next() {
+                let (second, fraction) = second.split_once('.').unwrap_or((second, ""));
+                date_time.second = second.parse().ok()?;
+                if !fraction.is_empty() {
+                    let fraction = format!("{:0<3}", fraction);
+                    date_time.millisecond = fraction.get(0..3)?.parse().ok()?;
+                }
+            }
+        }
+        if !(1..=12).contains(&date_time.month)
+            || !(1..=31).contains(&date_time.day)
+            || date_time.hour > 23
+            || date_time.minute > 59
+            || date_time.second > 59
+        {
+            return None;
+        }
+        Some(date_time)
+    }
+    /// Converts a serial number of the workbook's date system.
+    ///
+    /// The 1900 system counts 1900-01-01 as 1 and keeps Lotus 1-2-3's
+    /// nonexistent 1900-02-29 as 60, the 1904 system counts 1904-01-01 as 0.
+    /// Serial 0 of the 1900 system, which time only values use, is 1899-12-31.
+    ///
+    ///     assert_eq!(ExcelDateTime::from_serial(44927.0, false), Some(ExcelDateTime::new(2023, 1, 1, 0, 0, 0)));
+    ///
+    pub fn from_serial(serial: f64, is_date1904: bool) -> Option<Self> {
+        if !serial.is_finite() || serial < 0.0 {
+            return None;
+        }
+        let total = (serial * MILLISECONDS_PER_DAY as f64).round() as i64;
+        let serial_day = total / MILLISECONDS_PER_DAY;
+        let (year, month, day) = match (is_date1904, serial_day) {
+            (false, 60) => (1900, 2, 29),
+            (false, 0..=59) => civil_from_days(days_from_civil(1899, 12, 31) + serial_day),
+            (false, _) => civil_from_days(days_from_civil(1899, 12, 30) + serial_day),
+            (true, _) => civil_from_days(days_from_civil(1904, 1, 1) + serial_day),
+        };
+        if year > 9999 {
+            return None;
+        }
+        let time = total % MILLISECONDS_PER_DAY;
+        Some(
+            ExcelDateTime::new(
+                year,
+                month,
+                day,
+                (time / 3_600_000) as u32,
+                (time / 60_000 % 60) as u32,
+                (time / 1000 % 60) as u32,
+            )
+            .with_millisecond((time % 1000) as u32),
+        )
+    }
+    /// Inverse of `from_serial`.
+    pub fn to_serial(self, is_date1904: bool) -> f64 {
+        let days = if is_date1904 {
+            days_from_civil(self.year, self.month, self.day) - days_from_civil(1904, 1, 1)
+        } else if (self.year, self.month, self.day) == (1900, 2, 29) {
+            60
+        } else {
+            let days =
+                days_from_civil(self.year, self.month, self.day) - days_from_civil(1899, 12, 30);
+            if days <= 60 {
+                days - 1
+            } else {
+                days
+            }
+        };
+        let time = ((self.hour * 60 + self.minute) * 60 + self.second) as i64 * 1000
+            + self.millisecond as i64;
+        days as f64 + time as f64 / MILLISECONDS_PER_DAY as f64
+    }
+    pub fn get_year(&self) -> i32 {
+        self.year
+    }
+    pub fn get_month(&self) -> u32 {
+        self.month
+    }
+    pub fn get_day(&self) -> u32 {
+        self.day
+    }
+    pub fn get_hour(&self) -> u32 {
+        self.hour
+    }
+    pub fn get_minute(&self) -> u32 {
+        self.minute
+    }
+    pub fn get_second(&self) -> u32 {
+        self.second
+    }
+    pub fn get_millisecond(&self) -> u32 {
+        self.millisecond
+    }
+    /// 0 is Sunday.
+    pub fn get_weekday(&self) -> u32 {
+        // 1970-01-01 was a Thursday
+        (days_from_civil(self.year, self.month, self.day) + 4).rem_euclid(7) as u32
+    }
+}
+impl Display for ExcelDateTime {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )?;
+        if self.millisecond != 0 {
+            write!(f, ".{:03}", self.millisecond)?;
+        }
+        Ok(())
+    }
+}
+/// Reads the ISO 8601 text `Display` writes, or a serial number of the 1900 date system.
+impl<'de> Deserialize<'de> for ExcelDateTime {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct DateTimeVisitor;
+        impl<'de> Visitor<'de> for DateTimeVisitor {
+            type Value = ExcelDateTime;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "a date")
+            }
+            fn visit_str<E: de::Error>(self, v: &str) -> Result<ExcelDateTime, E> {
+                ExcelDateTime::parse_iso(v)
+                    .ok_or_else(|| E::invalid_value(Unexpected::Str(v), &self))
+            }
+            fn visit_f64<E: de::Error>(self, v: f64) -> Result<ExcelDateTime, E> {
+                ExcelDateTime::from_serial(v, false)
+                    .ok_or_else(|| E::invalid_value(Unexpected::Float(v), &self))
+            }
+            fn visit_i64<E: de::Error>(self, v: i64) -> Result<ExcelDateTime, E> {
+                self.visit_f64(v as f64)
+            }
+            fn visit_u64<E: de::Error>(self, v: u64) -> Result<ExcelDateTime, E> {
+                self.visit_f64(v as f64)
+            }
+        }
+        deserializer.deserialize_any(DateTimeVisitor)
+    }
+}
+/// Writes the ISO 8601 text `Display` writes, as a newtype other serializers see through.
+impl Serialize for ExcelDateTime {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_newtype_struct(DATE_TIME_NEWTYPE, &self.to_string())
+    }
+}
+
+/// Elapsed time shown by `[h]:mm:ss` style formats, which may exceed a day.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+pub struct ExcelDuration {
+    milliseconds: i64,
+}
+impl ExcelDuration {
+    pub fn from_milliseconds(milliseconds: i64) -> Self {
+        ExcelDuration { milliseconds }
+    }
+    /// `serial` counts days, so 1.5 is 36 hours.
+    pub fn from_serial(serial: f64) -> Option<Self> {
+        if !serial.is_finite() {
+            return None;
+        }
+        Some(ExcelDuration::from_milliseconds(
+            (serial * MILLISECONDS_PER_DAY as f64).round() as i64,
+        ))
+    }
+    pub fn to_serial(self) -> f64 {
+        self.milliseconds as f64 / MILLISECONDS_PER_DAY as f64
+    }
+    pub fn get_total_milliseconds(&self) -> i64 {
+        self.milliseconds
+    }
+    /// Whole hours, not wrapped at 24.
+    pub fn get_hours(&self) -> i64 {
+        self.milliseconds / 3_600_000
+    }
+    pub fn get_minutes(&self) -> i64 {
+        self.milliseconds / 60_000 % 60
+    }
+    pub fn get_seconds(&self) -> i64 {
+        self.milliseconds / 1000 % 60
+    }
+    pub fn get_milliseconds(&self) -> i64 {
+        self.milliseconds % 1000
+    }
+}
+impl Display for ExcelDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let sign = if self.milliseconds < 0 { "-" } else { "" };
+        let abs = ExcelDuration::from_milliseconds(self.milliseconds.abs());
+        write!(
+            f,
+            "{}{}:{:02}:{:02}",
+            sign,
+            abs.get_hours(),
+            abs.get_minutes(),
+            abs.get_seconds()
+        )?;
+        if abs.get_milliseconds() != 0 {
+            write!(f, ".{:03}", abs.get_milliseconds())?;
+        }
+        Ok(())
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic gregorian date.
+fn days_from_civil(year: i32, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year } as i64;
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = month as i64;
+    let day_of_year =
+        (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+fn civil_from_days(days: i64) -> (i32, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days - era * 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year as i32, month, day)
+}
+
+#[cfg(test)]
+mod excel_date_time_test {
+    use super::{ExcelDateTime, ExcelDuration};
+
+    #[test]
+    fn from_serial_test() {
+        let date = |serial| ExcelDateTime::from_serial(serial, false).unwrap();
+        assert_eq!(date(44927.0), ExcelDateTime::new(2023, 1, 1, 0, 0, 0));
+        assert_eq!(date(1.0), ExcelDateTime::new(1900, 1, 1, 0, 0, 0));
+        assert_eq!(date(59.0), ExcelDateTime::new(1900, 2, 28, 0, 0, 0));
+        // the day that does not exist, kept for compatibility with Lotus 1-2-3
+        assert_eq!(date(60.0), ExcelDateTime::new(1900, 2, 29, 0, 0, 0));
+        assert_eq!(date(61.0), ExcelDateTime::new(1900, 3, 1, 0, 0, 0));
+        assert_eq!(date(0.5), ExcelDateTime::new(1899, 12, 31, 12, 0, 0));
+        assert_eq!(date(45000.75), ExcelDateTime::new(2023, 3, 15, 18, 0, 0));
+        assert_eq!(
+            date(45000.999999999),
+            ExcelDateTime::new(2023, 3, 16, 0, 0, 0)
+        );
+        assert_eq!(
+            date(0.000_011_574_074),
+            ExcelDateTime::new(1899, 12, 31, 0, 0, 1)
+        );
+        assert_eq!(ExcelDateTime::from_serial(-1.0, false), None);
+        assert_eq!(ExcelDateTime::from_serial(3_000_000.0, false), None);
+    }
+    #[test]
+    fn from_serial_date1904_test() {
+        assert_eq!(
+            ExcelDateTime::from_serial(0.0, true),
+            Some(ExcelDateTime::new(1904, 1, 1, 0, 0, 0))
+        );
+        assert_eq!(
+            ExcelDateTime::from_serial(43465.0, true),
+            Some(ExcelDateTime::new(2023, 1, 1, 0, 0, 0))
+        );
+    }
+    #[test]
+    fn to_serial_test() {
+        for serial in [0.0, 1.0, 59.0, 60.0, 61.0, 44927.25, 2_958_465.0] {
+            let date = ExcelDateTime::from_serial(serial, false).unwrap();
+            assert_eq!(date.to_serial(false), serial);
+        }
+        let date = ExcelDateTime::new(2023, 1, 1, 0, 0, 0);
+        assert_eq!(date.to_serial(true), 43465.0);
+    }
+    #[test]
+    fn get_weekday_test() {
+        assert_eq!(ExcelDateTime::new(2023, 1, 1, 0, 0, 0).get_weekday(), 0);
+        assert_eq!(ExcelDateTime::new(1899, 12, 31, 0, 0, 0).get_weekday(), 0);
+        assert_eq!(ExcelDateTime::new(2024, 2, 29, 0, 0, 0).get_weekday(), 4);
+    }
+    #[test]
+    fn duration_test() {
+        let duration = ExcelDuration::from_serial(1.5 + 1.0 / 86400.0).unwrap();
+        assert_eq!(duration.get_hours(), 36);
+        assert_eq!(duration.get_seconds(), 1);
+        assert_eq!(duration.to_string(), "36:00:01");
+        assert_eq!(
+            ExcelDuration::from_serial(-0.25).unwrap().to_string(),
+            "-6:00:00"
+        );
+    }
+
+    #[test]
+    fn parse_iso_test() {
+        assert_eq!(
+            ExcelDateTime::parse_iso("2023-01-01"),
+            Some(ExcelDateTime::new(2023, 1, 1, 0, 0, 0))
+        );
+        assert_eq!(
+            ExcelDateTime::parse_iso("2023-01-01T09:30:15.5Z"),
+            Some(ExcelDateTime::new(2023, 1, 1, 9, 30, 15).with_millisecond(500))
+        );
+        assert_eq!(
+            ExcelDateTime::parse_iso("T09:30"),
+            Some(ExcelDateTime::new(1899, 12, 31, 9, 30, 0))
+        );
+        assert_eq!(ExcelDateTime::parse_iso("2023-13-01"), None);
+        assert_eq!(ExcelDateTime::parse_iso("abc"), None);
+    }
+    #[test]
+    fn to_string_test() {
+        assert_eq!(
+            ExcelDateTime::new(2023, 1, 1, 9, 5, 0).to_string(),
+            "2023-01-01T09:05:00"
+        );
+        assert_eq!(
+            ExcelDateTime::new(2023, 1, 1, 9, 5, 0)
+                .with_millisecond(20)
+                .to_string(),
+            "2023-01-01T09:05:00.020"
+        );
+    }
+}
@@ -0,0 +1,513 @@
+use crate::{
+    excel::formulas::{
+        ast::{
+            quote_sheet_name, quote_sheet_span, Area, CellReference, LineReference, MAX_COLUMN,
+            MAX_ROW,
+        },
+        parser::{find_references, ReferenceSpan},
+    },
+    xml::{escape::unescape, nodes::node::XMLNode},
+};
+
+use super::{cell::CellIndex, cell_range::RangeArea, line_shift::LineShift};
+
+/// How the `<f>` of a cell is shared with other cells.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FormulaKind {
+    Normal,
+    /// `t="shared"`, the cell writing the text also writes `ref`, the cells following it only `si`
+    Shared {
+        index: usize,
+        range: Option<String>,
+    },
+    /// `t="array"`, the formula fills `range`
+    Array {
+        range: String,
+    },
+    /// `t="dataTable"`, what-if analysis tables
+    DataTable {
+        range: Option<String>,
+    },
+}
+/// Formula of a cell without the leading `=`.
+///
+///     // <c r="H4"><f>$E$3&amp;G4</f></c>
+///     assert_eq!(formula.text, "$E$3&G4");
+///
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CellFormula {
+    pub text: String,
+    pub kind: FormulaKind,
+}
+impl CellFormula {
+    pub fn new(text: &str) -> Self {
+        CellFormula {
+            text: text.trim_start_matches('=').to_string(),
+            kind: FormulaKind::Normal,
+        }
+    }
+    /// Whether the `<f>` only refers to the shared formula of another cell.
+    pub fn is_shared_child(&self) -> bool {
+        matches!(self.kind, FormulaKind::Shared { range: None, .. }) && self.text.is_empty()
+    }
+}
+impl From<&XMLNode<'_>> for CellFormula {
+    /// Reads an `<f>` element.
+    fn from(node: &XMLNode) -> Self {
+        let text = node
+            .get_text()
+            .map(|text| unescape(&text))
+            .unwrap_or_default();
+        let range = node.search_element("ref").map(|range| range.to_string());
+        let kind = match node.search_element("t") {
+            Some("shared") => FormulaKind::Shared {
+                index: node
+                    .search_element("si")
+                    .and_then(|si| si.parse().ok())
+                    .unwrap_or_default(),
+                range,
+            },
+            Some("array") => FormulaKind::Array {
+                range: range.unwrap_or_default(),
+            },
+            Some("dataTable") => FormulaKind::DataTable { range },
+            _ => FormulaKind::Normal,
+        };
+        CellFormula { text, kind }
+    }
+}
+
+/// Moves the relative references of `formula` by the distance from `from` to `to`,
+/// as copying the cell does. `$` anchored parts, strings and names are kept.
+///
+///     assert_eq!(move_formula("A1+$B$2+B$3", "C3", "D5"), "B3+$B$2+C$3");
+///
+pub fn move_formula(formula: &str, from: &str, to: &str) -> String {
+    let from = CellIndex::new(from);
+    let to = CellIndex::new(to);
+    shift_references(
+        formula,
+        to.get_row_index() as isize - from.get_row_index() as isize,
+        to.get_column_index() as isize - from.get_column_index() as isize,
+    )
+}
+/// Adds the offsets to every relative reference of `formula`.
+/// A reference moved out of the sheet becomes `#REF!`.
+pub fn shift_references(formula: &str, row_offset: isize, column_offset: isize) -> String {
+    let shift = |line: LineReference, offset: isize, max: usize| {
+        if line.is_absolute {
+            return Some(line);
+        }
+        line.index
+            .checked_add_signed(offset)
+            .filter(|index| (1..=max).contains(index))
+            .map(|index| LineReference::new(index, false))
+    };
+    rewrite_references(formula, |span, text| {
+        let area = span.area.as_ref()?;
+        let moved = map_cells(
+            area,
+            |line| shift(line, row_offset, MAX_ROW),
+            |line| shift(line, column_offset, MAX_COLUMN),
+        );
+        write_area(span, text, area, moved)
+    })
+}
+
+/// Moves the references to `sheet` in a formula written on `formula_sheet`,
+/// as inserting or deleting lines of `sheet` moves its cells. Unlike copying, `$` anchored parts move too.
+/// A reference whose cells are all deleted becomes `#REF!`.
+///
+///     let shift = LineShift::DeleteRows { at: 2, count: 1 };
+///     assert_eq!(shift_lines("A2+SUM(A1:A3)", "Sheet1", "Sheet1", shift), "#REF!+SUM(A1:A2)");
+///
+pub fn shift_lines(formula: &str, formula_sheet: &str, sheet: &str, shift: LineShift) -> String {
+    map_sheet_areas(formula, formula_sheet, sheet, |area| {
+        let Some((start, end)) = get_lines(area, shift.is_row()) else {
+            return Some(area.clone());
+        };
+        let (first, last) = if let Area::Cell(_) = area {
+            let moved = shift.move_line(start.index)?;
+            (moved, moved)
+        } else if start.index <= end.index {
+            shift.move_span(start.index, end.index)?
+        } else {
+            let (last, first) = shift.move_span(end.index, start.index)?;
+            (first, last)
+        };
+        Some(with_lines(
+            area,
+            shift.is_row(),
+            (
+                LineReference::new(first, start.is_absolute),
+                LineReference::new(last, end.is_absolute),
+            ),
+        ))
+    })
+}
+
+/// Moves the references to cells of `area` on `sheet` in a formula written on `formula_sheet`,
+/// as cutting and pasting the cells does. Only references inside the area move, `$` anchored parts too.
+///
+///     let area = RangeArea::new("A1:B2").unwrap();
+///     assert_eq!(move_references("A1+$B$2+A1:C1", "Data", "Data", &area, 2, 0), "A3+$B$4+A1:C1");
+///
+pub fn move_references(
+    formula: &str,
+    formula_sheet: &str,
+    sheet: &str,
+    area: &RangeArea,
+    rows: isize,
+    columns: isize,
+) -> String {
+    let shift = |line: LineReference, offset: isize, max: usize| {
+        line.index
+            .checked_add_signed(offset)
+            .filter(|index| (1..=max).contains(index))
+            .map(|index| LineReference::new(index, line.is_absolute))
+    };
+    map_sheet_areas(formula, formula_sheet, sheet, |reference| {
+        let (first, last) = match reference {
+            Area::Cell(cell) => (cell, cell),
+            Area::Range(start, end) => (start, end),
+            // whole lines stay
+            _ => return Some(reference.clone()),
+        };
+        let bounds = RangeArea::from_bounds(
+            (first.row.index, first.column.index),
+            (last.row.index, last.column.index),
+        );
+        if !area.contains_area(&bounds) {
+            return Some(reference.clone());
+        }
+        map_cells(
+            reference,
+            |line| shift(line, rows, MAX_ROW),
+            |line| shift(line, columns, MAX_COLUMN),
+        )
+    })
+}
+
+/// Writes `new_name` for the sheet `old_name` in the references of `formula`, as renaming the sheet does.
+///
+///     assert_eq!(rename_sheet("Data!A1+'data'!B2+A3", "Data", "Sales 2024"), "'Sales 2024'!A1+'Sales 2024'!B2+A3");
+///
+pub fn rename_sheet(formula: &str, old_name: &str, new_name: &str) -> String {
+    let rename = |sheet: &str| {
+        if sheet.to_lowercase() == old_name.to_lowercase() {
+            new_name.to_string()
+        } else {
+            sheet.to_string()
+        }
+    };
+    rewrite_references(formula, |span, text| {
+        let sheet = span.sheet.as_deref()?;
+        let is_renamed = is_same_sheet(sheet, old_name)
+            || span
+                .last_sheet
+                .as_deref()
+                .is_some_and(|last_sheet| is_same_sheet(last_sheet, old_name));
+        if !is_renamed {
+            return None;
+        }
+        let prefix = match span.last_sheet.as_deref() {
+            Some(last_sheet) => quote_sheet_span(&rename(sheet), &rename(last_sheet)),
+            None => quote_sheet_name(new_name),
+        };
+        let item = text
+            .chars()
+            .skip(span.item_start - span.chars.start)
+            .collect::<String>();
+        Some(format!("{}!{}", prefix, item))
+    })
+}
+/// Turns the references to `sheet` in `formula` into `#REF!`, as deleting the sheet does.
+/// 3-D references starting or ending at the sheet become `#REF!` too.
+///
+///     assert_eq!(remove_sheet("SUM(Data!A1:B2)+A3", "Data"), "SUM(#REF!)+A3");
+///
+pub fn remove_sheet(formula: &str, sheet: &str) -> String {
+    rewrite_references(formula, |span, _| {
+        let is_removed = [span.sheet.as_deref(), span.last_sheet.as_deref()]
+            .into_iter()
+            .flatten()
+            .any(|target| is_same_sheet(target, sheet));
+        is_removed.then(|| "#REF!".to_string())
+    })
+}
+/// Sheets of other workbooks, `[1]Sheet1`, are never the same.
+fn is_same_sheet(target: &str, sheet: &str) -> bool {
+    target.to_lowercase() == sheet.to_lowercase()
+}
+
+/// Rewrites the references the parser finds in `formula` with `f`, which gets each one with
+/// the text it is written as and returns its new text, `None` keeping it.
+/// A formula that does not parse is kept as it is.
+fn rewrite_references(formula: &str, f: impl Fn(&ReferenceSpan, &str) -> Option<String>) -> String {
+    let Ok(spans) = find_references(formula) else {
+        return formula.to_string();
+    };
+    let chars = formula.chars().collect::<Vec<_>>();
+    let mut result = String::with_capacity(formula.len());
+    let mut written = 0;
+    for span in spans {
+        let text = chars[span.chars.clone()].iter().collect::<String>();
+        let Some(rewritten) = f(&span, &text) else {
+            continue;
+        };
+        result.extend(&chars[written..span.chars.start]);
+        result.push_str(&rewritten);
+        written = span.chars.end;
+    }
+    result.extend(&chars[written..]);
+    result
+}
+/// Rewrites the areas of the references to `sheet` in a formula written on `formula_sheet` with `f`,
+/// `None` turning the area into `#REF!`. 3-D references are kept.
+fn map_sheet_areas(
+    formula: &str,
+    formula_sheet: &str,
+    sheet: &str,
+    f: impl Fn(&Area) -> Option<Area>,
+) -> String {
+    rewrite_references(formula, |span, text| {
+        let area = span.area.as_ref()?;
+        let target = span.sheet.as_deref().unwrap_or(formula_sheet);
+        if span.last_sheet.is_some() || !is_same_sheet(target, sheet) {
+            return None;
+        }
+        write_area(span, text, area, f(area))
+    })
+}
+/// The sheet written before the area with the `moved` area, or `#REF!` without it.
+/// `None` when the area does not move, so that it keeps the way it is written.
+fn write_area(
+    span: &ReferenceSpan,
+    text: &str,
+    area: &Area,
+    moved: Option<Area>,
+) -> Option<String> {
+    let prefix = text
+        .chars()
+        .take(span.item_start - span.chars.start)
+        .collect::<String>();
+    match moved {
+        Some(moved) if moved == *area => None,
+        Some(moved) => Some(format!("{}{}", prefix, moved)),
+        None => Some(format!("{}#REF!", prefix)),
+    }
+}
+/// Applies `row` and `column` to the lines of the area, `None` when either leaves the sheet.
+fn map_cells(
+    area: &Area,
+    row: impl Fn(LineReference) -> Option<LineReference>,
+    column: impl Fn(LineReference) -> Option<LineReference>,
+) -> Option<Area> {
+    let cell = |cell: &CellReference| {
+        Some(CellReference {
+            column: column(cell.column)?,
+            row: row(cell.row)?,
+        })
+    };
+    Some(match area {
+        Area::Cell(start) => Area::Cell(cell(start)?),
+        Area::Range(start, end) => Area::Range(cell(start)?, cell(end)?),
+        Area::Columns(start, end) => Area::Columns(column(*start)?, column(*end)?),
+        Area::Rows(start, end) => Area::Rows(row(*start)?, row(*end)?),
+    })
+}
+/// First and last row, or column, of the area, `None` for whole columns read as rows and the reverse.
+fn get_lines(area: &Area, is_row: bool) -> Option<(LineReference, LineReference)> {
+    match (area, is_row) {
+        (Area::Cell(cell), true) => Some((cell.row, cell.row)),
+        (Area::Cell(cell), false) => Some((cell.column, cell.column)),
+        (Area::Range(start, end), true) => Some((start.row, end.row)),
+        (Area::Range(start, end), false) => Some((start.column, end.column)),
+        (Area::Rows(start, end), true) | (Area::Columns(start, end), false) => Some((*start, *end)),
+        _ => None,
+    }
+}
+/// The area with its first and last row, or column, replaced.
+fn with_lines(area: &Area, is_row: bool, (first, last): (LineReference, LineReference)) -> Area {
+    let with = |cell: CellReference, line: LineReference| {
+        if is_row {
+            CellReference { row: line, ..cell }
+        } else {
+            CellReference {
+                column: line,
+                ..cell
+            }
+        }
+    };
+    match area {
+        Area::Cell(cell) => Area::Cell(with(*cell, first)),
+        Area::Range(start, end) => Area::Range(with(*start, first), with(*end, last)),
+        Area::Columns(..) => Area::Columns(first, last),
+        Area::Rows(..) => Area::Rows(first, last),
+    }
+}
+
+#[cfg(test)]
+mod formula_test {
+    use crate::xml::nodes::node::XMLNode;
+
+    use crate::excel::cells::{cell_range::RangeArea, line_shift::LineShift};
+
+    use super::{
+        move_formula, move_references, remove_sheet, rename_sheet, shift_lines, shift_references,
+        CellFormula, FormulaKind,
+    };
+
+    #[test]
+    fn from_node_test() {
+        let formula = CellFormula::from(&XMLNode::from(r#"<f>$E$3&amp;G4</f>"#));
+        assert_eq!(formula, CellFormula::new("$E$3&G4"));
+        let formula = CellFormula::from(&XMLNode::from(r#"<f>"a  b"&amp;A2&amp;" "</f>"#));
+        assert_eq!(formula.text, r#""a  b"&A2&" ""#);
+        let formula = CellFormula::from(&XMLNode::from(
+            r#"<f t="shared" ref="B2:B10" si="0">A2*2</f>"#,
+        ));
+        assert_eq!(
+            formula.kind,
+            FormulaKind::Shared {
+                index: 0,
+                range: Some("B2:B10".to_string())
+            }
+        );
+        assert!(!formula.is_shared_child());
+        let formula = CellFormula::from(&XMLNode::from(r#"<f t="shared" si="0"></f>"#));
+        assert!(formula.is_shared_child());
+        let formula = CellFormula::from(&XMLNode::from(
+            r#"<f t="array" ref="C1:C3">A1:A3*B1:B3</f>"#,
+        ));
+        assert_eq!(
+            formula.kind,
+            FormulaKind::Array {
+                range: "C1:C3".to_string()
+            }
+        );
+        assert_eq!(formula.text, "A1:A3*B1:B3");
+        assert_eq!(CellFormula::new("=SUM(A1:A3)").text, "SUM(A1:A3)");
+    }
+    #[test]
+    fn move_formula_test() {
+        assert_eq!(move_formula("A1+$B$2+B$3", "C3", "D5"), "B3+$B$2+C$3");
+        assert_eq!(move_formula("SUM(A2:B3)*2", "C2", "C4"), "SUM(A4:B5)*2");
+        assert_eq!(
+            move_formula("SUM(A:A)+SUM(2:3)", "B1", "C3"),
+            "SUM(B:B)+SUM(4:5)"
+        );
+        assert_eq!(
+            move_formula(r#"IF(A1="B2",Sheet1!C3,'My Sheet'!D4)"#, "A1", "A2"),
+            r#"IF(A2="B2",Sheet1!C4,'My Sheet'!D5)"#
+        );
+        assert_eq!(
+            move_formula("LOG10(A1)+ATAN2(1,2)", "A1", "B1"),
+            "LOG10(B1)+ATAN2(1,2)"
+        );
+        assert_eq!(move_formula("A1*1.5E+3", "A1", "A2"), "A2*1.5E+3");
+        assert_eq!(move_formula("Total_2023+A1", "A1", "B1"), "Total_2023+B1");
+        assert_eq!(move_formula("$E$3&G4", "H4", "H5"), "$E$3&G5");
+    }
+    #[test]
+    fn shift_references_test() {
+        assert_eq!(shift_references("A1+B2", -1, 0), "#REF!+B1");
+        assert_eq!(shift_references("SUM(A1:A3)", 0, -1), "SUM(#REF!)");
+        assert_eq!(shift_references("XFD1", 0, 1), "#REF!");
+        assert_eq!(shift_references("$A$1", -5, -5), "$A$1");
+    }
+    #[test]
+    fn shift_lines_test() {
+        let insert = LineShift::InsertRows { at: 2, count: 2 };
+        assert_eq!(
+            shift_lines("$A$2+A1+SUM(B1:B2)+SUM(C:C)", "Data", "Data", insert),
+            "$A$4+A1+SUM(B1:B4)+SUM(C:C)"
+        );
+        assert_eq!(
+            shift_lines("A2+Data!A2+'It''s'!A2+data!1:2", "Other", "Data", insert),
+            "A2+Data!A4+'It''s'!A2+data!1:4"
+        );
+        assert_eq!(
+            shift_lines("'It''s'!A2&\"A2\"", "Other", "It's", insert),
+            "'It''s'!A4&\"A2\""
+        );
+        let delete = LineShift::DeleteColumns { at: 2, count: 2 };
+        assert_eq!(
+            shift_lines("B1+D1+SUM(A1:C1)+SUM(B:C)+SUM(2:2)", "Data", "Data", delete),
+            "#REF!+B1+SUM(A1:A1)+SUM(#REF!)+SUM(2:2)"
+        );
+        assert_eq!(
+            shift_lines("SUM(D1:A1)", "Data", "Data", delete),
+            "SUM(B1:A1)"
+        );
+    }
+    #[test]
+    fn move_references_test() {
+        let area = RangeArea::new("A1:B2").unwrap();
+        assert_eq!(
+            move_references(
+                "A1+$B$2+SUM(A1:B2)+A1:C1+SUM(A:A)",
+                "Data",
+                "Data",
+                &area,
+                2,
+                1
+            ),
+            "B3+$C$4+SUM(B3:C4)+A1:C1+SUM(A:A)"
+        );
+        assert_eq!(
+            move_references("A1+Data!A1+'data'!B2", "Other", "Data", &area, 0, 1),
+            "A1+Data!B1+'data'!C2"
+        );
+    }
+    #[test]
+    fn rename_sheet_test() {
+        assert_eq!(
+            rename_sheet(
+                "Data!A1+'data'!B2:C3+A3+SUM(Data!A:A)",
+                "Data",
+                "Sales 2024"
+            ),
+            "'Sales 2024'!A1+'Sales 2024'!B2:C3+A3+SUM('Sales 2024'!A:A)"
+        );
+        assert_eq!(
+            rename_sheet("'It''s'!A1&\"Data!A1\"&[1]Data!A1", "it's", "Data"),
+            "Data!A1&\"Data!A1\"&[1]Data!A1"
+        );
+        assert_eq!(rename_sheet("MyData!A1", "Data", "Other"), "MyData!A1");
+        assert_eq!(
+            rename_sheet("SUM(Jan:Mar!A1)+SUM( mar!a1 , B2)", "Mar", "Mar 2024"),
+            "SUM('Jan:Mar 2024'!A1)+SUM( 'Mar 2024'!a1 , B2)"
+        );
+        assert_eq!(rename_sheet("SUM(Data!A1", "Data", "Other"), "SUM(Data!A1");
+    }
+    #[test]
+    fn rewrite_keeps_text_test() {
+        let insert = LineShift::InsertRows { at: 2, count: 1 };
+        assert_eq!(
+            shift_lines("SUM( a1 , b3 )&\"  A3 \"", "Data", "Data", insert),
+            "SUM( a1 , B4 )&\"  A3 \""
+        );
+        assert_eq!(
+            shift_lines("SUM(Jan:Data!A3)+Sheet1:Sheet3!A1", "Data", "Data", insert),
+            "SUM(Jan:Data!A3)+Sheet1:Sheet3!A1"
+        );
+        assert_eq!(
+            move_formula("SUM(Jan:Mar!A1)", "B1", "B2"),
+            "SUM(Jan:Mar!A2)"
+        );
+        assert_eq!(move_formula("A1 + 1.50", "B1", "B2"), "A2 + 1.50");
+    }
+    #[test]
+    fn remove_sheet_test() {
+        assert_eq!(
+            remove_sheet("SUM(Data!A1:B2)+'Data'!$C$3*Other!A1", "data"),
+            "SUM(#REF!)+#REF!*Other!A1"
+        );
+        assert_eq!(remove_sheet("Data!Total+1", "Data"), "#REF!+1");
+        assert_eq!(
+            remove_sheet("SUM(Jan:Data!A1)+SUM(Data:Mar!A1)+SUM(Jan:Mar!A1)", "data"),
+            "SUM(#REF!)+SUM(#REF!)+SUM(Jan:Mar!A1)"
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::excel::formulas::ast::{MAX_COLUMN, MAX_ROW};
+
+use super::{
+    cell::{CellIndex, ColumnAlphabet},
+    cell_range::RangeArea,
+};
+
+/// Rows or columns inserted before `at` or deleted from `at`, and where that moves the cells.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LineShift {
+    InsertRows { at: usize, count: usize },
+    DeleteRows { at: usize, count: usize },
+    InsertColumns { at: usize, count: usize },
+    DeleteColumns { at: usize, count: usize },
+}
+impl LineShift {
+    pub fn is_row(&self) -> bool {
+        matches!(
+            self,
+            LineShift::InsertRows { .. } | LineShift::DeleteRows { .. }
+        )
+    }
+    fn get_max(&self) -> usize {
+        if self.is_row() {
+            MAX_ROW
+        } else {
+            MAX_COLUMN
+        }
+    }
+    /// New number of a row or column on the shifted axis, `None` when it is deleted or pushed off the sheet.
+    ///
+    ///     let shift = LineShift::DeleteRows { at: 2, count: 3 };
+    ///     assert_eq!(shift.move_line(3), None);
+    ///     assert_eq!(shift.move_line(6), Some(3));
+    ///
+    pub fn move_line(&self, index: usize) -> Option<usize> {
+        match *self {
+            LineShift::InsertRows { at, count } | LineShift::InsertColumns { at, count } => {
+                let moved = if index >= at { index + count } else { index };
+                (moved <= self.get_max()).then_some(moved)
+            }
+            LineShift::DeleteRows { at, count } | LineShift::DeleteColumns { at, count } => {
+                if index < at {
+                    Some(index)
+                } else if index >= at + count {
+                    Some(index - count)
+                } else {
+                    None
+                }
+            }
+        }
+    }
+    /// New first and last line of `first..=last`, `None` when all of them are deleted.
+    /// Lines inserted inside the span widen it and deleted ones narrow it, a span of every line stays so.
+    pub fn move_span(&self, first: usize, last: usize) -> Option<(usize, usize)> {
+        if first == 1 && last == self.get_max() {
+            return Some((first, last));
+        }
+        match *self {
+            LineShift::InsertRows { at, count } | LineShift::InsertColumns { at, count } => {
+                let move_by = |index: usize| if index >= at { index + count } else { index };
+                let first = move_by(first);
+                (first <= self.get_max()).then_some((first, move_by(last).min(self.get_max())))
+            }
+            LineShift::DeleteRows { at, count } | LineShift::DeleteColumns { at, count } => {
+                let end = at + count - 1;
+                let first = match first {
+                    first if first < at => first,
+                    first if first > end => first - count,
+                    _ => at,
+                };
+                let last = match last {
+                    last if last < at => last,
+                    last if last > end => last - count,
+                    _ => at - 1,
+                };
+                (last >= 1 && first <= last).then_some((first, last))
+            }
+        }
+    }
+    /// The area after the shift, `None` when all of its cells are deleted.
+    pub fn move_area(&self, area: &RangeArea) -> Option<RangeArea> {
+        if self.is_row() {
+            let (first, last) = self.move_span(area.get_first_row(), area.get_last_row())?;
+            Some(RangeArea::from_bounds(
+                (first, area.get_first_column()),
+                (last, area.get_last_column()),
+            ))
+        } else {
+            let (first, last) = self.move_span(area.get_first_column(), area.get_last_column())?;
+            Some(RangeArea::from_bounds(
+                (area.get_first_row(), first),
+                (area.get_last_row(), last),
+            ))
+        }
+    }
+    /// Index of the cell after the shift, `None` when it is deleted.
+    pub fn move_cell(&self, cell_index: &str) -> Option<String> {
+        let index = CellIndex::new(cell_index);
+        let (mut row, mut column) = (index.get_row_index(), index.get_column_index());
+        if self.is_row() {
+            row = self.move_line(row)?;
+        } else {
+            column = self.move_line(column)?;
+        }
+        Some(format!("{}{}", ColumnAlphabet::from_number(column), row))
+    }
+}
+
+#[cfg(test)]
+mod line_shift_test {
+    use crate::excel::{cells::cell_range::RangeArea, formulas::ast::MAX_ROW};
+
+    use super::LineShift;
+
+    #[test]
+    fn move_span_test() {
+        let insert = LineShift::InsertRows { at: 3, count: 2 };
+        assert_eq!(insert.move_span(1, 2), Some((1, 2)));
+        assert_eq!(insert.move_span(2, 3), Some((2, 5)));
+        assert_eq!(insert.move_span(3, 4), Some((5, 6)));
+        assert_eq!(insert.move_span(1, MAX_ROW), Some((1, MAX_ROW)));
+        assert_eq!(insert.move_span(MAX_ROW, MAX_ROW), None);
+        let delete = LineShift::DeleteColumns { at: 3, count: 2 };
+        assert_eq!(delete.move_span(1, 2), Some((1, 2)));
+        assert_eq!(delete.move_span(2, 6), Some((2, 4)));
+        assert_eq!(delete.move_span(4, 6), Some((3, 4)));
+        assert_eq!(delete.move_span(2, 3), Some((2, 2)));
+        assert_eq!(delete.move_span(3, 4), None);
+        assert_eq!(
+            LineShift::DeleteRows { at: 1, count: 1 }.move_span(1, 1),
+            None
+        );
+    }
+    #[test]
+    fn move_area_test() {
+        let area = RangeArea::new("B2:D4").unwrap();
+        let shift = LineShift::InsertColumns { at: 3, count: 1 };
+        assert_eq!(shift.move_area(&area).unwrap().to_string(), "B2:E4");
+        let shift = LineShift::DeleteRows { at: 1, count: 2 };
+        assert_eq!(shift.move_area(&area).unwrap().to_string(), "B1:D2");
+        assert_eq!(shift.move_cell("C2"), None);
+        assert_eq!(shift.move_cell("C5"), Some("C3".to_string()));
+    }
+}
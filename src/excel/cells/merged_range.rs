@@ -0,0 +1,139 @@
+use std::fmt::Display;
+
+use crate::excel::formulas::ast::CellReference;
+
+use super::{cell::ColumnAlphabet, cell_range::RangeArea};
+
+/// Cells merged into one, `B2:E2` of `<mergeCell ref="B2:E2"/>`.
+/// The top-left cell holds the value shown for the whole merge.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct MergedRange {
+    area: RangeArea,
+}
+impl MergedRange {
+    /// Reads `B2:E2`, `$B$2:$E$2` or a single cell, in any corner order.
+    ///
+    ///     let range = MergedRange::new("E3:B2").unwrap();
+    ///     assert_eq!(range.to_string(), "B2:E3");
+    ///
+    pub fn new(range: &str) -> Option<Self> {
+        let (start, end) = range
+            .trim()
+            .split_once(':')
+            .unwrap_or((range.trim(), range.trim()));
+        let (start, end) = (CellReference::new(start)?, CellReference::new(end)?);
+        Some(MergedRange::from(RangeArea::from_bounds(
+            (start.row.index, start.column.index),
+            (end.row.index, end.column.index),
+        )))
+    }
+    pub fn get_area(&self) -> RangeArea {
+        self.area
+    }
+    pub fn get_first_row(&self) -> usize {
+        self.area.get_first_row()
+    }
+    pub fn get_first_column(&self) -> usize {
+        self.area.get_first_column()
+    }
+    pub fn get_last_row(&self) -> usize {
+        self.area.get_last_row()
+    }
+    pub fn get_last_column(&self) -> usize {
+        self.area.get_last_column()
+    }
+    /// Index of the cell holding the value, e.g. `B2` of `B2:E3`.
+    pub fn get_top_left(&self) -> String {
+        self.area.get_top_left()
+    }
+    pub fn is_single_cell(&self) -> bool {
+        self.get_first_row() == self.get_last_row()
+            && self.get_first_column() == self.get_last_column()
+    }
+    pub fn contains(&self, cell_index: &str) -> bool {
+        self.area.contains(cell_index)
+    }
+    pub(crate) fn contains_position(&self, row: usize, column: usize) -> bool {
+        self.area.contains_position(row, column)
+    }
+    pub fn overlaps(&self, other: &MergedRange) -> bool {
+        self.area.intersect(&other.area).is_some()
+    }
+}
+impl From<RangeArea> for MergedRange {
+    fn from(area: RangeArea) -> Self {
+        MergedRange { area }
+    }
+}
+impl From<MergedRange> for RangeArea {
+    fn from(range: MergedRange) -> Self {
+        range.area
+    }
+}
+impl Display for MergedRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}:{}{}",
+            self.get_top_left(),
+            ColumnAlphabet::from_number(self.get_last_column()),
+            self.get_last_row()
+        )
+    }
+}
+
+/// Why a merge could not be added.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum MergeError {
+    InvalidRange(String),
+    /// A merge needs two cells or more
+    SingleCell(MergedRange),
+    /// Cells may belong to one merge only
+    Overlap {
+        range: MergedRange,
+        existing: MergedRange,
+    },
+}
+impl Display for MergeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MergeError::InvalidRange(range) => write!(f, "invalid range {:?}", range),
+            MergeError::SingleCell(range) => write!(f, "{} is a single cell", range),
+            MergeError::Overlap { range, existing } => {
+                write!(f, "{} overlaps the merged range {}", range, existing)
+            }
+        }
+    }
+}
+impl std::error::Error for MergeError {}
+
+#[cfg(test)]
+mod merged_range_test {
+    use super::MergedRange;
+    use crate::excel::cells::cell_range::RangeArea;
+
+    #[test]
+    fn new_test() {
+        let range = MergedRange::new("$E$3:B2").unwrap();
+        assert_eq!(range.to_string(), "B2:E3");
+        assert_eq!(range.get_top_left(), "B2");
+        assert_eq!((range.get_last_row(), range.get_last_column()), (3, 5));
+        assert!(MergedRange::new("A1").unwrap().is_single_cell());
+        assert_eq!(MergedRange::new("A:B"), None);
+        assert_eq!(MergedRange::new("1A:B2"), None);
+        let area = RangeArea::new("B2:E3").unwrap();
+        assert_eq!(MergedRange::from(area), range);
+        assert_eq!(range.get_area(), area);
+    }
+    #[test]
+    fn contains_and_overlaps_test() {
+        let range = MergedRange::new("B2:E3").unwrap();
+        assert!(range.contains("B2"));
+        assert!(range.contains("E3"));
+        assert!(!range.contains("A2"));
+        assert!(!range.contains("C4"));
+        assert!(range.overlaps(&MergedRange::new("E3:F4").unwrap()));
+        assert!(range.overlaps(&MergedRange::new("A1:Z9").unwrap()));
+        assert!(!range.overlaps(&MergedRange::new("F2:F3").unwrap()));
+    }
+}
@@ -1,40 +1,79 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    io::{self, Write},
+};
 
 use super::{
-    file_operator::XLSXOperator,
-    xmls::{shared_strings::SharedStrings, sheet::Sheet, workbook::WorkBook},
+    cells::{cell_range::CellRange, cell_value::CellValue, line_shift::LineShift},
+    file_operator::{XLSXOperator, XLSXPackage},
+    formulas::{
+        dependency::{CellAddress, CircularReference, DependencyGraph},
+        evaluator::{CellSource, Evaluator},
+    },
+    xmls::{
+        defined_name::DefinedName,
+        shared_strings::{SharedStrings, SharedStringsInterface},
+        sheet::Sheet,
+        styles::Styles,
+        workbook::WorkBook,
+    },
 };
 
 type SheetA<'a> = Sheet<'a, SharedStrings<'a>>;
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Debug, PartialEq)]
 pub struct Excel<'a, T: XLSXOperator<'a>> {
     xlsx_operator: &'a T,
     workbook: Option<WorkBook<'a>>,
     shared_strings: Option<SharedStrings<'a>>,
+    styles: Option<Styles<'a>>,
     sheets: HashMap<String, String>,
 }
 impl<'a, XOpe: XLSXOperator<'a>> Excel<'a, XOpe> {
+    /// Reads the workbook of the package, which the `Excel` borrows until it is dropped.
+    /// Packages from an upload are built by `XLSXPackage::from_bytes` or `XLSXPackage::from_reader`.
+    ///
+    ///     let mut package = XLSXPackage::from_reader(request.body())?;
+    ///     let mut excel = Excel::new(&mut package);
+    ///
     pub fn new(xlsx_operator: &'a mut XOpe) -> Self {
         let mut excel = Excel {
             xlsx_operator,
             workbook: None,
             shared_strings: None,
+            styles: None,
             sheets: HashMap::new(),
         };
-        excel.workbook = Some(WorkBook::new(excel.xlsx_operator.read_workbook()));
+        excel.workbook = Some(WorkBook::new(
+            excel.xlsx_operator.read_workbook(),
+            excel.xlsx_operator.read_workbook_rels(),
+        ));
         excel.shared_strings = Some(SharedStrings::new(
             excel.xlsx_operator.read_shared_strings(),
         ));
+        excel.styles = Some(Styles::new(excel.xlsx_operator.read_styles()));
         excel
     }
+    /// Writes the sheet, and the shared strings when the sheet added any.
+    /// A sheet with written, removed or moved formulas also marks the workbook for full
+    /// calculation on open and drops its calculation chain, as does a sheet keeping values
+    /// `recalculate` could not calculate, without dropping the chain.
     pub fn save(&self, sheet: SheetA) {
+        let workbook = self.workbook.as_ref().unwrap();
         self.xlsx_operator.write_sheet(
-            self.workbook
-                .as_ref()
-                .unwrap()
-                .get_excel_sheet_name(sheet.get_sheet_name()),
+            workbook.get_excel_sheet_name(sheet.get_sheet_name()),
             sheet.to_xml().as_str(),
-        )
+        );
+        if sheet.is_full_calc_needed() {
+            self.xlsx_operator.set_full_calc_on_load();
+        }
+        if sheet.is_formula_changed() {
+            self.xlsx_operator.remove_calc_chain();
+        }
+        let shared_strings = self.shared_strings.as_ref().unwrap();
+        if shared_strings.is_changed() {
+            self.xlsx_operator
+                .write_shared_strings(shared_strings.to_xml().as_str());
+        }
     }
     pub fn read_sheet(&mut self, sheet_name: &str) -> () {
         let e_sheet_name = self
@@ -53,22 +92,208 @@ impl<'a, XOpe: XLSXOperator<'a>> Excel<'a, XOpe> {
             .get_excel_sheet_name(&sheet_name);
         println!("{}", e_sheet_name);
         let source = self.sheets.get(e_sheet_name).unwrap();
-        let sheet = SheetA::new(sheet_name, source, &self.shared_strings.as_ref().unwrap());
+        let sheet = SheetA::new(sheet_name, source, &self.shared_strings.as_ref().unwrap())
+            .with_styles(self.styles.as_ref().unwrap())
+            .with_date1904(self.workbook.as_ref().unwrap().is_date1904());
         sheet
     }
+    /// Names of the workbook and of its sheets, e.g. `_xlnm.Print_Area`.
+    pub fn get_defined_names(&self) -> Vec<DefinedName> {
+        self.workbook.as_ref().unwrap().get_defined_names()
+    }
+    /// Sheet and cells of the defined name, `Sheet!Name` being the name scoped to the sheet.
+    /// `None` when the name is not defined or is not a range, e.g. a constant.
+    ///
+    ///     // <definedName name="TaxRate">Settings!$B$1</definedName>
+    ///     let (sheet, range) = excel.get_named_range("TaxRate").unwrap();
+    ///     excel.read_sheet(&sheet);
+    ///     let rate = excel.get_sheet(&sheet).get_cell_value(&range.get_areas()[0].get_top_left());
+    ///
+    pub fn get_named_range(&self, name: &str) -> Option<(String, CellRange)> {
+        let workbook = self.workbook.as_ref().unwrap();
+        match name.rsplit_once('!') {
+            Some((sheet, name)) => {
+                let sheet = sheet
+                    .strip_prefix('\'')
+                    .and_then(|sheet| sheet.strip_suffix('\''))
+                    .map(|sheet| sheet.replace("''", "'"))
+                    .unwrap_or_else(|| sheet.to_string());
+                workbook.get_named_range(name, Some(&sheet))
+            }
+            None => workbook.get_named_range(name, None),
+        }
+    }
+    /// Calculates the cell of the sheet from its formula, reading the cells of every sheet read so far.
+    /// Sheets not passed to `read_sheet` are `#REF!` to the formula.
+    ///
+    ///     excel.read_sheet("Data");
+    ///     excel.read_sheet("Summary");
+    ///     // Summary!A1 = SUM(Data!A:A)
+    ///     assert_eq!(excel.calculate_cell("Summary", "A1"), CellValue::Number(6.0));
+    ///
+    pub fn calculate_cell(&'a self, sheet_name: &str, cell_index: &str) -> CellValue {
+        let workbook = self.workbook.as_ref().unwrap();
+        let sheets = self
+            .sheets
+            .keys()
+            .filter_map(|e_sheet_name| workbook.get_user_sheet_name(e_sheet_name))
+            .map(|u_sheet_name| self.get_sheet(u_sheet_name))
+            .collect::<Vec<_>>();
+        let source = WorkBookSource {
+            workbook,
+            sheets: &sheets,
+        };
+        Evaluator::new(&source).evaluate_cell(sheet_name, cell_index)
+    }
+    /// Calculates again the formula cells the edits of the sheets affect, formulas read before
+    /// the formulas reading them, and writes the results into the cached values of the cells.
+    /// Only the given sheets are read and written, cells of other sheets are `#REF!` to formulas.
+    /// Nothing is written when affected formulas read each other.
+    /// Cells reaching a function not implemented here keep their cached values and are not
+    /// returned, the workbook saved with them is calculated again when Excel opens it.
+    ///
+    ///     let mut sheets = vec![excel.get_sheet("Data"), excel.get_sheet("Summary")];
+    ///     sheets[0].set_cell(&ECell::new("5", "A1"));
+    ///     // Summary!A1 = Data!A1*2
+    ///     let recalculated = excel.recalculate(&mut sheets)?;
+    ///     assert_eq!(recalculated, vec![CellAddress::from_index("Summary", "A1")]);
+    ///
+    pub fn recalculate(
+        &self,
+        sheets: &mut Vec<SheetA<'a>>,
+    ) -> Result<Vec<CellAddress>, CircularReference> {
+        let changed = sheets
+            .iter()
+            .flat_map(|sheet| sheet.get_changed_cells())
+            .collect::<Vec<_>>();
+        let source = WorkBookSource {
+            workbook: self.workbook.as_ref().unwrap(),
+            sheets: &*sheets,
+        };
+        let graph = DependencyGraph::new(&source);
+        let order = graph.get_calculation_order(&graph.get_affected_cells(&changed))?;
+        let evaluator = Evaluator::new(&source).with_stale_cells(&order);
+        let values = order
+            .iter()
+            .map(|cell| {
+                let cell_index = cell.get_cell_index();
+                let value = evaluator.evaluate_cell(&cell.sheet, &cell_index);
+                (value, evaluator.is_unsupported(&cell.sheet, &cell_index))
+            })
+            .collect::<Vec<_>>();
+        let mut recalculated = Vec::new();
+        for (cell, (value, is_unsupported)) in order.into_iter().zip(values) {
+            let Some(sheet) = sheets
+                .iter_mut()
+                .find(|sheet| sheet.get_sheet_name() == cell.sheet)
+            else {
+                continue;
+            };
+            // Excel's cached value is kept, and Excel calculates it again on opening
+            if is_unsupported {
+                sheet.set_full_calc_needed();
+                continue;
+            }
+            sheet.set_formula_value(&cell.get_cell_index(), &value);
+            recalculated.push(cell);
+        }
+        sheets
+            .iter_mut()
+            .for_each(|sheet| sheet.clear_changed_cells());
+        Ok(recalculated)
+    }
+    /// Inserts or deletes lines of the sheet named `sheet_name` among `sheets`,
+    /// and moves the references to them in the formulas of every sheet.
+    /// `sheets` are to hold every sheet with formulas, each passed to `save` afterwards,
+    /// as the formulas of a sheet not given keep pointing at the old lines.
+    /// The defined names and the tables of the sheet are moved in the package at once,
+    /// `get_defined_names` still reads the names this `Excel` was made with.
+    ///
+    ///     let mut sheets = vec![excel.get_sheet("Data"), excel.get_sheet("Summary")];
+    ///     // Summary!A1 = SUM(Data!A1:A3)
+    ///     excel.shift_lines(&mut sheets, "Data", LineShift::InsertRows { at: 2, count: 1 });
+    ///     assert_eq!(sheets[1].get_formula("A1").unwrap().text, "SUM(Data!A1:A4)");
+    ///
+    pub fn shift_lines(&self, sheets: &mut [SheetA<'a>], sheet_name: &str, shift: LineShift) {
+        for sheet in sheets.iter_mut() {
+            if sheet.get_sheet_name().to_lowercase() == sheet_name.to_lowercase() {
+                sheet.shift_lines(shift);
+            } else {
+                sheet.shift_formula_references(sheet_name, shift);
+            }
+        }
+        self.xlsx_operator.shift_lines(sheet_name, shift);
+    }
     fn close(&mut self) {
         self.xlsx_operator.to_excel()
     }
 }
+impl<'a> Excel<'a, XLSXPackage<'a>> {
+    /// Writes the package, including every sheet passed to `save`, into `writer`.
+    ///
+    ///     let mut package = XLSXPackage::from_bytes(&upload)?;
+    ///     let excel = Excel::new(&mut package);
+    ///     let response = excel.save_to(Vec::new())?;
+    ///
+    pub fn save_to<W: Write>(&self, writer: W) -> io::Result<W> {
+        self.xlsx_operator.write_to(writer)
+    }
+}
 impl<'a, T: XLSXOperator<'a>> Drop for Excel<'a, T> {
     fn drop(&mut self) {
         self.close()
     }
 }
 
+/// Cells of the sheets with the defined names of the workbook, for formulas reading names.
+struct WorkBookSource<'s, 'a, S: CellSource> {
+    workbook: &'s WorkBook<'a>,
+    sheets: &'s S,
+}
+impl<S: CellSource> CellSource for WorkBookSource<'_, '_, S> {
+    fn get_value_at(&self, sheet: &str, row: usize, column: usize) -> Option<CellValue> {
+        self.sheets.get_value_at(sheet, row, column)
+    }
+    fn get_formula_at(&self, sheet: &str, row: usize, column: usize) -> Option<String> {
+        self.sheets.get_formula_at(sheet, row, column)
+    }
+    fn get_used_range(&self, sheet: &str) -> Option<(usize, usize)> {
+        self.sheets.get_used_range(sheet)
+    }
+    fn get_sheet_names(&self) -> Vec<String> {
+        self.sheets.get_sheet_names()
+    }
+    fn get_formula_cells(&self, sheet: &str) -> Vec<(usize, usize)> {
+        self.sheets.get_formula_cells(sheet)
+    }
+    fn get_defined_name(&self, name: &str, sheet: &str) -> Option<String> {
+        self.workbook
+            .get_defined_name(name, Some(sheet))
+            .or_else(|| self.workbook.get_defined_name(name, None))
+            .map(|defined| defined.get_formula().to_string())
+            .or_else(|| self.sheets.get_defined_name(name, sheet))
+    }
+    fn is_date1904(&self) -> bool {
+        self.sheets.is_date1904()
+    }
+}
+
 #[cfg(test)]
 mod excel_tests {
-    use crate::excel::file_operator::XLSXOperator;
+    use crate::{
+        excel::{
+            cells::{
+                cell::ECell,
+                cell_value::{CellErrorKind, CellValue},
+                line_shift::LineShift,
+            },
+            excel::Excel,
+            file_operator::{XLSXOperator, XLSXPackage},
+            formulas::dependency::CellAddress,
+            xmls::{relationships::EMPTY_RELATIONSHIPS, sheet::WorkSheet, styles::EMPTY_STYLES},
+        },
+        zip::writer::ZipWriter,
+    };
 
     #[derive(Debug)]
     struct XLSXOperatorMock<'a> {
@@ -99,12 +324,27 @@ mod excel_tests {
         fn read_workbook(&self) -> &'a str {
             self.workbook
         }
+        fn read_workbook_rels(&self) -> &'a str {
+            EMPTY_RELATIONSHIPS
+        }
         fn read_shared_strings(&self) -> &'a str {
             self.shared_strings
         }
+        fn read_styles(&self) -> &'a str {
+            EMPTY_STYLES
+        }
         fn write_sheet(&self, _e_sheet_name: &str, _content: &str) -> () {
             println!("write")
         }
+        fn write_shared_strings(&self, _content: &str) {
+            println!("write")
+        }
+        fn write_workbook(&self, _content: &str) {
+            println!("write")
+        }
+        fn remove_calc_chain(&self) {}
+        fn set_full_calc_on_load(&self) {}
+        fn shift_lines(&self, _sheet_name: &str, _shift: LineShift) {}
     }
     #[test]
     fn excel_test() {
@@ -220,4 +460,500 @@ mod excel_tests {
 "#;
         let oprator = XLSXOperatorMock::new(sheet1, shared_strings, workbook);
     }
+    #[test]
+    fn save_to_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="term1" sheetId="8" r:id="rId3"/></sheets></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId3" Target="worksheets/sheet1.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("term1");
+        let mut sheet = excel.get_sheet("term1");
+        let cell = ECell::new("2", "A1");
+        sheet.set_cell(&cell);
+        excel.save(sheet);
+        let response = excel.save_to(Vec::new()).unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&response).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("term1");
+        let sheet = excel.get_sheet("term1");
+        assert_eq!(sheet.get_cell("A1"), Some("2".to_string()));
+        let package = XLSXPackage::from_bytes(&response).unwrap();
+        let shared_strings = package.read_shared_strings();
+        assert!(shared_strings.contains(r#"count="1" uniqueCount="1""#));
+        assert!(shared_strings.contains("<si><t>2</t></si>"));
+        let rels =
+            String::from_utf8(package.get_part("xl/_rels/workbook.xml.rels").unwrap()).unwrap();
+        assert!(rels.contains(r#"<Relationship Id="rId4" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="sharedStrings.xml"/></Relationships>"#));
+        assert!(!package.read_workbook().contains("fullCalcOnLoad"));
+    }
+    #[test]
+    fn create_sheet_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="term1" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData/></worksheet>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        package.create_sheet("Summary").unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("Summary");
+        let mut sheet = excel.get_sheet("Summary");
+        let cell = ECell::new("10", "B2");
+        sheet.set_cell(&cell);
+        excel.save(sheet);
+        let response = excel.save_to(Vec::new()).unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&response).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("Summary");
+        let sheet = excel.get_sheet("Summary");
+        assert_eq!(sheet.get_cell("B2"), Some("10".to_string()));
+    }
+    #[test]
+    fn calculate_cell_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="Data" sheetId="1" r:id="rId1"/><sheet name="Summary" sheetId="2" r:id="rId2"/></sheets></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="worksheets/sheet2.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c></row><row r="2"><c r="A2"><v>2</v></c></row><row r="3"><c r="A3"><f>A1+A2</f><v>0</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet2.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><f>SUM(Data!A:A)</f><v>0</v></c><c r="B1"><f>Other!A1</f><v>0</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("Data");
+        excel.read_sheet("Summary");
+        assert_eq!(
+            excel.calculate_cell("Summary", "A1"),
+            CellValue::Number(6.0)
+        );
+        assert_eq!(excel.calculate_cell("Data", "A3"), CellValue::Number(3.0));
+        assert_eq!(
+            excel.calculate_cell("Summary", "B1"),
+            CellValue::Error(CellErrorKind::Ref)
+        );
+    }
+    #[test]
+    fn defined_name_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="Settings" sheetId="1" r:id="rId1"/><sheet name="Sales 2024" sheetId="2" r:id="rId2"/></sheets><definedNames><definedName name="TaxRate">Settings!$B$1</definedName><definedName name="Price" localSheetId="1">$A$1:$A$2</definedName></definedNames></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="worksheets/sheet2.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="B1"><v>0.1</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet2.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>100</v></c><c r="B1"><f>SUM(Price)*TaxRate</f><v>0</v></c></row><row r="2"><c r="A2"><v>200</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        assert_eq!(excel.get_defined_names().len(), 2);
+        let range = |name: &str| {
+            excel
+                .get_named_range(name)
+                .map(|(sheet, range)| (sheet, range.to_string()))
+        };
+        assert_eq!(
+            range("taxrate"),
+            Some(("Settings".to_string(), "B1".to_string()))
+        );
+        assert_eq!(range("Price"), None);
+        assert_eq!(
+            range("'Sales 2024'!Price"),
+            Some(("Sales 2024".to_string(), "A1:A2".to_string()))
+        );
+        excel.read_sheet("Settings");
+        excel.read_sheet("Sales 2024");
+        assert_eq!(
+            excel.calculate_cell("Sales 2024", "B1"),
+            CellValue::Number(30.0)
+        );
+    }
+    #[test]
+    fn recalculate_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="Data" sheetId="1" r:id="rId1"/><sheet name="Summary" sheetId="2" r:id="rId2"/></sheets></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="worksheets/sheet2.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c><c r="B1"><f>B1</f><v>0</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet2.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><f>Data!A1*2</f><v>2</v></c><c r="B1" t="str"><f>IF(A1&gt;5,"big","small")</f><v>small</v></c><c r="C1"><f>1+1</f><v>99</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("Data");
+        excel.read_sheet("Summary");
+        let mut sheets = vec![excel.get_sheet("Data"), excel.get_sheet("Summary")];
+        let cell = ECell::new("5", "A1");
+        sheets[0].set_cell(&cell);
+        let recalculated = excel.recalculate(&mut sheets).unwrap();
+        assert_eq!(
+            recalculated,
+            vec![
+                CellAddress::from_index("Summary", "A1"),
+                CellAddress::from_index("Summary", "B1")
+            ]
+        );
+        let summary = sheets[1].to_xml();
+        assert!(summary.contains(r#"<c r="A1"><f>Data!A1*2</f><v>10</v></c>"#));
+        assert!(
+            summary.contains(r#"<c r="B1" t="str"><f>IF(A1&gt;5,"big","small")</f><v>big</v></c>"#)
+        );
+        assert!(summary.contains(r#"<c r="C1"><f>1+1</f><v>99</v></c>"#));
+        assert!(sheets[0].get_changed_cells().is_empty());
+
+        sheets[0].set_formula("B1", "=B1+Summary!C1");
+        let circular = excel.recalculate(&mut sheets).unwrap_err();
+        assert_eq!(
+            circular.cycles,
+            vec![vec![CellAddress::from_index("Data", "B1")]]
+        );
+    }
+    #[test]
+    fn shift_lines_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="Data" sheetId="1" r:id="rId1"/><sheet name="Summary" sheetId="2" r:id="rId2"/></sheets><definedNames><definedName name="Values">Data!$A$1:$A$2</definedName></definedNames></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Target="worksheets/sheet2.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c></row><row r="2"><c r="A2"><v>2</v></c></row><row r="3"><c r="A3"><f>SUM(A1:A2)</f><v>3</v></c></row></sheetData><tableParts count="1"><tablePart r:id="rId1"/></tableParts></worksheet>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/_rels/sheet1.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/table" Target="../tables/table1.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/tables/table1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<table id="1" name="Values1" displayName="Values1" ref="A1:A2"><autoFilter ref="A1:A2"/><tableColumns count="1"><tableColumn id="1" name="1"/></tableColumns></table>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet2.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><f>Data!A3*2</f><v>6</v></c><c r="A2"><f>A3</f><v>0</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("Data");
+        excel.read_sheet("Summary");
+        let mut sheets = vec![excel.get_sheet("Data"), excel.get_sheet("Summary")];
+        excel.shift_lines(
+            &mut sheets,
+            "data",
+            LineShift::InsertRows { at: 2, count: 1 },
+        );
+        assert_eq!(sheets[0].get_formula("A4").unwrap().text, "SUM(A1:A3)");
+        assert_eq!(sheets[1].get_formula("A1").unwrap().text, "Data!A4*2");
+        assert_eq!(sheets[1].get_formula("A2").unwrap().text, "A3");
+        assert!(sheets[1].is_formula_changed());
+        excel.shift_lines(
+            &mut sheets,
+            "Data",
+            LineShift::DeleteRows { at: 4, count: 1 },
+        );
+        assert_eq!(sheets[1].get_formula("A1").unwrap().text, "Data!#REF!*2");
+        excel.save(sheets.remove(1));
+        excel.save(sheets.remove(0));
+        let response = excel.save_to(Vec::new()).unwrap();
+        let package = XLSXPackage::from_bytes(&response).unwrap();
+        let workbook = package.read_workbook();
+        assert!(workbook.contains(r#"<definedName name="Values">Data!$A$1:$A$3</definedName>"#));
+        assert!(workbook.contains("fullCalcOnLoad"));
+        let table = String::from_utf8(package.get_part("xl/tables/table1.xml").unwrap()).unwrap();
+        assert!(table.contains(r#"ref="A1:A3"><autoFilter ref="A1:A3"/>"#));
+    }
+    #[test]
+    fn save_formula_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="term1" sheetId="8" r:id="rId3"/></sheets><calcPr calcId="191029"/></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId3" Target="worksheets/sheet1.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c><c r="B1"><f>A1*2</f><v>2</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("term1");
+        let mut sheet = excel.get_sheet("term1");
+        sheet.set_formula("B1", "=A1*3");
+        excel.save(sheet);
+        let response = excel.save_to(Vec::new()).unwrap();
+
+        let package = XLSXPackage::from_bytes(&response).unwrap();
+        let sheet =
+            String::from_utf8(package.get_part("xl/worksheets/sheet1.xml").unwrap()).unwrap();
+        assert!(sheet.contains(r#"<c r="B1"><f>A1*3</f></c>"#));
+        assert!(package
+            .read_workbook()
+            .contains(r#"<calcPr calcId="191029" fullCalcOnLoad="1"/>"#));
+    }
+    #[test]
+    fn save_calc_chain_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "[Content_Types].xml",
+                br#"<Types><Override PartName="/xl/workbook.xml" ContentType="workbook"/><Override PartName="/xl/calcChain.xml" ContentType="calcChain"/></Types>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="term1" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/calcChain" Target="calcChain.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c><c r="B1"><f>A1*2</f><v>2</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/calcChain.xml",
+                br#"<calcChain><c r="B1" i="1"/></calcChain>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("term1");
+        let mut sheet = excel.get_sheet("term1");
+        let cell = ECell::new("5", "A1");
+        sheet.set_cell(&cell);
+        excel.save(sheet);
+        let response = excel.save_to(Vec::new()).unwrap();
+        let package = XLSXPackage::from_bytes(&response).unwrap();
+        assert!(package.get_part("xl/calcChain.xml").is_some());
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("term1");
+        let mut sheet = excel.get_sheet("term1");
+        let cell = ECell::new("x", "B1");
+        sheet.set_cell(&cell);
+        excel.save(sheet);
+        let response = excel.save_to(Vec::new()).unwrap();
+        let package = XLSXPackage::from_bytes(&response).unwrap();
+        assert!(package.get_part("xl/calcChain.xml").is_none());
+        assert!(!package.read_workbook_rels().contains("calcChain"));
+        let content_types =
+            String::from_utf8(package.get_part("[Content_Types].xml").unwrap()).unwrap();
+        assert!(!content_types.contains("calcChain"));
+        assert!(package.read_workbook().contains("fullCalcOnLoad"));
+    }
+    #[test]
+    fn recalculate_unsupported_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry(
+                "xl/workbook.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook><sheets><sheet name="term1" sheetId="1" r:id="rId1"/></sheets></workbook>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/_rels/workbook.xml.rels",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/calcChain" Target="calcChain.xml"/></Relationships>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/worksheets/sheet1.xml",
+                br#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData><row r="1"><c r="A1"><v>1</v></c><c r="B1"><f>ROW()+A1</f><v>2</v></c><c r="C1"><f>B1*2</f><v>4</v></c><c r="D1"><f>IFERROR(OFFSET(A1,0,0),5)</f><v>1</v></c><c r="E1"><f>A1*3</f><v>3</v></c></row></sheetData></worksheet>"#,
+            )
+            .unwrap();
+        writer
+            .add_entry(
+                "xl/calcChain.xml",
+                br#"<calcChain><c r="B1" i="1"/></calcChain>"#,
+            )
+            .unwrap();
+        let upload = writer.finish().unwrap();
+
+        let mut package = XLSXPackage::from_bytes(&upload).unwrap();
+        let mut excel = Excel::new(&mut package);
+        excel.read_sheet("term1");
+        let mut sheets = vec![excel.get_sheet("term1")];
+        let cell = ECell::new("7", "A1");
+        sheets[0].set_cell(&cell);
+        let recalculated = excel.recalculate(&mut sheets).unwrap();
+        assert_eq!(recalculated, vec![CellAddress::from_index("term1", "E1")]);
+        let xml = sheets[0].to_xml();
+        assert!(xml.contains(r#"<c r="B1"><f>ROW()+A1</f><v>2</v></c>"#));
+        assert!(xml.contains(r#"<c r="C1"><f>B1*2</f><v>4</v></c>"#));
+        assert!(xml.contains(r#"<c r="D1"><f>IFERROR(OFFSET(A1,0,0),5)</f><v>1</v></c>"#));
+        assert!(xml.contains(r#"<c r="E1"><f>A1*3</f><v>21</v></c>"#));
+        assert!(sheets[0].is_full_calc_needed());
+        assert!(!sheets[0].is_formula_changed());
+
+        excel.save(sheets.remove(0));
+        let response = excel.save_to(Vec::new()).unwrap();
+        let package = XLSXPackage::from_bytes(&response).unwrap();
+        assert!(package.read_workbook().contains("fullCalcOnLoad"));
+        assert!(package.get_part("xl/calcChain.xml").is_some());
+    }
 }
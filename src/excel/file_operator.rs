@@ -1,24 +1,69 @@
 use std::{
+    cell::{Cell, RefCell},
     collections::HashMap,
-    fs::{File, OpenOptions},
-    io::{BufReader, BufWriter, Read, Write},
+    fs::{self, File, OpenOptions},
+    io::{self, BufReader, BufWriter, Read, Write},
     path::{Path, PathBuf},
-    process::Command,
+    process::{self, Command},
+};
+
+use crate::{
+    xml::{
+        escape::{escape, unescape},
+        nodes::{node::XMLNode, node_type::NodeType},
+    },
+    zip::{archive::ZipArchive, error::ZipError, writer::ZipWriter},
+};
+
+use super::{
+    cells::{
+        cell::ColumnAlphabet,
+        cell_range::RangeArea,
+        cell_value::CellValue,
+        formula::{remove_sheet, rename_sheet},
+        line_shift::LineShift,
+    },
+    xmls::{
+        defined_name::DefinedNameError,
+        relationships::{relative_part_path, Relationships, EMPTY_RELATIONSHIPS},
+        shared_strings::{SharedStrings, SharedStringsInterface},
+        sheet::{Sheet, WorkSheet},
+        styles::EMPTY_STYLES,
+        table::{
+            is_valid_name, new_table_xml, resize_table_xml, shift_table_xml, Table, TableError,
+            TABLE_CONTENT_TYPE, TABLE_TYPE,
+        },
+        workbook::{SheetError, WorkBook, CALC_CHAIN_TYPE, WORKBOOK_PART},
+    },
 };
 
 pub trait XLSXOperator<'a> {
     fn to_excel(&self) -> ();
     fn read_sheet(&'a self, e_sheet_name: &str) -> String;
     fn read_workbook(&'a self) -> &'a str;
+    fn read_workbook_rels(&'a self) -> &'a str;
     fn read_shared_strings(&'a self) -> &'a str;
+    fn read_styles(&'a self) -> &'a str;
     fn add_sheet(&mut self, e_sheet_name: &str) -> ();
     fn write_sheet(&self, e_sheet_name: &str, content: &str) -> ();
+    fn write_shared_strings(&self, content: &str) -> ();
+    fn write_workbook(&self, content: &str) -> ();
+    /// Removes the calculation chain with its workbook relationship and content type,
+    /// after formula cells are written, removed or moved. Excel builds it again on open.
+    fn remove_calc_chain(&self) -> ();
+    /// Marks the workbook as written so far to be calculated in full when Excel opens it.
+    fn set_full_calc_on_load(&self) -> ();
+    /// Moves the references to the sheet in the defined names, and the tables of the sheet,
+    /// with the lines inserted or deleted in it.
+    fn shift_lines(&self, sheet_name: &str, shift: LineShift) -> ();
 }
 pub struct XLSXFile<'a> {
     filename: &'a Path,
     zip_name: PathBuf,
     workbook: String,
+    workbook_rels: String,
     shared_strings: String,
+    styles: String,
     sheets: HashMap<String, String>,
 }
 
@@ -29,12 +74,16 @@ impl<'a> XLSXFile<'a> {
         to_zip(filename, &zip_name);
         decompress(&zip_name);
         let workbook = XLSXFile::read_workbook_file();
+        let workbook_rels = XLSXFile::read_file(XLSXFile::workbook_rels_path());
         let shared_strings = XLSXFile::read_shared_strings_file();
+        let styles = XLSXFile::read_file(XLSXFile::styles_path());
         XLSXFile {
             filename,
             zip_name,
             workbook,
+            workbook_rels,
             shared_strings,
+            styles,
             sheets: HashMap::new(),
         }
     }
@@ -59,12 +108,18 @@ impl<'a> XLSXFile<'a> {
     fn workbook_path() -> &'a str {
         "./xl/workbook.xml"
     }
+    fn workbook_rels_path() -> &'a str {
+        "./xl/_rels/workbook.xml.rels"
+    }
     fn sheet_path(e_sheet_name: &str) -> String {
-        format!("./xl/worksheets/{}.xml", e_sheet_name)
+        format!("./{}", e_sheet_name)
     }
     fn shared_strings_path() -> &'a str {
         "./xl/sharedStrings.xml"
     }
+    fn styles_path() -> &'a str {
+        "./xl/styles.xml"
+    }
     fn read_sheet_file(e_sheet_name: &str) -> String {
         XLSXFile::read_file(XLSXFile::sheet_path(e_sheet_name).as_str())
     }
@@ -141,10 +196,1688 @@ impl<'a> XLSXOperator<'a> for XLSXFile<'a> {
     fn read_shared_strings(&'a self) -> &'a str {
         &self.shared_strings
     }
+    fn read_styles(&'a self) -> &'a str {
+        &self.styles
+    }
     fn read_workbook(&'a self) -> &'a str {
         &self.workbook
     }
+    fn read_workbook_rels(&'a self) -> &'a str {
+        &self.workbook_rels
+    }
     fn write_sheet(&self, e_sheet_name: &str, content: &str) -> () {
         self.write_file(XLSXFile::sheet_path(e_sheet_name).as_str(), content);
     }
+    fn write_shared_strings(&self, content: &str) {
+        self.write_file(XLSXFile::shared_strings_path(), content);
+    }
+    fn write_workbook(&self, content: &str) {
+        self.write_file(XLSXFile::workbook_path(), content);
+    }
+    fn remove_calc_chain(&self) {
+        let mut workbook = WorkBook::new(&self.workbook, &self.workbook_rels);
+        let calc_chains = workbook.remove_relationships(CALC_CHAIN_TYPE);
+        if calc_chains.is_empty() {
+            return;
+        }
+        // write_file does not truncate, and the relationships get shorter
+        let _ = fs::write(
+            XLSXFile::workbook_rels_path(),
+            workbook.get_relationships().to_xml(),
+        );
+        let content_types_path = format!("./{}", CONTENT_TYPES_PART);
+        let mut content_types = XLSXFile::read_file(&content_types_path);
+        for calc_chain in calc_chains {
+            let _ = fs::remove_file(format!("./{}", calc_chain));
+            if let Some((start, end)) = find_override(&content_types, &calc_chain) {
+                content_types.replace_range(start..end, "");
+            }
+        }
+        let _ = fs::write(content_types_path, content_types);
+    }
+    fn set_full_calc_on_load(&self) {
+        let source = XLSXFile::read_workbook_file();
+        let workbook = WorkBook::new(&source, &self.workbook_rels);
+        // write_file does not truncate
+        let _ = fs::write(XLSXFile::workbook_path(), workbook.to_xml_with_full_calc());
+    }
+    fn shift_lines(&self, sheet_name: &str, shift: LineShift) {
+        let source = XLSXFile::read_workbook_file();
+        let mut workbook = WorkBook::new(&source, &self.workbook_rels);
+        if workbook.shift_lines(sheet_name, shift) {
+            let _ = fs::write(XLSXFile::workbook_path(), workbook.to_xml());
+        }
+        let Some(index) = workbook.get_sheet_index(sheet_name) else {
+            return;
+        };
+        let e_sheet_name = workbook.get_excel_sheet_name(&workbook.get_sheet_names()[index]);
+        let rels_path = format!("./{}", get_rels_part(e_sheet_name));
+        if !Path::new(&rels_path).exists() {
+            return;
+        }
+        let rels_source = XLSXFile::read_file(&rels_path);
+        let relationships = Relationships::new(&rels_source, e_sheet_name);
+        for rel in relationships.get_relationships() {
+            let Some(table_part) = relationships
+                .get_target_part(rel.get_id())
+                .filter(|_| rel.get_type() == TABLE_TYPE)
+            else {
+                continue;
+            };
+            let table_path = format!("./{}", table_part);
+            let source = XLSXFile::read_file(&table_path);
+            let xml = shift_table_xml(&source, shift);
+            if xml != source {
+                // write_file does not truncate, and the table may get shorter
+                let _ = fs::write(table_path, xml);
+            }
+        }
+    }
+}
+
+/// Appends `child` as the last child of the root element `root`, which may be self-closing.
+fn append_child(xml: &str, root: &str, child: &str) -> String {
+    let end_tag = format!("</{}>", root);
+    if xml.contains(&end_tag) {
+        return xml.replacen(&end_tag, &format!("{}{}", child, end_tag), 1);
+    }
+    let Some(close) = xml
+        .find(&format!("<{}", root))
+        .and_then(|start| xml[start..].find("/>").map(|close| start + close))
+    else {
+        return xml.to_string();
+    };
+    format!(
+        "{}>{}{}{}",
+        &xml[..close],
+        child,
+        end_tag,
+        &xml[close + 2..]
+    )
+}
+
+/// Lists the table of the relationship `id` in `<tableParts>` of the worksheet, leaving the rest of the text as it is.
+/// A new `<tableParts>` goes before the `<extLst>` of the worksheet, and `xmlns:r` is declared when missing.
+///
+///     let sheet = add_table_part(r#"<worksheet><sheetData/></worksheet>"#, "rId1");
+///     // <worksheet xmlns:r="..."><sheetData/><tableParts count="1"><tablePart r:id="rId1"/></tableParts></worksheet>
+///
+fn add_table_part(sheet: &str, id: &str) -> String {
+    let table_part = format!(r#"<tablePart r:id="{}"/>"#, id);
+    let mut table_parts: Vec<&str> = sheet
+        .match_indices("<tablePart ")
+        .filter_map(|(start, _)| {
+            sheet[start..]
+                .find("/>")
+                .map(|end| &sheet[start..start + end + 2])
+        })
+        .collect();
+    table_parts.push(&table_part);
+    let element = format!(
+        r#"<tableParts count="{}">{}</tableParts>"#,
+        table_parts.len(),
+        table_parts.concat()
+    );
+    let xml = if let Some(start) = sheet.find("<tableParts") {
+        let open_end = start + sheet[start..].find('>').unwrap_or_default() + 1;
+        let end = if sheet[..open_end].ends_with("/>") {
+            open_end
+        } else {
+            sheet[start..]
+                .find("</tableParts>")
+                .map_or(open_end, |end| start + end + "</tableParts>".len())
+        };
+        format!("{}{}{}", &sheet[..start], element, &sheet[end..])
+    } else if let Some(start) = find_worksheet_ext_list(sheet) {
+        format!("{}{}{}", &sheet[..start], element, &sheet[start..])
+    } else {
+        append_child(sheet, "worksheet", &element)
+    };
+    let Some(start) = xml.find("<worksheet") else {
+        return xml;
+    };
+    let open_end = start + xml[start..].find('>').unwrap_or_default();
+    if xml[start..open_end].contains("xmlns:r=") {
+        return xml;
+    }
+    let name_end = start + "<worksheet".len();
+    format!(
+        r#"{} xmlns:r="{}"{}"#,
+        &xml[..name_end],
+        RELATIONSHIPS_NAMESPACE,
+        &xml[name_end..]
+    )
+}
+/// Start of the `<extLst>` closing the worksheet, passing over the `<extLst>` nested in it.
+fn find_worksheet_ext_list(sheet: &str) -> Option<usize> {
+    let end = sheet.rfind("</worksheet>")?;
+    let before_end = sheet[..end].trim_end();
+    if before_end.ends_with("<extLst/>") {
+        return before_end.rfind("<extLst/>");
+    }
+    if !before_end.ends_with("</extLst>") {
+        return None;
+    }
+    let mut depth = 0;
+    let mut position = before_end.len();
+    while let Some(found) = sheet[..position].rfind("extLst") {
+        position = found;
+        if sheet[..found].ends_with("</") {
+            depth += 1;
+        } else if sheet[..found].ends_with('<') && !is_self_closing(&sheet[found..]) {
+            depth -= 1;
+            if depth == 0 {
+                return Some(found - 1);
+            }
+        }
+    }
+    None
+}
+fn is_self_closing(tag: &str) -> bool {
+    tag.find('>').is_some_and(|end| tag[..end].ends_with('/'))
+}
+
+/// Start and end of the `<Override>` element of the part in `[Content_Types].xml`.
+fn find_override(content_types: &str, part: &str) -> Option<(usize, usize)> {
+    let name = content_types.find(&format!(r#"PartName="/{}""#, part))?;
+    let start = content_types[..name].rfind("<Override")?;
+    let end = name + content_types[name..].find("/>")? + 2;
+    Some((start, end))
+}
+/// Splits a part name around its number, `xl/drawings/drawing1.xml` to `xl/drawings/drawing` and `.xml`.
+fn split_part_number(part: &str) -> (&str, &str) {
+    let extension_start = part.rfind('.').unwrap_or(part.len());
+    let stem = part[..extension_start].trim_end_matches(|c: char| c.is_ascii_digit());
+    (stem, &part[extension_start..])
+}
+/// Path of the `.rels` part holding the relationships of `part`.
+///
+///     assert_eq!(get_rels_part("xl/worksheets/sheet1.xml"), "xl/worksheets/_rels/sheet1.xml.rels");
+///
+fn get_rels_part(part: &str) -> String {
+    match part.rsplit_once('/') {
+        Some((dir, name)) => format!("{}/_rels/{}.rels", dir, name),
+        None => format!("_rels/{}.rels", part),
+    }
+}
+/// The part owning the relationships of a `.rels` part, the inverse of `get_rels_part`.
+fn get_rels_source_part(rels_part: &str) -> Option<String> {
+    let (dir, name) = rels_part.rsplit_once("_rels/")?;
+    Some(format!("{}{}", dir, name.strip_suffix(".rels")?))
+}
+/// Text of the header cells, the first row of `area`, which are to be text cells unique regardless of case.
+fn get_table_column_names<'a, S: SharedStringsInterface<'a>>(
+    sheet: &Sheet<'a, S>,
+    area: &RangeArea,
+) -> Result<Vec<String>, TableError> {
+    let mut names: Vec<String> = Vec::new();
+    for column in area.get_first_column()..=area.get_last_column() {
+        let cell_index = format!(
+            "{}{}",
+            ColumnAlphabet::from_number(column),
+            area.get_first_row()
+        );
+        let name = match sheet.get_cell_value(&cell_index) {
+            CellValue::String(text) | CellValue::InlineString(text) => text,
+            _ => return Err(TableError::InvalidHeader(cell_index)),
+        };
+        if name.trim().is_empty()
+            || names
+                .iter()
+                .any(|used| used.to_lowercase() == name.to_lowercase())
+        {
+            return Err(TableError::InvalidHeader(cell_index));
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+/// Rewrites the `<c:f>` formulas a chart reads its data with.
+fn rewrite_chart_formulas(xml: &str, f: impl Fn(&str) -> String) -> String {
+    rewrite_element_texts(xml, &["c:f"], f)
+}
+/// Rewrites the formulas of the cells, conditional formats and data validations of a worksheet.
+/// Shared formulas are rewritten in the cell holding them, as sheet names do not depend on the cell.
+fn rewrite_sheet_formulas(xml: &str, f: impl Fn(&str) -> String) -> String {
+    rewrite_element_texts(xml, &["f", "formula", "formula1", "formula2", "xm:f"], f)
+}
+/// Writes `f` of the text of every element named one of `tags`, leaving the rest of `xml`
+/// and the texts `f` keeps as they are.
+fn rewrite_element_texts(xml: &str, tags: &[&str], f: impl Fn(&str) -> String) -> String {
+    let mut result = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find('<') {
+        let tag = tags.iter().find(|tag| {
+            rest[start + 1..]
+                .strip_prefix(**tag)
+                .is_some_and(|after| after.starts_with(['>', '/', ' ', '\t', '\r', '\n']))
+        });
+        let Some(tag) = tag else {
+            result.push_str(&rest[..start + 1]);
+            rest = &rest[start + 1..];
+            continue;
+        };
+        let Some(text_start) = rest[start..].find('>').map(|end| start + end + 1) else {
+            break;
+        };
+        // shared formulas are written by their first cell only
+        if rest[..text_start].ends_with("/>") {
+            result.push_str(&rest[..text_start]);
+            rest = &rest[text_start..];
+            continue;
+        }
+        let Some(length) = rest[text_start..].find(&format!("</{}>", tag)) else {
+            break;
+        };
+        let text = &rest[text_start..text_start + length];
+        let formula = unescape(text);
+        let rewritten = f(&formula);
+        result.push_str(&rest[..text_start]);
+        if rewritten == formula {
+            result.push_str(text);
+        } else {
+            result.push_str(&escape(&rewritten));
+        }
+        rest = &rest[text_start + length..];
+    }
+    result.push_str(rest);
+    result
+}
+
+const CONTENT_TYPES_PART: &str = "[Content_Types].xml";
+const RELATIONSHIPS_NAMESPACE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships";
+const APP_PART: &str = "docProps/app.xml";
+/// Headings of the worksheets in `docProps/app.xml`, written in the language of Excel.
+const WORKSHEETS_HEADINGS: [&str; 2] = ["Worksheets", "ワークシート"];
+/// Parts a copied sheet shares with its source instead of copying them.
+const SHARED_PART_DIRS: [&str; 3] = ["xl/media/", "xl/pivotCache/", "xl/externalLinks/"];
+const WORKSHEET_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml";
+const EMPTY_WORKSHEET: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheetData></sheetData></worksheet>"#;
+const EMPTY_CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"></Types>"#;
+const EMPTY_SHARED_STRINGS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="0" uniqueCount="0"></sst>"#;
+
+/// XLSXOperator that reads every part of the package straight from the archive.
+///
+/// Unlike `XLSXFile`, nothing is extracted to the working directory, the only
+/// file touched is the workbook itself.
+/// Saving writes a complete new archive next to it and renames it over the
+/// original, so the workbook is never left half-written.
+/// A package built from bytes or a reader has no file behind it and is
+/// emitted with `write_to` instead.
+pub struct XLSXPackage<'a> {
+    filename: Option<&'a Path>,
+    workbook: String,
+    workbook_rels: String,
+    shared_strings: String,
+    styles: String,
+    parts: RefCell<Vec<(String, Vec<u8>)>>,
+    is_changed: Cell<bool>,
+}
+impl<'a> XLSXPackage<'a> {
+    pub fn open(filename: &'a str) -> Self {
+        let source =
+            fs::read(filename).unwrap_or_else(|e| panic!("{} is not found: {}", filename, e));
+        let mut package = XLSXPackage::from_bytes(&source)
+            .unwrap_or_else(|e| panic!("{} is not xlsx: {}", filename, e));
+        package.filename = Some(Path::new(filename));
+        package
+    }
+    /// Reads the package from the bytes of an xlsx file, e.g. an upload.
+    /// `Excel` borrows the package, so it is built from the package in a second step.
+    ///
+    ///     let mut package = XLSXPackage::from_bytes(&upload)?;
+    ///     let mut excel = Excel::new(&mut package);
+    ///
+    pub fn from_bytes(source: &[u8]) -> Result<Self, ZipError> {
+        let archive = ZipArchive::new(source)?;
+        let parts = archive
+            .get_entries()
+            .iter()
+            .filter(|entry| !entry.get_name().ends_with('/'))
+            .map(|entry| Ok((entry.get_name().to_string(), archive.read_entry(entry)?)))
+            .collect::<Result<Vec<_>, ZipError>>()?;
+        let workbook = XLSXPackage::find_part_text(&parts, XLSXPackage::workbook_path())
+            .ok_or_else(|| ZipError::NotFoundEntry(XLSXPackage::workbook_path().to_string()))?;
+        let workbook_rels = XLSXPackage::find_part_text(&parts, XLSXPackage::workbook_rels_path())
+            .unwrap_or_else(|| EMPTY_RELATIONSHIPS.to_string());
+        let shared_strings =
+            XLSXPackage::find_part_text(&parts, XLSXPackage::shared_strings_path())
+                .unwrap_or_else(|| EMPTY_SHARED_STRINGS.to_string());
+        let styles = XLSXPackage::find_part_text(&parts, XLSXPackage::styles_path())
+            .unwrap_or_else(|| EMPTY_STYLES.to_string());
+        Ok(XLSXPackage {
+            filename: None,
+            workbook,
+            workbook_rels,
+            shared_strings,
+            styles,
+            parts: RefCell::new(parts),
+            is_changed: Cell::new(false),
+        })
+    }
+    /// Reads the archive from the current position of `reader` to its end.
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut source = Vec::new();
+        reader.read_to_end(&mut source)?;
+        XLSXPackage::from_bytes(&source).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+    /// Writes the whole package as a zip archive and returns the writer.
+    pub fn write_to<W: Write>(&self, writer: W) -> io::Result<W> {
+        let mut writer = ZipWriter::new(writer);
+        for (name, data) in self.parts.borrow().iter() {
+            writer.add_entry(name, data)?;
+        }
+        writer.finish()
+    }
+    /// Writes every part into a temporary file and atomically renames it over the workbook.
+    pub fn save(&self) -> io::Result<()> {
+        let filename = self.filename.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "package is not opened from a file, use write_to",
+            )
+        })?;
+        let temp_path = XLSXPackage::temp_path(filename);
+        let result = self
+            .write_temp(&temp_path)
+            .and_then(|_| fs::rename(&temp_path, filename));
+        if result.is_err() {
+            let _ = fs::remove_file(&temp_path);
+        } else {
+            self.is_changed.set(false);
+        }
+        result
+    }
+    fn write_temp(&self, temp_path: &Path) -> io::Result<()> {
+        let writer = self.write_to(BufWriter::new(File::create(temp_path)?))?;
+        let file = writer.into_inner().map_err(|e| e.into_error())?;
+        file.sync_all()
+    }
+    fn temp_path(filename: &Path) -> PathBuf {
+        let name = filename
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        filename.with_file_name(format!(".{}.{}.tmp", name, process::id()))
+    }
+    pub fn get_part_names(&self) -> Vec<String> {
+        self.parts
+            .borrow()
+            .iter()
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+    pub fn get_part(&self, name: &str) -> Option<Vec<u8>> {
+        self.parts
+            .borrow()
+            .iter()
+            .find(|(part_name, _)| part_name == name)
+            .map(|(_, data)| data.clone())
+    }
+    fn set_part(&self, name: &str, data: Vec<u8>) {
+        let mut parts = self.parts.borrow_mut();
+        if let Some(part) = parts.iter_mut().find(|(part_name, _)| part_name == name) {
+            part.1 = data;
+        } else {
+            parts.push((name.to_string(), data));
+        }
+        self.is_changed.set(true);
+    }
+    /// A workbook without shared strings needs the content type and the workbook relationship of the new part.
+    fn register_shared_strings(&self) {
+        self.add_content_type(
+            XLSXPackage::shared_strings_path(),
+            "application/vnd.openxmlformats-officedocument.spreadsheetml.sharedStrings+xml",
+        );
+
+        let rels = self
+            .get_part(XLSXPackage::workbook_rels_path())
+            .map(|data| String::from_utf8_lossy(&data).into_owned())
+            .unwrap_or_else(|| EMPTY_RELATIONSHIPS.to_string());
+        let next_id = rels
+            .match_indices(r#"Id="rId"#)
+            .filter_map(|(i, pattern)| {
+                rels[i + pattern.len()..]
+                    .split('"')
+                    .next()
+                    .and_then(|id| id.parse::<usize>().ok())
+            })
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let relationship = format!(
+            r#"<Relationship Id="rId{}" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/sharedStrings" Target="sharedStrings.xml"/>"#,
+            next_id
+        );
+        let rels = append_child(&rels, "Relationships", &relationship);
+        self.set_part(XLSXPackage::workbook_rels_path(), rels.into_bytes());
+    }
+    /// Sheet names in the order of the tabs.
+    pub fn get_sheet_names(&self) -> Vec<String> {
+        WorkBook::new(&self.workbook, &self.workbook_rels).get_sheet_names()
+    }
+    /// Adds an empty worksheet after the last tab and returns its part, e.g. `xl/worksheets/sheet4.xml`.
+    /// Sheets are added, renamed, moved and deleted before the package is passed to `Excel::new`.
+    ///
+    ///     let mut package = XLSXPackage::open("report.xlsx");
+    ///     package.create_sheet("Summary")?;
+    ///     let mut excel = Excel::new(&mut package);
+    ///     excel.read_sheet("Summary");
+    ///
+    pub fn create_sheet(&mut self, sheet_name: &str) -> Result<String, SheetError> {
+        let part = self.get_free_part_name("xl/worksheets/sheet", ".xml");
+        self.edit_workbook(|workbook| workbook.add_sheet(sheet_name, &part))?;
+        self.set_part(&part, EMPTY_WORKSHEET.as_bytes().to_vec());
+        self.add_content_type(&part, WORKSHEET_CONTENT_TYPE);
+        self.update_app_properties();
+        Ok(part)
+    }
+    /// Deletes the sheet with its part and the parts only it uses, e.g. its drawings.
+    /// References to it in the formulas of the other sheets, charts and defined names become `#REF!`.
+    pub fn delete_sheet(&mut self, sheet_name: &str) -> Result<(), SheetError> {
+        let (part, calc_chains) = self.edit_workbook(|workbook| {
+            let part = workbook.remove_sheet(sheet_name)?;
+            // the calculation chain lists cells of the sheet, Excel builds it again without one
+            Ok((part, workbook.remove_relationships(CALC_CHAIN_TYPE)))
+        })?;
+        calc_chains
+            .iter()
+            .for_each(|calc_chain| self.remove_part_tree(calc_chain));
+        self.remove_part_tree(&part);
+        self.rewrite_sheet_references(sheet_name, None);
+        self.update_app_properties();
+        Ok(())
+    }
+    /// Renames the sheet, and the references to it in the formulas of every sheet, chart and defined name.
+    pub fn rename_sheet(&mut self, sheet_name: &str, new_name: &str) -> Result<(), SheetError> {
+        self.edit_workbook(|workbook| workbook.rename_sheet(sheet_name, new_name))?;
+        self.rewrite_sheet_references(sheet_name, Some(new_name));
+        self.update_app_properties();
+        Ok(())
+    }
+    /// Moves the tab of the sheet to `index`, 0 being the first tab.
+    pub fn move_sheet(&mut self, sheet_name: &str, index: usize) -> Result<(), SheetError> {
+        self.edit_workbook(|workbook| workbook.move_sheet(sheet_name, index))?;
+        self.update_app_properties();
+        Ok(())
+    }
+    /// Copies the sheet to a new tab `new_name` right after it, and returns the part of the copy.
+    /// Drawings, charts, comments and tables of the sheet are copied too, images are shared.
+    /// Charts of the copy read the copy, as in Excel.
+    pub fn duplicate_sheet(
+        &mut self,
+        sheet_name: &str,
+        new_name: &str,
+    ) -> Result<String, SheetError> {
+        let part = self.get_free_part_name("xl/worksheets/sheet", ".xml");
+        let mut source = (String::new(), String::new());
+        self.edit_workbook(|workbook| {
+            let index = workbook
+                .get_sheet_index(sheet_name)
+                .ok_or_else(|| SheetError::NotFound(sheet_name.to_string()))?;
+            let name = workbook.get_sheet_names().remove(index);
+            source = (workbook.get_excel_sheet_name(&name).to_string(), name);
+            workbook.add_sheet(new_name, &part)?;
+            workbook.move_sheet(new_name, index + 1)?;
+            workbook.copy_local_names(sheet_name, new_name)
+        })?;
+        let (source_part, source_name) = source;
+        self.copy_part_tree(
+            &source_part,
+            &part,
+            (&source_name, new_name),
+            &mut Vec::new(),
+        );
+        if let Some(sheet) = self.get_part_text(&part) {
+            // only the source stays selected
+            let sheet = sheet.replacen(r#" tabSelected="1""#, "", 1);
+            self.set_part(&part, sheet.into_bytes());
+        }
+        self.update_app_properties();
+        Ok(part)
+    }
+    /// Defines the name for the workbook, or for the sheet `sheet` only, or changes its formula.
+    /// Names of tables are not taken.
+    ///
+    ///     package.set_defined_name("TaxRate", "Settings!$B$1", None)?;
+    ///     let excel = Excel::new(&mut package);
+    ///     assert_eq!(excel.get_named_range("TaxRate").unwrap().0, "Settings");
+    ///
+    pub fn set_defined_name(
+        &mut self,
+        name: &str,
+        formula: &str,
+        sheet: Option<&str>,
+    ) -> Result<(), DefinedNameError> {
+        if self.get_table(name).is_some() {
+            return Err(DefinedNameError::DuplicateName(name.to_string()));
+        }
+        self.edit_workbook(|workbook| workbook.set_defined_name(name, formula, sheet))
+    }
+    /// Deletes the name of the workbook, or the one of the sheet `sheet`.
+    pub fn remove_defined_name(
+        &mut self,
+        name: &str,
+        sheet: Option<&str>,
+    ) -> Result<(), DefinedNameError> {
+        self.edit_workbook(|workbook| workbook.remove_defined_name(name, sheet))
+    }
+    /// Tables of the sheet, in the order the sheet lists them.
+    pub fn get_tables(&self, sheet_name: &str) -> Vec<Table> {
+        let Ok((sheet_name, part)) = self.get_sheet_part(sheet_name) else {
+            return Vec::new();
+        };
+        let (Some(sheet_source), Some(rels_source)) = (
+            self.get_part_text(&part),
+            self.get_part_text(&get_rels_part(&part)),
+        ) else {
+            return Vec::new();
+        };
+        if !sheet_source.contains("<tableParts") {
+            return Vec::new();
+        }
+        let shared_strings = SharedStrings::new(&self.shared_strings);
+        let sheet = Sheet::new(&sheet_name, &sheet_source, &shared_strings);
+        let relationships = Relationships::new(&rels_source, &part);
+        sheet
+            .get_table_part_ids()
+            .iter()
+            .filter_map(|id| relationships.get_target_part(id))
+            .filter_map(|table_part| {
+                Table::new(&self.get_part_text(&table_part)?, &table_part, &sheet_name)
+            })
+            .collect()
+    }
+    /// The table called `name` in any sheet, regardless of case.
+    ///
+    ///     let table = package.get_table("job_info").unwrap();
+    ///     let mut excel = Excel::new(&mut package);
+    ///     excel.read_sheet(table.get_sheet_name());
+    ///
+    pub fn get_table(&self, name: &str) -> Option<Table> {
+        self.get_all_tables()
+            .into_iter()
+            .find(|table| table.is_named(name))
+    }
+    /// Makes the table `name` over `area` of the sheet and returns it, the first row of the area being its header row.
+    /// The header cells name the columns, so they are to be unique text. The table has the default style of Excel.
+    ///
+    ///     let table = package.create_table("Orders", "OrderList", &RangeArea::new("B2:E20").unwrap())?;
+    ///     assert_eq!(table.get_part(), "xl/tables/table1.xml");
+    ///
+    pub fn create_table(
+        &mut self,
+        sheet_name: &str,
+        name: &str,
+        area: &RangeArea,
+    ) -> Result<Table, TableError> {
+        let (sheet_name, part) = self.get_sheet_part(sheet_name)?;
+        if !is_valid_name(name) {
+            return Err(TableError::InvalidName(name.to_string()));
+        }
+        let tables = self.get_all_tables();
+        if tables.iter().any(|table| table.is_named(name))
+            || WorkBook::new(&self.workbook, &self.workbook_rels).is_name_defined(name)
+        {
+            return Err(TableError::DuplicateName(name.to_string()));
+        }
+        if area.get_row_count() < 2 || area.is_whole_columns() || area.is_whole_rows() {
+            return Err(TableError::InvalidRange(area.to_string()));
+        }
+        self.check_table_overlap(&sheet_name, area, None)?;
+        let sheet_source = self.get_part_text(&part).unwrap_or_default();
+        let shared_strings = SharedStrings::new(&self.shared_strings);
+        let sheet = Sheet::new(&sheet_name, &sheet_source, &shared_strings);
+        let column_names = get_table_column_names(&sheet, area)?;
+
+        let table_id = tables
+            .iter()
+            .map(|table| table.get_id())
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let table_part = self.get_free_part_name("xl/tables/table", ".xml");
+        let table_source = new_table_xml(table_id, name, area, &column_names);
+        self.set_part(&table_part, table_source.as_bytes().to_vec());
+        self.add_content_type(&table_part, TABLE_CONTENT_TYPE);
+        let rels_part = get_rels_part(&part);
+        let rels_source = self
+            .get_part_text(&rels_part)
+            .unwrap_or_else(|| EMPTY_RELATIONSHIPS.to_string());
+        let mut relationships = Relationships::new(&rels_source, &part);
+        let id = relationships.add_relationship(TABLE_TYPE, &table_part);
+        self.set_part(&rels_part, relationships.to_xml().into_bytes());
+        self.set_part(&part, add_table_part(&sheet_source, &id).into_bytes());
+        Table::new(&table_source, &table_part, &sheet_name)
+            .ok_or_else(|| TableError::NotFound(name.to_string()))
+    }
+    /// Moves the table called `name` to `area`, which starts at the header row of the table.
+    /// Columns follow the header cells of the area, keeping their totals and formulas by name.
+    /// A totals row is the last row of the area, its cells are not written.
+    pub fn resize_table(&mut self, name: &str, area: &RangeArea) -> Result<Table, TableError> {
+        let table = self
+            .get_table(name)
+            .ok_or_else(|| TableError::NotFound(name.to_string()))?;
+        let min_row_count = 1 + table.has_header_row() as usize + table.has_totals_row() as usize;
+        if area.get_first_row() != table.get_area().get_first_row()
+            || area.get_row_count() < min_row_count
+            || area.is_whole_columns()
+            || area.is_whole_rows()
+        {
+            return Err(TableError::InvalidRange(area.to_string()));
+        }
+        self.check_table_overlap(table.get_sheet_name(), area, Some(table.get_part()))?;
+        let column_names = if table.has_header_row() {
+            let (sheet_name, part) = self.get_sheet_part(table.get_sheet_name())?;
+            let sheet_source = self.get_part_text(&part).unwrap_or_default();
+            let shared_strings = SharedStrings::new(&self.shared_strings);
+            let sheet = Sheet::new(&sheet_name, &sheet_source, &shared_strings);
+            get_table_column_names(&sheet, area)?
+        } else {
+            (0..area.get_column_count())
+                .map(|i| match table.get_columns().get(i) {
+                    Some(column) => column.get_name().to_string(),
+                    None => format!("Column{}", i + 1),
+                })
+                .collect()
+        };
+        let source = self.get_part_text(table.get_part()).unwrap_or_default();
+        let source = resize_table_xml(&source, area, &column_names);
+        self.set_part(table.get_part(), source.as_bytes().to_vec());
+        Table::new(&source, table.get_part(), table.get_sheet_name())
+            .ok_or_else(|| TableError::NotFound(name.to_string()))
+    }
+    fn get_all_tables(&self) -> Vec<Table> {
+        self.get_sheet_names()
+            .iter()
+            .flat_map(|sheet_name| self.get_tables(sheet_name))
+            .collect()
+    }
+    /// Name and part of the worksheet, regardless of the case of `sheet_name`.
+    fn get_sheet_part(&self, sheet_name: &str) -> Result<(String, String), SheetError> {
+        let workbook = WorkBook::new(&self.workbook, &self.workbook_rels);
+        let not_found = || SheetError::NotFound(sheet_name.to_string());
+        let index = workbook.get_sheet_index(sheet_name).ok_or_else(not_found)?;
+        let name = workbook.get_sheet_names().remove(index);
+        let part = workbook.get_excel_sheet_name(&name).to_string();
+        // chart sheets have no cells for tables
+        match self.get_part_text(&part) {
+            Some(source) if source.contains("<worksheet") => Ok((name, part)),
+            _ => Err(not_found()),
+        }
+    }
+    fn check_table_overlap(
+        &self,
+        sheet_name: &str,
+        area: &RangeArea,
+        except_part: Option<&str>,
+    ) -> Result<(), TableError> {
+        match self
+            .get_tables(sheet_name)
+            .iter()
+            .filter(|table| Some(table.get_part()) != except_part)
+            .find(|table| table.get_area().intersect(area).is_some())
+        {
+            Some(table) => Err(TableError::Overlap(table.get_name().to_string())),
+            None => Ok(()),
+        }
+    }
+    /// Applies `f` to the workbook and writes it and its relationships back.
+    /// Nothing is written when `f` fails.
+    fn edit_workbook<T, E>(
+        &mut self,
+        f: impl FnOnce(&mut WorkBook) -> Result<T, E>,
+    ) -> Result<T, E> {
+        let (source, rels_source) = self.get_current_workbook();
+        let mut workbook = WorkBook::new(&source, &rels_source);
+        let result = f(&mut workbook)?;
+        self.workbook = workbook.to_xml();
+        self.workbook_rels = workbook.get_relationships().to_xml();
+        self.set_part(
+            XLSXPackage::workbook_path(),
+            self.workbook.as_bytes().to_vec(),
+        );
+        self.set_part(
+            XLSXPackage::workbook_rels_path(),
+            self.workbook_rels.as_bytes().to_vec(),
+        );
+        Ok(result)
+    }
+    /// Sources of the workbook and its relationships.
+    fn get_current_workbook(&self) -> (String, String) {
+        // an `Excel` may have written the parts since the package was read
+        let source = self
+            .get_part_text(XLSXPackage::workbook_path())
+            .unwrap_or_else(|| self.workbook.clone());
+        let rels_source = self
+            .get_part_text(XLSXPackage::workbook_rels_path())
+            .unwrap_or_else(|| self.workbook_rels.clone());
+        (source, rels_source)
+    }
+    /// Writes `new_name` in the references to the sheet, or `#REF!` without it.
+    /// Only the formula texts are rewritten, the rest of each part is left as it is.
+    fn rewrite_sheet_references(&self, sheet_name: &str, new_name: Option<&str>) {
+        let rewrite = |text: &str| match new_name {
+            Some(new_name) => rename_sheet(text, sheet_name, new_name),
+            None => remove_sheet(text, sheet_name),
+        };
+        let workbook = WorkBook::new(&self.workbook, &self.workbook_rels);
+        let sheet_parts = workbook
+            .get_sheet_names()
+            .iter()
+            .map(|name| workbook.get_excel_sheet_name(name).to_string())
+            .collect::<Vec<_>>();
+        let chart_parts = self
+            .get_part_names()
+            .into_iter()
+            .filter(|name| name.starts_with("xl/charts/chart"));
+        for part in sheet_parts.into_iter().chain(chart_parts) {
+            let Some(source) = self.get_part_text(&part) else {
+                continue;
+            };
+            let xml = if part.starts_with("xl/charts/chart") {
+                rewrite_chart_formulas(&source, rewrite)
+            } else {
+                rewrite_sheet_formulas(&source, rewrite)
+            };
+            if xml != source {
+                self.set_part(&part, xml.into_bytes());
+            }
+        }
+    }
+    /// Lists the sheets under the worksheets heading of `docProps/app.xml`, the parts of the document Excel shows.
+    fn update_app_properties(&self) {
+        let Some(source) = self.get_part_text(APP_PART) else {
+            return;
+        };
+        let sheet_names = self.get_sheet_names();
+        let mut node = XMLNode::from(source.as_str());
+        let Some(properties) = node.search_node_mut("Properties") else {
+            return;
+        };
+        let Some(headings) = properties
+            .search_node_mut("HeadingPairs")
+            .and_then(|heading_pairs| heading_pairs.search_node_mut("vt:vector"))
+        else {
+            return;
+        };
+        // headings are pairs of a name and the number of titles under it
+        let mut variants = headings
+            .search_all_nodes_mut("vt:variant")
+            .unwrap_or_default();
+        let mut offset = 0;
+        let mut worksheets_count = None;
+        for pair in variants.chunks_mut(2) {
+            let [heading, count] = pair else {
+                break;
+            };
+            let heading = heading
+                .search_node("vt:lpstr")
+                .and_then(|heading| heading.get_text())
+                .unwrap_or_default();
+            let Some(count) = count.search_node_mut("vt:i4") else {
+                break;
+            };
+            let old_count = count
+                .get_text()
+                .and_then(|count| count.trim().parse::<usize>().ok())
+                .unwrap_or_default();
+            if WORKSHEETS_HEADINGS.contains(&heading.trim()) {
+                count.change_text(sheet_names.len().to_string());
+                worksheets_count = Some(old_count);
+                break;
+            }
+            offset += old_count;
+        }
+        let Some(old_count) = worksheets_count else {
+            return;
+        };
+        let Some(titles) = properties
+            .search_node_mut("TitlesOfParts")
+            .and_then(|titles| titles.search_node_mut("vt:vector"))
+        else {
+            return;
+        };
+        let mut parts = titles
+            .get_child_nodes()
+            .unwrap_or_default()
+            .into_iter()
+            .cloned()
+            .collect::<Vec<_>>();
+        let end = (offset + old_count).min(parts.len());
+        parts.splice(
+            offset.min(end)..end,
+            sheet_names.iter().map(|name| {
+                let mut title = XMLNode::new("vt:lpstr", NodeType::Element);
+                title.add_text(escape(name));
+                title
+            }),
+        );
+        titles.retain_nodes(|_| false);
+        titles.set_element("size", parts.len().to_string());
+        parts.into_iter().for_each(|part| titles.add_node(part));
+        self.set_part(APP_PART, node.to_string().into_bytes());
+    }
+    /// Copies `part` to `copy` with the parts its relationships point at, except those sheets share.
+    /// `sheet_names` are the source sheet and the copy, charts of the copy are pointed at the copy.
+    /// `copied` keeps the parts already copied, so a part used twice is copied once.
+    fn copy_part_tree(
+        &self,
+        part: &str,
+        copy: &str,
+        sheet_names: (&str, &str),
+        copied: &mut Vec<(String, String)>,
+    ) {
+        let Some(data) = self.get_part(part) else {
+            return;
+        };
+        copied.push((part.to_string(), copy.to_string()));
+        let data = match self.get_content_type(part) {
+            Some(content_type) if content_type.ends_with(".table+xml") => self
+                .rename_table_copy(&String::from_utf8_lossy(&data))
+                .into_bytes(),
+            _ if part.starts_with("xl/charts/chart") => {
+                rewrite_chart_formulas(&String::from_utf8_lossy(&data), |text| {
+                    rename_sheet(text, sheet_names.0, sheet_names.1)
+                })
+                .into_bytes()
+            }
+            _ => data,
+        };
+        self.set_part(copy, data);
+        if let Some(content_type) = self.get_content_type(part) {
+            self.add_content_type(copy, &content_type);
+        }
+        let Some(rels_source) = self.get_part_text(&get_rels_part(part)) else {
+            return;
+        };
+        let relationships = Relationships::new(&rels_source, part);
+        let copy_dir = copy
+            .rsplit_once('/')
+            .map(|(dir, _)| dir)
+            .unwrap_or_default();
+        let mut rels = rels_source.clone();
+        for rel in relationships.get_relationships() {
+            let Some(target) = relationships.get_target_part(rel.get_id()) else {
+                continue;
+            };
+            if SHARED_PART_DIRS.iter().any(|dir| target.starts_with(dir)) {
+                continue;
+            }
+            let target_copy = match copied.iter().find(|(part, _)| *part == target) {
+                Some((_, target_copy)) => target_copy.clone(),
+                None => {
+                    let (stem, extension) = split_part_number(&target);
+                    let target_copy = self.get_free_part_name(stem, extension);
+                    self.copy_part_tree(&target, &target_copy, sheet_names, copied);
+                    target_copy
+                }
+            };
+            rels = rels.replace(
+                &format!(r#"Target="{}""#, rel.get_target()),
+                &format!(r#"Target="{}""#, relative_part_path(copy_dir, &target_copy)),
+            );
+        }
+        self.set_part(&get_rels_part(copy), rels.into_bytes());
+    }
+    /// Gives a copied table the next free `id` and a name no other table has, e.g. `Table1` to `Table3`.
+    fn rename_table_copy(&self, source: &str) -> String {
+        let tables = self
+            .get_part_names()
+            .into_iter()
+            .filter(|name| name.starts_with("xl/tables/"))
+            .filter_map(|name| self.get_part_text(&name))
+            .collect::<Vec<_>>();
+        let tables = tables
+            .iter()
+            .map(|table| XMLNode::from(table.as_str()))
+            .collect::<Vec<_>>();
+        let tables = tables
+            .iter()
+            .filter_map(|table| table.search_node("table"))
+            .collect::<Vec<_>>();
+        let id = tables
+            .iter()
+            .filter_map(|table| table.search_element("id")?.parse::<usize>().ok())
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let names = tables
+            .iter()
+            .filter_map(|table| table.search_element("name"))
+            .map(|name| name.to_lowercase())
+            .collect::<Vec<_>>();
+        let mut node = XMLNode::from(source);
+        let Some(table) = node.search_node_mut("table") else {
+            return source.to_string();
+        };
+        let base = table
+            .search_element("displayName")
+            .unwrap_or("Table")
+            .trim_end_matches(|c: char| c.is_ascii_digit())
+            .to_string();
+        let name = (id..)
+            .map(|number| format!("{}{}", base, number))
+            .find(|name| !names.contains(&name.to_lowercase()))
+            .unwrap();
+        table.set_element("id", id.to_string());
+        table.set_element("name", name.clone());
+        table.set_element("displayName", name);
+        node.to_string()
+    }
+    /// The first of `{stem}1{extension}`, `{stem}2{extension}`, ... not in the package.
+    fn get_free_part_name(&self, stem: &str, extension: &str) -> String {
+        let names = self.get_part_names();
+        (1..)
+            .map(|number| format!("{}{}{}", stem, number, extension))
+            .find(|name| !names.contains(name))
+            .unwrap()
+    }
+    /// Removes `part`, its relationships and the parts they point at which no other part uses.
+    fn remove_part_tree(&self, part: &str) {
+        let rels_part = get_rels_part(part);
+        let targets = self
+            .get_part_text(&rels_part)
+            .map(|rels_source| {
+                let relationships = Relationships::new(&rels_source, part);
+                relationships
+                    .get_relationships()
+                    .iter()
+                    .filter_map(|rel| relationships.get_target_part(rel.get_id()))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        self.remove_part(part);
+        self.remove_part(&rels_part);
+        for target in targets {
+            if !self.is_part_used(&target) {
+                self.remove_part_tree(&target);
+            }
+        }
+    }
+    fn is_part_used(&self, part: &str) -> bool {
+        self.get_part_names()
+            .iter()
+            .filter(|name| name.ends_with(".rels"))
+            .any(|name| {
+                let (Some(rels_source), Some(source_part)) =
+                    (self.get_part_text(name), get_rels_source_part(name))
+                else {
+                    return false;
+                };
+                let relationships = Relationships::new(&rels_source, &source_part);
+                relationships
+                    .get_relationships()
+                    .iter()
+                    .any(|rel| relationships.get_target_part(rel.get_id()).as_deref() == Some(part))
+            })
+    }
+    fn remove_part(&self, name: &str) {
+        let mut parts = self.parts.borrow_mut();
+        let count = parts.len();
+        parts.retain(|(part_name, _)| part_name != name);
+        if parts.len() != count {
+            drop(parts);
+            self.remove_content_type(name);
+            self.is_changed.set(true);
+        }
+    }
+    fn get_part_text(&self, name: &str) -> Option<String> {
+        self.get_part(name)
+            .map(|data| String::from_utf8_lossy(&data).into_owned())
+    }
+    /// Content type `[Content_Types].xml` overrides for the part, `None` for parts typed by their extension.
+    fn get_content_type(&self, part: &str) -> Option<String> {
+        let content_types = self.get_part_text(CONTENT_TYPES_PART)?;
+        let (start, end) = find_override(&content_types, part)?;
+        let element = &content_types[start..end];
+        let value_start = element.find(r#"ContentType=""#)? + r#"ContentType=""#.len();
+        let value_length = element[value_start..].find('"')?;
+        Some(element[value_start..value_start + value_length].to_string())
+    }
+    fn add_content_type(&self, part: &str, content_type: &str) {
+        let content_types = self
+            .get_part_text(CONTENT_TYPES_PART)
+            .unwrap_or_else(|| EMPTY_CONTENT_TYPES.to_string());
+        let content_types = append_child(
+            &content_types,
+            "Types",
+            &format!(
+                r#"<Override PartName="/{}" ContentType="{}"/>"#,
+                part, content_type
+            ),
+        );
+        self.set_part(CONTENT_TYPES_PART, content_types.into_bytes());
+    }
+    fn remove_content_type(&self, part: &str) {
+        let Some(content_types) = self.get_part_text(CONTENT_TYPES_PART) else {
+            return;
+        };
+        if let Some((start, end)) = find_override(&content_types, part) {
+            let content_types = format!("{}{}", &content_types[..start], &content_types[end..]);
+            self.set_part(CONTENT_TYPES_PART, content_types.into_bytes());
+        }
+    }
+    fn find_part_text(parts: &[(String, Vec<u8>)], name: &str) -> Option<String> {
+        parts
+            .iter()
+            .find(|(part_name, _)| part_name == name)
+            .map(|(_, data)| String::from_utf8_lossy(data).into_owned())
+    }
+    fn workbook_path() -> &'static str {
+        WORKBOOK_PART
+    }
+    fn workbook_rels_path() -> &'static str {
+        "xl/_rels/workbook.xml.rels"
+    }
+    fn shared_strings_path() -> &'static str {
+        "xl/sharedStrings.xml"
+    }
+    fn styles_path() -> &'static str {
+        "xl/styles.xml"
+    }
+}
+impl<'a> XLSXOperator<'a> for XLSXPackage<'a> {
+    fn add_sheet(&mut self, _e_sheet_name: &str) {
+        // every part is already loaded at open
+    }
+    fn to_excel(&self) {
+        // in-memory packages are only written out by write_to
+        if !self.is_changed.get() || self.filename.is_none() {
+            return;
+        }
+        self.save()
+            .unwrap_or_else(|e| panic!("failed to save {:?}: {}", self.filename, e));
+    }
+    fn read_sheet(&'a self, e_sheet_name: &str) -> String {
+        let sheet = self
+            .get_part(e_sheet_name)
+            .unwrap_or_else(|| panic!("{} is not found in {:?}", e_sheet_name, self.filename));
+        String::from_utf8_lossy(&sheet).into_owned()
+    }
+    fn read_shared_strings(&'a self) -> &'a str {
+        &self.shared_strings
+    }
+    fn read_styles(&'a self) -> &'a str {
+        &self.styles
+    }
+    fn read_workbook(&'a self) -> &'a str {
+        &self.workbook
+    }
+    fn read_workbook_rels(&'a self) -> &'a str {
+        &self.workbook_rels
+    }
+    fn write_sheet(&self, e_sheet_name: &str, content: &str) {
+        self.set_part(e_sheet_name, content.as_bytes().to_vec());
+    }
+    fn write_shared_strings(&self, content: &str) {
+        let path = XLSXPackage::shared_strings_path();
+        if self.get_part(path).is_none() {
+            self.register_shared_strings();
+        }
+        self.set_part(path, content.as_bytes().to_vec());
+    }
+    fn write_workbook(&self, content: &str) {
+        self.set_part(XLSXPackage::workbook_path(), content.as_bytes().to_vec());
+    }
+    fn remove_calc_chain(&self) {
+        let rels = self
+            .get_part_text(XLSXPackage::workbook_rels_path())
+            .unwrap_or_else(|| self.workbook_rels.clone());
+        let mut workbook = WorkBook::new(&self.workbook, &rels);
+        let calc_chains = workbook.remove_relationships(CALC_CHAIN_TYPE);
+        if calc_chains.is_empty() {
+            return;
+        }
+        self.set_part(
+            XLSXPackage::workbook_rels_path(),
+            workbook.get_relationships().to_xml().into_bytes(),
+        );
+        calc_chains
+            .iter()
+            .for_each(|calc_chain| self.remove_part_tree(calc_chain));
+    }
+    fn set_full_calc_on_load(&self) {
+        let (source, rels_source) = self.get_current_workbook();
+        let workbook = WorkBook::new(&source, &rels_source);
+        self.write_workbook(&workbook.to_xml_with_full_calc());
+    }
+    fn shift_lines(&self, sheet_name: &str, shift: LineShift) {
+        let (source, rels_source) = self.get_current_workbook();
+        let mut workbook = WorkBook::new(&source, &rels_source);
+        if workbook.shift_lines(sheet_name, shift) {
+            self.write_workbook(&workbook.to_xml());
+        }
+        let Some(index) = workbook.get_sheet_index(sheet_name) else {
+            return;
+        };
+        let e_sheet_name = workbook.get_excel_sheet_name(&workbook.get_sheet_names()[index]);
+        let Some(rels_source) = self.get_part_text(&get_rels_part(e_sheet_name)) else {
+            return;
+        };
+        let relationships = Relationships::new(&rels_source, e_sheet_name);
+        for rel in relationships.get_relationships() {
+            let Some(table_part) = relationships
+                .get_target_part(rel.get_id())
+                .filter(|_| rel.get_type() == TABLE_TYPE)
+            else {
+                continue;
+            };
+            let Some(source) = self.get_part_text(&table_part) else {
+                continue;
+            };
+            let xml = shift_table_xml(&source, shift);
+            if xml != source {
+                self.set_part(&table_part, xml.into_bytes());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod xlsx_package_test {
+    use std::{env, fs, io::Cursor, path::Path};
+
+    use crate::zip::{archive::ZipArchive, writer::ZipWriter};
+
+    use super::{add_table_part, rewrite_sheet_formulas, XLSXOperator, XLSXPackage};
+    use crate::excel::{
+        cells::{
+            cell_range::RangeArea,
+            formula::{remove_sheet, rename_sheet},
+        },
+        xmls::{defined_name::DefinedNameError, table::TableError, workbook::SheetError},
+    };
+
+    fn create_xlsx_bytes() -> Vec<u8> {
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry("[Content_Types].xml", b"<Types></Types>")
+            .unwrap();
+        writer
+            .add_entry("xl/workbook.xml", b"<workbook><sheets></sheets></workbook>")
+            .unwrap();
+        writer
+            .add_entry("xl/worksheets/sheet1.xml", b"<worksheet></worksheet>")
+            .unwrap();
+        writer
+            .add_entry("docProps/app.xml", b"<Properties/>")
+            .unwrap();
+        writer.finish().unwrap()
+    }
+    fn create_xlsx(filename: &str) -> String {
+        let path = env::temp_dir().join(filename);
+        fs::write(&path, create_xlsx_bytes()).unwrap();
+        path.to_str().unwrap().to_string()
+    }
+    #[test]
+    fn open_test() {
+        let path = create_xlsx("xlsx_package_open_test.xlsx");
+        let package = XLSXPackage::open(&path);
+        assert_eq!(
+            package.read_workbook(),
+            "<workbook><sheets></sheets></workbook>"
+        );
+        assert!(package.read_shared_strings().contains("<sst"));
+        assert_eq!(
+            package.read_sheet("xl/worksheets/sheet1.xml"),
+            "<worksheet></worksheet>"
+        );
+        assert_eq!(
+            package.get_part("docProps/app.xml"),
+            Some(b"<Properties/>".to_vec())
+        );
+        fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn to_excel_test() {
+        let path = create_xlsx("xlsx_package_to_excel_test.xlsx");
+        let package = XLSXPackage::open(&path);
+        package.write_sheet(
+            "xl/worksheets/sheet1.xml",
+            "<worksheet><sheetData/></worksheet>",
+        );
+        package.to_excel();
+
+        let source = fs::read(&path).unwrap();
+        let archive = ZipArchive::new(&source).unwrap();
+        assert_eq!(
+            archive.get_names(),
+            vec![
+                "[Content_Types].xml",
+                "xl/workbook.xml",
+                "xl/worksheets/sheet1.xml",
+                "docProps/app.xml"
+            ]
+        );
+        assert_eq!(
+            archive.read("xl/worksheets/sheet1.xml").unwrap(),
+            b"<worksheet><sheetData/></worksheet>"
+        );
+        assert_eq!(archive.read("docProps/app.xml").unwrap(), b"<Properties/>");
+        // no temporary file is left next to the workbook
+        assert!(!XLSXPackage::temp_path(Path::new(&path)).exists());
+        fs::remove_file(&path).unwrap();
+    }
+    #[test]
+    fn from_bytes_test() {
+        let package = XLSXPackage::from_bytes(&create_xlsx_bytes()).unwrap();
+        assert_eq!(
+            package.read_sheet("xl/worksheets/sheet1.xml"),
+            "<worksheet></worksheet>"
+        );
+        assert!(XLSXPackage::from_bytes(b"<workbook/>").is_err());
+        // saving needs a file, in-memory packages are emitted by write_to
+        assert!(package.save().is_err());
+    }
+    #[test]
+    fn from_reader_and_write_to_test() {
+        // the archive starts where the reader is, e.g. after a multipart header
+        let mut source = b"header".to_vec();
+        source.extend(create_xlsx_bytes());
+        let mut reader = Cursor::new(source);
+        reader.set_position(6);
+        let package = XLSXPackage::from_reader(reader).unwrap();
+        package.write_sheet(
+            "xl/worksheets/sheet1.xml",
+            "<worksheet><sheetData/></worksheet>",
+        );
+        let bytes = package.write_to(Vec::new()).unwrap();
+
+        let package = XLSXPackage::from_bytes(&bytes).unwrap();
+        assert_eq!(
+            package.read_sheet("xl/worksheets/sheet1.xml"),
+            "<worksheet><sheetData/></worksheet>"
+        );
+        assert_eq!(
+            package.get_part_names(),
+            vec![
+                "[Content_Types].xml",
+                "xl/workbook.xml",
+                "xl/worksheets/sheet1.xml",
+                "docProps/app.xml"
+            ]
+        );
+    }
+    const HEADER: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>"#;
+    /// Data with a chart, an image and a table, and Summary reading Data.
+    fn create_sheets_xlsx_bytes() -> Vec<u8> {
+        let parts = [
+            (
+                "[Content_Types].xml",
+                r#"<Types><Default Extension="png" ContentType="image/png"/><Override PartName="/xl/workbook.xml" ContentType="workbook"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/worksheets/sheet2.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/drawings/drawing1.xml" ContentType="drawing"/><Override PartName="/xl/charts/chart1.xml" ContentType="chart"/><Override PartName="/xl/tables/table1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.table+xml"/><Override PartName="/xl/calcChain.xml" ContentType="calcChain"/></Types>"#,
+            ),
+            (
+                "xl/workbook.xml",
+                r#"<workbook xmlns:r="r"><bookViews><workbookView activeTab="0"/></bookViews><sheets><sheet name="Data" sheetId="1" r:id="rId1"/><sheet name="Summary" sheetId="2" r:id="rId2"/></sheets></workbook>"#,
+            ),
+            (
+                "xl/_rels/workbook.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet2.xml"/><Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/calcChain" Target="calcChain.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/worksheets/sheet1.xml",
+                r#"<worksheet><sheetViews><sheetView tabSelected="1" workbookViewId="0"/></sheetViews><sheetData><row r="1"><c r="A1"><v>1</v></c></row></sheetData><drawing r:id="rId1"/><tableParts count="1"><tablePart r:id="rId2"/></tableParts></worksheet>"#,
+            ),
+            (
+                "xl/worksheets/_rels/sheet1.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Type="drawing" Target="../drawings/drawing1.xml"/><Relationship Id="rId2" Type="table" Target="../tables/table1.xml"/></Relationships>"#,
+            ),
+            (
+                "xl/worksheets/sheet2.xml",
+                r#"<worksheet><sheetData><row r="1"><c r="A1"><f>SUM(Data!A1:A3)</f><v>1</v></c></row></sheetData></worksheet>"#,
+            ),
+            (
+                "xl/drawings/drawing1.xml",
+                r#"<xdr:wsDr><xdr:graphicFrame r:id="rId1"/><xdr:pic r:embed="rId2"/></xdr:wsDr>"#,
+            ),
+            (
+                "xl/drawings/_rels/drawing1.xml.rels",
+                r#"<Relationships><Relationship Id="rId1" Type="chart" Target="../charts/chart1.xml"/><Relationship Id="rId2" Type="image" Target="../media/image1.png"/></Relationships>"#,
+            ),
+            (
+                "xl/charts/chart1.xml",
+                r#"<c:chartSpace><c:val><c:f>Data!$A$1:$A$3</c:f></c:val></c:chartSpace>"#,
+            ),
+            ("xl/media/image1.png", "png"),
+            (
+                "xl/tables/table1.xml",
+                r#"<table id="1" name="Table1" displayName="Table1" ref="A1:A3"/>"#,
+            ),
+            (
+                "xl/calcChain.xml",
+                r#"<calcChain><c r="A1" i="2"/></calcChain>"#,
+            ),
+            (
+                "docProps/app.xml",
+                r#"<Properties xmlns:vt="vt"><HeadingPairs><vt:vector size="4" baseType="variant"><vt:variant><vt:lpstr>Worksheets</vt:lpstr></vt:variant><vt:variant><vt:i4>2</vt:i4></vt:variant><vt:variant><vt:lpstr>Named Ranges</vt:lpstr></vt:variant><vt:variant><vt:i4>1</vt:i4></vt:variant></vt:vector></HeadingPairs><TitlesOfParts><vt:vector size="3" baseType="lpstr"><vt:lpstr>Data</vt:lpstr><vt:lpstr>Summary</vt:lpstr><vt:lpstr>Total</vt:lpstr></vt:vector></TitlesOfParts></Properties>"#,
+            ),
+        ];
+        let mut writer = ZipWriter::new(Vec::new());
+        for (name, data) in parts {
+            let data = if data.starts_with('<') {
+                format!("{}\n{}", HEADER, data)
+            } else {
+                data.to_string()
+            };
+            writer.add_entry(name, data.as_bytes()).unwrap();
+        }
+        writer.finish().unwrap()
+    }
+    fn get_part_text(package: &XLSXPackage, name: &str) -> String {
+        String::from_utf8(package.get_part(name).unwrap()).unwrap()
+    }
+    fn get_titles(package: &XLSXPackage) -> String {
+        let app = get_part_text(package, "docProps/app.xml");
+        let start = app.find("<TitlesOfParts>").unwrap();
+        let end = app.find("</TitlesOfParts>").unwrap();
+        app[start..end].to_string()
+    }
+    #[test]
+    fn create_sheet_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        assert_eq!(
+            package.create_sheet("Notes"),
+            Ok("xl/worksheets/sheet3.xml".to_string())
+        );
+        assert_eq!(package.get_sheet_names(), vec!["Data", "Summary", "Notes"]);
+        assert!(package
+            .read_workbook()
+            .contains(r#"<sheet name="Notes" sheetId="3" r:id="rId4"/>"#));
+        assert!(package
+            .read_workbook_rels()
+            .contains(r#"Id="rId4" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet3.xml""#));
+        assert!(package
+            .read_sheet("xl/worksheets/sheet3.xml")
+            .contains("<sheetData>"));
+        assert!(get_part_text(&package, "[Content_Types].xml").contains(r#"<Override PartName="/xl/worksheets/sheet3.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/>"#));
+        let app = get_part_text(&package, "docProps/app.xml");
+        assert!(app.contains("<vt:i4>3</vt:i4>"));
+        assert!(get_titles(&package).contains(r#"<vt:vector size="4" baseType="lpstr"><vt:lpstr>Data</vt:lpstr><vt:lpstr>Summary</vt:lpstr><vt:lpstr>Notes</vt:lpstr><vt:lpstr>Total</vt:lpstr></vt:vector>"#));
+        assert_eq!(
+            package.create_sheet("notes"),
+            Err(SheetError::DuplicateName("notes".to_string()))
+        );
+    }
+    #[test]
+    fn add_table_part_test() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="main"><sheetData/><conditionalFormatting sqref="A1"><cfRule type="expression" priority="1"><formula>A1</formula><extLst><ext/></extLst></cfRule></conditionalFormatting>
+<extLst><ext uri="x14"><x14:conditionalFormattings/><extLst/></ext></extLst>
+</worksheet>"#;
+        assert_eq!(
+            add_table_part(source, "rId3"),
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships" xmlns="main"><sheetData/><conditionalFormatting sqref="A1"><cfRule type="expression" priority="1"><formula>A1</formula><extLst><ext/></extLst></cfRule></conditionalFormatting>
+<tableParts count="1"><tablePart r:id="rId3"/></tableParts><extLst><ext uri="x14"><x14:conditionalFormattings/><extLst/></ext></extLst>
+</worksheet>"#
+        );
+
+        let source = r#"<worksheet xmlns:r="rels"><sheetData/><tableParts count="1"><tablePart r:id="rId1"/></tableParts><extLst/></worksheet>"#;
+        assert_eq!(
+            add_table_part(source, "rId2"),
+            r#"<worksheet xmlns:r="rels"><sheetData/><tableParts count="2"><tablePart r:id="rId1"/><tablePart r:id="rId2"/></tableParts><extLst/></worksheet>"#
+        );
+        assert_eq!(
+            add_table_part(
+                r#"<worksheet><sheetData/><tableParts count="0"/></worksheet>"#,
+                "rId1"
+            ),
+            r#"<worksheet xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheetData/><tableParts count="1"><tablePart r:id="rId1"/></tableParts></worksheet>"#
+        );
+    }
+    #[test]
+    fn rewrite_sheet_formulas_test() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData>
+<row r="1"><c r="A1"><f>Data!B2+SUM('data'!A:A)+B2</f><v>3</v></c><c r="B1"><f>"a  &amp;"&amp;B2</f></c><c r="C1"><f t="shared" ref="C1:C2" si="0">Data!A1</f></c></row>
+<row r="2"><c r="C2"><f t="shared" si="0"/></c><c r="D2" t="inlineStr"><is><t>Data!A1</t></is></c></row>
+</sheetData><dataValidations count="1"><dataValidation type="list" sqref="D1"><formula1>Data!$A$1:$A$3</formula1></dataValidation></dataValidations></worksheet>"#;
+        let renamed =
+            rewrite_sheet_formulas(source, |text| rename_sheet(text, "Data", "Sales 2024"));
+        assert_eq!(
+            renamed,
+            source
+                .replace(
+                    "Data!B2+SUM('data'!A:A)",
+                    "'Sales 2024'!B2+SUM('Sales 2024'!A:A)"
+                )
+                .replace(">Data!A1</f>", ">'Sales 2024'!A1</f>")
+                .replace("<formula1>Data!", "<formula1>'Sales 2024'!")
+        );
+        let removed = rewrite_sheet_formulas(&renamed, |text| remove_sheet(text, "sales 2024"));
+        assert!(removed.contains("<f>#REF!+SUM(#REF!)+B2</f><v>3</v>"));
+        assert!(removed.contains(r#"<f t="shared" ref="C1:C2" si="0">#REF!</f>"#));
+        assert!(removed.contains("<formula1>#REF!</formula1>"));
+        assert!(removed.contains(r#"<f>"a  &amp;"&amp;B2</f>"#));
+        assert!(removed.contains("<t>Data!A1</t>"));
+    }
+    #[test]
+    fn rename_sheet_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        package.rename_sheet("data", "Sales 2024").unwrap();
+        assert_eq!(package.get_sheet_names(), vec!["Sales 2024", "Summary"]);
+        assert!(package
+            .read_sheet("xl/worksheets/sheet2.xml")
+            .contains("<f>SUM('Sales 2024'!A1:A3)</f>"));
+        assert!(get_part_text(&package, "xl/charts/chart1.xml")
+            .contains("<c:f>'Sales 2024'!$A$1:$A$3</c:f>"));
+        assert!(get_titles(&package)
+            .contains("<vt:lpstr>Sales 2024</vt:lpstr><vt:lpstr>Summary</vt:lpstr>"));
+        assert_eq!(
+            package.rename_sheet("Other", "Data"),
+            Err(SheetError::NotFound("Other".to_string()))
+        );
+    }
+    #[test]
+    fn move_sheet_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        package.move_sheet("Summary", 0).unwrap();
+        assert_eq!(package.get_sheet_names(), vec!["Summary", "Data"]);
+        assert!(package
+            .read_workbook()
+            .contains(r#"<workbookView activeTab="1"/>"#));
+        assert!(
+            get_titles(&package).contains("<vt:lpstr>Summary</vt:lpstr><vt:lpstr>Data</vt:lpstr>")
+        );
+    }
+    #[test]
+    fn delete_sheet_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        package.delete_sheet("Data").unwrap();
+        assert_eq!(package.get_sheet_names(), vec!["Summary"]);
+        assert_eq!(
+            package.get_part_names(),
+            vec![
+                "[Content_Types].xml",
+                "xl/workbook.xml",
+                "xl/_rels/workbook.xml.rels",
+                "xl/worksheets/sheet2.xml",
+                "docProps/app.xml"
+            ]
+        );
+        let content_types = get_part_text(&package, "[Content_Types].xml");
+        assert!(!content_types.contains("sheet1.xml"));
+        assert!(!content_types.contains("calcChain"));
+        assert!(content_types.contains("/xl/worksheets/sheet2.xml"));
+        assert!(!package.read_workbook_rels().contains("rId1"));
+        assert!(!package.read_workbook_rels().contains("calcChain"));
+        assert!(package
+            .read_sheet("xl/worksheets/sheet2.xml")
+            .contains("<f>SUM(#REF!)</f>"));
+        assert!(get_titles(&package).contains(r#"<vt:vector size="2" baseType="lpstr"><vt:lpstr>Summary</vt:lpstr><vt:lpstr>Total</vt:lpstr></vt:vector>"#));
+        assert_eq!(
+            package.delete_sheet("Summary"),
+            Err(SheetError::LastVisibleSheet("Summary".to_string()))
+        );
+    }
+    #[test]
+    fn duplicate_sheet_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        assert_eq!(
+            package.duplicate_sheet("Data", "Data (2)"),
+            Ok("xl/worksheets/sheet3.xml".to_string())
+        );
+        assert_eq!(
+            package.get_sheet_names(),
+            vec!["Data", "Data (2)", "Summary"]
+        );
+        let copy = package.read_sheet("xl/worksheets/sheet3.xml");
+        assert!(copy.contains(r#"<sheetView workbookViewId="0"/>"#));
+        assert!(copy.contains(r#"<c r="A1"><v>1</v></c>"#));
+        assert_eq!(
+            get_part_text(&package, "xl/worksheets/_rels/sheet3.xml.rels"),
+            format!(
+                "{}\n{}",
+                HEADER,
+                r#"<Relationships><Relationship Id="rId1" Type="drawing" Target="../drawings/drawing2.xml"/><Relationship Id="rId2" Type="table" Target="../tables/table2.xml"/></Relationships>"#
+            )
+        );
+        assert!(get_part_text(&package, "xl/drawings/_rels/drawing2.xml.rels")
+            .contains(r#"Target="../charts/chart2.xml"/><Relationship Id="rId2" Type="image" Target="../media/image1.png"/>"#));
+        assert!(get_part_text(&package, "xl/charts/chart2.xml")
+            .contains("<c:f>'Data (2)'!$A$1:$A$3</c:f>"));
+        assert!(
+            get_part_text(&package, "xl/charts/chart1.xml").contains("<c:f>Data!$A$1:$A$3</c:f>")
+        );
+        assert!(get_part_text(&package, "xl/tables/table2.xml")
+            .contains(r#"<table id="2" name="Table2" displayName="Table2" ref="A1:A3"/>"#));
+        let content_types = get_part_text(&package, "[Content_Types].xml");
+        assert!(content_types
+            .contains(r#"<Override PartName="/xl/drawings/drawing2.xml" ContentType="drawing"/>"#));
+        assert!(content_types.contains(r#"<Override PartName="/xl/tables/table2.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.table+xml"/>"#));
+        assert!(!package
+            .get_part_names()
+            .contains(&"xl/media/image2.png".to_string()));
+        assert!(get_titles(&package).contains(
+            "<vt:lpstr>Data</vt:lpstr><vt:lpstr>Data (2)</vt:lpstr><vt:lpstr>Summary</vt:lpstr>"
+        ));
+    }
+    #[test]
+    fn get_table_test() {
+        let package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        let tables = package.get_tables("data");
+        assert_eq!(tables.len(), 1);
+        assert_eq!(tables[0].get_part(), "xl/tables/table1.xml");
+        assert_eq!(tables[0].get_area().to_string(), "A1:A3");
+        let table = package.get_table("TABLE1").unwrap();
+        assert_eq!(table.get_sheet_name(), "Data");
+        assert!(package.get_tables("Summary").is_empty());
+        assert!(package.get_table("Table2").is_none());
+    }
+    #[test]
+    fn create_table_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        let area = |range: &str| RangeArea::new(range).unwrap();
+        assert_eq!(
+            package.create_table("Summary", "table1", &area("A1:A3")),
+            Err(TableError::DuplicateName("table1".to_string()))
+        );
+        assert_eq!(
+            package.create_table("Summary", "B2", &area("A1:A3")),
+            Err(TableError::InvalidName("B2".to_string()))
+        );
+        assert_eq!(
+            package.create_table("Data", "Totals", &area("A3:B5")),
+            Err(TableError::Overlap("Table1".to_string()))
+        );
+        assert_eq!(
+            package.create_table("Summary", "Totals", &area("A1")),
+            Err(TableError::InvalidRange("A1".to_string()))
+        );
+        assert_eq!(
+            package.create_table("Total", "Totals", &area("A1:A3")),
+            Err(TableError::Sheet(SheetError::NotFound("Total".to_string())))
+        );
+        assert_eq!(
+            package.create_table("Summary", "Totals", &area("A1:A3")),
+            Err(TableError::InvalidHeader("A1".to_string()))
+        );
+
+        let sheet = get_part_text(&package, "xl/worksheets/sheet2.xml").replace(
+            r#"<c r="A1"><f>SUM(Data!A1:A3)</f><v>1</v></c>"#,
+            r#"<c r="A1" t="inlineStr"><is><t>Amount</t></is></c>"#,
+        );
+        package.set_part("xl/worksheets/sheet2.xml", sheet.into_bytes());
+        assert_eq!(
+            package.create_table("Summary", "Totals", &area("A1:B3")),
+            Err(TableError::InvalidHeader("B1".to_string()))
+        );
+        let table = package
+            .create_table("Summary", "Totals", &area("A1:A3"))
+            .unwrap();
+        assert_eq!(table.get_part(), "xl/tables/table2.xml");
+        assert_eq!(table.get_id(), 2);
+        assert_eq!(table.get_columns()[0].get_name(), "Amount");
+        assert_eq!(package.get_table("totals"), Some(table));
+        let sheet = get_part_text(&package, "xl/worksheets/sheet2.xml");
+        assert!(sheet.contains(r#"<worksheet xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">"#));
+        assert!(sheet.contains(
+            r#"</sheetData><tableParts count="1"><tablePart r:id="rId1"/></tableParts>"#
+        ));
+        assert!(
+            get_part_text(&package, "xl/worksheets/_rels/sheet2.xml.rels").contains(
+                r#"<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/table" Target="../tables/table2.xml"/>"#
+            )
+        );
+        assert!(get_part_text(&package, "[Content_Types].xml").contains(
+            r#"<Override PartName="/xl/tables/table2.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.table+xml"/>"#
+        ));
+    }
+    #[test]
+    fn resize_table_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        let area = |range: &str| RangeArea::new(range).unwrap();
+        assert_eq!(
+            package.resize_table("Table1", &area("A2:A5")),
+            Err(TableError::InvalidRange("A2:A5".to_string()))
+        );
+        assert_eq!(
+            package.resize_table("Table9", &area("A1:A5")),
+            Err(TableError::NotFound("Table9".to_string()))
+        );
+        assert_eq!(
+            package.resize_table("Table1", &area("A1:A5")),
+            Err(TableError::InvalidHeader("A1".to_string()))
+        );
+        let sheet = get_part_text(&package, "xl/worksheets/sheet1.xml").replace(
+            r#"<c r="A1"><v>1</v></c>"#,
+            r#"<c r="A1" t="inlineStr"><is><t>Count</t></is></c>"#,
+        );
+        package.set_part("xl/worksheets/sheet1.xml", sheet.into_bytes());
+        let table = package.resize_table("Table1", &area("A1:A5")).unwrap();
+        assert_eq!(table.get_area().to_string(), "A1:A5");
+        assert_eq!(table.get_columns()[0].get_name(), "Count");
+        assert!(get_part_text(&package, "xl/tables/table1.xml").contains(
+            r#"<table id="1" name="Table1" displayName="Table1" ref="A1:A5"><tableColumns count="1"><tableColumn id="1" name="Count"/></tableColumns></table>"#
+        ));
+    }
+    #[test]
+    fn set_defined_name_test() {
+        let mut package = XLSXPackage::from_bytes(&create_sheets_xlsx_bytes()).unwrap();
+        assert_eq!(
+            package.set_defined_name("table1", "Data!$A$1", None),
+            Err(DefinedNameError::DuplicateName("table1".to_string()))
+        );
+        package
+            .set_defined_name("Values", "Data!$A$1:$A$3", None)
+            .unwrap();
+        package
+            .set_defined_name("_xlnm.Print_Area", "Summary!$A$1:$B$2", Some("Summary"))
+            .unwrap();
+        assert!(get_part_text(&package, "xl/workbook.xml").contains(
+            r#"</sheets><definedNames><definedName name="Values">Data!$A$1:$A$3</definedName><definedName name="_xlnm.Print_Area" localSheetId="1">Summary!$A$1:$B$2</definedName></definedNames></workbook>"#
+        ));
+        package.remove_defined_name("values", None).unwrap();
+        assert_eq!(
+            package.remove_defined_name("_xlnm.Print_Area", None),
+            Err(DefinedNameError::NotFound("_xlnm.Print_Area".to_string()))
+        );
+        assert!(!get_part_text(&package, "xl/workbook.xml").contains("Values"));
+    }
 }
@@ -0,0 +1,7 @@
+pub mod ast;
+pub mod dependency;
+pub mod evaluator;
+pub mod functions;
+pub mod lexer;
+pub mod parser;
+pub mod value;
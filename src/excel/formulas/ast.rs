@@ -0,0 +1,457 @@
+use std::fmt::Display;
+
+use crate::excel::cells::{
+    cell::{CellIndex, ColumnAlphabet},
+    cell_value::CellErrorKind,
+};
+
+pub const MAX_COLUMN: usize = 16384;
+pub const MAX_ROW: usize = 1048576;
+
+/// Parsed formula, written back by `Display` without the leading `=`.
+///
+///     let expr = parse("=SUM(A1:A3)*2")?;
+///     assert_eq!(expr.to_string(), "SUM(A1:A3)*2");
+///
+#[derive(Debug, PartialEq, Clone)]
+pub enum Expr {
+    Number(f64),
+    Text(String),
+    Bool(bool),
+    Error(CellErrorKind),
+    Reference(Reference),
+    Reference3D(Reference3D),
+    /// Defined name, optionally scoped to a sheet
+    Name {
+        sheet: Option<String>,
+        name: String,
+    },
+    StructuredReference(StructuredReference),
+    /// `{1,2;3,4}`, rows of constants
+    Array(Vec<Vec<Expr>>),
+    Function {
+        name: String,
+        args: Vec<Expr>,
+    },
+    Unary {
+        op: UnaryOp,
+        operand: Box<Expr>,
+    },
+    Binary {
+        op: BinaryOp,
+        left: Box<Expr>,
+        right: Box<Expr>,
+    },
+    /// Kept so that `Display` writes the parentheses of the source
+    Parenthesized(Box<Expr>),
+    /// Omitted function argument, e.g. the second of `IF(A1,,1)`
+    Missing,
+}
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UnaryOp {
+    Plus,
+    Minus,
+    /// Postfix `%`
+    Percent,
+}
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BinaryOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Pow,
+    Concat,
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    /// `:` between references that are not plain cells, e.g. `A1:INDEX(B:B,3)`
+    Range,
+    /// ` `
+    Intersect,
+    /// `,` inside parentheses
+    Union,
+}
+impl BinaryOp {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Mul => "*",
+            BinaryOp::Div => "/",
+            BinaryOp::Pow => "^",
+            BinaryOp::Concat => "&",
+            BinaryOp::Eq => "=",
+            BinaryOp::Ne => "<>",
+            BinaryOp::Lt => "<",
+            BinaryOp::Le => "<=",
+            BinaryOp::Gt => ">",
+            BinaryOp::Ge => ">=",
+            BinaryOp::Range => ":",
+            BinaryOp::Intersect => " ",
+            BinaryOp::Union => ",",
+        }
+    }
+}
+
+/// A1 style reference, optionally on another sheet.
+/// A sheet of another workbook starts with the index of the workbook, e.g. `[1]Sheet1`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Reference {
+    pub sheet: Option<String>,
+    pub area: Area,
+}
+/// The same cells of every sheet from `first_sheet` to `last_sheet` in tab order, e.g. `Sheet1:Sheet3!A1`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Reference3D {
+    pub first_sheet: String,
+    pub last_sheet: String,
+    pub area: Area,
+}
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Area {
+    Cell(CellReference),
+    Range(CellReference, CellReference),
+    /// `A:C`
+    Columns(LineReference, LineReference),
+    /// `1:3`
+    Rows(LineReference, LineReference),
+}
+/// `$B3` is column 2 absolute, row 3 relative.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CellReference {
+    pub column: LineReference,
+    pub row: LineReference,
+}
+/// Column or row number of a reference and whether it is anchored by `$`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct LineReference {
+    pub index: usize,
+    pub is_absolute: bool,
+}
+impl LineReference {
+    pub fn new(index: usize, is_absolute: bool) -> Self {
+        LineReference { index, is_absolute }
+    }
+    /// Reads `$C` or `C`.
+    pub fn from_column(s: &str) -> Option<Self> {
+        let (is_absolute, letters) = strip_dollar(s);
+        if letters.is_empty()
+            || letters.len() > 3
+            || !letters.chars().all(|c| c.is_ascii_alphabetic())
+        {
+            return None;
+        }
+        let index = ColumnAlphabet::new(&letters.to_ascii_uppercase()).to_number();
+        (index <= MAX_COLUMN).then_some(LineReference::new(index, is_absolute))
+    }
+    /// Reads `$3` or `3`.
+    pub fn from_row(s: &str) -> Option<Self> {
+        let (is_absolute, digits) = strip_dollar(s);
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let index = digits.parse().ok()?;
+        (1..=MAX_ROW)
+            .contains(&index)
+            .then_some(LineReference::new(index, is_absolute))
+    }
+    fn dollar(&self) -> &'static str {
+        if self.is_absolute {
+            "$"
+        } else {
+            ""
+        }
+    }
+}
+fn strip_dollar(s: &str) -> (bool, &str) {
+    match s.strip_prefix('$') {
+        Some(rest) => (true, rest),
+        None => (false, s),
+    }
+}
+impl CellReference {
+    /// Reads `A1`, `$A1`, `A$1` or `$A$1`.
+    pub fn new(s: &str) -> Option<Self> {
+        let (column_absolute, rest) = strip_dollar(s);
+        let split = rest.find(|c: char| !c.is_ascii_alphabetic())?;
+        let column = LineReference::from_column(&rest[..split])?;
+        let row = LineReference::from_row(&rest[split..])?;
+        Some(CellReference {
+            column: LineReference::new(column.index, column_absolute),
+            row,
+        })
+    }
+    /// Index without `$`, as the `r` of `<c>`.
+    ///
+    ///     assert_eq!(CellReference::new("$B$3").unwrap().to_cell_index(), "B3");
+    ///
+    pub fn to_cell_index(self) -> String {
+        format!(
+            "{}{}",
+            ColumnAlphabet::from_number(self.column.index),
+            self.row.index
+        )
+    }
+}
+impl From<&CellIndex<'_>> for CellReference {
+    fn from(index: &CellIndex) -> Self {
+        CellReference {
+            column: LineReference::new(index.get_column_index(), false),
+            row: LineReference::new(index.get_row_index(), false),
+        }
+    }
+}
+impl Display for CellReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}{}{}{}",
+            self.column.dollar(),
+            ColumnAlphabet::from_number(self.column.index),
+            self.row.dollar(),
+            self.row.index
+        )
+    }
+}
+impl Display for Area {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Area::Cell(cell) => write!(f, "{}", cell),
+            Area::Range(start, end) => write!(f, "{}:{}", start, end),
+            Area::Columns(start, end) => write!(
+                f,
+                "{}{}:{}{}",
+                start.dollar(),
+                ColumnAlphabet::from_number(start.index),
+                end.dollar(),
+                ColumnAlphabet::from_number(end.index)
+            ),
+            Area::Rows(start, end) => write!(
+                f,
+                "{}{}:{}{}",
+                start.dollar(),
+                start.index,
+                end.dollar(),
+                end.index
+            ),
+        }
+    }
+}
+impl Display for Reference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(sheet) = &self.sheet {
+            write!(f, "{}!", quote_sheet_name(sheet))?;
+        }
+        write!(f, "{}", self.area)
+    }
+}
+impl Display for Reference3D {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}!{}",
+            quote_sheet_span(&self.first_sheet, &self.last_sheet),
+            self.area
+        )
+    }
+}
+/// Sheet names other than plain words are written in single quotes, doubling the quotes inside.
+///
+///     assert_eq!(quote_sheet_name("テーブル一覧"), "'テーブル一覧'");
+///
+pub fn quote_sheet_name(sheet: &str) -> String {
+    let is_plain = sheet
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && sheet
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '.')
+        && CellReference::new(sheet).is_none();
+    if is_plain {
+        sheet.to_string()
+    } else {
+        format!("'{}'", sheet.replace('\'', "''"))
+    }
+}
+
+/// First and last sheet of a 3-D reference, in single quotes together when either is not a plain word.
+///
+///     assert_eq!(quote_sheet_span("Jan", "Mar 2024"), "'Jan:Mar 2024'");
+///
+pub fn quote_sheet_span(first: &str, last: &str) -> String {
+    let span = format!("{}:{}", first, last);
+    if quote_sheet_name(first) == first && quote_sheet_name(last) == last {
+        span
+    } else {
+        format!("'{}'", span.replace('\'', "''"))
+    }
+}
+
+/// `Table1[[#Headers],[Price]:[Tax]]` or `[@Price]` inside the table.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct StructuredReference {
+    pub table: Option<String>,
+    pub specifiers: Vec<TableSpecifier>,
+    pub columns: Option<ColumnRange>,
+}
+/// First and last column, a single column has no last.
+pub type ColumnRange = (String, Option<String>);
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TableSpecifier {
+    All,
+    Data,
+    Headers,
+    Totals,
+    ThisRow,
+}
+impl TableSpecifier {
+    pub fn new(s: &str) -> Option<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "#all" => Some(TableSpecifier::All),
+            "#data" => Some(TableSpecifier::Data),
+            "#headers" => Some(TableSpecifier::Headers),
+            "#totals" => Some(TableSpecifier::Totals),
+            "#this row" | "@" => Some(TableSpecifier::ThisRow),
+            _ => None,
+        }
+    }
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TableSpecifier::All => "#All",
+            TableSpecifier::Data => "#Data",
+            TableSpecifier::Headers => "#Headers",
+            TableSpecifier::Totals => "#Totals",
+            TableSpecifier::ThisRow => "#This Row",
+        }
+    }
+}
+/// Column names escape `[`, `]`, `#` and `'` with `'`.
+fn escape_column(column: &str) -> String {
+    column
+        .chars()
+        .fold(String::with_capacity(column.len()), |mut acc, c| {
+            if matches!(c, '[' | ']' | '#' | '\'') {
+                acc.push('\'');
+            }
+            acc.push(c);
+            acc
+        })
+}
+impl Display for StructuredReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(table) = &self.table {
+            write!(f, "{}", table)?;
+        }
+        let columns = self.columns.as_ref().map(|(first, last)| match last {
+            Some(last) => format!("[{}]:[{}]", escape_column(first), escape_column(last)),
+            None => format!("[{}]", escape_column(first)),
+        });
+        let body = match (self.specifiers.as_slice(), &self.columns) {
+            ([], None) => String::new(),
+            ([], Some((first, None))) => escape_column(first),
+            ([TableSpecifier::ThisRow], Some((first, None)))
+                if first.chars().all(|c| c.is_alphanumeric() || c == '_') =>
+            {
+                format!("@{}", first)
+            }
+            ([TableSpecifier::ThisRow], Some(_)) => format!("@{}", columns.unwrap()),
+            ([specifier], None) => specifier.as_str().to_string(),
+            (specifiers, _) => specifiers
+                .iter()
+                .map(|specifier| format!("[{}]", specifier.as_str()))
+                .chain(columns)
+                .collect::<Vec<_>>()
+                .join(","),
+        };
+        write!(f, "[{}]", body)
+    }
+}
+
+impl Display for Expr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Expr::Number(number) => write!(f, "{}", number),
+            Expr::Text(text) => write!(f, "\"{}\"", text.replace('"', "\"\"")),
+            Expr::Bool(true) => write!(f, "TRUE"),
+            Expr::Bool(false) => write!(f, "FALSE"),
+            Expr::Error(error) => write!(f, "{}", error.as_str()),
+            Expr::Reference(reference) => write!(f, "{}", reference),
+            Expr::Reference3D(reference) => write!(f, "{}", reference),
+            Expr::Name {
+                sheet: Some(sheet),
+                name,
+            } => write!(f, "{}!{}", quote_sheet_name(sheet), name),
+            Expr::Name { sheet: None, name } => write!(f, "{}", name),
+            Expr::StructuredReference(reference) => write!(f, "{}", reference),
+            Expr::Array(rows) => {
+                let rows = rows
+                    .iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|value| value.to_string())
+                            .collect::<Vec<_>>()
+                            .join(",")
+                    })
+                    .collect::<Vec<_>>();
+                write!(f, "{{{}}}", rows.join(";"))
+            }
+            Expr::Function { name, args } => {
+                let args = args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>();
+                write!(f, "{}({})", name, args.join(","))
+            }
+            Expr::Unary {
+                op: UnaryOp::Percent,
+                operand,
+            } => write!(f, "{}%", operand),
+            Expr::Unary {
+                op: UnaryOp::Minus,
+                operand,
+            } => write!(f, "-{}", operand),
+            Expr::Unary {
+                op: UnaryOp::Plus,
+                operand,
+            } => write!(f, "+{}", operand),
+            Expr::Binary { op, left, right } => write!(f, "{}{}{}", left, op.as_str(), right),
+            Expr::Parenthesized(expr) => write!(f, "({})", expr),
+            Expr::Missing => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod ast_test {
+    use super::{quote_sheet_name, quote_sheet_span, Area, CellReference, LineReference};
+
+    #[test]
+    fn cell_reference_test() {
+        let reference = CellReference::new("$B3").unwrap();
+        assert_eq!(reference.column, LineReference::new(2, true));
+        assert_eq!(reference.row, LineReference::new(3, false));
+        assert_eq!(reference.to_string(), "$B3");
+        assert_eq!(reference.to_cell_index(), "B3");
+        assert_eq!(
+            CellReference::new("XFD1048576").unwrap().to_string(),
+            "XFD1048576"
+        );
+        assert_eq!(CellReference::new("XFE1"), None);
+        assert_eq!(CellReference::new("A0"), None);
+        assert_eq!(CellReference::new("A1B"), None);
+        assert_eq!(CellReference::new("SUM"), None);
+        let columns = Area::Columns(LineReference::new(1, false), LineReference::new(3, true));
+        assert_eq!(columns.to_string(), "A:$C");
+    }
+    #[test]
+    fn quote_sheet_name_test() {
+        assert_eq!(quote_sheet_name("Sheet1"), "Sheet1");
+        assert_eq!(quote_sheet_name("My Sheet"), "'My Sheet'");
+        assert_eq!(quote_sheet_name("Bob's"), "'Bob''s'");
+        assert_eq!(quote_sheet_name("A1"), "'A1'");
+        assert_eq!(quote_sheet_span("Jan", "Mar"), "Jan:Mar");
+        assert_eq!(quote_sheet_span("Jan", "Bob's"), "'Jan:Bob''s'");
+    }
+}
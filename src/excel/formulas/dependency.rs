@@ -0,0 +1,470 @@
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+};
+
+use crate::excel::cells::cell::{CellIndex, ColumnAlphabet};
+
+use super::{
+    ast::{quote_sheet_name, Area, BinaryOp, Expr, Reference, Reference3D, MAX_COLUMN, MAX_ROW},
+    evaluator::{get_sheet_span, CellSource},
+    parser::parse,
+};
+
+/// A cell of the workbook, rows and columns are 1-based.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+pub struct CellAddress {
+    pub sheet: String,
+    pub row: usize,
+    pub column: usize,
+}
+impl CellAddress {
+    pub fn new(sheet: &str, row: usize, column: usize) -> Self {
+        CellAddress {
+            sheet: sheet.to_string(),
+            row,
+            column,
+        }
+    }
+    /// `CellAddress::from_index("Sheet1", "B3")`
+    pub fn from_index(sheet: &str, cell_index: &str) -> Self {
+        let index = CellIndex::new(cell_index);
+        CellAddress::new(sheet, index.get_row_index(), index.get_column_index())
+    }
+    /// `B3` of `Sheet1!B3`.
+    pub fn get_cell_index(&self) -> String {
+        format!("{}{}", ColumnAlphabet::from_number(self.column), self.row)
+    }
+    /// Sheet names in formulas ignore case.
+    fn is_same_cell(&self, other: &CellAddress) -> bool {
+        self.row == other.row
+            && self.column == other.column
+            && self.sheet.to_lowercase() == other.sheet.to_lowercase()
+    }
+}
+impl Display for CellAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}!{}",
+            quote_sheet_name(&self.sheet),
+            self.get_cell_index()
+        )
+    }
+}
+
+/// Formula cells reading each other, each cycle lists its cells.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct CircularReference {
+    pub cycles: Vec<Vec<CellAddress>>,
+}
+impl Display for CircularReference {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let cycles = self
+            .cycles
+            .iter()
+            .map(|cycle| {
+                cycle
+                    .iter()
+                    .map(CellAddress::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            })
+            .collect::<Vec<_>>();
+        write!(f, "circular reference: {}", cycles.join(" / "))
+    }
+}
+impl std::error::Error for CircularReference {}
+
+/// Rectangle of cells a formula reads.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Precedent {
+    sheet: String,
+    rows: (usize, usize),
+    columns: (usize, usize),
+}
+impl Precedent {
+    fn new(sheet: &str, area: &Area) -> Self {
+        let (rows, columns) = match area {
+            Area::Cell(cell) => (
+                (cell.row.index, cell.row.index),
+                (cell.column.index, cell.column.index),
+            ),
+            Area::Range(start, end) => (
+                (
+                    start.row.index.min(end.row.index),
+                    start.row.index.max(end.row.index),
+                ),
+                (
+                    start.column.index.min(end.column.index),
+                    start.column.index.max(end.column.index),
+                ),
+            ),
+            Area::Columns(start, end) => (
+                (1, MAX_ROW),
+                (start.index.min(end.index), start.index.max(end.index)),
+            ),
+            Area::Rows(start, end) => (
+                (start.index.min(end.index), start.index.max(end.index)),
+                (1, MAX_COLUMN),
+            ),
+        };
+        Precedent {
+            sheet: sheet.to_lowercase(),
+            rows,
+            columns,
+        }
+    }
+    /// The smallest rectangle holding both, as `A1:B2:C3` reads.
+    fn union(self, other: Precedent) -> Option<Precedent> {
+        if self.sheet != other.sheet {
+            return None;
+        }
+        Some(Precedent {
+            sheet: self.sheet,
+            rows: (self.rows.0.min(other.rows.0), self.rows.1.max(other.rows.1)),
+            columns: (
+                self.columns.0.min(other.columns.0),
+                self.columns.1.max(other.columns.1),
+            ),
+        })
+    }
+    fn contains(&self, cell: &CellAddress) -> bool {
+        (self.rows.0..=self.rows.1).contains(&cell.row)
+            && (self.columns.0..=self.columns.1).contains(&cell.column)
+            && self.sheet == cell.sheet.to_lowercase()
+    }
+}
+
+/// Which formula cells read which cells, over every sheet of the source.
+///
+///     let graph = DependencyGraph::new(&sheets);
+///     // Sheet2!A1 = Sheet1!A1*2, Sheet2!B1 = Sheet2!A1+1
+///     let affected = graph.get_affected_cells(&[CellAddress::from_index("Sheet1", "A1")]);
+///     assert_eq!(graph.get_calculation_order(&affected)?.len(), 2);
+///
+#[derive(Debug, PartialEq, Clone)]
+pub struct DependencyGraph {
+    formulas: Vec<(CellAddress, Vec<Precedent>)>,
+}
+impl DependencyGraph {
+    pub fn new<S: CellSource>(source: &S) -> Self {
+        let mut formulas = Vec::new();
+        for sheet in source.get_sheet_names() {
+            for (row, column) in source.get_formula_cells(&sheet) {
+                let Some(formula) = source.get_formula_at(&sheet, row, column) else {
+                    continue;
+                };
+                let mut precedents = Vec::new();
+                if let Ok(expr) = parse(&formula) {
+                    collect_precedents(source, &sheet, &expr, &mut precedents, &mut Vec::new());
+                }
+                formulas.push((CellAddress::new(&sheet, row, column), precedents));
+            }
+        }
+        DependencyGraph { formulas }
+    }
+    pub fn get_formula_cells(&self) -> Vec<&CellAddress> {
+        self.formulas.iter().map(|(cell, _)| cell).collect()
+    }
+    /// Formula cells reading the cell directly.
+    pub fn get_dependents(&self, cell: &CellAddress) -> Vec<&CellAddress> {
+        self.formulas
+            .iter()
+            .filter(|(_, precedents)| precedents.iter().any(|range| range.contains(cell)))
+            .map(|(formula_cell, _)| formula_cell)
+            .collect()
+    }
+    /// Formula cells to calculate again after the cells changed: changed formula cells
+    /// and every formula cell reading a changed cell directly or through other formulas.
+    pub fn get_affected_cells(&self, changed: &[CellAddress]) -> Vec<CellAddress> {
+        let mut affected = HashSet::new();
+        let mut queue = changed.iter().cloned().collect::<VecDeque<_>>();
+        for cell in changed {
+            if let Some(index) = self.find(cell) {
+                affected.insert(index);
+            }
+        }
+        while let Some(cell) = queue.pop_front() {
+            for (index, (formula_cell, precedents)) in self.formulas.iter().enumerate() {
+                if precedents.iter().any(|range| range.contains(&cell)) && affected.insert(index) {
+                    queue.push_back(formula_cell.clone());
+                }
+            }
+        }
+        let mut affected = affected.into_iter().collect::<Vec<_>>();
+        affected.sort_unstable();
+        affected
+            .into_iter()
+            .map(|index| self.formulas[index].0.clone())
+            .collect()
+    }
+    /// Orders the formula cells so that each comes after the formula cells it reads.
+    /// Cells reading each other are returned as the error instead.
+    pub fn get_calculation_order(
+        &self,
+        cells: &[CellAddress],
+    ) -> Result<Vec<CellAddress>, CircularReference> {
+        let nodes = cells
+            .iter()
+            .filter_map(|cell| self.find(cell))
+            .collect::<Vec<_>>();
+        let successors = nodes
+            .iter()
+            .map(|node| {
+                let precedents = &self.formulas[*node].1;
+                nodes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, other)| {
+                        precedents
+                            .iter()
+                            .any(|range| range.contains(&self.formulas[**other].0))
+                    })
+                    .map(|(position, _)| position)
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+        let mut order = Vec::new();
+        let mut cycles = Vec::new();
+        for component in strongly_connected_components(&successors) {
+            let is_cycle = component.len() > 1 || successors[component[0]].contains(&component[0]);
+            let mut component = component
+                .into_iter()
+                .map(|position| self.formulas[nodes[position]].0.clone())
+                .collect::<Vec<_>>();
+            if is_cycle {
+                component.sort();
+                cycles.push(component);
+            } else {
+                order.append(&mut component);
+            }
+        }
+        if !cycles.is_empty() {
+            cycles.sort();
+            return Err(CircularReference { cycles });
+        }
+        Ok(order)
+    }
+    /// Every cycle of the workbook's formulas.
+    pub fn find_circular_references(&self) -> Option<CircularReference> {
+        let cells = self
+            .formulas
+            .iter()
+            .map(|(cell, _)| cell.clone())
+            .collect::<Vec<_>>();
+        self.get_calculation_order(&cells).err()
+    }
+    fn find(&self, cell: &CellAddress) -> Option<usize> {
+        self.formulas
+            .iter()
+            .position(|(formula_cell, _)| formula_cell.is_same_cell(cell))
+    }
+}
+
+/// Ranges `expr` reads on `sheet`, defined names are followed to the ranges they name.
+fn collect_precedents<S: CellSource>(
+    source: &S,
+    sheet: &str,
+    expr: &Expr,
+    precedents: &mut Vec<Precedent>,
+    names: &mut Vec<String>,
+) {
+    let collect = |expr: &Expr, precedents: &mut Vec<Precedent>, names: &mut Vec<String>| {
+        collect_precedents(source, sheet, expr, precedents, names)
+    };
+    match expr {
+        Expr::Reference(Reference {
+            sheet: target,
+            area,
+        }) => precedents.push(Precedent::new(target.as_deref().unwrap_or(sheet), area)),
+        Expr::Reference3D(Reference3D {
+            first_sheet,
+            last_sheet,
+            area,
+        }) => precedents.extend(
+            get_sheet_span(source, first_sheet, last_sheet)
+                .unwrap_or_default()
+                .iter()
+                .map(|target| Precedent::new(target, area)),
+        ),
+        Expr::Binary {
+            op: BinaryOp::Range,
+            left,
+            right,
+        } => {
+            let mut sides = Vec::new();
+            collect(left, &mut sides, names);
+            collect(right, &mut sides, names);
+            match sides.as_slice() {
+                [left, right] => match left.clone().union(right.clone()) {
+                    Some(range) => precedents.push(range),
+                    None => precedents.append(&mut sides),
+                },
+                _ => precedents.append(&mut sides),
+            }
+        }
+        Expr::Name { sheet: scope, name } => {
+            let scope = scope.as_deref().unwrap_or(sheet);
+            let key = format!("{}!{}", scope, name).to_lowercase();
+            if names.contains(&key) {
+                return;
+            }
+            let Some(formula) = source.get_defined_name(name, scope) else {
+                return;
+            };
+            names.push(key);
+            if let Ok(expr) = parse(&formula) {
+                collect(&expr, precedents, names);
+            }
+        }
+        Expr::Array(rows) => rows
+            .iter()
+            .flatten()
+            .for_each(|expr| collect(expr, precedents, names)),
+        Expr::Function { args, .. } => args
+            .iter()
+            .for_each(|expr| collect(expr, precedents, names)),
+        Expr::Unary { operand, .. } => collect(operand, precedents, names),
+        Expr::Binary { left, right, .. } => {
+            collect(left, precedents, names);
+            collect(right, precedents, names);
+        }
+        Expr::Parenthesized(expr) => collect(expr, precedents, names),
+        Expr::Number(_)
+        | Expr::Text(_)
+        | Expr::Bool(_)
+        | Expr::Error(_)
+        | Expr::StructuredReference(_)
+        | Expr::Missing => (),
+    }
+}
+/// Tarjan's algorithm without recursion, so that long chains of formulas do not overflow the stack.
+/// A component comes after every component its nodes reach.
+fn strongly_connected_components(successors: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut indexes: Vec<Option<usize>> = vec![None; successors.len()];
+    let mut low_links = vec![0; successors.len()];
+    let mut on_stack = vec![false; successors.len()];
+    let mut stack = Vec::new();
+    let mut components = Vec::new();
+    let mut next_index = 0;
+    for root in 0..successors.len() {
+        if indexes[root].is_some() {
+            continue;
+        }
+        let mut work = vec![(root, 0)];
+        while let Some((node, position)) = work.pop() {
+            if indexes[node].is_none() {
+                indexes[node] = Some(next_index);
+                low_links[node] = next_index;
+                next_index += 1;
+                stack.push(node);
+                on_stack[node] = true;
+            }
+            if let Some(&successor) = successors[node].get(position) {
+                work.push((node, position + 1));
+                match indexes[successor] {
+                    None => work.push((successor, 0)),
+                    Some(index) if on_stack[successor] => {
+                        low_links[node] = low_links[node].min(index)
+                    }
+                    Some(_) => (),
+                }
+                continue;
+            }
+            if Some(low_links[node]) == indexes[node] {
+                let mut component = Vec::new();
+                while let Some(member) = stack.pop() {
+                    on_stack[member] = false;
+                    component.push(member);
+                    if member == node {
+                        break;
+                    }
+                }
+                components.push(component);
+            }
+            if let Some(&(parent, _)) = work.last() {
+                low_links[parent] = low_links[parent].min(low_links[node]);
+            }
+        }
+    }
+    components
+}
+
+#[cfg(test)]
+mod dependency_test {
+    use crate::excel::formulas::evaluator::evaluator_test::SourceMock;
+
+    use super::{CellAddress, DependencyGraph};
+
+    fn cell(sheet: &str, cell_index: &str) -> CellAddress {
+        CellAddress::from_index(sheet, cell_index)
+    }
+    #[test]
+    fn affected_cells_test() {
+        let sources = vec![
+            SourceMock::new(
+                "Sheet1",
+                &[
+                    ("A1", "1"),
+                    ("A2", "=A1*2"),
+                    ("A3", "=SUM(A1:A2)"),
+                    ("B2", "=SUM(A:A)"),
+                    ("B1", "=C1"),
+                ],
+            ),
+            SourceMock::new("Sheet2", &[("A1", "=Sheet1!A2+1"), ("A2", "=A1")]),
+        ];
+        let graph = DependencyGraph::new(&sources);
+        assert_eq!(
+            graph.get_dependents(&cell("Sheet1", "A1")),
+            vec![
+                &cell("Sheet1", "A2"),
+                &cell("Sheet1", "B2"),
+                &cell("Sheet1", "A3")
+            ]
+        );
+        let affected = graph.get_affected_cells(&[cell("Sheet1", "A1")]);
+        assert_eq!(affected.len(), 5);
+        assert!(!affected.contains(&cell("Sheet1", "B1")));
+        let order = graph.get_calculation_order(&affected).unwrap();
+        let position = |target: CellAddress| order.iter().position(|c| *c == target).unwrap();
+        assert!(position(cell("Sheet1", "A2")) < position(cell("Sheet1", "A3")));
+        assert!(position(cell("Sheet1", "A2")) < position(cell("Sheet2", "A1")));
+        assert!(position(cell("Sheet2", "A1")) < position(cell("Sheet2", "A2")));
+        assert_eq!(
+            graph.get_affected_cells(&[cell("sheet1", "C1")]),
+            vec![cell("Sheet1", "B1")]
+        );
+    }
+    #[test]
+    fn circular_reference_test() {
+        let sources = vec![SourceMock::new(
+            "Sheet1",
+            &[
+                ("A1", "=B1+1"),
+                ("B1", "=A1*2"),
+                ("C1", "=C1"),
+                ("D1", "=A1"),
+                ("E1", "1"),
+            ],
+        )];
+        let graph = DependencyGraph::new(&sources);
+        let circular = graph.find_circular_references().unwrap();
+        assert_eq!(
+            circular.cycles,
+            vec![
+                vec![cell("Sheet1", "A1"), cell("Sheet1", "B1")],
+                vec![cell("Sheet1", "C1")]
+            ]
+        );
+        assert_eq!(
+            circular.to_string(),
+            "circular reference: Sheet1!A1, Sheet1!B1 / Sheet1!C1"
+        );
+        assert_eq!(
+            graph.get_calculation_order(&[cell("Sheet1", "D1")]),
+            Ok(vec![cell("Sheet1", "D1")])
+        );
+    }
+}
@@ -0,0 +1,789 @@
+use std::{
+    cell::{Cell, RefCell},
+    collections::{HashMap, HashSet},
+};
+
+use crate::excel::cells::{
+    cell_value::{CellErrorKind, CellValue},
+    date_time::ExcelDateTime,
+};
+
+use super::{
+    ast::{Area, BinaryOp, Expr, Reference, UnaryOp},
+    dependency::CellAddress,
+    functions::{self, FunctionContext},
+    parser::parse,
+    value::{compare, to_bool, to_number, to_text, Value},
+};
+
+/// Cells the evaluator reads, rows and columns are 1-based.
+/// Every method answers `None` for a sheet it does not have.
+pub trait CellSource {
+    fn get_value_at(&self, sheet: &str, row: usize, column: usize) -> Option<CellValue>;
+    /// Formula without `=`, shared formulas already moved to the cell.
+    fn get_formula_at(&self, sheet: &str, row: usize, column: usize) -> Option<String>;
+    /// Last row and column holding a cell, whole column and row references stop there.
+    fn get_used_range(&self, sheet: &str) -> Option<(usize, usize)>;
+    /// Sheets the source has, in workbook order.
+    fn get_sheet_names(&self) -> Vec<String>;
+    /// Row and column of every cell holding a formula.
+    fn get_formula_cells(&self, sheet: &str) -> Vec<(usize, usize)>;
+    /// Formula of a defined name, a sheet scoped one first.
+    fn get_defined_name(&self, _name: &str, _sheet: &str) -> Option<String> {
+        None
+    }
+    fn is_date1904(&self) -> bool {
+        false
+    }
+}
+/// The first source having the sheet answers.
+impl<C: CellSource> CellSource for Vec<C> {
+    fn get_value_at(&self, sheet: &str, row: usize, column: usize) -> Option<CellValue> {
+        self.iter()
+            .find_map(|source| source.get_value_at(sheet, row, column))
+    }
+    fn get_formula_at(&self, sheet: &str, row: usize, column: usize) -> Option<String> {
+        self.iter()
+            .find_map(|source| source.get_formula_at(sheet, row, column))
+    }
+    fn get_used_range(&self, sheet: &str) -> Option<(usize, usize)> {
+        self.iter().find_map(|source| source.get_used_range(sheet))
+    }
+    fn get_sheet_names(&self) -> Vec<String> {
+        self.iter()
+            .flat_map(|source| source.get_sheet_names())
+            .collect()
+    }
+    fn get_formula_cells(&self, sheet: &str) -> Vec<(usize, usize)> {
+        self.iter()
+            .find(|source| source.get_used_range(sheet).is_some())
+            .map(|source| source.get_formula_cells(sheet))
+            .unwrap_or_default()
+    }
+    fn get_defined_name(&self, name: &str, sheet: &str) -> Option<String> {
+        self.iter()
+            .find_map(|source| source.get_defined_name(name, sheet))
+    }
+    fn is_date1904(&self) -> bool {
+        self.iter().any(|source| source.is_date1904())
+    }
+}
+
+/// Sheets of a 3-D reference from `first` to `last` in the order of the source, `None` when either is missing.
+pub(crate) fn get_sheet_span<S: CellSource>(
+    source: &S,
+    first: &str,
+    last: &str,
+) -> Option<Vec<String>> {
+    let sheets = source.get_sheet_names();
+    let position = |name: &str| {
+        sheets
+            .iter()
+            .position(|sheet| sheet.to_lowercase() == name.to_lowercase())
+    };
+    let (first, last) = (position(first)?, position(last)?);
+    Some(sheets[first.min(last)..=first.max(last)].to_vec())
+}
+
+type CellKey = (String, usize, usize);
+/// Calculates formulas, reading referenced formula cells by calculating them too.
+///
+///     let evaluator = Evaluator::new(&sheet);
+///     assert_eq!(evaluator.evaluate_cell("Sheet1", "C1"), CellValue::Number(3.0));
+///
+pub struct Evaluator<'s, S: CellSource> {
+    source: &'s S,
+    values: RefCell<HashMap<CellKey, CellValue>>,
+    evaluating: RefCell<HashSet<CellKey>>,
+    stale_cells: Option<HashSet<CellKey>>,
+    is_unsupported: Cell<bool>,
+    unsupported_cells: RefCell<HashSet<CellKey>>,
+}
+impl<'s, S: CellSource> Evaluator<'s, S> {
+    pub fn new(source: &'s S) -> Self {
+        Evaluator {
+            source,
+            values: RefCell::new(HashMap::new()),
+            evaluating: RefCell::new(HashSet::new()),
+            stale_cells: None,
+            is_unsupported: Cell::new(false),
+            unsupported_cells: RefCell::new(HashSet::new()),
+        }
+    }
+    /// Calculates only these formula cells, other formula cells are read from their cached values.
+    pub fn with_stale_cells(mut self, cells: &[CellAddress]) -> Self {
+        self.stale_cells = Some(
+            cells
+                .iter()
+                .map(|cell| (cell.sheet.to_lowercase(), cell.row, cell.column))
+                .collect(),
+        );
+        self
+    }
+    /// Value of the cell, calculating it when it holds a formula.
+    pub fn evaluate_cell(&self, sheet: &str, cell_index: &str) -> CellValue {
+        let Some(reference) = super::ast::CellReference::new(cell_index) else {
+            return CellValue::Error(CellErrorKind::Ref);
+        };
+        self.get_cell(sheet, reference.row.index, reference.column.index)
+            .unwrap_or(CellValue::Error(CellErrorKind::Ref))
+    }
+    /// Whether the value of the cell came from a function or a formula this evaluator
+    /// does not know, directly or through the cells it reads, so Excel may calculate another one.
+    ///
+    ///     // A1 = ROW(), B1 = IFERROR(A1,0)
+    ///     evaluator.evaluate_cell("Sheet1", "B1");
+    ///     assert!(evaluator.is_unsupported("Sheet1", "B1"));
+    ///
+    pub fn is_unsupported(&self, sheet: &str, cell_index: &str) -> bool {
+        super::ast::CellReference::new(cell_index).is_some_and(|reference| {
+            self.unsupported_cells.borrow().contains(&(
+                sheet.to_lowercase(),
+                reference.row.index,
+                reference.column.index,
+            ))
+        })
+    }
+    /// Calculates `formula` as if written on `sheet`.
+    pub fn evaluate(&self, sheet: &str, formula: &str) -> CellValue {
+        match parse(formula) {
+            Ok(expr) => match self.evaluate_expr(sheet, &expr).into_scalar() {
+                CellValue::Empty => CellValue::Number(0.0),
+                value => value,
+            },
+            Err(_) => {
+                self.is_unsupported.set(true);
+                CellValue::Error(CellErrorKind::Name)
+            }
+        }
+    }
+    /// `None` when the sheet does not exist.
+    /// A cell met again while calculating itself is 0, as Excel shows a circular reference.
+    fn get_cell(&self, sheet: &str, row: usize, column: usize) -> Option<CellValue> {
+        let key = (sheet.to_lowercase(), row, column);
+        if let Some(value) = self.values.borrow().get(&key) {
+            if self.unsupported_cells.borrow().contains(&key) {
+                self.is_unsupported.set(true);
+            }
+            return Some(value.clone());
+        }
+        let is_stale = self
+            .stale_cells
+            .as_ref()
+            .is_none_or(|stale_cells| stale_cells.contains(&key));
+        let formula = is_stale
+            .then(|| self.source.get_formula_at(sheet, row, column))
+            .flatten();
+        let Some(formula) = formula else {
+            return self
+                .source
+                .get_value_at(sheet, row, column)
+                .map(|value| self.normalize(value));
+        };
+        if !self.evaluating.borrow_mut().insert(key.clone()) {
+            return Some(CellValue::Number(0.0));
+        }
+        // the flag is per cell, a dependent inherits it when reading this one
+        let is_outer_unsupported = self.is_unsupported.replace(false);
+        let value = self.evaluate(sheet, &formula);
+        self.evaluating.borrow_mut().remove(&key);
+        if self.is_unsupported.get() {
+            self.unsupported_cells.borrow_mut().insert(key.clone());
+        }
+        self.is_unsupported
+            .set(is_outer_unsupported || self.is_unsupported.get());
+        self.values.borrow_mut().insert(key, value.clone());
+        Some(value)
+    }
+    /// Dates become serial numbers and inline strings strings.
+    fn normalize(&self, value: CellValue) -> CellValue {
+        match value {
+            CellValue::DateTime(date_time) => {
+                CellValue::Number(date_time.to_serial(self.is_date1904_for(&date_time)))
+            }
+            CellValue::Duration(duration) => CellValue::Number(duration.to_serial()),
+            CellValue::InlineString(s) => CellValue::String(s),
+            value => value,
+        }
+    }
+    /// Time only values sit on 1899-12-31 in both date systems.
+    fn is_date1904_for(&self, date_time: &ExcelDateTime) -> bool {
+        date_time.get_year() > 1899 && self.source.is_date1904()
+    }
+    fn context(&self) -> FunctionContext {
+        FunctionContext {
+            is_date1904: self.source.is_date1904(),
+        }
+    }
+    pub(crate) fn evaluate_expr(&self, sheet: &str, expr: &Expr) -> Value {
+        match expr {
+            Expr::Number(number) => Value::Scalar(CellValue::Number(*number)),
+            Expr::Text(text) => Value::Scalar(CellValue::String(text.clone())),
+            Expr::Bool(b) => Value::Scalar(CellValue::Bool(*b)),
+            Expr::Error(kind) => Value::error(*kind),
+            Expr::Missing => Value::Scalar(CellValue::Empty),
+            Expr::Parenthesized(expr) => self.evaluate_expr(sheet, expr),
+            Expr::Array(rows) => Value::Array(
+                rows.iter()
+                    .map(|row| {
+                        row.iter()
+                            .map(|value| self.evaluate_expr(sheet, value).into_scalar())
+                            .collect()
+                    })
+                    .collect(),
+            ),
+            Expr::Reference(reference) => self.evaluate_reference(sheet, reference),
+            Expr::Reference3D(reference) => {
+                let Some(sheets) =
+                    get_sheet_span(self.source, &reference.first_sheet, &reference.last_sheet)
+                else {
+                    return Value::error(CellErrorKind::Ref);
+                };
+                // as a union, aggregate functions are the only readers
+                let values = sheets
+                    .iter()
+                    .flat_map(|target| {
+                        let reference = Reference {
+                            sheet: Some(target.clone()),
+                            area: reference.area.clone(),
+                        };
+                        self.evaluate_reference(sheet, &reference)
+                            .values()
+                            .into_iter()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                Value::Array(vec![values])
+            }
+            Expr::Name { sheet: scope, name } => {
+                let scope = scope.as_deref().unwrap_or(sheet);
+                match self.source.get_defined_name(name, scope) {
+                    Some(formula) => match parse(&formula) {
+                        Ok(expr) => self.evaluate_expr(scope, &expr),
+                        Err(_) => {
+                            self.is_unsupported.set(true);
+                            Value::error(CellErrorKind::Name)
+                        }
+                    },
+                    None => Value::error(CellErrorKind::Name),
+                }
+            }
+            Expr::StructuredReference(_) => {
+                self.is_unsupported.set(true);
+                Value::error(CellErrorKind::Ref)
+            }
+            Expr::Unary { op, operand } => {
+                let operand = self.evaluate_expr(sheet, operand);
+                operand.map(|value| {
+                    number_result(to_number(&value).map(|number| match op {
+                        UnaryOp::Plus => number,
+                        UnaryOp::Minus => -number,
+                        UnaryOp::Percent => number / 100.0,
+                    }))
+                })
+            }
+            Expr::Binary { op, left, right } => self.evaluate_binary(sheet, *op, left, right),
+            Expr::Function { name, args } => self.evaluate_function(sheet, name, args),
+        }
+    }
+    fn evaluate_reference(&self, sheet: &str, reference: &Reference) -> Value {
+        let sheet = reference.sheet.as_deref().unwrap_or(sheet);
+        match self.get_area_bounds(sheet, &reference.area) {
+            Some(bounds) => self.read_area(sheet, bounds),
+            None => Value::error(CellErrorKind::Ref),
+        }
+    }
+    /// First and last row, then first and last column.
+    fn get_area_bounds(&self, sheet: &str, area: &Area) -> Option<(usize, usize, usize, usize)> {
+        let (last_row, last_column) = self.source.get_used_range(sheet)?;
+        let bounds = match area {
+            Area::Cell(cell) => (
+                cell.row.index,
+                cell.row.index,
+                cell.column.index,
+                cell.column.index,
+            ),
+            Area::Range(start, end) => (
+                start.row.index.min(end.row.index),
+                start.row.index.max(end.row.index),
+                start.column.index.min(end.column.index),
+                start.column.index.max(end.column.index),
+            ),
+            Area::Columns(start, end) => (
+                1,
+                last_row.max(1),
+                start.index.min(end.index),
+                start.index.max(end.index),
+            ),
+            Area::Rows(start, end) => (
+                start.index.min(end.index),
+                start.index.max(end.index),
+                1,
+                last_column.max(1),
+            ),
+        };
+        Some(bounds)
+    }
+    fn read_area(&self, sheet: &str, bounds: (usize, usize, usize, usize)) -> Value {
+        let (first_row, last_row, first_column, last_column) = bounds;
+        Value::Array(
+            (first_row..=last_row)
+                .map(|row| {
+                    (first_column..=last_column)
+                        .map(|column| {
+                            self.get_cell(sheet, row, column)
+                                .unwrap_or(CellValue::Error(CellErrorKind::Ref))
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+    /// Bounds of a reference expression, for the `:` and space operators.
+    fn evaluate_bounds(
+        &self,
+        sheet: &str,
+        expr: &Expr,
+    ) -> Option<(String, (usize, usize, usize, usize))> {
+        match expr {
+            Expr::Reference(reference) => {
+                let sheet = reference.sheet.as_deref().unwrap_or(sheet);
+                Some((
+                    sheet.to_string(),
+                    self.get_area_bounds(sheet, &reference.area)?,
+                ))
+            }
+            Expr::Parenthesized(expr) => self.evaluate_bounds(sheet, expr),
+            _ => None,
+        }
+    }
+    fn evaluate_binary(&self, sheet: &str, op: BinaryOp, left: &Expr, right: &Expr) -> Value {
+        match op {
+            BinaryOp::Range | BinaryOp::Intersect => {
+                let (Some((left_sheet, left)), Some((right_sheet, right))) = (
+                    self.evaluate_bounds(sheet, left),
+                    self.evaluate_bounds(sheet, right),
+                ) else {
+                    return Value::error(CellErrorKind::Value);
+                };
+                if !left_sheet.eq_ignore_ascii_case(&right_sheet) {
+                    return Value::error(CellErrorKind::Value);
+                }
+                let bounds = if op == BinaryOp::Range {
+                    (
+                        left.0.min(right.0),
+                        left.1.max(right.1),
+                        left.2.min(right.2),
+                        left.3.max(right.3),
+                    )
+                } else {
+                    let bounds = (
+                        left.0.max(right.0),
+                        left.1.min(right.1),
+                        left.2.max(right.2),
+                        left.3.min(right.3),
+                    );
+                    if bounds.0 > bounds.1 || bounds.2 > bounds.3 {
+                        return Value::error(CellErrorKind::Null);
+                    }
+                    bounds
+                };
+                self.read_area(&left_sheet, bounds)
+            }
+            BinaryOp::Union => {
+                // aggregate functions are the only readers, a single row keeps every value
+                let values = [left, right]
+                    .iter()
+                    .flat_map(|expr| {
+                        self.evaluate_expr(sheet, expr)
+                            .values()
+                            .into_iter()
+                            .cloned()
+                            .collect::<Vec<_>>()
+                    })
+                    .collect();
+                Value::Array(vec![values])
+            }
+            op => {
+                let left = self.evaluate_expr(sheet, left);
+                let right = self.evaluate_expr(sheet, right);
+                left.zip(right, |left, right| calculate(op, &left, &right))
+            }
+        }
+    }
+    /// IF, IFS, IFERROR, IFNA and CHOOSE only calculate the argument they return.
+    fn evaluate_function(&self, sheet: &str, name: &str, args: &[Expr]) -> Value {
+        let name = functions::normalize_name(name);
+        let evaluate = |expr: &Expr| self.evaluate_expr(sheet, expr);
+        let condition = |expr: &Expr| to_bool(&evaluate(expr).into_scalar());
+        match name.as_str() {
+            "IF" => {
+                if args.is_empty() || args.len() > 3 {
+                    return Value::error(CellErrorKind::Value);
+                }
+                match condition(&args[0]) {
+                    Ok(true) => args.get(1).map(evaluate),
+                    Ok(false) => Some(
+                        args.get(2)
+                            .map(evaluate)
+                            .unwrap_or(Value::Scalar(CellValue::Bool(false))),
+                    ),
+                    Err(kind) => Some(Value::error(kind)),
+                }
+                .map(|value| match value {
+                    Value::Scalar(CellValue::Empty) => Value::Scalar(CellValue::Number(0.0)),
+                    value => value,
+                })
+                .unwrap_or(Value::Scalar(CellValue::Bool(true)))
+            }
+            "IFS" => {
+                for pair in args.chunks(2) {
+                    let [test, value] = pair else {
+                        return Value::error(CellErrorKind::Value);
+                    };
+                    match condition(test) {
+                        Ok(true) => return evaluate(value),
+                        Ok(false) => continue,
+                        Err(kind) => return Value::error(kind),
+                    }
+                }
+                Value::error(CellErrorKind::NA)
+            }
+            "IFERROR" | "IFNA" => {
+                let [value, fallback] = args else {
+                    return Value::error(CellErrorKind::Value);
+                };
+                let value = evaluate(value);
+                let is_caught = |cell: &CellValue| match cell {
+                    CellValue::Error(CellErrorKind::NA) => true,
+                    CellValue::Error(_) => name == "IFERROR",
+                    _ => false,
+                };
+                match value {
+                    Value::Scalar(ref cell) if is_caught(cell) => evaluate(fallback),
+                    Value::Array(_) => {
+                        let fallback = evaluate(fallback).into_scalar();
+                        value.map(|cell| {
+                            if is_caught(&cell) {
+                                fallback.clone()
+                            } else {
+                                cell
+                            }
+                        })
+                    }
+                    value => value,
+                }
+            }
+            "CHOOSE" => {
+                let Some((index, choices)) = args.split_first() else {
+                    return Value::error(CellErrorKind::Value);
+                };
+                match to_number(&evaluate(index).into_scalar()) {
+                    Ok(index) if index >= 1.0 && (index as usize) <= choices.len() => {
+                        evaluate(&choices[index as usize - 1])
+                    }
+                    Ok(_) => Value::error(CellErrorKind::Value),
+                    Err(kind) => Value::error(kind),
+                }
+            }
+            _ => {
+                let args = args.iter().map(evaluate).collect::<Vec<_>>();
+                functions::call(&name, &args, &self.context()).unwrap_or_else(|| {
+                    self.is_unsupported.set(true);
+                    Value::error(CellErrorKind::Name)
+                })
+            }
+        }
+    }
+}
+/// Arithmetic, `&` and comparisons of two values, an error on either side is the result.
+fn calculate(op: BinaryOp, left: &CellValue, right: &CellValue) -> CellValue {
+    if let CellValue::Error(kind) = left {
+        return CellValue::Error(*kind);
+    }
+    if let CellValue::Error(kind) = right {
+        return CellValue::Error(*kind);
+    }
+    let numbers = || Ok::<_, CellErrorKind>((to_number(left)?, to_number(right)?));
+    match op {
+        BinaryOp::Add => number_result(numbers().map(|(l, r)| l + r)),
+        BinaryOp::Sub => number_result(numbers().map(|(l, r)| l - r)),
+        BinaryOp::Mul => number_result(numbers().map(|(l, r)| l * r)),
+        BinaryOp::Div => number_result(numbers().and_then(|(l, r)| {
+            if r == 0.0 {
+                Err(CellErrorKind::Div0)
+            } else {
+                Ok(l / r)
+            }
+        })),
+        BinaryOp::Pow => number_result(numbers().map(|(l, r)| l.powf(r))),
+        BinaryOp::Concat => match (to_text(left), to_text(right)) {
+            (Ok(left), Ok(right)) => CellValue::String(left + &right),
+            (Err(kind), _) | (_, Err(kind)) => CellValue::Error(kind),
+        },
+        BinaryOp::Eq => CellValue::Bool(compare(left, right).is_eq()),
+        BinaryOp::Ne => CellValue::Bool(compare(left, right).is_ne()),
+        BinaryOp::Lt => CellValue::Bool(compare(left, right).is_lt()),
+        BinaryOp::Le => CellValue::Bool(compare(left, right).is_le()),
+        BinaryOp::Gt => CellValue::Bool(compare(left, right).is_gt()),
+        BinaryOp::Ge => CellValue::Bool(compare(left, right).is_ge()),
+        BinaryOp::Range | BinaryOp::Intersect | BinaryOp::Union => {
+            CellValue::Error(CellErrorKind::Value)
+        }
+    }
+}
+/// Infinite or NaN results are `#NUM!`.
+pub(crate) fn number_result(result: Result<f64, CellErrorKind>) -> CellValue {
+    match result {
+        Ok(number) if number.is_finite() => CellValue::Number(number),
+        Ok(_) => CellValue::Error(CellErrorKind::Num),
+        Err(kind) => CellValue::Error(kind),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod evaluator_test {
+    use std::collections::HashMap;
+
+    use crate::excel::{
+        cells::{
+            cell::CellIndex,
+            cell_value::{CellErrorKind, CellValue},
+        },
+        formulas::value::parse_number,
+    };
+
+    use super::{CellSource, Evaluator};
+
+    /// Cells written as in a sheet, `=` starts a formula.
+    pub(crate) struct SourceMock {
+        pub name: &'static str,
+        pub cells: HashMap<(usize, usize), &'static str>,
+    }
+    impl SourceMock {
+        pub fn new(name: &'static str, cells: &[(&'static str, &'static str)]) -> Self {
+            SourceMock {
+                name,
+                cells: cells
+                    .iter()
+                    .map(|(index, value)| {
+                        let index = CellIndex::new(index);
+                        ((index.get_row_index(), index.get_column_index()), *value)
+                    })
+                    .collect(),
+            }
+        }
+    }
+    impl CellSource for SourceMock {
+        fn get_value_at(&self, sheet: &str, row: usize, column: usize) -> Option<CellValue> {
+            if sheet != self.name {
+                return None;
+            }
+            let value = match self.cells.get(&(row, column)) {
+                None => CellValue::Empty,
+                Some(value) if value.starts_with('=') => CellValue::Empty,
+                Some(value) => match (*value, parse_number(value)) {
+                    ("TRUE", _) => CellValue::Bool(true),
+                    ("FALSE", _) => CellValue::Bool(false),
+                    (value, _) if value.starts_with('#') => {
+                        CellValue::Error(CellErrorKind::new(value).unwrap())
+                    }
+                    (value, _) if value.starts_with('\'') => {
+                        CellValue::String(value[1..].to_string())
+                    }
+                    (_, Some(number)) => CellValue::Number(number),
+                    (value, None) => CellValue::String(value.to_string()),
+                },
+            };
+            Some(value)
+        }
+        fn get_formula_at(&self, sheet: &str, row: usize, column: usize) -> Option<String> {
+            if sheet != self.name {
+                return None;
+            }
+            self.cells
+                .get(&(row, column))
+                .and_then(|value| value.strip_prefix('='))
+                .map(|formula| formula.to_string())
+        }
+        fn get_used_range(&self, sheet: &str) -> Option<(usize, usize)> {
+            if sheet != self.name {
+                return None;
+            }
+            Some((
+                self.cells.keys().map(|key| key.0).max().unwrap_or(0),
+                self.cells.keys().map(|key| key.1).max().unwrap_or(0),
+            ))
+        }
+        fn get_sheet_names(&self) -> Vec<String> {
+            vec![self.name.to_string()]
+        }
+        fn get_formula_cells(&self, sheet: &str) -> Vec<(usize, usize)> {
+            if sheet != self.name {
+                return Vec::new();
+            }
+            let mut cells = self
+                .cells
+                .iter()
+                .filter(|(_, value)| value.starts_with('='))
+                .map(|(key, _)| *key)
+                .collect::<Vec<_>>();
+            cells.sort_unstable();
+            cells
+        }
+    }
+    /// Calculates `formula` on a sheet "Sheet1" holding `cells`.
+    pub(crate) fn evaluate(cells: &[(&'static str, &'static str)], formula: &str) -> CellValue {
+        let source = SourceMock::new("Sheet1", cells);
+        Evaluator::new(&source).evaluate("Sheet1", formula)
+    }
+    fn text(s: &str) -> CellValue {
+        CellValue::String(s.to_string())
+    }
+
+    #[test]
+    fn operator_test() {
+        let cells = [("A1", "1"), ("A2", "2"), ("A3", "abc"), ("B1", "=A1+A2")];
+        assert_eq!(evaluate(&cells, "=1+2*3"), CellValue::Number(7.0));
+        assert_eq!(evaluate(&cells, "=-2^2"), CellValue::Number(4.0));
+        assert_eq!(evaluate(&cells, "=B1*10"), CellValue::Number(30.0));
+        assert_eq!(evaluate(&cells, "=A1&\"-\"&A3"), text("1-abc"));
+        assert_eq!(evaluate(&cells, "=50%"), CellValue::Number(0.5));
+        assert_eq!(evaluate(&cells, "=A3>A1"), CellValue::Bool(true));
+        assert_eq!(evaluate(&cells, "=A3=\"ABC\""), CellValue::Bool(true));
+        assert_eq!(evaluate(&cells, "=Z9"), CellValue::Number(0.0));
+        assert_eq!(evaluate(&cells, "=Z9=\"\""), CellValue::Bool(true));
+    }
+    #[test]
+    fn error_propagation_test() {
+        let cells = [("A1", "abc"), ("A2", "#N/A"), ("A3", "0")];
+        assert_eq!(
+            evaluate(&cells, "=1/A3"),
+            CellValue::Error(CellErrorKind::Div0)
+        );
+        assert_eq!(
+            evaluate(&cells, "=A1+1"),
+            CellValue::Error(CellErrorKind::Value)
+        );
+        assert_eq!(
+            evaluate(&cells, "=A2&\"x\""),
+            CellValue::Error(CellErrorKind::NA)
+        );
+        assert_eq!(
+            evaluate(&cells, "=1/0+A2"),
+            CellValue::Error(CellErrorKind::Div0)
+        );
+        assert_eq!(
+            evaluate(&cells, "=Other!A1"),
+            CellValue::Error(CellErrorKind::Ref)
+        );
+        assert_eq!(
+            evaluate(&cells, "=UNKNOWN(1)"),
+            CellValue::Error(CellErrorKind::Name)
+        );
+        assert_eq!(
+            evaluate(&cells, "=SUM(A1:A2 B1:B2)"),
+            CellValue::Error(CellErrorKind::Null)
+        );
+    }
+    #[test]
+    fn lazy_function_test() {
+        let cells = [("A1", "0"), ("A2", "#N/A")];
+        assert_eq!(evaluate(&cells, "=IF(A1=0,\"zero\",1/A1)"), text("zero"));
+        assert_eq!(evaluate(&cells, "=IF(FALSE,1)"), CellValue::Bool(false));
+        assert_eq!(evaluate(&cells, "=IF(TRUE,)"), CellValue::Number(0.0));
+        assert_eq!(
+            evaluate(&cells, "=IF(A2,1,2)"),
+            CellValue::Error(CellErrorKind::NA)
+        );
+        assert_eq!(evaluate(&cells, "=IFS(A1>1,\"a\",A1=0,\"b\")"), text("b"));
+        assert_eq!(
+            evaluate(&cells, "=IFS(A1>1,\"a\")"),
+            CellValue::Error(CellErrorKind::NA)
+        );
+        assert_eq!(evaluate(&cells, "=IFERROR(1/A1,\"div\")"), text("div"));
+        assert_eq!(evaluate(&cells, "=IFNA(A2,\"na\")"), text("na"));
+        assert_eq!(
+            evaluate(&cells, "=IFNA(1/A1,\"na\")"),
+            CellValue::Error(CellErrorKind::Div0)
+        );
+        assert_eq!(evaluate(&cells, "=CHOOSE(2,\"a\",\"b\")"), text("b"));
+    }
+    #[test]
+    fn cross_sheet_and_cycle_test() {
+        let sources = vec![
+            SourceMock::new(
+                "Sheet1",
+                &[("A1", "=テーブル一覧!B2*2"), ("A2", "=A3"), ("A3", "=A2+1")],
+            ),
+            SourceMock::new("テーブル一覧", &[("B2", "=C2+1"), ("C2", "20")]),
+        ];
+        let evaluator = Evaluator::new(&sources);
+        assert_eq!(
+            evaluator.evaluate_cell("Sheet1", "A1"),
+            CellValue::Number(42.0)
+        );
+        assert_eq!(
+            evaluator.evaluate("Sheet1", "='テーブル一覧'!C2"),
+            CellValue::Number(20.0)
+        );
+        assert_eq!(
+            evaluator.evaluate_cell("Sheet1", "A3"),
+            CellValue::Number(1.0)
+        );
+    }
+    #[test]
+    fn reference_3d_test() {
+        let sources = vec![
+            SourceMock::new("Jan", &[("A1", "1"), ("A2", "10")]),
+            SourceMock::new("Feb", &[("A1", "2")]),
+            SourceMock::new("Mar", &[("A1", "=Feb!A1*2")]),
+        ];
+        let evaluator = Evaluator::new(&sources);
+        assert_eq!(
+            evaluator.evaluate("Jan", "=SUM(Jan:Mar!A1)"),
+            CellValue::Number(7.0)
+        );
+        assert_eq!(
+            evaluator.evaluate("Jan", "=SUM(Feb:Jan!A1:A2)"),
+            CellValue::Number(13.0)
+        );
+        assert_eq!(
+            evaluator.evaluate("Jan", "=SUM(Jan:Apr!A1)"),
+            CellValue::Error(CellErrorKind::Ref)
+        );
+    }
+    #[test]
+    fn unsupported_test() {
+        let sources = vec![SourceMock::new(
+            "Sheet1",
+            &[
+                ("A1", "1"),
+                ("B1", "=ROW()+A1"),
+                ("C1", "=B1*2"),
+                ("D1", "=IFERROR(OFFSET(A1,0,0),5)"),
+                ("E1", "=A1*3"),
+                ("F1", "=C1+E1"),
+            ],
+        )];
+        let evaluator = Evaluator::new(&sources);
+        assert_eq!(
+            evaluator.evaluate_cell("Sheet1", "D1"),
+            CellValue::Number(5.0)
+        );
+        assert!(evaluator.is_unsupported("Sheet1", "D1"));
+        evaluator.evaluate_cell("Sheet1", "F1");
+        assert!(evaluator.is_unsupported("Sheet1", "F1"));
+        assert!(evaluator.is_unsupported("Sheet1", "C1"));
+        assert!(evaluator.is_unsupported("Sheet1", "B1"));
+        assert!(!evaluator.is_unsupported("Sheet1", "E1"));
+        assert!(!evaluator.is_unsupported("Sheet1", "A1"));
+
+        // read again from the values calculated before
+        let evaluator = Evaluator::new(&sources);
+        evaluator.evaluate_cell("Sheet1", "B1");
+        evaluator.evaluate_cell("Sheet1", "C1");
+        assert!(evaluator.is_unsupported("Sheet1", "C1"));
+    }
+}
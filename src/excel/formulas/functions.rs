@@ -0,0 +1,141 @@
+pub mod criteria;
+pub mod date;
+pub mod logical;
+pub mod lookup;
+pub mod math;
+pub mod text;
+
+use crate::excel::cells::cell_value::{CellErrorKind, CellValue};
+
+use super::{
+    evaluator::number_result,
+    value::{to_bool, to_number, to_text, Value},
+};
+
+/// What functions read besides their arguments.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FunctionContext {
+    pub is_date1904: bool,
+}
+pub(crate) type FunctionResult = Result<Value, CellErrorKind>;
+
+/// Upper case name without the `_xlfn.` prefix newer functions are saved with.
+///
+///     assert_eq!(normalize_name("_xlfn.xlookup"), "XLOOKUP");
+///
+pub fn normalize_name(name: &str) -> String {
+    let upper = name.to_ascii_uppercase();
+    ["_XLFN.", "_XLWS."]
+        .iter()
+        .fold(upper.as_str(), |name, prefix| {
+            name.strip_prefix(prefix).unwrap_or(name)
+        })
+        .to_string()
+}
+/// Calls the function by its normalized name,
+/// `None` when it is not implemented here though Excel may know it.
+pub fn call(name: &str, args: &[Value], context: &FunctionContext) -> Option<Value> {
+    math::call(name, args)
+        .or_else(|| logical::call(name, args))
+        .or_else(|| lookup::call(name, args))
+        .or_else(|| text::call(name, args, context))
+        .or_else(|| date::call(name, args, context))
+        .map(|result| result.unwrap_or_else(Value::error))
+}
+
+pub(crate) fn expect_count(args: &[Value], min: usize, max: usize) -> Result<(), CellErrorKind> {
+    if (min..=max).contains(&args.len()) {
+        Ok(())
+    } else {
+        Err(CellErrorKind::Value)
+    }
+}
+/// The argument as one value, an error value is returned as the error.
+pub(crate) fn scalar(args: &[Value], index: usize) -> Result<CellValue, CellErrorKind> {
+    match args.get(index).cloned().map(Value::into_scalar) {
+        Some(CellValue::Error(kind)) => Err(kind),
+        Some(value) => Ok(value),
+        None => Err(CellErrorKind::Value),
+    }
+}
+/// Omitted or left empty, as the second of `ROUND(1.5,)`.
+pub(crate) fn is_omitted(args: &[Value], index: usize) -> bool {
+    matches!(
+        args.get(index),
+        None | Some(Value::Scalar(CellValue::Empty))
+    )
+}
+pub(crate) fn number(args: &[Value], index: usize) -> Result<f64, CellErrorKind> {
+    to_number(&scalar(args, index)?)
+}
+pub(crate) fn optional_number(
+    args: &[Value],
+    index: usize,
+    default: f64,
+) -> Result<f64, CellErrorKind> {
+    if is_omitted(args, index) {
+        return Ok(default);
+    }
+    number(args, index)
+}
+pub(crate) fn text(args: &[Value], index: usize) -> Result<String, CellErrorKind> {
+    to_text(&scalar(args, index)?)
+}
+pub(crate) fn boolean(args: &[Value], index: usize) -> Result<bool, CellErrorKind> {
+    to_bool(&scalar(args, index)?)
+}
+pub(crate) fn optional_boolean(
+    args: &[Value],
+    index: usize,
+    default: bool,
+) -> Result<bool, CellErrorKind> {
+    if is_omitted(args, index) {
+        return Ok(default);
+    }
+    boolean(args, index)
+}
+/// Numbers to aggregate: only numbers of ranges and arrays count,
+/// values written as arguments are read as numbers. Errors are returned.
+pub(crate) fn numbers(args: &[Value]) -> Result<Vec<f64>, CellErrorKind> {
+    let mut numbers = Vec::new();
+    for arg in args {
+        match arg {
+            Value::Array(_) => {
+                for value in arg.values() {
+                    match value {
+                        CellValue::Number(number) => numbers.push(*number),
+                        CellValue::Error(kind) => return Err(*kind),
+                        _ => (),
+                    }
+                }
+            }
+            Value::Scalar(CellValue::Empty) => (),
+            Value::Scalar(value) => numbers.push(to_number(value)?),
+        }
+    }
+    Ok(numbers)
+}
+pub(crate) fn number_value(number: f64) -> FunctionResult {
+    match number_result(Ok(number)) {
+        CellValue::Error(kind) => Err(kind),
+        value => Ok(Value::Scalar(value)),
+    }
+}
+pub(crate) fn text_value(text: impl Into<String>) -> FunctionResult {
+    Ok(Value::Scalar(CellValue::String(text.into())))
+}
+pub(crate) fn bool_value(b: bool) -> FunctionResult {
+    Ok(Value::Scalar(CellValue::Bool(b)))
+}
+
+#[cfg(test)]
+mod functions_test {
+    use super::normalize_name;
+
+    #[test]
+    fn normalize_name_test() {
+        assert_eq!(normalize_name("_xlfn.xlookup"), "XLOOKUP");
+        assert_eq!(normalize_name("_xlfn._xlws.SORT"), "SORT");
+        assert_eq!(normalize_name("Sum"), "SUM");
+    }
+}
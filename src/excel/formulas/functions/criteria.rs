@@ -0,0 +1,176 @@
+use std::cmp::Ordering;
+
+use crate::excel::{
+    cells::cell_value::CellValue,
+    formulas::value::{compare, parse_number},
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum CriteriaOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+/// Condition of COUNTIF and SUMIF, e.g. `">=10"`, `"<>"` or `"東*"`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Criteria {
+    op: CriteriaOp,
+    value: CellValue,
+}
+impl Criteria {
+    pub fn new(criteria: &CellValue) -> Self {
+        let text = match criteria {
+            CellValue::String(text) | CellValue::InlineString(text) => text,
+            CellValue::Empty => {
+                return Criteria {
+                    op: CriteriaOp::Eq,
+                    value: CellValue::Number(0.0),
+                }
+            }
+            value => {
+                return Criteria {
+                    op: CriteriaOp::Eq,
+                    value: value.clone(),
+                }
+            }
+        };
+        let (op, rest) = [
+            (">=", CriteriaOp::Ge),
+            ("<=", CriteriaOp::Le),
+            ("<>", CriteriaOp::Ne),
+            (">", CriteriaOp::Gt),
+            ("<", CriteriaOp::Lt),
+            ("=", CriteriaOp::Eq),
+        ]
+        .iter()
+        .find_map(|(prefix, op)| text.strip_prefix(prefix).map(|rest| (*op, rest)))
+        .unwrap_or((CriteriaOp::Eq, text.as_str()));
+        let value = match parse_number(rest) {
+            Some(number) => CellValue::Number(number),
+            None if rest.eq_ignore_ascii_case("TRUE") => CellValue::Bool(true),
+            None if rest.eq_ignore_ascii_case("FALSE") => CellValue::Bool(false),
+            None => CellValue::String(rest.to_string()),
+        };
+        Criteria { op, value }
+    }
+    pub fn is_match(&self, value: &CellValue) -> bool {
+        let value = match value {
+            CellValue::InlineString(s) => CellValue::String(s.clone()),
+            value => value.clone(),
+        };
+        let is_blank = matches!(&value, CellValue::Empty)
+            || matches!(&value, CellValue::String(s) if s.is_empty());
+        match (&self.value, self.op) {
+            (CellValue::String(pattern), CriteriaOp::Eq) if pattern.is_empty() => is_blank,
+            (CellValue::String(pattern), CriteriaOp::Ne) if pattern.is_empty() => !is_blank,
+            (CellValue::String(pattern), CriteriaOp::Eq) => value
+                .as_str()
+                .is_some_and(|text| wildcard_match(pattern, text)),
+            (CellValue::String(pattern), CriteriaOp::Ne) => !value
+                .as_str()
+                .is_some_and(|text| wildcard_match(pattern, text)),
+            (CellValue::Number(number), op) => {
+                let value = match &value {
+                    CellValue::Number(value) => Some(*value),
+                    CellValue::String(text) if op == CriteriaOp::Eq => parse_number(text),
+                    _ => None,
+                };
+                match value {
+                    Some(value) => self.is_ordering_match(value.partial_cmp(number)),
+                    None => op == CriteriaOp::Ne,
+                }
+            }
+            (criteria, op) => {
+                let is_same_type =
+                    std::mem::discriminant(criteria) == std::mem::discriminant(&value);
+                if !is_same_type {
+                    return op == CriteriaOp::Ne;
+                }
+                self.is_ordering_match(Some(compare(&value, criteria)))
+            }
+        }
+    }
+    fn is_ordering_match(&self, ordering: Option<Ordering>) -> bool {
+        let Some(ordering) = ordering else {
+            return false;
+        };
+        match self.op {
+            CriteriaOp::Eq => ordering.is_eq(),
+            CriteriaOp::Ne => ordering.is_ne(),
+            CriteriaOp::Lt => ordering.is_lt(),
+            CriteriaOp::Le => ordering.is_le(),
+            CriteriaOp::Gt => ordering.is_gt(),
+            CriteriaOp::Ge => ordering.is_ge(),
+        }
+    }
+}
+/// Matches ignoring case, `*` is any text, `?` any char and `~` makes the next char literal.
+///
+///     assert!(wildcard_match("東*", "東京"));
+///
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let pattern = pattern.to_lowercase().chars().collect::<Vec<_>>();
+    let text = text.to_lowercase().chars().collect::<Vec<_>>();
+    fn is_match(pattern: &[char], text: &[char]) -> bool {
+        match pattern.first() {
+            None => text.is_empty(),
+            Some('*') => (0..=text.len()).any(|skip| is_match(&pattern[1..], &text[skip..])),
+            Some('?') => !text.is_empty() && is_match(&pattern[1..], &text[1..]),
+            Some('~') if pattern.len() > 1 => {
+                text.first() == Some(&pattern[1]) && is_match(&pattern[2..], &text[1..])
+            }
+            Some(c) => text.first() == Some(c) && is_match(&pattern[1..], &text[1..]),
+        }
+    }
+    is_match(&pattern, &text)
+}
+/// Whether the lookup value holds a wildcard to match text with.
+pub fn has_wildcard(text: &str) -> bool {
+    text.contains(['*', '?', '~'])
+}
+
+#[cfg(test)]
+mod criteria_test {
+    use crate::excel::cells::cell_value::CellValue;
+
+    use super::{wildcard_match, Criteria};
+
+    fn text(s: &str) -> CellValue {
+        CellValue::String(s.to_string())
+    }
+    #[test]
+    fn criteria_test() {
+        let criteria = Criteria::new(&text(">=10"));
+        assert!(criteria.is_match(&CellValue::Number(10.0)));
+        assert!(!criteria.is_match(&CellValue::Number(9.0)));
+        assert!(!criteria.is_match(&text("abc")));
+        let criteria = Criteria::new(&CellValue::Number(1.0));
+        assert!(criteria.is_match(&CellValue::Number(1.0)));
+        assert!(criteria.is_match(&text("1")));
+        let criteria = Criteria::new(&text("東*"));
+        assert!(criteria.is_match(&text("東京")));
+        assert!(!criteria.is_match(&text("大阪")));
+        let criteria = Criteria::new(&text("<>"));
+        assert!(criteria.is_match(&text("a")));
+        assert!(!criteria.is_match(&CellValue::Empty));
+        let criteria = Criteria::new(&text(""));
+        assert!(criteria.is_match(&CellValue::Empty));
+        let criteria = Criteria::new(&text("<>apple"));
+        assert!(criteria.is_match(&CellValue::Number(1.0)));
+        assert!(!criteria.is_match(&text("APPLE")));
+        let criteria = Criteria::new(&text("<b"));
+        assert!(criteria.is_match(&text("apple")));
+        assert!(!criteria.is_match(&text("cherry")));
+    }
+    #[test]
+    fn wildcard_match_test() {
+        assert!(wildcard_match("a?c", "ABC"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("a~*", "a*"));
+        assert!(!wildcard_match("a~*", "ab"));
+        assert!(!wildcard_match("a?", "a"));
+    }
+}
@@ -0,0 +1,275 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::excel::{
+    cells::{
+        cell_value::{CellErrorKind, CellValue},
+        date_time::ExcelDateTime,
+    },
+    formulas::value::{parse_number, to_number, Value},
+};
+
+use super::{
+    expect_count, number, number_value, optional_number, scalar, text, FunctionContext,
+    FunctionResult,
+};
+
+/// Serial number of 1970-01-01 in the 1900 date system.
+const UNIX_EPOCH_SERIAL: f64 = 25569.0;
+/// Days between the epochs of the 1900 and the 1904 date systems.
+const DATE1904_OFFSET: f64 = 1462.0;
+
+pub(super) fn call(
+    name: &str,
+    args: &[Value],
+    context: &FunctionContext,
+) -> Option<FunctionResult> {
+    let is_date1904 = context.is_date1904;
+    let result = match name {
+        "DATE" => date(args, is_date1904),
+        "TIME" => time(args),
+        "YEAR" => part(args, is_date1904, |date| date.get_year() as f64),
+        "MONTH" => part(args, is_date1904, |date| date.get_month() as f64),
+        "DAY" => part(args, is_date1904, |date| date.get_day() as f64),
+        "HOUR" => part(args, is_date1904, |date| date.get_hour() as f64),
+        "MINUTE" => part(args, is_date1904, |date| date.get_minute() as f64),
+        "SECOND" => part(args, is_date1904, |date| date.get_second() as f64),
+        "WEEKDAY" => weekday(args, is_date1904),
+        "TODAY" => expect_count(args, 0, 0).and_then(|_| number_value(now(is_date1904).floor())),
+        "NOW" => expect_count(args, 0, 0).and_then(|_| number_value(now(is_date1904))),
+        "EDATE" => shift_months(args, is_date1904, false),
+        "EOMONTH" => shift_months(args, is_date1904, true),
+        "DAYS" => expect_count(args, 2, 2).and_then(|_| {
+            number_value(
+                serial(args, 0, is_date1904)?.trunc() - serial(args, 1, is_date1904)?.trunc(),
+            )
+        }),
+        "DATEDIF" => date_dif(args, is_date1904),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// A date argument as a serial number, text such as `2023-01-31` is read as a date.
+fn serial(args: &[Value], index: usize, is_date1904: bool) -> Result<f64, CellErrorKind> {
+    match scalar(args, index)? {
+        CellValue::String(s) => parse_number(&s)
+            .or_else(|| ExcelDateTime::parse_iso(&s).map(|date| date.to_serial(is_date1904)))
+            .ok_or(CellErrorKind::Value),
+        value => to_number(&value),
+    }
+}
+fn to_date(serial: f64, is_date1904: bool) -> Result<ExcelDateTime, CellErrorKind> {
+    ExcelDateTime::from_serial(serial, is_date1904).ok_or(CellErrorKind::Num)
+}
+fn date_argument(
+    args: &[Value],
+    index: usize,
+    is_date1904: bool,
+) -> Result<ExcelDateTime, CellErrorKind> {
+    to_date(serial(args, index, is_date1904)?, is_date1904)
+}
+/// Serial number of the first day of the month, months out of 1 to 12 move the year.
+fn month_start(year: i32, month: i32, is_date1904: bool) -> Result<f64, CellErrorKind> {
+    let months = year * 12 + month - 1;
+    let (year, month) = (months.div_euclid(12), months.rem_euclid(12) + 1);
+    if !(0..=9999).contains(&year) {
+        return Err(CellErrorKind::Num);
+    }
+    Ok(ExcelDateTime::new(year, month as u32, 1, 0, 0, 0).to_serial(is_date1904))
+}
+fn days_in_month(year: i32, month: i32, is_date1904: bool) -> Result<f64, CellErrorKind> {
+    Ok(month_start(year, month + 1, is_date1904)? - month_start(year, month, is_date1904)?)
+}
+
+/// DATE(year, month, day), a year below 1900 counts from 1900 and days out of the month carry over.
+fn date(args: &[Value], is_date1904: bool) -> FunctionResult {
+    expect_count(args, 3, 3)?;
+    let mut year = number(args, 0)?.trunc() as i32;
+    let month = number(args, 1)?.trunc() as i32;
+    let day = number(args, 2)?.trunc();
+    if (0..1900).contains(&year) {
+        year += 1900;
+    }
+    let serial = month_start(year, month, is_date1904)? + day - 1.0;
+    if serial < 0.0 {
+        return Err(CellErrorKind::Num);
+    }
+    number_value(serial)
+}
+fn time(args: &[Value]) -> FunctionResult {
+    expect_count(args, 3, 3)?;
+    let seconds = number(args, 0)?.trunc() * 3600.0
+        + number(args, 1)?.trunc() * 60.0
+        + number(args, 2)?.trunc();
+    if seconds < 0.0 {
+        return Err(CellErrorKind::Num);
+    }
+    number_value((seconds / 86400.0).fract())
+}
+fn part(args: &[Value], is_date1904: bool, f: impl Fn(&ExcelDateTime) -> f64) -> FunctionResult {
+    expect_count(args, 1, 1)?;
+    number_value(f(&date_argument(args, 0, is_date1904)?))
+}
+/// WEEKDAY(date, [type]), 1 numbers Sunday as 1, 2 Monday as 1 and 3 Monday as 0.
+fn weekday(args: &[Value], is_date1904: bool) -> FunctionResult {
+    expect_count(args, 1, 2)?;
+    let weekday = date_argument(args, 0, is_date1904)?.get_weekday() as f64;
+    let number = match optional_number(args, 1, 1.0)? as i32 {
+        1 => weekday + 1.0,
+        2 => (weekday + 6.0) % 7.0 + 1.0,
+        3 => (weekday + 6.0) % 7.0,
+        _ => return Err(CellErrorKind::Num),
+    };
+    number_value(number)
+}
+/// The current time in UTC as a serial number.
+fn now(is_date1904: bool) -> f64 {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs_f64())
+        .unwrap_or_default();
+    let serial = seconds / 86400.0 + UNIX_EPOCH_SERIAL;
+    if is_date1904 {
+        serial - DATE1904_OFFSET
+    } else {
+        serial
+    }
+}
+/// EDATE(date, months) keeps the day within the month, EOMONTH is the month's last day.
+fn shift_months(args: &[Value], is_date1904: bool, is_end_of_month: bool) -> FunctionResult {
+    expect_count(args, 2, 2)?;
+    let date = date_argument(args, 0, is_date1904)?;
+    let (year, month) = (
+        date.get_year(),
+        date.get_month() as i32 + number(args, 1)?.trunc() as i32,
+    );
+    let days = days_in_month(year, month, is_date1904)?;
+    let day = if is_end_of_month {
+        days
+    } else {
+        (date.get_day() as f64).min(days)
+    };
+    let serial = month_start(year, month, is_date1904)? + day - 1.0;
+    if serial < 0.0 {
+        return Err(CellErrorKind::Num);
+    }
+    number_value(serial)
+}
+/// DATEDIF(start, end, unit) with the units Y, M, D, MD, YM and YD.
+fn date_dif(args: &[Value], is_date1904: bool) -> FunctionResult {
+    expect_count(args, 3, 3)?;
+    let (start_serial, end_serial) = (
+        serial(args, 0, is_date1904)?.trunc(),
+        serial(args, 1, is_date1904)?.trunc(),
+    );
+    if start_serial > end_serial {
+        return Err(CellErrorKind::Num);
+    }
+    let (start, end) = (
+        to_date(start_serial, is_date1904)?,
+        to_date(end_serial, is_date1904)?,
+    );
+    let is_day_before = end.get_day() < start.get_day();
+    let months = (end.get_year() - start.get_year()) * 12 + end.get_month() as i32
+        - start.get_month() as i32
+        - i32::from(is_day_before);
+    let result = match text(args, 2)?.to_ascii_uppercase().as_str() {
+        "Y" => (months / 12) as f64,
+        "M" => months as f64,
+        "D" => end_serial - start_serial,
+        "YM" => (months % 12) as f64,
+        "MD" if is_day_before => {
+            let days = days_in_month(end.get_year(), end.get_month() as i32 - 1, is_date1904)?;
+            days - start.get_day() as f64 + end.get_day() as f64
+        }
+        "MD" => (end.get_day() - start.get_day()) as f64,
+        "YD" => {
+            let same_year = |year: i32| {
+                month_start(year, start.get_month() as i32, is_date1904)
+                    .map(|serial| serial + start.get_day() as f64 - 1.0)
+            };
+            let mut anniversary = same_year(end.get_year())?;
+            if anniversary > end_serial {
+                anniversary = same_year(end.get_year() - 1)?;
+            }
+            end_serial - anniversary
+        }
+        _ => return Err(CellErrorKind::Num),
+    };
+    number_value(result)
+}
+
+#[cfg(test)]
+mod date_test {
+    use crate::excel::{
+        cells::cell_value::{CellErrorKind, CellValue},
+        formulas::evaluator::evaluator_test::evaluate,
+    };
+
+    fn number(formula: &str) -> CellValue {
+        evaluate(&[], formula)
+    }
+    #[test]
+    fn date_test() {
+        assert_eq!(number("=DATE(2023,1,1)"), CellValue::Number(44927.0));
+        assert_eq!(number("=DATE(2023,13,1)"), CellValue::Number(45292.0));
+        assert_eq!(number("=DATE(2023,3,0)"), CellValue::Number(44985.0));
+        assert_eq!(number("=DATE(123,1,1)"), CellValue::Number(44927.0));
+        assert_eq!(
+            number("=TIME(12,30,0)"),
+            CellValue::Number(0.5208333333333334)
+        );
+        assert_eq!(number("=YEAR(44927)"), CellValue::Number(2023.0));
+        assert_eq!(number("=MONTH(\"2023-02-15\")"), CellValue::Number(2.0));
+        assert_eq!(number("=DAY(DATE(2024,2,29))"), CellValue::Number(29.0));
+        assert_eq!(number("=HOUR(0.75)"), CellValue::Number(18.0));
+        assert_eq!(number("=YEAR(-1)"), CellValue::Error(CellErrorKind::Num));
+    }
+    #[test]
+    fn weekday_test() {
+        // 2023-01-01 was a Sunday
+        assert_eq!(number("=WEEKDAY(44927)"), CellValue::Number(1.0));
+        assert_eq!(number("=WEEKDAY(44927,2)"), CellValue::Number(7.0));
+        assert_eq!(number("=WEEKDAY(44927,3)"), CellValue::Number(6.0));
+        assert_eq!(number("=TODAY()<=NOW()"), CellValue::Bool(true));
+        assert_eq!(number("=TODAY()>44927"), CellValue::Bool(true));
+    }
+    #[test]
+    fn month_shift_test() {
+        assert_eq!(
+            number("=EDATE(DATE(2023,1,31),1)"),
+            number("=DATE(2023,2,28)")
+        );
+        assert_eq!(
+            number("=EDATE(DATE(2023,3,15),-3)"),
+            number("=DATE(2022,12,15)")
+        );
+        assert_eq!(
+            number("=EOMONTH(DATE(2024,1,10),1)"),
+            number("=DATE(2024,2,29)")
+        );
+        assert_eq!(
+            number("=DAYS(\"2023-03-01\",\"2023-02-01\")"),
+            CellValue::Number(28.0)
+        );
+    }
+    #[test]
+    fn date_dif_test() {
+        let dif = |unit: &str| {
+            number(&format!(
+                "=DATEDIF(DATE(2020,5,20),DATE(2023,3,10),\"{}\")",
+                unit
+            ))
+        };
+        assert_eq!(dif("Y"), CellValue::Number(2.0));
+        assert_eq!(dif("M"), CellValue::Number(33.0));
+        assert_eq!(dif("D"), CellValue::Number(1024.0));
+        assert_eq!(dif("YM"), CellValue::Number(9.0));
+        assert_eq!(dif("MD"), CellValue::Number(18.0));
+        assert_eq!(dif("YD"), CellValue::Number(294.0));
+        assert_eq!(
+            number("=DATEDIF(DATE(2023,1,2),DATE(2023,1,1),\"D\")"),
+            CellValue::Error(CellErrorKind::Num)
+        );
+    }
+}
@@ -0,0 +1,145 @@
+use crate::excel::{
+    cells::cell_value::{CellErrorKind, CellValue},
+    formulas::value::{compare, to_bool, Value},
+};
+
+use super::{bool_value, boolean, expect_count, scalar, FunctionResult};
+
+pub(super) fn call(name: &str, args: &[Value]) -> Option<FunctionResult> {
+    let result = match name {
+        "AND" => booleans(args).map(|booleans| booleans.iter().all(|b| *b)),
+        "OR" => booleans(args).map(|booleans| booleans.iter().any(|b| *b)),
+        "XOR" => booleans(args).map(|booleans| booleans.iter().filter(|b| **b).count() % 2 == 1),
+        "NOT" => expect_count(args, 1, 1).and_then(|_| boolean(args, 0).map(|b| !b)),
+        "TRUE" => expect_count(args, 0, 0).map(|_| true),
+        "FALSE" => expect_count(args, 0, 0).map(|_| false),
+        "ISBLANK" => is(args, |value| matches!(value, CellValue::Empty)),
+        "ISERROR" => is(args, |value| matches!(value, CellValue::Error(_))),
+        "ISERR" => is(
+            args,
+            |value| matches!(value, CellValue::Error(kind) if *kind != CellErrorKind::NA),
+        ),
+        "ISNA" => is(args, |value| {
+            matches!(value, CellValue::Error(CellErrorKind::NA))
+        }),
+        "ISNUMBER" => is(args, |value| matches!(value, CellValue::Number(_))),
+        "ISTEXT" => is(args, |value| matches!(value, CellValue::String(_))),
+        "ISLOGICAL" => is(args, |value| matches!(value, CellValue::Bool(_))),
+        "NA" => return Some(expect_count(args, 0, 0).and(Err(CellErrorKind::NA))),
+        "SWITCH" => return Some(switch(args)),
+        _ => return None,
+    };
+    Some(result.and_then(bool_value))
+}
+
+/// Booleans of AND and OR, text and empty cells of ranges are skipped.
+fn booleans(args: &[Value]) -> Result<Vec<bool>, CellErrorKind> {
+    let mut booleans = Vec::new();
+    for arg in args {
+        match arg {
+            Value::Array(_) => {
+                for value in arg.values() {
+                    match value {
+                        CellValue::Error(kind) => return Err(*kind),
+                        CellValue::Number(_) | CellValue::Bool(_) => booleans.push(to_bool(value)?),
+                        _ => (),
+                    }
+                }
+            }
+            Value::Scalar(CellValue::Empty) => (),
+            Value::Scalar(value) => booleans.push(to_bool(value)?),
+        }
+    }
+    if booleans.is_empty() {
+        return Err(CellErrorKind::Value);
+    }
+    Ok(booleans)
+}
+fn is(args: &[Value], f: impl Fn(&CellValue) -> bool) -> Result<bool, CellErrorKind> {
+    expect_count(args, 1, 1)?;
+    Ok(f(&args[0].clone().into_scalar()))
+}
+/// SWITCH(value, case1, result1, ..., [default])
+fn switch(args: &[Value]) -> FunctionResult {
+    if args.len() < 3 {
+        return Err(CellErrorKind::Value);
+    }
+    let value = scalar(args, 0)?;
+    let mut rest = args[1..].chunks_exact(2);
+    for pair in rest.by_ref() {
+        let case = pair[0].clone().into_scalar();
+        if let CellValue::Error(kind) = case {
+            return Err(kind);
+        }
+        let is_same_type = std::mem::discriminant(&case) == std::mem::discriminant(&value);
+        if is_same_type && compare(&value, &case).is_eq() {
+            return Ok(pair[1].clone());
+        }
+    }
+    rest.remainder().first().cloned().ok_or(CellErrorKind::NA)
+}
+
+#[cfg(test)]
+mod logical_test {
+    use crate::excel::{
+        cells::cell_value::{CellErrorKind, CellValue},
+        formulas::evaluator::evaluator_test::evaluate,
+    };
+
+    #[test]
+    fn logical_test() {
+        let cells = [("A1", "1"), ("A2", "abc"), ("A3", "#N/A"), ("A4", "0")];
+        assert_eq!(evaluate(&cells, "=AND(A1,TRUE)"), CellValue::Bool(true));
+        assert_eq!(evaluate(&cells, "=AND(A1:A2,A4)"), CellValue::Bool(false));
+        assert_eq!(evaluate(&cells, "=OR(A4,FALSE)"), CellValue::Bool(false));
+        assert_eq!(
+            evaluate(&cells, "=XOR(TRUE,TRUE,TRUE)"),
+            CellValue::Bool(true)
+        );
+        assert_eq!(evaluate(&cells, "=NOT(A1)"), CellValue::Bool(false));
+        assert_eq!(
+            evaluate(&cells, "=AND(\"abc\")"),
+            CellValue::Error(CellErrorKind::Value)
+        );
+        assert_eq!(
+            evaluate(&cells, "=OR(A1:A3)"),
+            CellValue::Error(CellErrorKind::NA)
+        );
+    }
+    #[test]
+    fn is_test() {
+        let cells = [
+            ("A1", "1"),
+            ("A2", "abc"),
+            ("A3", "#N/A"),
+            ("A4", "#DIV/0!"),
+        ];
+        assert_eq!(evaluate(&cells, "=ISBLANK(B1)"), CellValue::Bool(true));
+        assert_eq!(evaluate(&cells, "=ISNUMBER(A1)"), CellValue::Bool(true));
+        assert_eq!(evaluate(&cells, "=ISTEXT(A1)"), CellValue::Bool(false));
+        assert_eq!(evaluate(&cells, "=ISNA(A3)"), CellValue::Bool(true));
+        assert_eq!(evaluate(&cells, "=ISERR(A3)"), CellValue::Bool(false));
+        assert_eq!(evaluate(&cells, "=ISERR(A4)"), CellValue::Bool(true));
+        assert_eq!(evaluate(&cells, "=ISERROR(1/0)"), CellValue::Bool(true));
+        assert_eq!(
+            evaluate(&cells, "=NA()"),
+            CellValue::Error(CellErrorKind::NA)
+        );
+    }
+    #[test]
+    fn switch_test() {
+        let cells = [("A1", "2")];
+        assert_eq!(
+            evaluate(&cells, "=SWITCH(A1,1,\"one\",2,\"two\")"),
+            CellValue::String("two".to_string())
+        );
+        assert_eq!(
+            evaluate(&cells, "=SWITCH(A1,1,\"one\",\"other\")"),
+            CellValue::String("other".to_string())
+        );
+        assert_eq!(
+            evaluate(&cells, "=SWITCH(A1,1,\"one\")"),
+            CellValue::Error(CellErrorKind::NA)
+        );
+    }
+}
@@ -0,0 +1,351 @@
+use std::cmp::Ordering;
+
+use crate::excel::{
+    cells::cell_value::{CellErrorKind, CellValue},
+    formulas::value::{compare, Value},
+};
+
+use super::{
+    criteria::{has_wildcard, wildcard_match},
+    expect_count, is_omitted, number, number_value, optional_boolean, optional_number, scalar,
+    FunctionResult,
+};
+
+pub(super) fn call(name: &str, args: &[Value]) -> Option<FunctionResult> {
+    let result = match name {
+        "VLOOKUP" => table_lookup(args, false),
+        "HLOOKUP" => table_lookup(args, true),
+        "XLOOKUP" => xlookup(args),
+        "MATCH" => match_(args),
+        "INDEX" => index(args),
+        "ROWS" => expect_count(args, 1, 1).and_then(|_| number_value(args[0].size().0 as f64)),
+        "COLUMNS" => expect_count(args, 1, 1).and_then(|_| number_value(args[0].size().1 as f64)),
+        _ => return None,
+    };
+    Some(result)
+}
+
+/// How a lookup value is searched for.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum MatchMode {
+    /// Same value, text may hold wildcards when `true`.
+    Exact(bool),
+    /// Largest value not greater than the lookup value, in ascending values.
+    SortedLess,
+    /// Smallest value not less than the lookup value, in descending values.
+    SortedGreater,
+    /// Exact, or else the nearest smaller (`Less`) or larger (`Greater`) value in any order.
+    Nearest(Ordering),
+}
+/// Position of the lookup value in `values`, searched from the end when `is_reverse`.
+fn find(
+    lookup: &CellValue,
+    values: &[&CellValue],
+    mode: MatchMode,
+    is_reverse: bool,
+) -> Option<usize> {
+    let is_comparable = |value: &CellValue| {
+        std::mem::discriminant(lookup) == std::mem::discriminant(value)
+            || matches!(lookup, CellValue::Empty)
+    };
+    let mut positions = (0..values.len()).collect::<Vec<_>>();
+    if is_reverse {
+        positions.reverse();
+    }
+    match mode {
+        MatchMode::Exact(is_wildcard) => {
+            positions.into_iter().find(|i| match (lookup, values[*i]) {
+                (CellValue::String(pattern), CellValue::String(text))
+                    if is_wildcard && has_wildcard(pattern) =>
+                {
+                    wildcard_match(pattern, text)
+                }
+                (lookup, value) => is_comparable(value) && compare(value, lookup).is_eq(),
+            })
+        }
+        MatchMode::SortedLess | MatchMode::SortedGreater => {
+            let beyond = if mode == MatchMode::SortedLess {
+                Ordering::Greater
+            } else {
+                Ordering::Less
+            };
+            let mut found = None;
+            for i in positions {
+                if !is_comparable(values[i]) {
+                    continue;
+                }
+                if compare(values[i], lookup) == beyond {
+                    break;
+                }
+                found = Some(i);
+            }
+            found
+        }
+        MatchMode::Nearest(side) => {
+            let mut found: Option<usize> = None;
+            for i in positions {
+                let value = values[i];
+                if !is_comparable(value) {
+                    continue;
+                }
+                match compare(value, lookup) {
+                    Ordering::Equal => return Some(i),
+                    ordering if ordering == side => {
+                        let is_nearer = found
+                            .is_none_or(|found| compare(value, values[found]) == side.reverse());
+                        if is_nearer {
+                            found = Some(i);
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            found
+        }
+    }
+}
+/// Values of a single row or column, anything else is `None`.
+fn vector(value: &Value) -> Option<Vec<&CellValue>> {
+    let (rows, columns) = value.size();
+    if rows == 1 || columns == 1 {
+        Some(value.values())
+    } else {
+        None
+    }
+}
+fn lookup_value(args: &[Value]) -> Result<CellValue, CellErrorKind> {
+    scalar(args, 0)
+}
+
+/// VLOOKUP(lookup, table, column, [approximate]) and HLOOKUP with rows and columns swapped.
+fn table_lookup(args: &[Value], is_horizontal: bool) -> FunctionResult {
+    expect_count(args, 3, 4)?;
+    let lookup = lookup_value(args)?;
+    let mut rows = args[1].to_rows();
+    if is_horizontal {
+        rows = transpose(rows);
+    }
+    let index = number(args, 2)?.trunc();
+    if index < 1.0 {
+        return Err(CellErrorKind::Value);
+    }
+    let index = index as usize - 1;
+    if rows.first().is_none_or(|row| index >= row.len()) {
+        return Err(CellErrorKind::Ref);
+    }
+    let mode = if optional_boolean(args, 3, true)? {
+        MatchMode::SortedLess
+    } else {
+        MatchMode::Exact(true)
+    };
+    let keys = rows.iter().map(|row| &row[0]).collect::<Vec<_>>();
+    let found = find(&lookup, &keys, mode, false).ok_or(CellErrorKind::NA)?;
+    Ok(Value::Scalar(rows[found][index].clone()))
+}
+fn transpose(rows: Vec<Vec<CellValue>>) -> Vec<Vec<CellValue>> {
+    let columns = rows.first().map(Vec::len).unwrap_or(0);
+    (0..columns)
+        .map(|column| rows.iter().map(|row| row[column].clone()).collect())
+        .collect()
+}
+/// MATCH(lookup, values, [type]), 1 is sorted ascending, 0 exact and -1 sorted descending.
+fn match_(args: &[Value]) -> FunctionResult {
+    expect_count(args, 2, 3)?;
+    let lookup = lookup_value(args)?;
+    let values = vector(&args[1]).ok_or(CellErrorKind::NA)?;
+    let mode = match optional_number(args, 2, 1.0)? {
+        kind if kind > 0.0 => MatchMode::SortedLess,
+        kind if kind < 0.0 => MatchMode::SortedGreater,
+        _ => MatchMode::Exact(true),
+    };
+    let found = find(&lookup, &values, mode, false).ok_or(CellErrorKind::NA)?;
+    number_value((found + 1) as f64)
+}
+/// XLOOKUP(lookup, values, results, [if_not_found], [match_mode], [search_mode])
+fn xlookup(args: &[Value]) -> FunctionResult {
+    expect_count(args, 3, 6)?;
+    let lookup = lookup_value(args)?;
+    let values = vector(&args[1]).ok_or(CellErrorKind::Value)?;
+    let mode = match optional_number(args, 4, 0.0)? as i32 {
+        0 => MatchMode::Exact(false),
+        -1 => MatchMode::Nearest(Ordering::Less),
+        1 => MatchMode::Nearest(Ordering::Greater),
+        2 => MatchMode::Exact(true),
+        _ => return Err(CellErrorKind::Value),
+    };
+    let is_reverse = match optional_number(args, 5, 1.0)? as i32 {
+        1 => false,
+        -1 => true,
+        _ => return Err(CellErrorKind::Value),
+    };
+    let Some(found) = find(&lookup, &values, mode, is_reverse) else {
+        if is_omitted(args, 3) {
+            return Err(CellErrorKind::NA);
+        }
+        return Ok(args[3].clone());
+    };
+    let results = args[2].to_rows();
+    let is_column = args[1].size().1 == 1 && args[1].size().0 > 1;
+    let result = if is_column {
+        results.get(found).cloned().map(|row| vec![row])
+    } else {
+        transpose(results)
+            .get(found)
+            .map(|column| column.iter().map(|value| vec![value.clone()]).collect())
+    };
+    match result {
+        Some(rows) if rows.len() == 1 && rows[0].len() == 1 => {
+            Ok(Value::Scalar(rows[0][0].clone()))
+        }
+        Some(rows) => Ok(Value::Array(rows)),
+        None => Err(CellErrorKind::Value),
+    }
+}
+/// INDEX(values, row, [column]), 0 takes the whole row or column.
+/// A single row is indexed by the column given as the second argument.
+fn index(args: &[Value]) -> FunctionResult {
+    expect_count(args, 2, 3)?;
+    let rows = args[0].to_rows();
+    let (height, width) = args[0].size();
+    let first = optional_number(args, 1, 0.0)?.trunc();
+    let second = optional_number(args, 2, 0.0)?.trunc();
+    if first < 0.0 || second < 0.0 {
+        return Err(CellErrorKind::Value);
+    }
+    let (row, column) = if height == 1 && args.len() == 2 {
+        (1, first as usize)
+    } else if width == 1 && args.len() == 2 {
+        (first as usize, 1)
+    } else {
+        (first as usize, second as usize)
+    };
+    if row > height || column > width {
+        return Err(CellErrorKind::Ref);
+    }
+    let value = match (row, column) {
+        (0, 0) => Value::Array(rows),
+        (0, column) => Value::Array(rows.iter().map(|r| vec![r[column - 1].clone()]).collect()),
+        (row, 0) => Value::Array(vec![rows[row - 1].clone()]),
+        (row, column) => Value::Scalar(rows[row - 1][column - 1].clone()),
+    };
+    match value {
+        Value::Array(rows) if rows.len() == 1 && rows[0].len() == 1 => {
+            Ok(Value::Scalar(rows[0][0].clone()))
+        }
+        value => Ok(value),
+    }
+}
+
+#[cfg(test)]
+mod lookup_test {
+    use crate::excel::{
+        cells::cell_value::{CellErrorKind, CellValue},
+        formulas::evaluator::evaluator_test::evaluate,
+    };
+
+    const TABLE: [(&str, &str); 12] = [
+        ("A1", "10"),
+        ("B1", "apple"),
+        ("C1", "100"),
+        ("A2", "20"),
+        ("B2", "banana"),
+        ("C2", "200"),
+        ("A3", "30"),
+        ("B3", "cherry"),
+        ("C3", "300"),
+        ("E1", "x"),
+        ("F1", "y"),
+        ("G1", "z"),
+    ];
+    fn text(s: &str) -> CellValue {
+        CellValue::String(s.to_string())
+    }
+    #[test]
+    fn vlookup_test() {
+        assert_eq!(
+            evaluate(&TABLE, "=VLOOKUP(20,A1:C3,2,FALSE)"),
+            text("banana")
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=VLOOKUP(25,A1:C3,3)"),
+            CellValue::Number(200.0)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=VLOOKUP(5,A1:C3,2)"),
+            CellValue::Error(CellErrorKind::NA)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=VLOOKUP(20,A1:C3,4,FALSE)"),
+            CellValue::Error(CellErrorKind::Ref)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=VLOOKUP(20,A1:C3,0,FALSE)"),
+            CellValue::Error(CellErrorKind::Value)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=VLOOKUP(\"b*\",B1:C3,2,FALSE)"),
+            CellValue::Number(200.0)
+        );
+        assert_eq!(evaluate(&TABLE, "=HLOOKUP(\"y\",E1:G1,1,FALSE)"), text("y"));
+    }
+    #[test]
+    fn match_index_test() {
+        assert_eq!(
+            evaluate(&TABLE, "=MATCH(\"CHERRY\",B1:B3,0)"),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(evaluate(&TABLE, "=MATCH(29,A1:A3)"), CellValue::Number(2.0));
+        assert_eq!(
+            evaluate(&TABLE, "=MATCH(\"z\",E1:G1,0)"),
+            CellValue::Number(3.0)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=INDEX(A1:C3,MATCH(\"cherry\",B1:B3,0),3)"),
+            CellValue::Number(300.0)
+        );
+        assert_eq!(evaluate(&TABLE, "=INDEX(E1:G1,2)"), text("y"));
+        assert_eq!(
+            evaluate(&TABLE, "=SUM(INDEX(A1:C3,0,3))"),
+            CellValue::Number(600.0)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=INDEX(A1:C3,4,1)"),
+            CellValue::Error(CellErrorKind::Ref)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=ROWS(A1:C3)*COLUMNS(E1:G1)"),
+            CellValue::Number(9.0)
+        );
+    }
+    #[test]
+    fn xlookup_test() {
+        assert_eq!(
+            evaluate(&TABLE, "=XLOOKUP(\"banana\",B1:B3,C1:C3)"),
+            CellValue::Number(200.0)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=XLOOKUP(\"b*\",B1:B3,C1:C3)"),
+            CellValue::Error(CellErrorKind::NA)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=XLOOKUP(\"b*\",B1:B3,C1:C3,,2)"),
+            CellValue::Number(200.0)
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=XLOOKUP(\"kiwi\",B1:B3,C1:C3,\"none\")"),
+            text("none")
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=XLOOKUP(25,A1:A3,B1:B3,,1)"),
+            text("cherry")
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=XLOOKUP(25,A1:A3,B1:B3,,-1)"),
+            text("banana")
+        );
+        assert_eq!(
+            evaluate(&TABLE, "=SUM(XLOOKUP(20,A1:A3,A1:C3))"),
+            CellValue::Number(220.0)
+        );
+    }
+}
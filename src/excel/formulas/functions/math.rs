@@ -0,0 +1,310 @@
+use crate::excel::{
+    cells::cell_value::{CellErrorKind, CellValue},
+    formulas::value::{to_number, Value},
+};
+
+use super::{
+    criteria::Criteria, expect_count, number, number_value, numbers, optional_number,
+    FunctionResult,
+};
+
+pub(super) fn call(name: &str, args: &[Value]) -> Option<FunctionResult> {
+    let result = match name {
+        "SUM" => numbers(args).and_then(|numbers| number_value(numbers.iter().sum())),
+        "PRODUCT" => numbers(args).and_then(|numbers| number_value(numbers.iter().product())),
+        "AVERAGE" => numbers(args).and_then(|numbers| average(&numbers)),
+        "MIN" => numbers(args).and_then(|numbers| {
+            number_value(numbers.into_iter().reduce(f64::min).unwrap_or_default())
+        }),
+        "MAX" => numbers(args).and_then(|numbers| {
+            number_value(numbers.into_iter().reduce(f64::max).unwrap_or_default())
+        }),
+        "COUNT" => count(args),
+        "COUNTA" => count_values(args, |value| !value.is_empty()),
+        "COUNTBLANK" => count_values(args, |value| match value {
+            CellValue::Empty => true,
+            CellValue::String(s) => s.is_empty(),
+            _ => false,
+        }),
+        "SUMPRODUCT" => sum_product(args),
+        "COUNTIF" => {
+            expect_count(args, 2, 2).and_then(|_| conditional(None, args, Aggregate::Count))
+        }
+        "COUNTIFS" => conditional(None, args, Aggregate::Count),
+        "SUMIF" => aggregate_if(args, Aggregate::Sum),
+        "SUMIFS" => aggregate_ifs(args, Aggregate::Sum),
+        "AVERAGEIF" => aggregate_if(args, Aggregate::Average),
+        "AVERAGEIFS" => aggregate_ifs(args, Aggregate::Average),
+        "MINIFS" => aggregate_ifs(args, Aggregate::Min),
+        "MAXIFS" => aggregate_ifs(args, Aggregate::Max),
+        "ROUND" => round(args, |scaled| scaled.round()),
+        "ROUNDUP" => round(args, |scaled| scaled.abs().ceil() * scaled.signum()),
+        "ROUNDDOWN" | "TRUNC" => round(args, f64::trunc),
+        "INT" => one_number(args, f64::floor),
+        "ABS" => one_number(args, f64::abs),
+        "SIGN" => one_number(args, |n| if n == 0.0 { 0.0 } else { n.signum() }),
+        "SQRT" => one_number(args, f64::sqrt),
+        "POWER" => two_numbers(args, f64::powf),
+        "MOD" => modulo(args),
+        "MROUND" => mround(args),
+        "CEILING" | "CEILING.MATH" => to_multiple(args, f64::ceil),
+        "FLOOR" | "FLOOR.MATH" => to_multiple(args, f64::floor),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn average(numbers: &[f64]) -> FunctionResult {
+    if numbers.is_empty() {
+        return Err(CellErrorKind::Div0);
+    }
+    number_value(numbers.iter().sum::<f64>() / numbers.len() as f64)
+}
+/// Numbers of ranges, and arguments that read as numbers.
+fn count(args: &[Value]) -> FunctionResult {
+    let count = args
+        .iter()
+        .map(|arg| match arg {
+            Value::Array(_) => arg
+                .values()
+                .iter()
+                .filter(|value| matches!(value, CellValue::Number(_)))
+                .count(),
+            Value::Scalar(CellValue::Empty | CellValue::Error(_)) => 0,
+            Value::Scalar(value) => usize::from(to_number(value).is_ok()),
+        })
+        .sum::<usize>();
+    number_value(count as f64)
+}
+fn count_values(args: &[Value], f: impl Fn(&CellValue) -> bool) -> FunctionResult {
+    let count = args
+        .iter()
+        .flat_map(|arg| arg.values())
+        .filter(|value| f(value))
+        .count();
+    number_value(count as f64)
+}
+fn sum_product(args: &[Value]) -> FunctionResult {
+    let size = args.first().ok_or(CellErrorKind::Value)?.size();
+    if args.iter().any(|arg| arg.size() != size) {
+        return Err(CellErrorKind::Value);
+    }
+    let arrays = args.iter().map(Value::values).collect::<Vec<_>>();
+    let mut sum = 0.0;
+    for i in 0..size.0 * size.1 {
+        let mut product = 1.0;
+        for array in &arrays {
+            product *= match array[i] {
+                CellValue::Number(number) => *number,
+                CellValue::Error(kind) => return Err(*kind),
+                _ => 0.0,
+            };
+        }
+        sum += product;
+    }
+    number_value(sum)
+}
+
+enum Aggregate {
+    Count,
+    Sum,
+    Average,
+    Min,
+    Max,
+}
+/// SUMIF(range, criteria, [values]) and AVERAGEIF, the range is aggregated when values are omitted.
+fn aggregate_if(args: &[Value], aggregate: Aggregate) -> FunctionResult {
+    expect_count(args, 2, 3)?;
+    conditional(Some(args.get(2).unwrap_or(&args[0])), &args[..2], aggregate)
+}
+/// SUMIFS(values, range1, criteria1, ...) and the other `*IFS` with values first.
+fn aggregate_ifs(args: &[Value], aggregate: Aggregate) -> FunctionResult {
+    let (values, pairs) = args.split_first().ok_or(CellErrorKind::Value)?;
+    conditional(Some(values), pairs, aggregate)
+}
+/// Aggregates `values` where every range of the range and criteria pairs matches,
+/// counting the matches when there are no values.
+fn conditional(values: Option<&Value>, pairs: &[Value], aggregate: Aggregate) -> FunctionResult {
+    if pairs.is_empty() || !pairs.len().is_multiple_of(2) {
+        return Err(CellErrorKind::Value);
+    }
+    let size = pairs[0].size();
+    let conditions = pairs
+        .chunks(2)
+        .map(|pair| {
+            if pair[0].size() != size {
+                return Err(CellErrorKind::Value);
+            }
+            Ok((
+                pair[0].values(),
+                Criteria::new(&pair[1].clone().into_scalar()),
+            ))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let values = values.map(Value::values).unwrap_or_default();
+    let mut matched = Vec::new();
+    for i in 0..size.0 * size.1 {
+        let is_match = conditions
+            .iter()
+            .all(|(range, criteria)| criteria.is_match(range[i]));
+        if !is_match {
+            continue;
+        }
+        match values.get(i) {
+            Some(CellValue::Number(number)) => matched.push(Some(*number)),
+            Some(CellValue::Error(kind)) if !matches!(aggregate, Aggregate::Count) => {
+                return Err(*kind)
+            }
+            _ => matched.push(None),
+        }
+    }
+    let numbers = matched.iter().flatten().copied().collect::<Vec<_>>();
+    match aggregate {
+        Aggregate::Count => number_value(matched.len() as f64),
+        Aggregate::Sum => number_value(numbers.iter().sum()),
+        Aggregate::Average => average(&numbers),
+        Aggregate::Min => number_value(numbers.into_iter().reduce(f64::min).unwrap_or_default()),
+        Aggregate::Max => number_value(numbers.into_iter().reduce(f64::max).unwrap_or_default()),
+    }
+}
+
+fn one_number(args: &[Value], f: impl Fn(f64) -> f64) -> FunctionResult {
+    expect_count(args, 1, 1)?;
+    number_value(f(number(args, 0)?))
+}
+fn two_numbers(args: &[Value], f: impl Fn(f64, f64) -> f64) -> FunctionResult {
+    expect_count(args, 2, 2)?;
+    number_value(f(number(args, 0)?, number(args, 1)?))
+}
+/// ROUND family, `f` rounds the number scaled by the digits to an integer.
+/// The scaled number is cut to 15 digits first so that 2.675 is 267.5 and not 267.49999.
+fn round(args: &[Value], f: impl Fn(f64) -> f64) -> FunctionResult {
+    expect_count(args, 1, 2)?;
+    let value = number(args, 0)?;
+    let digits = optional_number(args, 1, 0.0)?.trunc() as i32;
+    let factor = 10_f64.powi(digits);
+    let scaled = format!("{:.14e}", value * factor)
+        .parse::<f64>()
+        .unwrap_or(value * factor);
+    number_value(f(scaled) / factor)
+}
+fn modulo(args: &[Value]) -> FunctionResult {
+    expect_count(args, 2, 2)?;
+    let (value, divisor) = (number(args, 0)?, number(args, 1)?);
+    if divisor == 0.0 {
+        return Err(CellErrorKind::Div0);
+    }
+    number_value(value - divisor * (value / divisor).floor())
+}
+fn mround(args: &[Value]) -> FunctionResult {
+    expect_count(args, 2, 2)?;
+    let (value, multiple) = (number(args, 0)?, number(args, 1)?);
+    if multiple == 0.0 {
+        return number_value(0.0);
+    }
+    if value.signum() != multiple.signum() && value != 0.0 {
+        return Err(CellErrorKind::Num);
+    }
+    number_value((value / multiple).round() * multiple)
+}
+/// CEILING and FLOOR to a multiple of the significance, 1 when omitted.
+fn to_multiple(args: &[Value], f: impl Fn(f64) -> f64) -> FunctionResult {
+    expect_count(args, 1, 3)?;
+    let value = number(args, 0)?;
+    let significance = optional_number(args, 1, 1.0)?;
+    if significance == 0.0 {
+        return number_value(0.0);
+    }
+    if value > 0.0 && significance < 0.0 {
+        return Err(CellErrorKind::Num);
+    }
+    number_value(f(value / significance) * significance)
+}
+
+#[cfg(test)]
+mod math_test {
+    use crate::excel::{
+        cells::cell_value::{CellErrorKind, CellValue},
+        formulas::evaluator::evaluator_test::evaluate,
+    };
+
+    const CELLS: [(&str, &str); 9] = [
+        ("A1", "10"),
+        ("A2", "20"),
+        ("A3", "abc"),
+        ("A4", "TRUE"),
+        ("B1", "東京"),
+        ("B2", "大阪"),
+        ("B3", "東北"),
+        ("B4", "東京"),
+        ("C1", "#DIV/0!"),
+    ];
+    fn number(formula: &str) -> CellValue {
+        evaluate(&CELLS, formula)
+    }
+    #[test]
+    fn aggregate_test() {
+        assert_eq!(number("=SUM(A1:A4)"), CellValue::Number(30.0));
+        assert_eq!(number("=SUM(A1:A2,5,\"5\",TRUE)"), CellValue::Number(41.0));
+        assert_eq!(number("=SUM((A1,A2))"), CellValue::Number(30.0));
+        assert_eq!(number("=AVERAGE(A1:A4)"), CellValue::Number(15.0));
+        assert_eq!(
+            number("=AVERAGE(B1:B4)"),
+            CellValue::Error(CellErrorKind::Div0)
+        );
+        assert_eq!(number("=COUNT(A1:B4)"), CellValue::Number(2.0));
+        assert_eq!(number("=COUNTA(A1:B4)"), CellValue::Number(8.0));
+        assert_eq!(number("=COUNTBLANK(A1:A6)"), CellValue::Number(2.0));
+        assert_eq!(number("=MAX(A1:A4)-MIN(A1:A4)"), CellValue::Number(10.0));
+        assert_eq!(number("=SUM(A1:C1)"), CellValue::Error(CellErrorKind::Div0));
+        assert_eq!(
+            number("=SUMPRODUCT((A1:A2>15)*A1:A2)"),
+            CellValue::Number(20.0)
+        );
+    }
+    #[test]
+    fn conditional_test() {
+        assert_eq!(number("=COUNTIF(B1:B4,\"東*\")"), CellValue::Number(3.0));
+        assert_eq!(number("=COUNTIF(A1:A4,\">15\")"), CellValue::Number(1.0));
+        assert_eq!(
+            number("=COUNTIFS(B1:B4,\"東京\",A1:A4,\">5\")"),
+            CellValue::Number(1.0)
+        );
+        assert_eq!(
+            number("=SUMIF(B1:B4,\"東京\",A1:A4)"),
+            CellValue::Number(10.0)
+        );
+        assert_eq!(number("=SUMIF(A1:A4,\"<>10\")"), CellValue::Number(20.0));
+        assert_eq!(
+            number("=SUMIFS(A1:A4,B1:B4,\"<>大阪\")"),
+            CellValue::Number(10.0)
+        );
+        assert_eq!(
+            number("=AVERAGEIF(B1:B4,\"大阪\",A1:A4)"),
+            CellValue::Number(20.0)
+        );
+        assert_eq!(
+            number("=COUNTIFS(A1:A4,1,B1:B2,1)"),
+            CellValue::Error(CellErrorKind::Value)
+        );
+    }
+    #[test]
+    fn round_test() {
+        assert_eq!(number("=ROUND(2.675,2)"), CellValue::Number(2.68));
+        assert_eq!(number("=ROUND(-2.5,0)"), CellValue::Number(-3.0));
+        assert_eq!(number("=ROUND(1234.5,-2)"), CellValue::Number(1200.0));
+        assert_eq!(number("=ROUNDUP(-1.21,1)"), CellValue::Number(-1.3));
+        assert_eq!(number("=ROUNDDOWN(1.99,1)"), CellValue::Number(1.9));
+        assert_eq!(number("=INT(-1.5)"), CellValue::Number(-2.0));
+        assert_eq!(number("=MOD(-3,2)"), CellValue::Number(1.0));
+        assert_eq!(number("=MOD(1,0)"), CellValue::Error(CellErrorKind::Div0));
+        assert_eq!(number("=MROUND(10,3)"), CellValue::Number(9.0));
+        assert_eq!(
+            number("=MROUND(5,-2)"),
+            CellValue::Error(CellErrorKind::Num)
+        );
+        assert_eq!(number("=CEILING(2.1,0.5)"), CellValue::Number(2.5));
+        assert_eq!(number("=FLOOR(2.9)"), CellValue::Number(2.0));
+        assert_eq!(number("=SQRT(-1)"), CellValue::Error(CellErrorKind::Num));
+    }
+}
@@ -0,0 +1,309 @@
+use crate::excel::{
+    cells::cell_value::{CellErrorKind, CellValue},
+    formulas::value::{parse_number, to_text, Value},
+    number_formats::render::format_value,
+};
+
+use super::{
+    bool_value, boolean, expect_count, number, number_value, optional_number, scalar, text,
+    text_value, FunctionContext, FunctionResult,
+};
+
+pub(super) fn call(
+    name: &str,
+    args: &[Value],
+    context: &FunctionContext,
+) -> Option<FunctionResult> {
+    let result = match name {
+        "LEFT" => left_or_right(args, true),
+        "RIGHT" => left_or_right(args, false),
+        "MID" => mid(args),
+        "LEN" => expect_count(args, 1, 1)
+            .and_then(|_| text(args, 0))
+            .and_then(|s| number_value(s.chars().count() as f64)),
+        "CONCAT" => concat(args),
+        "CONCATENATE" => (0..args.len())
+            .map(|i| text(args, i))
+            .collect::<Result<String, _>>()
+            .and_then(text_value),
+        "TEXTJOIN" => text_join(args),
+        "TEXT" => format_text(args, context),
+        "TRIM" => one_text(args, |s| {
+            s.split(' ')
+                .filter(|word| !word.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ")
+        }),
+        "UPPER" => one_text(args, |s| s.to_uppercase()),
+        "LOWER" => one_text(args, |s| s.to_lowercase()),
+        "PROPER" => one_text(args, proper),
+        "SUBSTITUTE" => substitute(args),
+        "FIND" => find(args, true),
+        "SEARCH" => find(args, false),
+        "VALUE" => value(args),
+        "REPT" => rept(args),
+        "EXACT" => expect_count(args, 2, 2)
+            .and_then(|_| Ok(text(args, 0)? == text(args, 1)?))
+            .and_then(bool_value),
+        _ => return None,
+    };
+    Some(result)
+}
+
+fn one_text(args: &[Value], f: impl Fn(&str) -> String) -> FunctionResult {
+    expect_count(args, 1, 1)?;
+    text_value(f(&text(args, 0)?))
+}
+/// A count of chars, negative ones are `#VALUE!`.
+fn count(args: &[Value], index: usize, default: f64) -> Result<usize, CellErrorKind> {
+    let count = optional_number(args, index, default)?.trunc();
+    if count < 0.0 {
+        return Err(CellErrorKind::Value);
+    }
+    Ok(count as usize)
+}
+fn left_or_right(args: &[Value], is_left: bool) -> FunctionResult {
+    expect_count(args, 1, 2)?;
+    let chars = text(args, 0)?.chars().collect::<Vec<_>>();
+    let count = count(args, 1, 1.0)?.min(chars.len());
+    let chars = if is_left {
+        &chars[..count]
+    } else {
+        &chars[chars.len() - count..]
+    };
+    text_value(chars.iter().collect::<String>())
+}
+fn mid(args: &[Value]) -> FunctionResult {
+    expect_count(args, 3, 3)?;
+    let s = text(args, 0)?;
+    let start = number(args, 1)?.trunc();
+    if start < 1.0 {
+        return Err(CellErrorKind::Value);
+    }
+    let count = count(args, 2, 0.0)?;
+    text_value(
+        s.chars()
+            .skip(start as usize - 1)
+            .take(count)
+            .collect::<String>(),
+    )
+}
+/// Text of every value, ranges included.
+fn texts(args: &[Value]) -> Result<Vec<String>, CellErrorKind> {
+    args.iter()
+        .flat_map(|arg| arg.values())
+        .map(to_text)
+        .collect()
+}
+fn concat(args: &[Value]) -> FunctionResult {
+    text_value(texts(args)?.concat())
+}
+/// TEXTJOIN(delimiter, ignore_empty, text1, ...)
+fn text_join(args: &[Value]) -> FunctionResult {
+    if args.len() < 3 {
+        return Err(CellErrorKind::Value);
+    }
+    let delimiter = text(args, 0)?;
+    let ignore_empty = boolean(args, 1)?;
+    let texts = texts(&args[2..])?
+        .into_iter()
+        .filter(|s| !ignore_empty || !s.is_empty())
+        .collect::<Vec<_>>();
+    text_value(texts.join(&delimiter))
+}
+/// TEXT(value, format), numeric text is formatted as a number.
+fn format_text(args: &[Value], context: &FunctionContext) -> FunctionResult {
+    expect_count(args, 2, 2)?;
+    let value = match scalar(args, 0)? {
+        CellValue::String(s) => parse_number(&s)
+            .map(CellValue::Number)
+            .unwrap_or(CellValue::String(s)),
+        value => value,
+    };
+    let format_code = text(args, 1)?;
+    text_value(format_value(&value, &format_code, context.is_date1904))
+}
+/// Upper case first letters of words, lower case the rest.
+fn proper(s: &str) -> String {
+    let mut is_word_start = true;
+    s.chars()
+        .flat_map(|c| {
+            let converted = if is_word_start {
+                c.to_uppercase().collect::<Vec<_>>()
+            } else {
+                c.to_lowercase().collect::<Vec<_>>()
+            };
+            is_word_start = !c.is_alphabetic();
+            converted
+        })
+        .collect()
+}
+/// SUBSTITUTE(text, old, new, [instance]), every instance when omitted.
+fn substitute(args: &[Value]) -> FunctionResult {
+    expect_count(args, 3, 4)?;
+    let (s, old, new) = (text(args, 0)?, text(args, 1)?, text(args, 2)?);
+    if old.is_empty() {
+        return text_value(s);
+    }
+    if args.len() == 3 {
+        return text_value(s.replace(&old, &new));
+    }
+    let instance = number(args, 3)?.trunc();
+    if instance < 1.0 {
+        return Err(CellErrorKind::Value);
+    }
+    match s.match_indices(&old).nth(instance as usize - 1) {
+        Some((position, _)) => text_value(format!(
+            "{}{}{}",
+            &s[..position],
+            new,
+            &s[position + old.len()..]
+        )),
+        None => text_value(s),
+    }
+}
+/// FIND(find, within, [start]) is case-sensitive, SEARCH ignores case and takes wildcards.
+fn find(args: &[Value], is_case_sensitive: bool) -> FunctionResult {
+    expect_count(args, 2, 3)?;
+    let (needle, haystack) = (text(args, 0)?, text(args, 1)?);
+    let start = optional_number(args, 2, 1.0)?.trunc();
+    let haystack = haystack.chars().collect::<Vec<_>>();
+    if start < 1.0 || start as usize > haystack.len() + 1 {
+        return Err(CellErrorKind::Value);
+    }
+    let needle = needle.chars().collect::<Vec<_>>();
+    let found = (start as usize - 1..=haystack.len()).find(|&from| {
+        if is_case_sensitive {
+            haystack[from..].starts_with(&needle)
+        } else {
+            search_at(&needle, &haystack[from..])
+        }
+    });
+    match found {
+        Some(position) => number_value((position + 1) as f64),
+        None => Err(CellErrorKind::Value),
+    }
+}
+/// Whether the pattern matches the start of `text`, ignoring case with `*` and `?` wildcards.
+fn search_at(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => true,
+        Some('*') => (0..=text.len()).any(|skip| search_at(&pattern[1..], &text[skip..])),
+        Some('?') => !text.is_empty() && search_at(&pattern[1..], &text[1..]),
+        Some('~') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && search_at(&pattern[2..], &text[1..])
+        }
+        Some(c) => {
+            text.first()
+                .is_some_and(|t| t.to_lowercase().eq(c.to_lowercase()))
+                && search_at(&pattern[1..], &text[1..])
+        }
+    }
+}
+fn value(args: &[Value]) -> FunctionResult {
+    expect_count(args, 1, 1)?;
+    match scalar(args, 0)? {
+        CellValue::Number(number) => number_value(number),
+        CellValue::Empty => number_value(0.0),
+        CellValue::String(s) => number_value(parse_number(&s).ok_or(CellErrorKind::Value)?),
+        _ => Err(CellErrorKind::Value),
+    }
+}
+fn rept(args: &[Value]) -> FunctionResult {
+    expect_count(args, 2, 2)?;
+    let s = text(args, 0)?;
+    let times = count(args, 1, 0.0)?;
+    if s.len() * times > 32767 {
+        return Err(CellErrorKind::Value);
+    }
+    text_value(s.repeat(times))
+}
+
+#[cfg(test)]
+mod text_test {
+    use crate::excel::{
+        cells::cell_value::{CellErrorKind, CellValue},
+        formulas::evaluator::evaluator_test::evaluate,
+    };
+
+    fn text(s: &str) -> CellValue {
+        CellValue::String(s.to_string())
+    }
+    #[test]
+    fn slice_test() {
+        let cells = [("A1", "東京都千代田区"), ("A2", "  a   b  ")];
+        assert_eq!(evaluate(&cells, "=LEFT(A1,3)"), text("東京都"));
+        assert_eq!(evaluate(&cells, "=RIGHT(A1)"), text("区"));
+        assert_eq!(evaluate(&cells, "=MID(A1,4,3)"), text("千代田"));
+        assert_eq!(evaluate(&cells, "=MID(A1,10,3)"), text(""));
+        assert_eq!(evaluate(&cells, "=LEN(A1)"), CellValue::Number(7.0));
+        assert_eq!(
+            evaluate(&cells, "=LEFT(A1,-1)"),
+            CellValue::Error(CellErrorKind::Value)
+        );
+        assert_eq!(evaluate(&cells, "=TRIM(A2)"), text("a b"));
+    }
+    #[test]
+    fn join_test() {
+        let cells = [("A1", "a"), ("A2", "1"), ("A3", "TRUE")];
+        assert_eq!(evaluate(&cells, "=CONCAT(A1:A4,\"!\")"), text("a1TRUE!"));
+        assert_eq!(evaluate(&cells, "=CONCATENATE(A1,\"-\",A2)"), text("a-1"));
+        assert_eq!(
+            evaluate(&cells, "=TEXTJOIN(\",\",TRUE,A1:A4)"),
+            text("a,1,TRUE")
+        );
+        assert_eq!(
+            evaluate(&cells, "=TEXTJOIN(\",\",FALSE,A1:A4)"),
+            text("a,1,TRUE,")
+        );
+    }
+    #[test]
+    fn convert_test() {
+        let cells = [("A1", "1234.5"), ("A2", "hello world")];
+        assert_eq!(evaluate(&cells, "=TEXT(A1,\"#,##0.00\")"), text("1,234.50"));
+        assert_eq!(
+            evaluate(&cells, "=TEXT(45000,\"yyyy-mm-dd\")"),
+            text("2023-03-15")
+        );
+        assert_eq!(evaluate(&cells, "=PROPER(A2)"), text("Hello World"));
+        assert_eq!(evaluate(&cells, "=UPPER(A2)"), text("HELLO WORLD"));
+        assert_eq!(
+            evaluate(&cells, "=SUBSTITUTE(\"a-b-c\",\"-\",\"+\",2)"),
+            text("a-b+c")
+        );
+        assert_eq!(
+            evaluate(&cells, "=SUBSTITUTE(\"a-b-c\",\"-\",\"\")"),
+            text("abc")
+        );
+        assert_eq!(
+            evaluate(&cells, "=VALUE(\"1,000\")"),
+            CellValue::Number(1000.0)
+        );
+        assert_eq!(evaluate(&cells, "=REPT(\"ab\",3)"), text("ababab"));
+        assert_eq!(
+            evaluate(&cells, "=EXACT(\"a\",\"A\")"),
+            CellValue::Bool(false)
+        );
+    }
+    #[test]
+    fn find_test() {
+        let cells = [("A1", "Hello World")];
+        assert_eq!(evaluate(&cells, "=FIND(\"o\",A1)"), CellValue::Number(5.0));
+        assert_eq!(
+            evaluate(&cells, "=FIND(\"o\",A1,6)"),
+            CellValue::Number(8.0)
+        );
+        assert_eq!(
+            evaluate(&cells, "=FIND(\"w\",A1)"),
+            CellValue::Error(CellErrorKind::Value)
+        );
+        assert_eq!(
+            evaluate(&cells, "=SEARCH(\"w\",A1)"),
+            CellValue::Number(7.0)
+        );
+        assert_eq!(
+            evaluate(&cells, "=SEARCH(\"w?r\",A1)"),
+            CellValue::Number(7.0)
+        );
+    }
+}
@@ -0,0 +1,287 @@
+use crate::excel::cells::cell_value::CellErrorKind;
+
+use super::parser::FormulaError;
+
+const ERRORS: [&str; 10] = [
+    "#NULL!",
+    "#DIV/0!",
+    "#VALUE!",
+    "#REF!",
+    "#NAME?",
+    "#NUM!",
+    "#N/A",
+    "#GETTING_DATA",
+    "#SPILL!",
+    "#CALC!",
+];
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Token {
+    Number(f64),
+    /// `"..."` with the doubled quotes already undone
+    Text(String),
+    /// Function, defined name, table, reference or sheet name, e.g. `SUM`, `$A$1`, `Sheet1`
+    Word(String),
+    /// `'...'` sheet name
+    QuotedName(String),
+    Error(CellErrorKind),
+    /// Inside of `[...]` of a structured reference, nested brackets kept
+    Bracket(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Ampersand,
+    Percent,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Colon,
+    Comma,
+    Semicolon,
+    Bang,
+    LeftParen,
+    RightParen,
+    LeftBrace,
+    RightBrace,
+    /// Whitespace, which is the intersection operator between references
+    Space,
+}
+
+/// Splits the formula into tokens paired with their char position.
+///
+///     assert_eq!(tokenize("A1+1")?, vec![(Token::Word("A1".to_string()), 0), (Token::Plus, 2), (Token::Number(1.0), 3)]);
+///
+pub fn tokenize(formula: &str) -> Result<Vec<(Token, usize)>, FormulaError> {
+    let chars = formula.chars().collect::<Vec<_>>();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i];
+        let token = match c {
+            c if c.is_whitespace() => {
+                while chars.get(i).is_some_and(|c| c.is_whitespace()) {
+                    i += 1;
+                }
+                tokens.push((Token::Space, start));
+                continue;
+            }
+            '"' => {
+                let (text, end) =
+                    read_quoted(&chars, i, '"').ok_or(FormulaError::UnterminatedText(start))?;
+                i = end;
+                tokens.push((Token::Text(text), start));
+                continue;
+            }
+            '\'' => {
+                let (name, end) =
+                    read_quoted(&chars, i, '\'').ok_or(FormulaError::UnterminatedText(start))?;
+                i = end;
+                tokens.push((Token::QuotedName(name), start));
+                continue;
+            }
+            '[' => {
+                let end =
+                    find_bracket_end(&chars, i).ok_or(FormulaError::UnclosedBracket(start))?;
+                tokens.push((Token::Bracket(chars[i + 1..end].iter().collect()), start));
+                i = end + 1;
+                continue;
+            }
+            '#' => {
+                let rest = chars[i..].iter().collect::<String>().to_ascii_uppercase();
+                let error = ERRORS
+                    .iter()
+                    .find(|error| rest.starts_with(*error))
+                    .ok_or(FormulaError::UnexpectedChar(c, start))?;
+                i += error.chars().count();
+                tokens.push((Token::Error(CellErrorKind::new(error).unwrap()), start));
+                continue;
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && chars.get(i + 1).is_some_and(|c| c.is_ascii_digit())) =>
+            {
+                let end = read_number(&chars, i);
+                let number = chars[i..end].iter().collect::<String>();
+                i = end;
+                let number = number
+                    .parse()
+                    .map_err(|_| FormulaError::UnexpectedChar(c, start))?;
+                tokens.push((Token::Number(number), start));
+                continue;
+            }
+            c if is_word_start(c) => {
+                let end = chars[i..]
+                    .iter()
+                    .position(|c| !is_word_char(*c))
+                    .map(|end| i + end)
+                    .unwrap_or(chars.len());
+                let word = chars[i..end].iter().collect::<String>();
+                i = end;
+                tokens.push((Token::Word(word), start));
+                continue;
+            }
+            '<' if chars.get(i + 1) == Some(&'>') => Token::NotEqual,
+            '<' if chars.get(i + 1) == Some(&'=') => Token::LessEqual,
+            '>' if chars.get(i + 1) == Some(&'=') => Token::GreaterEqual,
+            '+' => Token::Plus,
+            '-' => Token::Minus,
+            '*' => Token::Star,
+            '/' => Token::Slash,
+            '^' => Token::Caret,
+            '&' => Token::Ampersand,
+            '%' => Token::Percent,
+            '=' => Token::Equal,
+            '<' => Token::Less,
+            '>' => Token::Greater,
+            ':' => Token::Colon,
+            ',' => Token::Comma,
+            ';' => Token::Semicolon,
+            '!' => Token::Bang,
+            '(' => Token::LeftParen,
+            ')' => Token::RightParen,
+            '{' => Token::LeftBrace,
+            '}' => Token::RightBrace,
+            c => return Err(FormulaError::UnexpectedChar(c, start)),
+        };
+        i += match token {
+            Token::NotEqual | Token::LessEqual | Token::GreaterEqual => 2,
+            _ => 1,
+        };
+        tokens.push((token, start));
+    }
+    Ok(tokens)
+}
+fn is_word_start(c: char) -> bool {
+    c.is_alphabetic() || matches!(c, '_' | '\\' | '$')
+}
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '\\' | '$' | '.' | '?')
+}
+/// Returns the text between the quotes, where a doubled quote is one quote, and the position after it.
+fn read_quoted(chars: &[char], start: usize, quote: char) -> Option<(String, usize)> {
+    let mut text = String::new();
+    let mut i = start + 1;
+    loop {
+        match chars.get(i)? {
+            c if *c == quote && chars.get(i + 1) == Some(&quote) => {
+                text.push(quote);
+                i += 2;
+            }
+            c if *c == quote => return Some((text, i + 1)),
+            c => {
+                text.push(*c);
+                i += 1;
+            }
+        }
+    }
+}
+/// Position of the `]` closing the `[` at `start`, `'` escapes the next char.
+fn find_bracket_end(chars: &[char], start: usize) -> Option<usize> {
+    let mut depth = 0;
+    let mut i = start;
+    while i < chars.len() {
+        match chars[i] {
+            '\'' => i += 1,
+            '[' => depth += 1,
+            ']' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(i);
+                }
+            }
+            _ => (),
+        }
+        i += 1;
+    }
+    None
+}
+fn read_number(chars: &[char], start: usize) -> usize {
+    let digits_end = |from: usize| {
+        chars[from..]
+            .iter()
+            .position(|c| !c.is_ascii_digit())
+            .map(|end| from + end)
+            .unwrap_or(chars.len())
+    };
+    let mut i = digits_end(start);
+    if chars.get(i) == Some(&'.') {
+        i = digits_end(i + 1);
+    }
+    if matches!(chars.get(i), Some('e' | 'E')) {
+        let sign = usize::from(matches!(chars.get(i + 1), Some('+' | '-')));
+        if chars.get(i + 1 + sign).is_some_and(|c| c.is_ascii_digit()) {
+            i = digits_end(i + 1 + sign);
+        }
+    }
+    i
+}
+
+#[cfg(test)]
+mod lexer_test {
+    use crate::excel::{cells::cell_value::CellErrorKind, formulas::parser::FormulaError};
+
+    use super::{tokenize, Token};
+
+    fn tokens(formula: &str) -> Vec<Token> {
+        tokenize(formula)
+            .unwrap()
+            .into_iter()
+            .map(|(token, _)| token)
+            .collect()
+    }
+    #[test]
+    fn tokenize_test() {
+        assert_eq!(
+            tokens(r#"IF(A1<>"say ""hi""",1.5E+3%,#N/A)"#),
+            vec![
+                Token::Word("IF".to_string()),
+                Token::LeftParen,
+                Token::Word("A1".to_string()),
+                Token::NotEqual,
+                Token::Text(r#"say "hi""#.to_string()),
+                Token::Comma,
+                Token::Number(1500.0),
+                Token::Percent,
+                Token::Comma,
+                Token::Error(CellErrorKind::NA),
+                Token::RightParen,
+            ]
+        );
+        assert_eq!(
+            tokens("'テーブル一覧'!$A$1 Table1[[#Headers],[Price]]"),
+            vec![
+                Token::QuotedName("テーブル一覧".to_string()),
+                Token::Bang,
+                Token::Word("$A$1".to_string()),
+                Token::Space,
+                Token::Word("Table1".to_string()),
+                Token::Bracket("[#Headers],[Price]".to_string()),
+            ]
+        );
+        assert_eq!(
+            tokens("_xlfn.STDEV.S(1:3)"),
+            vec![
+                Token::Word("_xlfn.STDEV.S".to_string()),
+                Token::LeftParen,
+                Token::Number(1.0),
+                Token::Colon,
+                Token::Number(3.0),
+                Token::RightParen,
+            ]
+        );
+    }
+    #[test]
+    fn tokenize_error_test() {
+        assert_eq!(tokenize(r#""abc"#), Err(FormulaError::UnterminatedText(0)));
+        assert_eq!(
+            tokenize("A1+#FOO"),
+            Err(FormulaError::UnexpectedChar('#', 3))
+        );
+        assert_eq!(tokenize("T[[a]"), Err(FormulaError::UnclosedBracket(1)));
+    }
+}
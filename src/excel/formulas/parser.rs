@@ -0,0 +1,887 @@
+use std::{fmt::Display, ops::Range};
+
+use crate::excel::cells::cell_value::CellErrorKind;
+
+use super::{
+    ast::{
+        Area, BinaryOp, CellReference, ColumnRange, Expr, LineReference, Reference, Reference3D,
+        StructuredReference, TableSpecifier, UnaryOp,
+    },
+    lexer::{tokenize, Token},
+};
+
+/// Positions count chars of the formula after the leading `=`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum FormulaError {
+    UnexpectedChar(char, usize),
+    UnterminatedText(usize),
+    UnclosedBracket(usize),
+    UnexpectedToken(String, usize),
+    UnexpectedEnd,
+    InvalidStructuredReference(usize),
+}
+impl Display for FormulaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FormulaError::UnexpectedChar(c, position) => {
+                write!(f, "unexpected char {:?} at {}", c, position)
+            }
+            FormulaError::UnterminatedText(position) => {
+                write!(f, "quote opened at {} is not closed", position)
+            }
+            FormulaError::UnclosedBracket(position) => {
+                write!(f, "bracket opened at {} is not closed", position)
+            }
+            FormulaError::UnexpectedToken(token, position) => {
+                write!(f, "unexpected {} at {}", token, position)
+            }
+            FormulaError::UnexpectedEnd => write!(f, "unexpected end of formula"),
+            FormulaError::InvalidStructuredReference(position) => {
+                write!(f, "invalid structured reference at {}", position)
+            }
+        }
+    }
+}
+impl std::error::Error for FormulaError {}
+
+/// Parses a formula with or without the leading `=`.
+///
+///     let expr = parse("='テーブル一覧'!A1&\"様\"")?;
+///
+pub fn parse(formula: &str) -> Result<Expr, FormulaError> {
+    let formula = formula.strip_prefix('=').unwrap_or(formula);
+    let mut parser = Parser::new(formula)?;
+    let expr = parser.parse_expr()?;
+    parser.expect_end()?;
+    Ok(expr)
+}
+/// References of the formula and the names written after a sheet, with where they are written.
+/// Commas between top level items are read as a union, as in the formulas of defined names.
+///
+///     let references = find_references("SUM(Data!A1:A3)+Total")?;
+///     assert_eq!((references[0].chars.clone(), references[0].item_start), (4..14, 9));
+///
+pub fn find_references(formula: &str) -> Result<Vec<ReferenceSpan>, FormulaError> {
+    let offset = usize::from(formula.starts_with('='));
+    let mut parser = Parser::new(&formula[offset..])?;
+    parser.parse_expr()?;
+    while parser.peek() == Some(&Token::Comma) {
+        parser.next()?;
+        parser.parse_expr()?;
+    }
+    parser.expect_end()?;
+    Ok(parser
+        .references
+        .into_iter()
+        .map(|span| ReferenceSpan {
+            chars: span.chars.start + offset..span.chars.end + offset,
+            item_start: span.item_start + offset,
+            ..span
+        })
+        .collect())
+}
+/// Where a reference, or a name or `#REF!` after a sheet, is written in a formula.
+/// Positions count the chars of the formula.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ReferenceSpan {
+    /// From the sheet to the end of the area or name
+    pub chars: Range<usize>,
+    /// Where the area or name after `!` starts, `chars.start` without a sheet
+    pub item_start: usize,
+    pub sheet: Option<String>,
+    /// Last sheet of a 3-D reference
+    pub last_sheet: Option<String>,
+    /// `None` for a name or `#REF!`
+    pub area: Option<Area>,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    length: usize,
+    references: Vec<ReferenceSpan>,
+}
+impl Parser {
+    fn new(formula: &str) -> Result<Self, FormulaError> {
+        Ok(Parser {
+            tokens: tokenize(formula)?,
+            position: 0,
+            length: formula.chars().count(),
+            references: Vec::new(),
+        })
+    }
+    fn expect_end(&mut self) -> Result<(), FormulaError> {
+        match self.peek_with_position() {
+            Some((token, position)) => Err(unexpected(&token, position)),
+            None => Ok(()),
+        }
+    }
+    /// Keeps where `expr`, read from `start` up to the current token, is written.
+    fn record(&mut self, start: usize, item_start: usize, sheets: &[String], expr: &Expr) {
+        let area = match expr {
+            Expr::Reference(reference) => Some(reference.area.clone()),
+            Expr::Reference3D(reference) => Some(reference.area.clone()),
+            _ => None,
+        };
+        if area.is_none() && sheets.is_empty() {
+            return;
+        }
+        let end = self
+            .tokens
+            .get(self.position)
+            .map(|(_, position)| *position)
+            .unwrap_or(self.length);
+        self.references.push(ReferenceSpan {
+            chars: start..end,
+            item_start,
+            sheet: sheets.first().cloned(),
+            last_sheet: sheets.get(1).cloned(),
+            area,
+        });
+    }
+    fn skip_spaces(&mut self) {
+        while matches!(self.tokens.get(self.position), Some((Token::Space, _))) {
+            self.position += 1;
+        }
+    }
+    fn peek(&mut self) -> Option<&Token> {
+        self.skip_spaces();
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+    fn peek_with_position(&mut self) -> Option<(Token, usize)> {
+        self.skip_spaces();
+        self.tokens.get(self.position).cloned()
+    }
+    /// Token right after the current one, spaces are not skipped.
+    fn peek_raw(&self, offset: usize) -> Option<&Token> {
+        self.tokens
+            .get(self.position + offset)
+            .map(|(token, _)| token)
+    }
+    /// `Sheet3` of `:Sheet3!` right after the first sheet of a 3-D reference.
+    fn peek_last_sheet(&self) -> Option<String> {
+        match (self.peek_raw(0)?, self.peek_raw(1)?, self.peek_raw(2)?) {
+            (Token::Colon, Token::Word(last), Token::Bang) => Some(last.clone()),
+            _ => None,
+        }
+    }
+    fn next(&mut self) -> Result<(Token, usize), FormulaError> {
+        self.skip_spaces();
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or(FormulaError::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(token)
+    }
+    fn expect(&mut self, expected: Token) -> Result<(), FormulaError> {
+        match self.next()? {
+            (token, _) if token == expected => Ok(()),
+            (token, position) => Err(unexpected(&token, position)),
+        }
+    }
+    fn parse_expr(&mut self) -> Result<Expr, FormulaError> {
+        self.parse_binary(0)
+    }
+    /// Operators from the loosest, each level is left associative.
+    fn parse_binary(&mut self, level: usize) -> Result<Expr, FormulaError> {
+        const LEVELS: [&[(Token, BinaryOp)]; 5] = [
+            &[
+                (Token::Equal, BinaryOp::Eq),
+                (Token::NotEqual, BinaryOp::Ne),
+                (Token::Less, BinaryOp::Lt),
+                (Token::LessEqual, BinaryOp::Le),
+                (Token::Greater, BinaryOp::Gt),
+                (Token::GreaterEqual, BinaryOp::Ge),
+            ],
+            &[(Token::Ampersand, BinaryOp::Concat)],
+            &[(Token::Plus, BinaryOp::Add), (Token::Minus, BinaryOp::Sub)],
+            &[(Token::Star, BinaryOp::Mul), (Token::Slash, BinaryOp::Div)],
+            &[(Token::Caret, BinaryOp::Pow)],
+        ];
+        let Some(operators) = LEVELS.get(level) else {
+            return self.parse_unary();
+        };
+        let mut left = self.parse_binary(level + 1)?;
+        while let Some(op) = self.peek().and_then(|token| {
+            operators
+                .iter()
+                .find(|(operator, _)| operator == token)
+                .map(|(_, op)| *op)
+        }) {
+            self.next()?;
+            let right = self.parse_binary(level + 1)?;
+            left = Expr::Binary {
+                op,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+    /// Negation binds tighter than `^`, so `-2^2` is 4.
+    fn parse_unary(&mut self) -> Result<Expr, FormulaError> {
+        let op = match self.peek() {
+            Some(Token::Minus) => UnaryOp::Minus,
+            Some(Token::Plus) => UnaryOp::Plus,
+            _ => return self.parse_percent(),
+        };
+        self.next()?;
+        Ok(Expr::Unary {
+            op,
+            operand: Box::new(self.parse_unary()?),
+        })
+    }
+    fn parse_percent(&mut self) -> Result<Expr, FormulaError> {
+        let mut expr = self.parse_intersection()?;
+        while self.peek() == Some(&Token::Percent) {
+            self.next()?;
+            expr = Expr::Unary {
+                op: UnaryOp::Percent,
+                operand: Box::new(expr),
+            };
+        }
+        Ok(expr)
+    }
+    /// A space is the intersection only when another reference follows it.
+    fn parse_intersection(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.parse_range()?;
+        loop {
+            if self.peek_raw(0) != Some(&Token::Space) {
+                return Ok(left);
+            }
+            let is_reference_next = matches!(
+                self.peek_raw(1),
+                Some(Token::Word(_) | Token::QuotedName(_) | Token::Bracket(_) | Token::LeftParen)
+            );
+            if !is_reference_next {
+                return Ok(left);
+            }
+            self.skip_spaces();
+            let right = self.parse_range()?;
+            left = Expr::Binary {
+                op: BinaryOp::Intersect,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+    }
+    fn parse_range(&mut self) -> Result<Expr, FormulaError> {
+        let mut left = self.parse_primary()?;
+        while self.peek_raw(0) == Some(&Token::Colon) {
+            self.position += 1;
+            let right = self.parse_primary()?;
+            left = Expr::Binary {
+                op: BinaryOp::Range,
+                left: Box::new(left),
+                right: Box::new(right),
+            };
+        }
+        Ok(left)
+    }
+    fn parse_primary(&mut self) -> Result<Expr, FormulaError> {
+        let (token, position) = self.next()?;
+        match token {
+            Token::Number(number) => match self.parse_rows(None, &number.to_string()) {
+                Some(rows) => {
+                    self.record(position, position, &[], &rows);
+                    Ok(rows)
+                }
+                None => Ok(Expr::Number(number)),
+            },
+            Token::Text(text) => Ok(Expr::Text(text)),
+            Token::Error(error) => Ok(Expr::Error(error)),
+            Token::LeftParen => {
+                let mut expr = self.parse_expr()?;
+                while self.peek() == Some(&Token::Comma) {
+                    self.next()?;
+                    let right = self.parse_expr()?;
+                    expr = Expr::Binary {
+                        op: BinaryOp::Union,
+                        left: Box::new(expr),
+                        right: Box::new(right),
+                    };
+                }
+                self.expect(Token::RightParen)?;
+                Ok(Expr::Parenthesized(Box::new(expr)))
+            }
+            Token::LeftBrace => self.parse_array(),
+            Token::Bracket(book) => match (self.peek_raw(0), self.peek_raw(1)) {
+                (Some(Token::Word(sheet)), Some(Token::Bang)) => {
+                    // `[1]Sheet1!A1` of another workbook
+                    let sheet = format!("[{}]{}", book, sheet);
+                    self.position += 2;
+                    self.parse_sheet_item(position, vec![sheet])
+                }
+                _ => Ok(Expr::StructuredReference(parse_structured_reference(
+                    None, &book, position,
+                )?)),
+            },
+            Token::QuotedName(sheet) => {
+                self.expect(Token::Bang)?;
+                // sheet names have no `:`, it is between the sheets of a 3-D reference
+                let sheets = match sheet.split_once(':') {
+                    Some((first, last)) if !sheet.starts_with('[') => {
+                        vec![first.to_string(), last.to_string()]
+                    }
+                    _ => vec![sheet],
+                };
+                self.parse_sheet_item(position, sheets)
+            }
+            Token::Word(word) => match self.peek_raw(0) {
+                Some(Token::LeftParen) => self.parse_function(word),
+                Some(Token::Bang) => {
+                    self.position += 1;
+                    self.parse_sheet_item(position, vec![word])
+                }
+                Some(Token::Colon) if self.peek_last_sheet().is_some() => {
+                    let last = self.peek_last_sheet().unwrap();
+                    self.position += 3;
+                    self.parse_sheet_item(position, vec![word, last])
+                }
+                Some(Token::Bracket(content)) => {
+                    let content = content.clone();
+                    let bracket_position = self.tokens[self.position].1;
+                    self.position += 1;
+                    Ok(Expr::StructuredReference(parse_structured_reference(
+                        Some(word),
+                        &content,
+                        bracket_position,
+                    )?))
+                }
+                _ if word.eq_ignore_ascii_case("TRUE") => Ok(Expr::Bool(true)),
+                _ if word.eq_ignore_ascii_case("FALSE") => Ok(Expr::Bool(false)),
+                _ => {
+                    let expr = self.parse_word(None, word);
+                    self.record(position, position, &[], &expr);
+                    Ok(expr)
+                }
+            },
+            token => Err(unexpected(&token, position)),
+        }
+    }
+    /// What follows `Sheet1!`, or `Sheet1:Sheet3!` of a 3-D reference, written from `start`.
+    fn parse_sheet_item(
+        &mut self,
+        start: usize,
+        sheets: Vec<String>,
+    ) -> Result<Expr, FormulaError> {
+        let sheet = sheets[0].clone();
+        let (token, item_start) = self.next()?;
+        let expr = match token {
+            Token::Word(word) => self.parse_word(Some(sheet), word),
+            Token::Number(number) => self
+                .parse_rows(Some(sheet), &number.to_string())
+                .ok_or_else(|| unexpected(&Token::Number(number), item_start))?,
+            Token::Error(CellErrorKind::Ref) => Expr::Error(CellErrorKind::Ref),
+            token => return Err(unexpected(&token, item_start)),
+        };
+        let expr = match (sheets.get(1), expr) {
+            (None, expr) => expr,
+            (Some(last_sheet), Expr::Reference(Reference { sheet, area })) => {
+                Expr::Reference3D(Reference3D {
+                    first_sheet: sheet.unwrap_or_default(),
+                    last_sheet: last_sheet.clone(),
+                    area,
+                })
+            }
+            (Some(_), Expr::Error(kind)) => Expr::Error(kind),
+            // names are not 3-D
+            (Some(_), _) => return Err(unexpected(&Token::Bang, item_start - 1)),
+        };
+        self.record(start, item_start, &sheets, &expr);
+        Ok(expr)
+    }
+    /// Cell, range, columns, rows or else a defined name.
+    fn parse_word(&mut self, sheet: Option<String>, word: String) -> Expr {
+        let reference = |area| {
+            Expr::Reference(Reference {
+                sheet: sheet.clone(),
+                area,
+            })
+        };
+        if let Some(start) = CellReference::new(&word) {
+            if let Some(end) = self.take_range_end(CellReference::new) {
+                return reference(Area::Range(start, end));
+            }
+            return reference(Area::Cell(start));
+        }
+        if let Some(start) = LineReference::from_column(&word) {
+            if let Some(end) = self.take_range_end(LineReference::from_column) {
+                return reference(Area::Columns(start, end));
+            }
+        }
+        if let Some(rows) = self.parse_rows(sheet.clone(), &word) {
+            return rows;
+        }
+        Expr::Name { sheet, name: word }
+    }
+    /// `1:3` or `$1:$3`, after the first row has been read.
+    fn parse_rows(&mut self, sheet: Option<String>, first: &str) -> Option<Expr> {
+        let start = LineReference::from_row(first)?;
+        let end = self.take_range_end(LineReference::from_row)?;
+        Some(Expr::Reference(Reference {
+            sheet,
+            area: Area::Rows(start, end),
+        }))
+    }
+    /// Takes `:` and the end of the range when it reads as `read`.
+    fn take_range_end<T>(&mut self, read: impl Fn(&str) -> Option<T>) -> Option<T> {
+        if self.peek_raw(0) != Some(&Token::Colon) {
+            return None;
+        }
+        let end = match self.peek_raw(1)? {
+            Token::Word(word) => read(word),
+            Token::Number(number) => read(&number.to_string()),
+            _ => None,
+        }?;
+        self.position += 2;
+        Some(end)
+    }
+    fn parse_function(&mut self, name: String) -> Result<Expr, FormulaError> {
+        self.expect(Token::LeftParen)?;
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::RightParen) {
+            self.next()?;
+            return Ok(Expr::Function { name, args });
+        }
+        loop {
+            match self.peek() {
+                Some(Token::Comma | Token::RightParen) => args.push(Expr::Missing),
+                _ => args.push(self.parse_expr()?),
+            }
+            match self.next()? {
+                (Token::Comma, _) => continue,
+                (Token::RightParen, _) => return Ok(Expr::Function { name, args }),
+                (token, position) => return Err(unexpected(&token, position)),
+            }
+        }
+    }
+    /// `{1,2;3,4}`, the `{` has been read.
+    fn parse_array(&mut self) -> Result<Expr, FormulaError> {
+        let mut rows = vec![Vec::new()];
+        loop {
+            let value = match self.next()? {
+                (Token::Minus, _) => match self.next()? {
+                    (Token::Number(number), _) => Expr::Number(-number),
+                    (token, position) => return Err(unexpected(&token, position)),
+                },
+                (Token::Number(number), _) => Expr::Number(number),
+                (Token::Text(text), _) => Expr::Text(text),
+                (Token::Error(error), _) => Expr::Error(error),
+                (Token::Word(word), _) if word.eq_ignore_ascii_case("TRUE") => Expr::Bool(true),
+                (Token::Word(word), _) if word.eq_ignore_ascii_case("FALSE") => Expr::Bool(false),
+                (token, position) => return Err(unexpected(&token, position)),
+            };
+            rows.last_mut().unwrap().push(value);
+            match self.next()? {
+                (Token::Comma, _) => (),
+                (Token::Semicolon, _) => rows.push(Vec::new()),
+                (Token::RightBrace, _) => return Ok(Expr::Array(rows)),
+                (token, position) => return Err(unexpected(&token, position)),
+            }
+        }
+    }
+}
+fn unexpected(token: &Token, position: usize) -> FormulaError {
+    FormulaError::UnexpectedToken(format!("{:?}", token), position)
+}
+
+/// Reads the inside of the outer brackets, e.g. `[#Headers],[Price]:[Tax]` or `@Price`.
+fn parse_structured_reference(
+    table: Option<String>,
+    content: &str,
+    position: usize,
+) -> Result<StructuredReference, FormulaError> {
+    let error = FormulaError::InvalidStructuredReference(position);
+    let content = content.trim();
+    let mut reference = StructuredReference {
+        table,
+        specifiers: Vec::new(),
+        columns: None,
+    };
+    if let Some(rest) = content.strip_prefix('@') {
+        reference.specifiers.push(TableSpecifier::ThisRow);
+        let rest = rest.trim();
+        if rest.starts_with('[') {
+            let (specifiers, columns) = parse_bracket_items(rest).ok_or(error)?;
+            reference.specifiers.extend(specifiers);
+            reference.columns = columns;
+        } else if !rest.is_empty() {
+            reference.columns = Some((unescape_column(rest), None));
+        }
+        return Ok(reference);
+    }
+    if content.starts_with('[') {
+        let (specifiers, columns) = parse_bracket_items(content).ok_or(error)?;
+        reference.specifiers = specifiers;
+        reference.columns = columns;
+    } else if content.starts_with('#') {
+        reference.specifiers = vec![TableSpecifier::new(content).ok_or(error)?];
+    } else if !content.is_empty() {
+        reference.columns = Some((unescape_column(content), None));
+    }
+    Ok(reference)
+}
+/// Splits `[#Headers],[Price]:[Tax]` into the specifiers and the columns.
+fn parse_bracket_items(content: &str) -> Option<(Vec<TableSpecifier>, Option<ColumnRange>)> {
+    let chars = content.chars().collect::<Vec<_>>();
+    let mut items = Vec::new();
+    let mut separators = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '[' => {
+                let mut item = String::new();
+                i += 1;
+                while *chars.get(i)? != ']' {
+                    if chars[i] == '\'' {
+                        item.push('\'');
+                        i += 1;
+                    }
+                    item.push(*chars.get(i)?);
+                    i += 1;
+                }
+                items.push(item);
+            }
+            c @ (',' | ':') => separators.push(c),
+            c if c.is_whitespace() => (),
+            _ => return None,
+        }
+        i += 1;
+    }
+    let mut specifiers = Vec::new();
+    let mut columns = None;
+    let mut index = 0;
+    while index < items.len() {
+        let item = items[index].trim();
+        if item.starts_with('#') {
+            specifiers.push(TableSpecifier::new(item)?);
+        } else if separators.get(index) == Some(&':') {
+            let last = items.get(index + 1)?;
+            columns = Some((unescape_column(item), Some(unescape_column(last))));
+            index += 1;
+        } else {
+            columns = Some((unescape_column(item), None));
+        }
+        index += 1;
+    }
+    Some((specifiers, columns))
+}
+/// `'` makes the next char of a column name literal.
+fn unescape_column(column: &str) -> String {
+    let mut result = String::with_capacity(column.len());
+    let mut chars = column.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => result.extend(chars.next()),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod parser_test {
+    use crate::excel::{
+        cells::cell_value::CellErrorKind,
+        formulas::ast::{
+            Area, BinaryOp, CellReference, Expr, LineReference, Reference, Reference3D,
+            StructuredReference, TableSpecifier, UnaryOp,
+        },
+    };
+
+    use super::{find_references, parse, FormulaError};
+
+    fn cell(s: &str) -> Expr {
+        Expr::Reference(Reference {
+            sheet: None,
+            area: Area::Cell(CellReference::new(s).unwrap()),
+        })
+    }
+    fn binary(op: BinaryOp, left: Expr, right: Expr) -> Expr {
+        Expr::Binary {
+            op,
+            left: Box::new(left),
+            right: Box::new(right),
+        }
+    }
+    #[test]
+    fn precedence_test() {
+        assert_eq!(
+            parse("=1+2*3^2").unwrap(),
+            binary(
+                BinaryOp::Add,
+                Expr::Number(1.0),
+                binary(
+                    BinaryOp::Mul,
+                    Expr::Number(2.0),
+                    binary(BinaryOp::Pow, Expr::Number(3.0), Expr::Number(2.0))
+                )
+            )
+        );
+        assert_eq!(
+            parse("-2^2").unwrap(),
+            binary(
+                BinaryOp::Pow,
+                Expr::Unary {
+                    op: UnaryOp::Minus,
+                    operand: Box::new(Expr::Number(2.0))
+                },
+                Expr::Number(2.0)
+            )
+        );
+        assert_eq!(
+            parse("A1&B1=\"x\"").unwrap(),
+            binary(
+                BinaryOp::Eq,
+                binary(BinaryOp::Concat, cell("A1"), cell("B1")),
+                Expr::Text("x".to_string())
+            )
+        );
+        assert_eq!(
+            parse("1-2-3").unwrap(),
+            binary(
+                BinaryOp::Sub,
+                binary(BinaryOp::Sub, Expr::Number(1.0), Expr::Number(2.0)),
+                Expr::Number(3.0)
+            )
+        );
+        assert_eq!(
+            parse("50%*2").unwrap(),
+            binary(
+                BinaryOp::Mul,
+                Expr::Unary {
+                    op: UnaryOp::Percent,
+                    operand: Box::new(Expr::Number(50.0))
+                },
+                Expr::Number(2.0)
+            )
+        );
+    }
+    #[test]
+    fn reference_test() {
+        assert_eq!(
+            parse("'テーブル一覧'!$A$1:B2").unwrap(),
+            Expr::Reference(Reference {
+                sheet: Some("テーブル一覧".to_string()),
+                area: Area::Range(
+                    CellReference::new("$A$1").unwrap(),
+                    CellReference::new("B2").unwrap()
+                )
+            })
+        );
+        assert_eq!(
+            parse("Sheet1!C:$D").unwrap(),
+            Expr::Reference(Reference {
+                sheet: Some("Sheet1".to_string()),
+                area: Area::Columns(LineReference::new(3, false), LineReference::new(4, true))
+            })
+        );
+        assert_eq!(
+            parse("2:$5").unwrap(),
+            Expr::Reference(Reference {
+                sheet: None,
+                area: Area::Rows(LineReference::new(2, false), LineReference::new(5, true))
+            })
+        );
+        assert_eq!(
+            parse("TaxRate*Sheet2!Total").unwrap(),
+            binary(
+                BinaryOp::Mul,
+                Expr::Name {
+                    sheet: None,
+                    name: "TaxRate".to_string()
+                },
+                Expr::Name {
+                    sheet: Some("Sheet2".to_string()),
+                    name: "Total".to_string()
+                }
+            )
+        );
+        assert_eq!(
+            parse("A1:INDEX(B:B,2)").unwrap().to_string(),
+            "A1:INDEX(B:B,2)"
+        );
+        assert_eq!(
+            parse("Sheet1:Sheet3!A1").unwrap(),
+            Expr::Reference3D(Reference3D {
+                first_sheet: "Sheet1".to_string(),
+                last_sheet: "Sheet3".to_string(),
+                area: Area::Cell(CellReference::new("A1").unwrap())
+            })
+        );
+        assert_eq!(
+            parse("'Jan 1:Mar'!$1:$2").unwrap(),
+            Expr::Reference3D(Reference3D {
+                first_sheet: "Jan 1".to_string(),
+                last_sheet: "Mar".to_string(),
+                area: Area::Rows(LineReference::new(1, true), LineReference::new(2, true))
+            })
+        );
+        assert_eq!(
+            parse("Sheet1:Sheet3!Total"),
+            Err(FormulaError::UnexpectedToken("Bang".to_string(), 13))
+        );
+        assert_eq!(
+            parse("[1]Sheet1!A1").unwrap(),
+            Expr::Reference(Reference {
+                sheet: Some("[1]Sheet1".to_string()),
+                area: Area::Cell(CellReference::new("A1").unwrap())
+            })
+        );
+        assert_eq!(
+            parse("SUM(A1:B2 B1:C3)").unwrap(),
+            Expr::Function {
+                name: "SUM".to_string(),
+                args: vec![binary(
+                    BinaryOp::Intersect,
+                    parse("A1:B2").unwrap(),
+                    parse("B1:C3").unwrap()
+                )]
+            }
+        );
+    }
+    #[test]
+    fn function_test() {
+        assert_eq!(
+            parse("IF( A1 ,,#N/A)").unwrap(),
+            Expr::Function {
+                name: "IF".to_string(),
+                args: vec![cell("A1"), Expr::Missing, Expr::Error(CellErrorKind::NA)]
+            }
+        );
+        assert_eq!(
+            parse("NOW()").unwrap(),
+            Expr::Function {
+                name: "NOW".to_string(),
+                args: vec![]
+            }
+        );
+        assert_eq!(
+            parse("LOG10(100)").unwrap(),
+            Expr::Function {
+                name: "LOG10".to_string(),
+                args: vec![Expr::Number(100.0)]
+            }
+        );
+        assert_eq!(parse("SUM((A1,B2))").unwrap().to_string(), "SUM((A1,B2))");
+    }
+    #[test]
+    fn array_test() {
+        assert_eq!(
+            parse(r#"{1,-2;"a",TRUE}"#).unwrap(),
+            Expr::Array(vec![
+                vec![Expr::Number(1.0), Expr::Number(-2.0)],
+                vec![Expr::Text("a".to_string()), Expr::Bool(true)]
+            ])
+        );
+    }
+    #[test]
+    fn structured_reference_test() {
+        assert_eq!(
+            parse("Table1[[#Headers],[Unit Price]:[Tax]]").unwrap(),
+            Expr::StructuredReference(StructuredReference {
+                table: Some("Table1".to_string()),
+                specifiers: vec![TableSpecifier::Headers],
+                columns: Some(("Unit Price".to_string(), Some("Tax".to_string())))
+            })
+        );
+        assert_eq!(
+            parse("[@Price]").unwrap(),
+            Expr::StructuredReference(StructuredReference {
+                table: None,
+                specifiers: vec![TableSpecifier::ThisRow],
+                columns: Some(("Price".to_string(), None))
+            })
+        );
+        assert_eq!(
+            parse("Table1[[#This Row],[Price]]").unwrap(),
+            parse("Table1[@Price]").unwrap()
+        );
+        assert_eq!(
+            parse("Sales[#Totals]").unwrap(),
+            Expr::StructuredReference(StructuredReference {
+                table: Some("Sales".to_string()),
+                specifiers: vec![TableSpecifier::Totals],
+                columns: None
+            })
+        );
+        assert_eq!(
+            parse("Sales['#Items]").unwrap(),
+            Expr::StructuredReference(StructuredReference {
+                table: Some("Sales".to_string()),
+                specifiers: vec![],
+                columns: Some(("#Items".to_string(), None))
+            })
+        );
+    }
+    #[test]
+    fn display_test() {
+        for formula in [
+            "$E$3&G4",
+            "SUM(A1:A3)*2",
+            "'テーブル一覧'!A1&\"様\"",
+            "IF(A1>=10%,\"say \"\"hi\"\"\",FALSE)",
+            "Table1[[#Headers],[Unit Price]:[Tax]]",
+            "Table1[@Price]+[@[Unit Price]]",
+            "{1,2;3,4}",
+            "-(1+2)^2",
+            "_xlfn.STDEV.S(Sheet1!1:3)",
+            "#REF!+1",
+            "SUM(Jan:Mar!A1:B2)",
+            "SUM('Jan:Mar 2024'!A1)",
+        ] {
+            assert_eq!(parse(formula).unwrap().to_string(), formula);
+        }
+    }
+    #[test]
+    fn find_references_test() {
+        let spans =
+            find_references("=SUM( Data!A1:a3 ,Jan:Mar!B2)+'My Sheet'!Total+A1&\"B1\"").unwrap();
+        let written = spans
+            .iter()
+            .map(|span| {
+                (
+                    span.chars.clone(),
+                    span.item_start,
+                    span.sheet.as_deref(),
+                    span.last_sheet.as_deref(),
+                    span.area.as_ref().map(|area| area.to_string()),
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            written,
+            vec![
+                (6..16, 11, Some("Data"), None, Some("A1:A3".to_string())),
+                (18..28, 26, Some("Jan"), Some("Mar"), Some("B2".to_string())),
+                (30..46, 41, Some("My Sheet"), None, None),
+                (47..49, 47, None, None, Some("A1".to_string())),
+            ]
+        );
+        let spans = find_references("Data!$1:$1,Data!$A:$A").unwrap();
+        assert_eq!(
+            spans
+                .iter()
+                .map(|span| span.chars.clone())
+                .collect::<Vec<_>>(),
+            vec![0..10, 11..21]
+        );
+        assert!(find_references("SUM(1,").is_err());
+    }
+    #[test]
+    fn parse_error_test() {
+        assert_eq!(parse("SUM(1,2"), Err(FormulaError::UnexpectedEnd));
+        assert_eq!(
+            parse("1+*2"),
+            Err(FormulaError::UnexpectedToken("Star".to_string(), 2))
+        );
+        assert_eq!(parse("A1 B1 +"), Err(FormulaError::UnexpectedEnd));
+        assert_eq!(
+            parse("1 2"),
+            Err(FormulaError::UnexpectedToken("Number(2.0)".to_string(), 2))
+        );
+    }
+}
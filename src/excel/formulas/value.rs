@@ -0,0 +1,255 @@
+use std::cmp::Ordering;
+
+use crate::excel::{
+    cells::cell_value::{CellErrorKind, CellValue},
+    number_formats::render::format_value,
+};
+
+/// Result of an expression, references and array constants keep their shape.
+/// Values are already plain, dates are serial numbers and inline strings are strings.
+#[derive(Debug, PartialEq, Clone)]
+pub enum Value {
+    Scalar(CellValue),
+    Array(Vec<Vec<CellValue>>),
+}
+impl Value {
+    pub fn error(kind: CellErrorKind) -> Self {
+        Value::Scalar(CellValue::Error(kind))
+    }
+    /// Top left value of an array, as a formula showing a range in one cell.
+    pub fn into_scalar(self) -> CellValue {
+        match self {
+            Value::Scalar(value) => value,
+            Value::Array(rows) => rows
+                .into_iter()
+                .next()
+                .and_then(|row| row.into_iter().next())
+                .unwrap_or(CellValue::Error(CellErrorKind::Value)),
+        }
+    }
+    pub fn is_array(&self) -> bool {
+        matches!(self, Value::Array(_))
+    }
+    /// Every value row by row, a scalar is a single value.
+    pub fn values(&self) -> Vec<&CellValue> {
+        match self {
+            Value::Scalar(value) => vec![value],
+            Value::Array(rows) => rows.iter().flatten().collect(),
+        }
+    }
+    /// Rows and columns, a scalar is 1x1.
+    pub fn size(&self) -> (usize, usize) {
+        match self {
+            Value::Scalar(_) => (1, 1),
+            Value::Array(rows) => (rows.len(), rows.first().map(Vec::len).unwrap_or(0)),
+        }
+    }
+    pub fn to_rows(&self) -> Vec<Vec<CellValue>> {
+        match self {
+            Value::Scalar(value) => vec![vec![value.clone()]],
+            Value::Array(rows) => rows.clone(),
+        }
+    }
+    /// Applies `f` to the value or to each value of the array.
+    pub fn map(self, f: impl Fn(CellValue) -> CellValue) -> Value {
+        match self {
+            Value::Scalar(value) => Value::Scalar(f(value)),
+            Value::Array(rows) => Value::Array(
+                rows.into_iter()
+                    .map(|row| row.into_iter().map(&f).collect())
+                    .collect(),
+            ),
+        }
+    }
+    /// Pairs the values of two operands, a single value is repeated over the other's shape
+    /// and positions outside the smaller array are `#N/A`.
+    pub fn zip(self, other: Value, f: impl Fn(CellValue, CellValue) -> CellValue) -> Value {
+        let (left_size, right_size) = (self.size(), other.size());
+        if !self.is_array() && !other.is_array() {
+            return Value::Scalar(f(self.into_scalar(), other.into_scalar()));
+        }
+        let (left, right) = (self.to_rows(), other.to_rows());
+        let get = |rows: &Vec<Vec<CellValue>>, size: (usize, usize), row: usize, column: usize| {
+            let row = if size.0 == 1 { 0 } else { row };
+            let column = if size.1 == 1 { 0 } else { column };
+            rows.get(row)
+                .and_then(|values| values.get(column))
+                .cloned()
+                .unwrap_or(CellValue::Error(CellErrorKind::NA))
+        };
+        let rows = left_size.0.max(right_size.0);
+        let columns = left_size.1.max(right_size.1);
+        Value::Array(
+            (0..rows)
+                .map(|row| {
+                    (0..columns)
+                        .map(|column| {
+                            f(
+                                get(&left, left_size, row, column),
+                                get(&right, right_size, row, column),
+                            )
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+}
+
+/// Reads the value as a number, text must look like one.
+pub fn to_number(value: &CellValue) -> Result<f64, CellErrorKind> {
+    match value {
+        CellValue::Number(number) => Ok(*number),
+        CellValue::Empty => Ok(0.0),
+        CellValue::Bool(b) => Ok(if *b { 1.0 } else { 0.0 }),
+        CellValue::String(s) | CellValue::InlineString(s) => {
+            parse_number(s).ok_or(CellErrorKind::Value)
+        }
+        CellValue::Error(kind) => Err(*kind),
+        CellValue::DateTime(date_time) => Ok(date_time.to_serial(false)),
+        CellValue::Duration(duration) => Ok(duration.to_serial()),
+    }
+}
+/// Numbers written as text, also `50%` and `1,000`.
+pub fn parse_number(s: &str) -> Option<f64> {
+    let s = s.trim();
+    if s.is_empty() {
+        return None;
+    }
+    if let Some(percent) = s.strip_suffix('%') {
+        return parse_number(percent).map(|number| number / 100.0);
+    }
+    s.replace(',', "").parse().ok()
+}
+/// Text of the value as `&` joins it, numbers in the General format.
+pub fn to_text(value: &CellValue) -> Result<String, CellErrorKind> {
+    match value {
+        CellValue::Error(kind) => Err(*kind),
+        CellValue::Number(_) => Ok(format_value(value, "General", false)),
+        CellValue::DateTime(_) | CellValue::Duration(_) => Ok(format_value(
+            &CellValue::Number(to_number(value)?),
+            "General",
+            false,
+        )),
+        value => Ok(value.to_string()),
+    }
+}
+pub fn to_bool(value: &CellValue) -> Result<bool, CellErrorKind> {
+    match value {
+        CellValue::Bool(b) => Ok(*b),
+        CellValue::Empty => Ok(false),
+        CellValue::String(s) | CellValue::InlineString(s) => {
+            if s.eq_ignore_ascii_case("TRUE") {
+                Ok(true)
+            } else if s.eq_ignore_ascii_case("FALSE") {
+                Ok(false)
+            } else {
+                Err(CellErrorKind::Value)
+            }
+        }
+        value => to_number(value).map(|number| number != 0.0),
+    }
+}
+/// Orders values as comparison operators do: numbers, then text ignoring case, then booleans.
+/// An empty value is 0, "" or FALSE by the other side.
+pub fn compare(left: &CellValue, right: &CellValue) -> Ordering {
+    let empty_as = |other: &CellValue| match other {
+        CellValue::String(_) | CellValue::InlineString(_) => CellValue::String(String::new()),
+        CellValue::Bool(_) => CellValue::Bool(false),
+        _ => CellValue::Number(0.0),
+    };
+    let (left, right) = match (left, right) {
+        (CellValue::Empty, CellValue::Empty) => return Ordering::Equal,
+        (CellValue::Empty, right) => (empty_as(right), right.clone()),
+        (left, CellValue::Empty) => (left.clone(), empty_as(left)),
+        (left, right) => (left.clone(), right.clone()),
+    };
+    let rank = |value: &CellValue| match value {
+        CellValue::String(_) | CellValue::InlineString(_) => 1,
+        CellValue::Bool(_) => 2,
+        CellValue::Error(_) => 3,
+        _ => 0,
+    };
+    match (rank(&left), rank(&right)) {
+        (0, 0) => to_number(&left)
+            .unwrap_or_default()
+            .partial_cmp(&to_number(&right).unwrap_or_default())
+            .unwrap_or(Ordering::Equal),
+        (1, 1) => left
+            .as_str()
+            .unwrap_or_default()
+            .to_lowercase()
+            .cmp(&right.as_str().unwrap_or_default().to_lowercase()),
+        (2, 2) => left.as_bool().cmp(&right.as_bool()),
+        (left_rank, right_rank) => left_rank.cmp(&right_rank),
+    }
+}
+
+#[cfg(test)]
+mod value_test {
+    use std::cmp::Ordering;
+
+    use crate::excel::cells::cell_value::{CellErrorKind, CellValue};
+
+    use super::{compare, to_bool, to_number, to_text, Value};
+
+    #[test]
+    fn coerce_test() {
+        assert_eq!(
+            to_number(&CellValue::String(" 1,000 ".to_string())),
+            Ok(1000.0)
+        );
+        assert_eq!(to_number(&CellValue::String("50%".to_string())), Ok(0.5));
+        assert_eq!(
+            to_number(&CellValue::String("abc".to_string())),
+            Err(CellErrorKind::Value)
+        );
+        assert_eq!(to_number(&CellValue::Bool(true)), Ok(1.0));
+        assert_eq!(
+            to_text(&CellValue::Number(0.1 + 0.2)),
+            Ok("0.3".to_string())
+        );
+        assert_eq!(to_text(&CellValue::Bool(false)), Ok("FALSE".to_string()));
+        assert_eq!(to_bool(&CellValue::String("true".to_string())), Ok(true));
+        assert_eq!(
+            to_bool(&CellValue::Error(CellErrorKind::NA)),
+            Err(CellErrorKind::NA)
+        );
+    }
+    #[test]
+    fn compare_test() {
+        let text = |s: &str| CellValue::String(s.to_string());
+        assert_eq!(compare(&text("abc"), &text("ABC")), Ordering::Equal);
+        assert_eq!(
+            compare(&CellValue::Number(100.0), &text("1")),
+            Ordering::Less
+        );
+        assert_eq!(compare(&text("z"), &CellValue::Bool(false)), Ordering::Less);
+        assert_eq!(
+            compare(&CellValue::Empty, &CellValue::Number(0.0)),
+            Ordering::Equal
+        );
+        assert_eq!(compare(&CellValue::Empty, &text("")), Ordering::Equal);
+    }
+    #[test]
+    fn zip_test() {
+        let column = Value::Array(vec![
+            vec![CellValue::Number(1.0)],
+            vec![CellValue::Number(2.0)],
+        ]);
+        let add = |left: CellValue, right: CellValue| {
+            CellValue::Number(to_number(&left).unwrap() + to_number(&right).unwrap())
+        };
+        assert_eq!(
+            column
+                .clone()
+                .zip(Value::Scalar(CellValue::Number(10.0)), add),
+            Value::Array(vec![
+                vec![CellValue::Number(11.0)],
+                vec![CellValue::Number(12.0)]
+            ])
+        );
+        let row = Value::Array(vec![vec![CellValue::Number(1.0), CellValue::Number(2.0)]]);
+        assert_eq!(column.zip(row, add).size(), (2, 2));
+    }
+}
@@ -0,0 +1,2 @@
+pub mod format_code;
+pub mod render;
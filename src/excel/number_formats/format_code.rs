@@ -0,0 +1,173 @@
+/// What a number format code shows its value as.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum NumberFormatKind {
+    General,
+    Number,
+    Text,
+    Date,
+    Time,
+    DateTime,
+    /// elapsed time, `[h]:mm:ss`
+    Duration,
+}
+impl NumberFormatKind {
+    pub fn is_date_time(&self) -> bool {
+        matches!(
+            self,
+            NumberFormatKind::Date | NumberFormatKind::Time | NumberFormatKind::DateTime
+        )
+    }
+}
+
+/// Splits a format code into its `;` separated sections.
+/// Separators inside quoted literals, escapes and brackets are kept.
+///
+///     assert_eq!(split_sections(r##"#,##0;[Red]"-"#,##0"##), vec!["#,##0", r##"[Red]"-"#,##0"##]);
+///
+pub fn split_sections(code: &str) -> Vec<&str> {
+    let mut sections = Vec::new();
+    let mut start = 0;
+    let mut in_quote = false;
+    let mut in_bracket = false;
+    let mut is_escaped = false;
+    for (i, c) in code.char_indices() {
+        if is_escaped {
+            is_escaped = false;
+            continue;
+        }
+        match c {
+            '"' if !in_bracket => in_quote = !in_quote,
+            '\\' | '_' | '*' if !in_quote && !in_bracket => is_escaped = true,
+            '[' if !in_quote => in_bracket = true,
+            ']' if !in_quote => in_bracket = false,
+            ';' if !in_quote && !in_bracket => {
+                sections.push(&code[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    sections.push(&code[start..]);
+    sections
+}
+
+/// Classifies a format code by the tokens of its first section.
+/// Quoted literals, escaped characters, colours and locale tags are not tokens,
+/// so `"d"0` is a number format and `[$-411]ge.m.d` is a date format.
+///
+///     assert_eq!(get_number_format_kind("yyyy/mm/dd"), NumberFormatKind::Date);
+///     assert_eq!(get_number_format_kind("[h]:mm"), NumberFormatKind::Duration);
+///
+pub fn get_number_format_kind(code: &str) -> NumberFormatKind {
+    let section = split_sections(code)[0];
+    let lower = section.to_lowercase();
+    let chars = lower.chars().collect::<Vec<_>>();
+    let (mut has_date, mut has_time, mut has_month_or_minute) = (false, false, false);
+    let (mut has_elapsed, mut has_text, mut has_digit, mut has_general) =
+        (false, false, false, false);
+    let mut i = 0;
+    while i < chars.len() {
+        match chars[i] {
+            '"' => {
+                i += 1;
+                while i < chars.len() && chars[i] != '"' {
+                    i += 1;
+                }
+            }
+            '\\' | '_' | '*' => i += 1,
+            '[' => {
+                let start = i + 1;
+                while i < chars.len() && chars[i] != ']' {
+                    i += 1;
+                }
+                let content = &chars[start..i.min(chars.len())];
+                if !content.is_empty()
+                    && content.iter().all(|c| *c == content[0])
+                    && matches!(content[0], 'h' | 'm' | 's')
+                {
+                    has_elapsed = true;
+                }
+            }
+            'g' if chars[i..].starts_with(&['g', 'e', 'n', 'e', 'r', 'a', 'l']) => {
+                has_general = true;
+                i += 6;
+            }
+            'a' if chars[i..].starts_with(&['a', 'm', '/', 'p', 'm']) => {
+                has_time = true;
+                i += 4;
+            }
+            'a' if chars[i..].starts_with(&['a', '/', 'p']) => {
+                has_time = true;
+                i += 2;
+            }
+            // scientific notation, not the year
+            'e' if matches!(chars.get(i + 1), Some('+') | Some('-')) => {
+                has_digit = true;
+                i += 1;
+            }
+            'y' | 'd' | 'g' | 'e' => has_date = true,
+            'h' | 's' => has_time = true,
+            'm' => has_month_or_minute = true,
+            '@' => has_text = true,
+            '0' | '#' | '?' => has_digit = true,
+            _ => (),
+        }
+        i += 1;
+    }
+    if has_elapsed {
+        return NumberFormatKind::Duration;
+    }
+    match (has_date, has_time, has_month_or_minute) {
+        (true, true, _) => NumberFormatKind::DateTime,
+        (true, false, _) | (false, false, true) => NumberFormatKind::Date,
+        (false, true, _) => NumberFormatKind::Time,
+        _ if has_text => NumberFormatKind::Text,
+        _ if has_general && !has_digit => NumberFormatKind::General,
+        _ => NumberFormatKind::Number,
+    }
+}
+
+#[cfg(test)]
+mod format_code_test {
+    use super::{get_number_format_kind, split_sections, NumberFormatKind};
+
+    #[test]
+    fn split_sections_test() {
+        assert_eq!(
+            split_sections(r##"#,##0;[Red]"-"#,##0;"zero;";@"##),
+            vec!["#,##0", r##"[Red]"-"#,##0"##, r#""zero;""#, "@"]
+        );
+        assert_eq!(split_sections(r#"0\;0"#), vec![r#"0\;0"#]);
+        assert_eq!(split_sections("General"), vec!["General"]);
+    }
+    #[test]
+    fn get_number_format_kind_test() {
+        let cases = [
+            ("General", NumberFormatKind::General),
+            ("0.00", NumberFormatKind::Number),
+            ("#,##0 ;[Red](#,##0)", NumberFormatKind::Number),
+            ("0.00E+00", NumberFormatKind::Number),
+            (r##""¥"#,##0;[Red]"¥"\-#,##0"##, NumberFormatKind::Number),
+            (r#"0"日""#, NumberFormatKind::Number),
+            (r#"\d0"#, NumberFormatKind::Number),
+            ("@", NumberFormatKind::Text),
+            ("mm-dd-yy", NumberFormatKind::Date),
+            ("yyyy/mm/dd", NumberFormatKind::Date),
+            ("mmm-yy", NumberFormatKind::Date),
+            (r#"yyyy"年"m"月"d"日""#, NumberFormatKind::Date),
+            ("[$-411]ge.m.d", NumberFormatKind::Date),
+            ("[$-F800]dddd, mmmm dd, yyyy", NumberFormatKind::Date),
+            ("h:mm AM/PM", NumberFormatKind::Time),
+            ("mm:ss", NumberFormatKind::Time),
+            (r#"h"時"mm"分""#, NumberFormatKind::Time),
+            ("m/d/yy h:mm", NumberFormatKind::DateTime),
+            ("yyyy-mm-dd hh:mm:ss.000", NumberFormatKind::DateTime),
+            ("[h]:mm:ss", NumberFormatKind::Duration),
+            ("[mm]:ss", NumberFormatKind::Duration),
+            ("[Red][<=100]0.0", NumberFormatKind::Number),
+        ];
+        for (code, kind) in cases {
+            assert_eq!(get_number_format_kind(code), kind, "{}", code);
+        }
+    }
+}
@@ -0,0 +1,1130 @@
+use crate::excel::cells::{
+    cell_value::CellValue,
+    date_time::{ExcelDateTime, ExcelDuration},
+};
+
+use super::format_code::split_sections;
+
+const MONTH_NAMES: [&str; 12] = [
+    "January",
+    "February",
+    "March",
+    "April",
+    "May",
+    "June",
+    "July",
+    "August",
+    "September",
+    "October",
+    "November",
+    "December",
+];
+const WEEKDAY_NAMES: [&str; 7] = [
+    "Sunday",
+    "Monday",
+    "Tuesday",
+    "Wednesday",
+    "Thursday",
+    "Friday",
+    "Saturday",
+];
+/// Japanese eras from their first day, with the initial and the name shown for `g` and `ggg`.
+const JAPANESE_ERAS: [((i32, u32, u32), &str, &str); 5] = [
+    ((1868, 9, 8), "M", "明治"),
+    ((1912, 7, 30), "T", "大正"),
+    ((1926, 12, 25), "S", "昭和"),
+    ((1989, 1, 8), "H", "平成"),
+    ((2019, 5, 1), "R", "令和"),
+];
+/// General shows at most this many characters of a number.
+const GENERAL_WIDTH: usize = 11;
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Literal(String),
+    /// `0`, `#` or `?`
+    Digit(char),
+    DecimalPoint,
+    Comma,
+    Percent,
+    /// `true` for `E+`, which always shows the sign
+    Exponent(bool),
+    Slash,
+    Text,
+    General,
+    Year(usize),
+    Month(usize),
+    Day(usize),
+    Hour(usize),
+    Minute(usize),
+    Second(usize),
+    SubSecond(usize),
+    /// `true` for `AM/PM`, `false` for `A/P`
+    AmPm(bool),
+    /// `[h]`, `[mm]` or `[ss]`
+    Elapsed(char, usize),
+    /// `g`, `gg` or `ggg`, the Japanese era
+    Era(usize),
+    /// `e` or `ee`, the year of the Japanese era
+    EraYear(usize),
+}
+impl Token {
+    fn is_date(&self) -> bool {
+        matches!(
+            self,
+            Token::Year(_)
+                | Token::Month(_)
+                | Token::Day(_)
+                | Token::Hour(_)
+                | Token::Minute(_)
+                | Token::Second(_)
+                | Token::SubSecond(_)
+                | Token::AmPm(_)
+                | Token::Elapsed(_, _)
+                | Token::Era(_)
+                | Token::EraYear(_)
+        )
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+struct Section {
+    tokens: Vec<Token>,
+    color: Option<String>,
+    condition: Option<(String, f64)>,
+    /// `[$-411]` or `[$-ja-JP]`, where `e` is the year of the Japanese era
+    is_japanese: bool,
+}
+impl Section {
+    fn new(source: &str) -> Self {
+        let chars = source.chars().collect::<Vec<_>>();
+        let mut section = Section::default();
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let lower = c.to_ascii_lowercase();
+            let rest = chars[i..]
+                .iter()
+                .map(|c| c.to_ascii_lowercase())
+                .collect::<String>();
+            let run = chars[i..]
+                .iter()
+                .take_while(|next| next.to_ascii_lowercase() == lower)
+                .count();
+            let token = match lower {
+                '"' => {
+                    let literal = chars[i + 1..]
+                        .iter()
+                        .take_while(|next| **next != '"')
+                        .collect::<String>();
+                    i += literal.chars().count() + 1;
+                    Some(Token::Literal(literal))
+                }
+                '\\' => {
+                    i += 1;
+                    chars.get(i).map(|next| Token::Literal(next.to_string()))
+                }
+                '_' => {
+                    i += 1;
+                    Some(Token::Literal(" ".to_string()))
+                }
+                '*' => {
+                    i += 1;
+                    None
+                }
+                '[' => {
+                    let content = chars[i + 1..]
+                        .iter()
+                        .take_while(|next| **next != ']')
+                        .collect::<String>();
+                    i += content.chars().count() + 1;
+                    section.read_bracket(&content)
+                }
+                _ if rest.starts_with("general") => {
+                    i += 6;
+                    Some(Token::General)
+                }
+                _ if rest.starts_with("am/pm") => {
+                    i += 4;
+                    Some(Token::AmPm(true))
+                }
+                _ if rest.starts_with("a/p") => {
+                    i += 2;
+                    Some(Token::AmPm(false))
+                }
+                'e' if matches!(chars.get(i + 1), Some('+') | Some('-')) => {
+                    i += 1;
+                    Some(Token::Exponent(chars[i] == '+'))
+                }
+                'y' | 'e' | 'm' | 'd' | 'h' | 's' | 'g' => {
+                    i += run - 1;
+                    match lower {
+                        'y' => Some(Token::Year(run)),
+                        'e' => Some(Token::EraYear(run)),
+                        'm' => Some(Token::Month(run)),
+                        'd' => Some(Token::Day(run)),
+                        'h' => Some(Token::Hour(run)),
+                        's' => Some(Token::Second(run)),
+                        _ => Some(Token::Era(run)),
+                    }
+                }
+                '.' if matches!(
+                    section.tokens.last(),
+                    Some(Token::Second(_)) | Some(Token::Elapsed('s', _))
+                ) && chars.get(i + 1) == Some(&'0') =>
+                {
+                    let zeros = chars[i + 1..]
+                        .iter()
+                        .take_while(|next| **next == '0')
+                        .count();
+                    i += zeros;
+                    Some(Token::SubSecond(zeros.min(3)))
+                }
+                '0' | '#' | '?' => Some(Token::Digit(c)),
+                '.' => Some(Token::DecimalPoint),
+                ',' => Some(Token::Comma),
+                '%' => Some(Token::Percent),
+                '/' => Some(Token::Slash),
+                '@' => Some(Token::Text),
+                _ => Some(Token::Literal(c.to_string())),
+            };
+            if let Some(token) = token {
+                section.tokens.push(token);
+            }
+            i += 1;
+        }
+        section.resolve_minutes();
+        section.resolve_era_years();
+        section
+    }
+    /// `[Red]`, `[>=100]`, `[$€-407]` or `[h]`
+    fn read_bracket(&mut self, content: &str) -> Option<Token> {
+        let lower = content.to_ascii_lowercase();
+        let first = lower.chars().next()?;
+        if matches!(first, 'h' | 'm' | 's') && lower.chars().all(|c| c == first) {
+            return Some(Token::Elapsed(first, lower.len()));
+        }
+        if let Some(currency) = content.strip_prefix('$') {
+            let (symbol, locale) = currency.split_once('-').unwrap_or((currency, ""));
+            let locale = locale.to_ascii_lowercase();
+            self.is_japanese |= locale.ends_with("411") || locale.starts_with("ja");
+            return Some(Token::Literal(symbol.to_string()));
+        }
+        if matches!(first, '<' | '>' | '=') {
+            let op_len = content
+                .chars()
+                .take_while(|c| matches!(c, '<' | '>' | '='))
+                .count();
+            if let Ok(value) = content[op_len..].trim().parse() {
+                self.condition = Some((content[..op_len].to_string(), value));
+            }
+            return None;
+        }
+        self.color = Some(content.to_string());
+        None
+    }
+    /// `m` is minutes right after hours or right before seconds.
+    fn resolve_minutes(&mut self) {
+        let date_indexes = (0..self.tokens.len())
+            .filter(|i| self.tokens[*i].is_date())
+            .collect::<Vec<_>>();
+        for (n, i) in date_indexes.iter().enumerate() {
+            let Token::Month(len) = self.tokens[*i] else {
+                continue;
+            };
+            let after_hour = n > 0
+                && matches!(
+                    self.tokens[date_indexes[n - 1]],
+                    Token::Hour(_) | Token::Elapsed('h', _)
+                );
+            let before_second = date_indexes.get(n + 1).is_some_and(|next| {
+                matches!(
+                    self.tokens[*next],
+                    Token::Second(_) | Token::Elapsed('s', _)
+                )
+            });
+            if after_hour || before_second {
+                self.tokens[*i] = Token::Minute(len);
+            }
+        }
+    }
+    /// Out of Japanese formats without an era, `e` is the year as `yyyy` is.
+    fn resolve_era_years(&mut self) {
+        if self.is_japanese || self.tokens.iter().any(|t| matches!(t, Token::Era(_))) {
+            return;
+        }
+        for token in self.tokens.iter_mut() {
+            if let Token::EraYear(_) = token {
+                *token = Token::Year(4);
+            }
+        }
+    }
+    fn is_date(&self) -> bool {
+        self.tokens.iter().any(|token| token.is_date())
+    }
+    fn has_text(&self) -> bool {
+        self.tokens.contains(&Token::Text)
+    }
+    fn matches(&self, value: f64) -> Option<bool> {
+        let (op, operand) = self.condition.as_ref()?;
+        let operand = *operand;
+        Some(match op.as_str() {
+            "<" => value < operand,
+            "<=" | "=<" => value <= operand,
+            ">" => value > operand,
+            ">=" | "=>" => value >= operand,
+            "<>" => value != operand,
+            _ => value == operand,
+        })
+    }
+}
+
+/// Text Excel shows for `value` in a cell formatted with `format_code`.
+///
+///     let value = CellValue::Number(1234.5);
+///     assert_eq!(format_value(&value, "#,##0.00", false), "1,234.50");
+///     assert_eq!(format_value(&value, "0.0%", false), "123450.0%");
+///
+pub fn format_value(value: &CellValue, format_code: &str, is_date1904: bool) -> String {
+    let sections = parse_sections(format_code);
+    match value {
+        CellValue::Empty => String::new(),
+        CellValue::Bool(true) => "TRUE".to_string(),
+        CellValue::Bool(false) => "FALSE".to_string(),
+        CellValue::Error(kind) => kind.to_string(),
+        CellValue::String(s) | CellValue::InlineString(s) => format_text(&sections, s),
+        CellValue::Number(n) => format_number(&sections, *n, is_date1904),
+        CellValue::DateTime(date_time) => {
+            format_number(&sections, date_time.to_serial(is_date1904), is_date1904)
+        }
+        CellValue::Duration(duration) => {
+            format_number(&sections, duration.to_serial(), is_date1904)
+        }
+    }
+}
+/// Colour tag of the section `value` is shown with, e.g. `Red` for `[Red]`.
+pub fn get_format_color(value: &CellValue, format_code: &str) -> Option<String> {
+    let sections = parse_sections(format_code);
+    let section = match value {
+        CellValue::String(_) | CellValue::InlineString(_) => text_section(&sections)?,
+        CellValue::Number(n) => select_section(&sections, *n).0,
+        _ => return None,
+    };
+    section.color.clone()
+}
+
+fn parse_sections(format_code: &str) -> Vec<Section> {
+    if format_code.is_empty() {
+        return vec![Section::new("General")];
+    }
+    split_sections(format_code)
+        .into_iter()
+        .map(Section::new)
+        .collect()
+}
+fn text_section(sections: &[Section]) -> Option<&Section> {
+    match sections.get(3) {
+        Some(section) => Some(section),
+        None => sections.first().filter(|section| section.has_text()),
+    }
+}
+fn format_text(sections: &[Section], text: &str) -> String {
+    let Some(section) = text_section(sections) else {
+        return text.to_string();
+    };
+    section
+        .tokens
+        .iter()
+        .map(|token| match token {
+            Token::Literal(literal) => literal.as_str(),
+            Token::Text => text,
+            _ => "",
+        })
+        .collect()
+}
+/// Returns the section for `value` and whether its sign must be written.
+/// Without conditions the sections are positive;negative;zero;text,
+/// and only a lone first section writes the minus sign itself.
+fn select_section(sections: &[Section], value: f64) -> (&Section, bool) {
+    let numeric = &sections[..sections.len().min(3)];
+    if numeric.iter().any(|section| section.condition.is_some()) {
+        for section in numeric.iter().take(2) {
+            match section.matches(value) {
+                Some(true) => return (section, false),
+                Some(false) => continue,
+                None => return (section, true),
+            }
+        }
+        return match numeric.get(2) {
+            Some(section) => (section, true),
+            None => (&numeric[0], true),
+        };
+    }
+    match numeric.len() {
+        1 => (&numeric[0], true),
+        2 if value < 0.0 => (&numeric[1], false),
+        2 => (&numeric[0], true),
+        _ if value < 0.0 => (&numeric[1], false),
+        _ if value == 0.0 => (&numeric[2], false),
+        _ => (&numeric[0], true),
+    }
+}
+fn format_number(sections: &[Section], value: f64, is_date1904: bool) -> String {
+    let (section, is_signed) = select_section(sections, value);
+    if section.is_date() {
+        return format_date(section, value, is_date1904).unwrap_or_else(|| "#".repeat(8));
+    }
+    let body = format_number_body(section, value.abs());
+    let is_zero = body
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .all(|c| c == '0');
+    if is_signed && value < 0.0 && !is_zero {
+        format!("-{}", body)
+    } else {
+        body
+    }
+}
+
+fn format_number_body(section: &Section, value: f64) -> String {
+    let tokens = &section.tokens;
+    if tokens.contains(&Token::General) {
+        return tokens
+            .iter()
+            .map(|token| match token {
+                Token::Literal(literal) => literal.clone(),
+                Token::General => format_general(value),
+                _ => String::new(),
+            })
+            .collect();
+    }
+    let mut value = value;
+    let percents = tokens.iter().filter(|t| **t == Token::Percent).count();
+    value *= 100_f64.powi(percents as i32);
+    // commas right after the last placeholder of the number divide by a thousand each
+    let int_end = tokens
+        .iter()
+        .position(|t| matches!(t, Token::DecimalPoint | Token::Exponent(_) | Token::Slash))
+        .unwrap_or(tokens.len());
+    let number_end = tokens
+        .iter()
+        .position(|t| matches!(t, Token::Exponent(_) | Token::Slash))
+        .unwrap_or(tokens.len());
+    let last_digit = tokens[..number_end]
+        .iter()
+        .rposition(|t| matches!(t, Token::Digit(_)));
+    let mut scaling_commas = Vec::new();
+    if let Some(last_digit) = last_digit {
+        let mut i = last_digit + 1;
+        while tokens.get(i) == Some(&Token::Comma) {
+            scaling_commas.push(i);
+            i += 1;
+        }
+    }
+    value /= 1000_f64.powi(scaling_commas.len() as i32);
+    let is_grouped = tokens[..int_end]
+        .iter()
+        .enumerate()
+        .any(|(i, t)| *t == Token::Comma && !scaling_commas.contains(&i));
+    let tokens = tokens
+        .iter()
+        .enumerate()
+        .filter(|(i, t)| **t != Token::Comma || !scaling_commas.contains(i) && *i < int_end)
+        .map(|(_, t)| t.clone())
+        .collect::<Vec<_>>();
+    if tokens.contains(&Token::Slash) && tokens.iter().any(|t| matches!(t, Token::Digit(_))) {
+        return format_fraction(&tokens, value);
+    }
+    if let Some(exponent) = tokens.iter().position(|t| matches!(t, Token::Exponent(_))) {
+        return format_scientific(&tokens, exponent, value, is_grouped);
+    }
+    let decimals = count_digits(&tokens[int_end_of(&tokens)..]);
+    let (int_digits, frac_digits) = round_decimal(value, decimals);
+    render_fixed(&tokens, &int_digits, &frac_digits, is_grouped)
+}
+fn int_end_of(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .position(|t| matches!(t, Token::DecimalPoint | Token::Exponent(_)))
+        .unwrap_or(tokens.len())
+}
+fn count_digits(tokens: &[Token]) -> usize {
+    tokens
+        .iter()
+        .filter(|t| matches!(t, Token::Digit(_)))
+        .count()
+}
+
+/// Fills the placeholders of `tokens` before and after the decimal point.
+fn render_fixed(tokens: &[Token], int_digits: &str, frac_digits: &str, is_grouped: bool) -> String {
+    let int_end = int_end_of(tokens);
+    let has_placeholder = tokens.iter().any(|t| matches!(t, Token::Digit(_)));
+    let mut int_part = render_integer(&tokens[..int_end], int_digits, is_grouped);
+    if count_digits(&tokens[..int_end]) == 0 && has_placeholder && !int_digits.is_empty() {
+        // `.00` still shows the integer part of 12.5
+        int_part.push(group(int_digits, is_grouped));
+    }
+    int_part.append(&mut render_fraction_digits(&tokens[int_end..], frac_digits));
+    int_part.concat()
+}
+/// Integer placeholders take digits from the right, the leftmost one takes every remaining digit.
+fn render_integer(tokens: &[Token], digits: &str, is_grouped: bool) -> Vec<String> {
+    let digits = digits.chars().collect::<Vec<_>>();
+    let placeholder_len = count_digits(tokens);
+    let mut out = vec![String::new(); tokens.len()];
+    let mut position = 0;
+    let mut placeholder_index = 0;
+    for (i, token) in tokens.iter().enumerate().rev() {
+        match token {
+            Token::Digit(placeholder) => {
+                placeholder_index += 1;
+                let is_leftmost = placeholder_index == placeholder_len;
+                let mut piece = String::new();
+                let take = if is_leftmost {
+                    digits.len().saturating_sub(position).max(1)
+                } else {
+                    1
+                };
+                for _ in 0..take {
+                    let digit = digits.len().checked_sub(position + 1).map(|i| digits[i]);
+                    let c = match (digit, placeholder) {
+                        (Some(digit), _) => Some(digit),
+                        (None, '0') => Some('0'),
+                        (None, '?') => {
+                            piece.insert(0, ' ');
+                            None
+                        }
+                        _ => None,
+                    };
+                    if let Some(c) = c {
+                        if is_grouped && position > 0 && position % 3 == 0 {
+                            piece.insert(0, ',');
+                        }
+                        piece.insert(0, c);
+                    }
+                    position += 1;
+                }
+                out[i] = piece;
+            }
+            Token::Literal(literal) => out[i] = literal.clone(),
+            Token::Percent => out[i] = "%".to_string(),
+            Token::Slash => out[i] = "/".to_string(),
+            _ => (),
+        }
+    }
+    out
+}
+/// `tokens` starts at the decimal point. Trailing zeros of `#` are dropped and of `?` become spaces.
+fn render_fraction_digits(tokens: &[Token], digits: &str) -> Vec<String> {
+    let digits = digits.chars().collect::<Vec<_>>();
+    let placeholders = tokens
+        .iter()
+        .filter_map(|t| match t {
+            Token::Digit(placeholder) => Some(*placeholder),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let mut shown = digits
+        .iter()
+        .map(|d| Some(d.to_string()))
+        .collect::<Vec<_>>();
+    for i in (0..placeholders.len()).rev() {
+        if digits[i] != '0' || placeholders[i] == '0' {
+            break;
+        }
+        shown[i] = if placeholders[i] == '?' {
+            Some(" ".to_string())
+        } else {
+            None
+        };
+    }
+    let mut index = 0;
+    tokens
+        .iter()
+        .map(|token| match token {
+            Token::DecimalPoint => ".".to_string(),
+            Token::Digit(_) => {
+                index += 1;
+                shown[index - 1].clone().unwrap_or_default()
+            }
+            Token::Literal(literal) => literal.clone(),
+            Token::Percent => "%".to_string(),
+            _ => String::new(),
+        })
+        .collect()
+}
+fn group(digits: &str, is_grouped: bool) -> String {
+    if !is_grouped {
+        return digits.to_string();
+    }
+    let len = digits.len();
+    digits
+        .chars()
+        .enumerate()
+        .fold(String::new(), |mut acc, (i, c)| {
+            if i > 0 && (len - i).is_multiple_of(3) {
+                acc.push(',');
+            }
+            acc.push(c);
+            acc
+        })
+}
+
+/// `0.00E+00`, and `##0.0E+0` which keeps the exponent a multiple of three
+fn format_scientific(tokens: &[Token], exponent: usize, value: f64, is_grouped: bool) -> String {
+    let mantissa_tokens = &tokens[..exponent];
+    let int_end = int_end_of(mantissa_tokens);
+    let int_placeholders = mantissa_tokens[..int_end]
+        .iter()
+        .filter_map(|t| match t {
+            Token::Digit(placeholder) => Some(*placeholder),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    let decimals = count_digits(&mantissa_tokens[int_end..]);
+    let width = int_placeholders.len() as i32;
+    let is_engineering = width > 1 && int_placeholders.contains(&'#');
+    let place_exponent = |e10: i32| {
+        if is_engineering {
+            e10.div_euclid(width) * width
+        } else {
+            e10 - (width - 1).max(0)
+        }
+    };
+    let mut exp = if value == 0.0 {
+        0
+    } else {
+        place_exponent(decimal_exponent(value))
+    };
+    let (mut int_digits, mut frac_digits) = round_decimal(value / 10_f64.powi(exp), decimals);
+    // 9.99 may round up to 10.0
+    if value != 0.0 && int_digits.len() as i32 > width.max(1) {
+        exp = place_exponent(exp + int_digits.len() as i32 - 1);
+        (int_digits, frac_digits) = round_decimal(value / 10_f64.powi(exp), decimals);
+    }
+    let mantissa = render_fixed(mantissa_tokens, &int_digits, &frac_digits, is_grouped);
+    let Token::Exponent(is_plus) = tokens[exponent] else {
+        unreachable!()
+    };
+    let sign = match (exp < 0, is_plus) {
+        (true, _) => "-",
+        (false, true) => "+",
+        (false, false) => "",
+    };
+    let exp_tokens = &tokens[exponent + 1..];
+    let exp_digits = render_integer(exp_tokens, &exp.unsigned_abs().to_string(), false).concat();
+    format!("{}E{}{}", mantissa, sign, exp_digits)
+}
+
+/// `# ?/?`, `# ??/??` or `?/8` with a fixed denominator
+fn format_fraction(tokens: &[Token], value: f64) -> String {
+    let slash = tokens.iter().position(|t| *t == Token::Slash).unwrap();
+    let numerator_start = tokens[..slash]
+        .iter()
+        .rposition(|t| !matches!(t, Token::Digit(_)))
+        .map(|i| i + 1)
+        .unwrap_or(0);
+    let int_tokens = &tokens[..numerator_start];
+    let numerator_tokens = &tokens[numerator_start..slash];
+    let denominator_len = tokens[slash + 1..]
+        .iter()
+        .take_while(|t| match t {
+            Token::Digit(_) => true,
+            Token::Literal(literal) => literal.chars().all(|c| c.is_ascii_digit()),
+            _ => false,
+        })
+        .count();
+    let denominator_tokens = &tokens[slash + 1..slash + 1 + denominator_len];
+    let suffix_tokens = &tokens[slash + 1 + denominator_len..];
+    let has_int = count_digits(int_tokens) > 0;
+    let (mut whole, fraction) = if has_int {
+        (value.trunc(), value.fract())
+    } else {
+        (0.0, value)
+    };
+    let fixed_denominator = denominator_tokens
+        .iter()
+        .map(|t| match t {
+            Token::Digit(placeholder) => placeholder.to_string(),
+            Token::Literal(literal) => literal.clone(),
+            _ => String::new(),
+        })
+        .collect::<String>();
+    let (mut numerator, denominator) = if fixed_denominator.chars().all(|c| c.is_ascii_digit())
+        && fixed_denominator.chars().any(|c| c != '0')
+    {
+        let denominator = fixed_denominator.parse::<u64>().unwrap_or(1).max(1);
+        ((fraction * denominator as f64).round() as u64, denominator)
+    } else {
+        let max_denominator = 10_u64.pow(denominator_len as u32) - 1;
+        best_fraction(fraction, max_denominator.max(1))
+    };
+    if has_int && numerator == denominator {
+        whole += 1.0;
+        numerator = 0;
+    }
+    let whole_digits = if whole == 0.0 {
+        String::new()
+    } else {
+        format!("{}", whole)
+    };
+    let mut out = if has_int {
+        let mut int_part = render_integer(int_tokens, &whole_digits, false).concat();
+        if numerator == 0 && whole == 0.0 && int_part.trim().is_empty() {
+            int_part = render_integer(int_tokens, "0", false).concat();
+        }
+        int_part
+    } else {
+        String::new()
+    };
+    let fraction_len;
+    if numerator == 0 && has_int {
+        let numerator_part = render_integer(numerator_tokens, "0", false).concat();
+        fraction_len = numerator_part.chars().count() + 1 + denominator_len.max(1);
+        out.push_str(&" ".repeat(fraction_len));
+    } else {
+        out.push_str(&render_integer(numerator_tokens, &numerator.to_string(), false).concat());
+        out.push('/');
+        let denominator = denominator.to_string();
+        out.push_str(&denominator);
+        let padding = denominator_tokens
+            .iter()
+            .filter(|t| **t == Token::Digit('?'))
+            .count()
+            .saturating_sub(denominator.len());
+        out.push_str(&" ".repeat(padding));
+    }
+    for token in suffix_tokens {
+        if let Token::Literal(literal) = token {
+            out.push_str(literal);
+        }
+    }
+    out
+}
+/// Closest fraction with a denominator up to `max_denominator`.
+fn best_fraction(value: f64, max_denominator: u64) -> (u64, u64) {
+    let mut best = (value.round() as u64, 1);
+    let mut best_error = (value - best.0 as f64).abs();
+    for denominator in 2..=max_denominator {
+        let numerator = (value * denominator as f64).round();
+        let error = (value - numerator / denominator as f64).abs();
+        if error < best_error - f64::EPSILON {
+            best = (numerator as u64, denominator);
+            best_error = error;
+        }
+    }
+    best
+}
+
+/// The General format shows up to 11 characters, switching to scientific notation for larger numbers.
+fn format_general(value: f64) -> String {
+    if value == 0.0 {
+        return "0".to_string();
+    }
+    let sign = if value < 0.0 { "-" } else { "" };
+    let abs = value.abs();
+    if !(1e-9..1e11).contains(&abs) {
+        let exp = decimal_exponent(abs);
+        let (mut int_digits, mut frac_digits) = round_decimal(abs / 10_f64.powi(exp), 5);
+        let mut exp = exp;
+        if int_digits.len() > 1 {
+            exp += 1;
+            (int_digits, frac_digits) = round_decimal(abs / 10_f64.powi(exp), 5);
+        }
+        let frac = frac_digits.trim_end_matches('0');
+        let mantissa = if frac.is_empty() {
+            int_digits
+        } else {
+            format!("{}.{}", int_digits, frac)
+        };
+        let exp_sign = if exp < 0 { "-" } else { "+" };
+        return format!("{}{}E{}{:02}", sign, mantissa, exp_sign, exp.abs());
+    }
+    let int_len = (decimal_exponent(abs) + 1).max(1) as usize;
+    let decimals = GENERAL_WIDTH.saturating_sub(int_len + 1);
+    let (int_digits, frac_digits) = round_decimal(abs, decimals);
+    let int_digits = if int_digits.is_empty() {
+        "0".to_string()
+    } else {
+        int_digits
+    };
+    let frac = frac_digits.trim_end_matches('0');
+    if frac.is_empty() {
+        format!("{}{}", sign, int_digits)
+    } else {
+        format!("{}{}.{}", sign, int_digits, frac)
+    }
+}
+
+/// Power of ten of the first significant digit, read from the shortest decimal representation.
+fn decimal_exponent(value: f64) -> i32 {
+    let scientific = format!("{:.14e}", value);
+    scientific
+        .split_once('e')
+        .and_then(|(_, exp)| exp.parse().ok())
+        .unwrap_or_default()
+}
+/// Rounds half away from zero on the 15 significant digits Excel keeps,
+/// returning the integer digits without leading zeros and exactly `decimals` fraction digits.
+fn round_decimal(value: f64, decimals: usize) -> (String, String) {
+    let value = value.abs();
+    if value == 0.0 {
+        return (String::new(), "0".repeat(decimals));
+    }
+    let scientific = format!("{:.14e}", value);
+    let (mantissa, exp) = scientific.split_once('e').unwrap();
+    let exp = exp.parse::<i32>().unwrap();
+    let mut digits = mantissa
+        .chars()
+        .filter(|c| c.is_ascii_digit())
+        .map(|c| c as u8 - b'0')
+        .collect::<Vec<_>>();
+    let mut point = exp + 1;
+    if point <= 0 {
+        let mut zeros = vec![0; (-point) as usize];
+        zeros.append(&mut digits);
+        digits = zeros;
+        point = 0;
+    }
+    let point = point as usize;
+    let keep = point + decimals;
+    if digits.len() < keep {
+        digits.resize(keep, 0);
+    }
+    let round_up = digits.get(keep).is_some_and(|digit| *digit >= 5);
+    digits.truncate(keep);
+    let mut point = point;
+    if round_up {
+        let mut i = keep;
+        loop {
+            if i == 0 {
+                digits.insert(0, 1);
+                point += 1;
+                break;
+            }
+            i -= 1;
+            if digits[i] == 9 {
+                digits[i] = 0;
+            } else {
+                digits[i] += 1;
+                break;
+            }
+        }
+    }
+    let to_string = |digits: &[u8]| {
+        digits
+            .iter()
+            .map(|d| (d + b'0') as char)
+            .collect::<String>()
+    };
+    let int_digits = to_string(&digits[..point]);
+    let int_digits = int_digits.trim_start_matches('0').to_string();
+    (int_digits, to_string(&digits[point..]))
+}
+
+fn format_date(section: &Section, serial: f64, is_date1904: bool) -> Option<String> {
+    let sub_second = section
+        .tokens
+        .iter()
+        .find_map(|token| match token {
+            Token::SubSecond(len) => Some(*len),
+            _ => None,
+        })
+        .unwrap_or(0);
+    // the value is rounded to what the format can show
+    let unit = 10_i64.pow(3 - sub_second as u32);
+    let milliseconds = (serial * 86_400_000.0).round() as i64;
+    let milliseconds = ((milliseconds as f64 / unit as f64).round() as i64) * unit;
+    let serial = milliseconds as f64 / 86_400_000.0;
+    let elapsed = ExcelDuration::from_milliseconds(milliseconds);
+    let has_elapsed = section
+        .tokens
+        .iter()
+        .any(|t| matches!(t, Token::Elapsed(_, _)));
+    let date_time = if has_elapsed && serial < 0.0 {
+        ExcelDateTime::new(1899, 12, 31, 0, 0, 0)
+    } else {
+        ExcelDateTime::from_serial(serial, is_date1904)?
+    };
+    let is_twelve_hour = section.tokens.iter().any(|t| matches!(t, Token::AmPm(_)));
+    let mut out = String::new();
+    if has_elapsed && milliseconds < 0 {
+        out.push('-');
+    }
+    let elapsed = ExcelDuration::from_milliseconds(elapsed.get_total_milliseconds().abs());
+    let mut largest_elapsed = None;
+    for token in section.tokens.iter() {
+        if let Token::Elapsed(unit, _) = token {
+            largest_elapsed.get_or_insert(*unit);
+        }
+    }
+    for token in section.tokens.iter() {
+        match token {
+            Token::Literal(literal) => out.push_str(literal),
+            Token::Digit(c) => out.push(*c),
+            Token::DecimalPoint => out.push('.'),
+            Token::Comma => out.push(','),
+            Token::Percent => out.push('%'),
+            Token::Slash => out.push('/'),
+            Token::Exponent(true) => out.push_str("E+"),
+            Token::Exponent(false) => out.push_str("E-"),
+            Token::Text | Token::General => (),
+            Token::Year(len) if *len <= 2 => {
+                out.push_str(&format!("{:02}", date_time.get_year() % 100))
+            }
+            Token::Year(_) => out.push_str(&format!("{:04}", date_time.get_year())),
+            Token::Month(len) => {
+                let month = date_time.get_month();
+                let name = MONTH_NAMES[month as usize - 1];
+                match len {
+                    1 => out.push_str(&month.to_string()),
+                    2 => out.push_str(&format!("{:02}", month)),
+                    3 => out.push_str(&name[..3]),
+                    4 => out.push_str(name),
+                    _ => out.push_str(&name[..1]),
+                }
+            }
+            Token::Day(len) => {
+                let day = date_time.get_day();
+                let name = WEEKDAY_NAMES[date_time.get_weekday() as usize];
+                match len {
+                    1 => out.push_str(&day.to_string()),
+                    2 => out.push_str(&format!("{:02}", day)),
+                    3 => out.push_str(&name[..3]),
+                    _ => out.push_str(name),
+                }
+            }
+            Token::Hour(len) => {
+                let hour = match (is_twelve_hour, date_time.get_hour()) {
+                    (true, 0) => 12,
+                    (true, hour) if hour > 12 => hour - 12,
+                    (_, hour) => hour,
+                };
+                push_padded(&mut out, hour as i64, *len);
+            }
+            Token::Minute(len) => {
+                let minute = match largest_elapsed {
+                    Some('h') => elapsed.get_minutes(),
+                    _ => date_time.get_minute() as i64,
+                };
+                push_padded(&mut out, minute, *len)
+            }
+            Token::Second(len) => {
+                let second = match largest_elapsed {
+                    Some('h') | Some('m') => elapsed.get_seconds(),
+                    _ => date_time.get_second() as i64,
+                };
+                push_padded(&mut out, second, *len)
+            }
+            Token::SubSecond(len) => {
+                let millisecond = format!("{:03}", date_time.get_millisecond());
+                out.push('.');
+                out.push_str(&millisecond[..*len]);
+            }
+            Token::AmPm(is_full) => {
+                let is_pm = date_time.get_hour() >= 12;
+                out.push_str(match (is_full, is_pm) {
+                    (true, false) => "AM",
+                    (true, true) => "PM",
+                    (false, false) => "A",
+                    (false, true) => "P",
+                })
+            }
+            Token::Elapsed(unit, len) => {
+                let total = match unit {
+                    'h' => elapsed.get_hours(),
+                    'm' => elapsed.get_total_milliseconds() / 60_000,
+                    _ => elapsed.get_total_milliseconds() / 1000,
+                };
+                push_padded(&mut out, total, *len)
+            }
+            Token::Era(len) => {
+                if let Some((initial, name, _)) = get_japanese_era(&date_time) {
+                    match len {
+                        1 => out.push_str(initial),
+                        2 => out.extend(name.chars().next()),
+                        _ => out.push_str(name),
+                    }
+                }
+            }
+            Token::EraYear(len) => {
+                let year = get_japanese_era(&date_time)
+                    .map(|(_, _, year)| year)
+                    .unwrap_or(date_time.get_year());
+                push_padded(&mut out, year as i64, *len)
+            }
+        }
+    }
+    Some(out)
+}
+/// Initial and name of the era of the date and the year in it, `None` before the Meiji era.
+fn get_japanese_era(date_time: &ExcelDateTime) -> Option<(&'static str, &'static str, i32)> {
+    let date = (
+        date_time.get_year(),
+        date_time.get_month(),
+        date_time.get_day(),
+    );
+    let (start, initial, name) = JAPANESE_ERAS
+        .iter()
+        .rev()
+        .find(|(start, _, _)| *start <= date)?;
+    Some((initial, name, date.0 - start.0 + 1))
+}
+fn push_padded(out: &mut String, value: i64, len: usize) {
+    out.push_str(&format!("{:0width$}", value, width = len.min(2)));
+}
+
+#[cfg(test)]
+mod render_test {
+    use crate::excel::cells::{
+        cell_value::{CellErrorKind, CellValue},
+        date_time::{ExcelDateTime, ExcelDuration},
+    };
+
+    use super::{format_value, get_format_color};
+
+    fn format(value: f64, code: &str) -> String {
+        format_value(&CellValue::Number(value), code, false)
+    }
+    #[test]
+    fn general_test() {
+        assert_eq!(format(10.0, "General"), "10");
+        assert_eq!(format(-0.25, "General"), "-0.25");
+        assert_eq!(format(1.0 / 3.0, "General"), "0.333333333");
+        assert_eq!(format(123456.789, "General"), "123456.789");
+        assert_eq!(format(12345678901.0, "General"), "12345678901");
+        assert_eq!(format(123456789012.0, "General"), "1.23457E+11");
+        assert_eq!(format(0.000000000123, "General"), "1.23E-10");
+        assert_eq!(format(0.1 + 0.2, ""), "0.3");
+        assert_eq!(format(5.0, r#"General"円""#), "5円");
+    }
+    #[test]
+    fn number_test() {
+        assert_eq!(format(1234.5, "0"), "1235");
+        assert_eq!(format(1234.5, "0.00"), "1234.50");
+        assert_eq!(format(2.675, "0.00"), "2.68");
+        assert_eq!(format(1234567.891, "#,##0.00"), "1,234,567.89");
+        assert_eq!(format(-1234.5, "#,##0"), "-1,235");
+        assert_eq!(format(0.0, "#,##0"), "0");
+        assert_eq!(format(0.5, "#.##"), ".5");
+        assert_eq!(format(1.0, "#.##"), "1.");
+        assert_eq!(format(12.5, ".00"), "12.50");
+        assert_eq!(format(7.0, "000"), "007");
+        assert_eq!(format(1.5, "??0.0?"), "  1.5 ");
+        assert_eq!(format(1234567.0, "#,##0,"), "1,235");
+        assert_eq!(format(1234567.0, r#"0.0,,"M""#), "1.2M");
+        assert_eq!(format(1_234_567.0, "000-0000"), "123-4567");
+        assert_eq!(format(-0.001, "0"), "0");
+    }
+    #[test]
+    fn percent_and_scientific_test() {
+        assert_eq!(format(0.256, "0%"), "26%");
+        assert_eq!(format(0.256, "0.00%"), "25.60%");
+        assert_eq!(format(12345.678, "0.00E+00"), "1.23E+04");
+        assert_eq!(format(0.00012345, "0.00E+00"), "1.23E-04");
+        assert_eq!(format(9.999, "0.00E+00"), "1.00E+01");
+        assert_eq!(format(12345.0, "##0.0E+0"), "12.3E+3");
+        assert_eq!(format(0.0, "0.00E+00"), "0.00E+00");
+        assert_eq!(format(12345.0, "0.0E-0"), "1.2E4");
+    }
+    #[test]
+    fn fraction_test() {
+        assert_eq!(format(1.25, "# ?/?"), "1 1/4");
+        assert_eq!(format(0.3333, "# ??/??"), "  1/3 ");
+        assert_eq!(format(3.0, "# ?/?"), "3    ");
+        assert_eq!(format(0.5, "?/8"), "4/8");
+        assert_eq!(format(2.5, "?/?"), "5/2");
+        assert_eq!(format(1.7, "# ?/100"), "1 70/100");
+    }
+    #[test]
+    fn section_test() {
+        let code = r#"#,##0;[Red]\(#,##0\);"zero";"text: "@"#;
+        assert_eq!(format(1234.0, code), "1,234");
+        assert_eq!(format(-1234.0, code), "(1,234)");
+        assert_eq!(format(0.0, code), "zero");
+        assert_eq!(
+            format_value(&CellValue::String("abc".to_string()), code, false),
+            "text: abc"
+        );
+        assert_eq!(
+            get_format_color(&CellValue::Number(-1.0), code),
+            Some("Red".to_string())
+        );
+        assert_eq!(get_format_color(&CellValue::Number(1.0), code), None);
+        assert_eq!(format(-5.0, r##""¥"#,##0;[Red]"¥"\-#,##0"##), "¥-5");
+        assert_eq!(format(5.0, ";;;"), "");
+        assert_eq!(format(-5.0, "0;;"), "");
+    }
+    #[test]
+    fn condition_test() {
+        let code = r#"[Red][<=100]0;[Blue][>100]0.0"#;
+        assert_eq!(format(50.0, code), "50");
+        assert_eq!(format(150.0, code), "150.0");
+        assert_eq!(
+            get_format_color(&CellValue::Number(150.0), code),
+            Some("Blue".to_string())
+        );
+        assert_eq!(format(5.0, "[$€-407]#,##0.00"), "€5.00");
+        assert_eq!(format(5.0, r#"0_);\(0\)"#), "5 ");
+        assert_eq!(format(5.0, r#"* 0"#), "5");
+    }
+    #[test]
+    fn text_test() {
+        let text = CellValue::String("会社名".to_string());
+        assert_eq!(format_value(&text, "@", false), "会社名");
+        assert_eq!(format_value(&text, r#""["@"]""#, false), "[会社名]");
+        assert_eq!(format_value(&text, "0.00", false), "会社名");
+        assert_eq!(format_value(&CellValue::Bool(true), "0.00", false), "TRUE");
+        assert_eq!(
+            format_value(&CellValue::Error(CellErrorKind::NA), "0.00", false),
+            "#N/A"
+        );
+        assert_eq!(format_value(&CellValue::Empty, "0.00", false), "");
+    }
+    #[test]
+    fn date_test() {
+        let serial = 45000.75;
+        assert_eq!(format(serial, "yyyy/mm/dd"), "2023/03/15");
+        assert_eq!(format(serial, "mm-dd-yy"), "03-15-23");
+        assert_eq!(format(serial, "d-mmm-yy"), "15-Mar-23");
+        assert_eq!(
+            format(serial, "dddd, mmmm d, yyyy"),
+            "Wednesday, March 15, 2023"
+        );
+        assert_eq!(format(serial, "ddd mmmmm"), "Wed M");
+        assert_eq!(format(serial, "h:mm AM/PM"), "6:00 PM");
+        assert_eq!(format(serial, "hh:mm:ss A/P"), "06:00:00 P");
+        assert_eq!(format(serial, "m/d/yy h:mm"), "3/15/23 18:00");
+        assert_eq!(format(serial, r#"yyyy"年"m"月"d"日""#), "2023年3月15日");
+        assert_eq!(format(serial, "[$-411]ge.m.d"), "R5.3.15");
+        assert_eq!(format(serial, r#"ggge"年"m"月"d"日""#), "令和5年3月15日");
+        assert_eq!(format(serial, "[$-ja-JP]gg ee/mm/dd"), "令 05/03/15");
+        // the last day of Showa and the first of Heisei
+        assert_eq!(format(32515.0, "[$-411]ggge/m/d"), "昭和64/1/7");
+        assert_eq!(format(32516.0, "[$-411]ggge/m/d"), "平成1/1/8");
+        assert_eq!(format(serial, "e/m/d"), "2023/3/15");
+        assert_eq!(format(0.5 + 1.0 / 86400.0 * 0.4, "hh:mm:ss"), "12:00:00");
+        assert_eq!(format(0.5 + 0.1234 / 86400.0, "mm:ss.00"), "00:00.12");
+        assert_eq!(format(1.0 / 24.0 + 0.6 / 86400.0, "h:mm:ss"), "1:00:01");
+        assert_eq!(format(-1.0, "yyyy/mm/dd"), "########");
+        assert_eq!(
+            format_value(&CellValue::Number(0.0), "yyyy/mm/dd", true),
+            "1904/01/01"
+        );
+        let date = CellValue::DateTime(ExcelDateTime::new(2023, 1, 1, 9, 30, 0));
+        assert_eq!(
+            format_value(&date, "yyyy-mm-dd hh:mm", false),
+            "2023-01-01 09:30"
+        );
+        assert_eq!(format_value(&date, "General", false), "44927.39583");
+    }
+    #[test]
+    fn elapsed_test() {
+        assert_eq!(format(1.5, "[h]:mm:ss"), "36:00:00");
+        assert_eq!(format(1.5 + 90.0 / 86400.0, "[mm]:ss"), "2161:30");
+        assert_eq!(format(1.0 / 24.0, "[ss]"), "3600");
+        assert_eq!(format(-0.25, "[h]:mm"), "-6:00");
+        let duration = CellValue::Duration(ExcelDuration::from_milliseconds(90 * 60_000));
+        assert_eq!(format_value(&duration, "[h]:mm", false), "1:30");
+    }
+}
@@ -0,0 +1,4 @@
+pub mod deserializer;
+pub mod record_error;
+pub mod serializer;
+pub mod table_view;
@@ -0,0 +1,511 @@
+use serde::{
+    de::{
+        value::StrDeserializer, DeserializeSeed, Deserializer, IntoDeserializer, MapAccess,
+        SeqAccess, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+use crate::excel::cells::{
+    cell::ColumnAlphabet,
+    cell_value::{CellErrorKind, CellValue},
+};
+
+use super::record_error::RecordError;
+
+/// Reads the cells of a row as a record, the field of each cell named by the header cell above it.
+/// Cells under an empty header are skipped, tuples and sequences read every cell in order.
+///
+///     // Name | Unit Price
+///     let headers = vec![Some("Name".to_string()), Some("Unit Price".to_string())];
+///     let item = Item::deserialize(RowDeserializer::new(&headers, &values, 2, 1))?;
+///
+pub struct RowDeserializer<'r> {
+    headers: &'r [Option<String>],
+    values: &'r [CellValue],
+    row: usize,
+    first_column: usize,
+    is_date1904: bool,
+}
+impl<'r> RowDeserializer<'r> {
+    /// `row` and `first_column` are where `values` start, for the cell of errors.
+    pub fn new(
+        headers: &'r [Option<String>],
+        values: &'r [CellValue],
+        row: usize,
+        first_column: usize,
+    ) -> Self {
+        RowDeserializer {
+            headers,
+            values,
+            row,
+            first_column,
+            is_date1904: false,
+        }
+    }
+    /// Dates read as numbers count from 1904-01-01.
+    pub fn with_date1904(mut self, is_date1904: bool) -> Self {
+        self.is_date1904 = is_date1904;
+        self
+    }
+    fn get_cell_index(&self, index: usize) -> String {
+        format!(
+            "{}{}",
+            ColumnAlphabet::from_number(self.first_column + index),
+            self.row
+        )
+    }
+    fn get_cell(&self, index: usize) -> CellDeserializer<'r> {
+        CellDeserializer::new(self.values.get(index).unwrap_or(&CellValue::Empty))
+            .with_date1904(self.is_date1904)
+    }
+}
+impl<'de, 'r> Deserializer<'de> for RowDeserializer<'r> {
+    type Error = RecordError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        visitor.visit_map(RowAccess {
+            row: self,
+            index: 0,
+        })
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        visitor.visit_seq(RowAccess {
+            row: self,
+            index: 0,
+        })
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        self.deserialize_seq(visitor)
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        self.deserialize_seq(visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct
+        map struct enum identifier ignored_any
+    }
+}
+
+/// Steps through the cells of a row, as the fields of a map or the items of a sequence.
+struct RowAccess<'r> {
+    row: RowDeserializer<'r>,
+    index: usize,
+}
+impl<'de, 'r> MapAccess<'de> for RowAccess<'r> {
+    type Error = RecordError;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, RecordError> {
+        while self.index < self.row.headers.len() {
+            if let Some(header) = &self.row.headers[self.index] {
+                let key: StrDeserializer<RecordError> = header.as_str().into_deserializer();
+                return seed.deserialize(key).map(Some);
+            }
+            self.index += 1;
+        }
+        Ok(None)
+    }
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, RecordError> {
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(self.row.get_cell(index))
+            .map_err(|e| e.at_cell(&self.row.get_cell_index(index)))
+    }
+}
+impl<'de, 'r> SeqAccess<'de> for RowAccess<'r> {
+    type Error = RecordError;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, RecordError> {
+        if self.index >= self.row.values.len() {
+            return Ok(None);
+        }
+        let index = self.index;
+        self.index += 1;
+        seed.deserialize(self.row.get_cell(index))
+            .map(Some)
+            .map_err(|e| e.at_cell(&self.row.get_cell_index(index)))
+    }
+}
+
+/// Reads a cell value as the type asked for, converting text such as `"12"` to numbers.
+/// Dates read as numbers are their serial values, error values such as `#N/A` fail every type.
+pub struct CellDeserializer<'c> {
+    value: &'c CellValue,
+    is_date1904: bool,
+}
+impl<'c> CellDeserializer<'c> {
+    pub fn new(value: &'c CellValue) -> Self {
+        CellDeserializer {
+            value,
+            is_date1904: false,
+        }
+    }
+    pub fn with_date1904(mut self, is_date1904: bool) -> Self {
+        self.is_date1904 = is_date1904;
+        self
+    }
+    fn invalid(&self, expected: &str) -> RecordError {
+        match self.value {
+            CellValue::Empty => {
+                RecordError::new(format!("expected {}, got an empty cell", expected))
+            }
+            value => RecordError::new(format!("expected {}, got '{}'", expected, value)),
+        }
+    }
+    fn get_error_kind(&self) -> Option<&CellErrorKind> {
+        match self.value {
+            CellValue::Error(kind) => Some(kind),
+            _ => None,
+        }
+    }
+    fn check_error(&self) -> Result<(), RecordError> {
+        match self.get_error_kind() {
+            Some(kind) => Err(RecordError::new(format!("the cell is {}", kind))),
+            None => Ok(()),
+        }
+    }
+    fn get_text(&self) -> Option<&str> {
+        self.value.as_str().map(|text| text.trim())
+    }
+    fn get_f64(&self) -> Option<f64> {
+        match self.value {
+            CellValue::Number(n) => Some(*n),
+            CellValue::DateTime(date_time) => Some(date_time.to_serial(self.is_date1904)),
+            CellValue::Duration(duration) => Some(duration.to_serial()),
+            _ => self.get_text()?.parse().ok(),
+        }
+    }
+    fn get_integer(&self) -> Option<i128> {
+        if let Some(integer) = self.get_text().and_then(|text| text.parse().ok()) {
+            return Some(integer);
+        }
+        self.get_f64()
+            .filter(|n| n.is_finite() && n.fract() == 0.0)
+            .map(|n| n as i128)
+    }
+}
+
+macro_rules! deserialize_integer {
+    ($method:ident, $visit:ident, $ty:ty) => {
+        fn $method<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+            self.check_error()?;
+            let integer = self
+                .get_integer()
+                .and_then(|integer| <$ty>::try_from(integer).ok())
+                .ok_or_else(|| self.invalid("integer"))?;
+            visitor.$visit(integer)
+        }
+    };
+}
+impl<'de, 'c> Deserializer<'de> for CellDeserializer<'c> {
+    type Error = RecordError;
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        match self.value {
+            CellValue::Empty => visitor.visit_unit(),
+            CellValue::Number(n) => visitor.visit_f64(*n),
+            CellValue::String(s) | CellValue::InlineString(s) => visitor.visit_str(s),
+            CellValue::Bool(b) => visitor.visit_bool(*b),
+            value => visitor.visit_string(value.to_string()),
+        }
+    }
+    fn deserialize_bool<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        let b = match self.value {
+            CellValue::Bool(b) => Some(*b),
+            CellValue::Number(n) if *n == 0.0 || *n == 1.0 => Some(*n == 1.0),
+            _ => match self.get_text().map(|text| text.to_lowercase()).as_deref() {
+                Some("true") => Some(true),
+                Some("false") => Some(false),
+                _ => None,
+            },
+        };
+        visitor.visit_bool(b.ok_or_else(|| self.invalid("bool"))?)
+    }
+    deserialize_integer!(deserialize_i8, visit_i8, i8);
+    deserialize_integer!(deserialize_i16, visit_i16, i16);
+    deserialize_integer!(deserialize_i32, visit_i32, i32);
+    deserialize_integer!(deserialize_i64, visit_i64, i64);
+    deserialize_integer!(deserialize_i128, visit_i128, i128);
+    deserialize_integer!(deserialize_u8, visit_u8, u8);
+    deserialize_integer!(deserialize_u16, visit_u16, u16);
+    deserialize_integer!(deserialize_u32, visit_u32, u32);
+    deserialize_integer!(deserialize_u64, visit_u64, u64);
+    deserialize_integer!(deserialize_u128, visit_u128, u128);
+    fn deserialize_f32<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        let n = self.get_f64().ok_or_else(|| self.invalid("number"))?;
+        visitor.visit_f32(n as f32)
+    }
+    fn deserialize_f64<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        visitor.visit_f64(self.get_f64().ok_or_else(|| self.invalid("number"))?)
+    }
+    fn deserialize_char<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        let text = self.value.to_string();
+        let mut chars = text.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => visitor.visit_char(c),
+            _ => Err(self.invalid("a character")),
+        }
+    }
+    fn deserialize_str<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        match self.value {
+            CellValue::String(s) | CellValue::InlineString(s) => visitor.visit_str(s),
+            value => visitor.visit_string(value.to_string()),
+        }
+    }
+    fn deserialize_string<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_str(visitor)
+    }
+    fn deserialize_bytes<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        visitor.visit_bytes(self.value.to_string().as_bytes())
+    }
+    fn deserialize_byte_buf<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_bytes(visitor)
+    }
+    /// Empty cells and blank text are `None`.
+    fn deserialize_option<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        match self.value {
+            CellValue::Empty => visitor.visit_none(),
+            _ if self.get_text() == Some("") => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+    fn deserialize_unit<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        match self.value {
+            CellValue::Empty => visitor.visit_unit(),
+            _ => Err(self.invalid("an empty cell")),
+        }
+    }
+    fn deserialize_unit_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        self.deserialize_unit(visitor)
+    }
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        visitor.visit_newtype_struct(self)
+    }
+    fn deserialize_seq<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, RecordError> {
+        Err(self.invalid("a sequence"))
+    }
+    fn deserialize_tuple<V: Visitor<'de>>(
+        self,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        Err(self.invalid("a tuple"))
+    }
+    fn deserialize_tuple_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _len: usize,
+        _visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        Err(self.invalid("a tuple"))
+    }
+    fn deserialize_map<V: Visitor<'de>>(self, _visitor: V) -> Result<V::Value, RecordError> {
+        Err(self.invalid("a map"))
+    }
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        _fields: &'static [&'static str],
+        _visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        Err(self.invalid(name))
+    }
+    /// Variants without data, named by the text of the cell.
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        _variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, RecordError> {
+        self.check_error()?;
+        if self.value.is_empty() {
+            return Err(self.invalid("a variant"));
+        }
+        let variant = self.value.to_string();
+        let variant: StrDeserializer<RecordError> = variant.trim().into_deserializer();
+        visitor.visit_enum(variant)
+    }
+    fn deserialize_identifier<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        self.deserialize_str(visitor)
+    }
+    fn deserialize_ignored_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, RecordError> {
+        visitor.visit_unit()
+    }
+}
+
+#[cfg(test)]
+mod deserializer_test {
+    use serde::{de::DeserializeOwned, Deserialize};
+
+    use crate::excel::{
+        cells::{
+            cell_value::{CellErrorKind, CellValue},
+            date_time::ExcelDateTime,
+        },
+        records::record_error::RecordError,
+    };
+
+    use super::{CellDeserializer, RowDeserializer};
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Status {
+        Open,
+        Closed,
+    }
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct Order {
+        #[serde(rename = "Order No")]
+        number: u32,
+        #[serde(rename = "Price")]
+        price: f64,
+        #[serde(rename = "Paid")]
+        is_paid: bool,
+        #[serde(rename = "Status")]
+        status: Status,
+        #[serde(rename = "Date")]
+        date: ExcelDateTime,
+        #[serde(rename = "Note")]
+        note: Option<String>,
+    }
+    fn headers() -> Vec<Option<String>> {
+        ["Order No", "Price", "", "Paid", "Status", "Date", "Note"]
+            .iter()
+            .map(|header| (!header.is_empty()).then(|| header.to_string()))
+            .collect()
+    }
+    #[test]
+    fn deserialize_row_test() {
+        let values = vec![
+            CellValue::String("12".to_string()),
+            CellValue::Number(1.5),
+            CellValue::String("skipped".to_string()),
+            CellValue::Bool(true),
+            CellValue::String("open".to_string()),
+            CellValue::DateTime(ExcelDateTime::new(2023, 1, 2, 0, 0, 0)),
+        ];
+        let headers = headers();
+        let order = Order::deserialize(RowDeserializer::new(&headers, &values, 3, 1)).unwrap();
+        assert_eq!(
+            order,
+            Order {
+                number: 12,
+                price: 1.5,
+                is_paid: true,
+                status: Status::Open,
+                date: ExcelDateTime::new(2023, 1, 2, 0, 0, 0),
+                note: None,
+            }
+        );
+        // a serial date without a date format
+        let mut values = values;
+        values[5] = CellValue::Number(44928.0);
+        values.push(CellValue::String("first".to_string()));
+        let order = Order::deserialize(RowDeserializer::new(&headers, &values, 3, 1)).unwrap();
+        assert_eq!(order.date, ExcelDateTime::new(2023, 1, 2, 0, 0, 0));
+        assert_eq!(order.note, Some("first".to_string()));
+
+        let tuple =
+            <(String, f64)>::deserialize(RowDeserializer::new(&headers, &values[..2], 3, 1))
+                .unwrap();
+        assert_eq!(tuple, ("12".to_string(), 1.5));
+    }
+    #[test]
+    fn deserialize_error_test() {
+        let headers = headers();
+        let mut values = vec![
+            CellValue::Number(12.0),
+            CellValue::String("abc".to_string()),
+            CellValue::Empty,
+            CellValue::Bool(false),
+            CellValue::String("open".to_string()),
+            CellValue::Number(44928.0),
+        ];
+        let error = Order::deserialize(RowDeserializer::new(&headers, &values, 12, 5)).unwrap_err();
+        assert_eq!(error.to_string(), "F12: expected number, got 'abc'");
+        assert_eq!(error.get_cell(), Some("F12"));
+
+        values[1] = CellValue::Number(1.0);
+        values[4] = CellValue::String("pending".to_string());
+        let error = Order::deserialize(RowDeserializer::new(&headers, &values, 12, 5)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "I12: unknown variant `pending`, expected `open` or `closed`"
+        );
+
+        values[4] = CellValue::Error(CellErrorKind::NA);
+        let error = Order::deserialize(RowDeserializer::new(&headers, &values, 12, 5)).unwrap_err();
+        assert_eq!(error.to_string(), "I12: the cell is #N/A");
+
+        values[4] = CellValue::String("closed".to_string());
+        values[0] = CellValue::Number(1.5);
+        let error = Order::deserialize(RowDeserializer::new(&headers, &values, 12, 5)).unwrap_err();
+        assert_eq!(error.to_string(), "E12: expected integer, got '1.5'");
+
+        values[0] = CellValue::Number(12.0);
+        let error = Order::deserialize(RowDeserializer::new(&headers[..2], &values, 12, 5))
+            .unwrap_err()
+            .at_row(12);
+        assert_eq!(error.to_string(), "row 12: missing field `Paid`");
+    }
+    fn read<T: DeserializeOwned>(value: CellValue) -> Result<T, RecordError> {
+        T::deserialize(CellDeserializer::new(&value))
+    }
+    #[test]
+    fn deserialize_cell_test() {
+        assert_eq!(read::<i64>(CellValue::String(" -3 ".to_string())), Ok(-3));
+        assert!(read::<u8>(CellValue::Number(256.0)).is_err());
+        assert_eq!(read::<bool>(CellValue::Number(0.0)), Ok(false));
+        assert_eq!(
+            read::<bool>(CellValue::String("TRUE".to_string())),
+            Ok(true)
+        );
+        assert_eq!(
+            read::<String>(CellValue::Number(12.0)),
+            Ok("12".to_string())
+        );
+        assert_eq!(
+            read::<Option<f64>>(CellValue::String(" ".to_string())),
+            Ok(None)
+        );
+        assert_eq!(
+            read::<f64>(CellValue::Empty).unwrap_err().to_string(),
+            "expected number, got an empty cell"
+        );
+    }
+}
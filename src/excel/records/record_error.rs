@@ -0,0 +1,64 @@
+use std::fmt::Display;
+
+/// Why a row could not be read as a record, with the cell or row it happened at.
+///
+///     assert_eq!(error.to_string(), "F12: expected number, got 'abc'");
+///
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RecordError {
+    cell: Option<String>,
+    row: Option<usize>,
+    message: String,
+}
+impl RecordError {
+    pub fn new(message: impl Into<String>) -> Self {
+        RecordError {
+            cell: None,
+            row: None,
+            message: message.into(),
+        }
+    }
+    /// Sets the cell the error happened at, unless a nested value has already set it.
+    pub fn at_cell(mut self, cell_index: &str) -> Self {
+        if self.cell.is_none() {
+            self.cell = Some(cell_index.to_string());
+        }
+        self
+    }
+    /// Sets the row of errors not caused by a single cell, e.g. a missing column.
+    pub fn at_row(mut self, row: usize) -> Self {
+        if self.row.is_none() {
+            self.row = Some(row);
+        }
+        self
+    }
+    pub fn get_cell(&self) -> Option<&str> {
+        self.cell.as_deref()
+    }
+    pub fn get_row(&self) -> Option<usize> {
+        self.row
+    }
+    pub fn get_message(&self) -> &str {
+        &self.message
+    }
+}
+impl Display for RecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (&self.cell, self.row) {
+            (Some(cell), _) => write!(f, "{}: {}", cell, self.message),
+            (None, Some(row)) => write!(f, "row {}: {}", row, self.message),
+            (None, None) => write!(f, "{}", self.message),
+        }
+    }
+}
+impl std::error::Error for RecordError {}
+impl serde::de::Error for RecordError {
+    fn custom<T: Display>(message: T) -> Self {
+        RecordError::new(message.to_string())
+    }
+}
+impl serde::ser::Error for RecordError {
+    fn custom<T: Display>(message: T) -> Self {
+        RecordError::new(message.to_string())
+    }
+}
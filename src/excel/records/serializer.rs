@@ -0,0 +1,555 @@
+use serde::{
+    ser::{
+        Impossible, SerializeMap, SerializeSeq, SerializeStruct, SerializeTuple,
+        SerializeTupleStruct,
+    },
+    Serialize, Serializer,
+};
+
+use crate::excel::cells::{
+    cell::ColumnAlphabet,
+    cell_value::CellValue,
+    date_time::{ExcelDateTime, DATE_TIME_NEWTYPE},
+};
+
+use super::record_error::RecordError;
+
+/// Cells of a serialized row, each with the header it is written under.
+pub type RowCells = Vec<(Option<String>, CellValue)>;
+
+/// Turns a record into the cells of a row, named by its fields.
+/// Structs and maps name each cell, tuples and sequences leave them unnamed.
+///
+///     let cells = item.serialize(RowSerializer::new(3, 2))?;
+///     assert_eq!(cells[0], (Some("Name".to_string()), CellValue::String("pen".to_string())));
+///
+#[derive(Debug, Clone, Copy)]
+pub struct RowSerializer {
+    row: usize,
+    first_column: usize,
+}
+impl RowSerializer {
+    /// `row` and `first_column` are where the cells will be written, for the cell of errors.
+    pub fn new(row: usize, first_column: usize) -> Self {
+        RowSerializer { row, first_column }
+    }
+    fn get_cell_index(&self, index: usize) -> String {
+        format!(
+            "{}{}",
+            ColumnAlphabet::from_number(self.first_column + index),
+            self.row
+        )
+    }
+    fn single_cell(self, value: Result<CellValue, RecordError>) -> Result<RowCells, RecordError> {
+        value
+            .map(|value| vec![(None, value)])
+            .map_err(|e| e.at_cell(&self.get_cell_index(0)))
+    }
+    fn collect(self, len: Option<usize>) -> RowCollector {
+        RowCollector {
+            row: self,
+            cells: Vec::with_capacity(len.unwrap_or_default()),
+            key: None,
+        }
+    }
+}
+
+macro_rules! serialize_single_cell {
+    ($method:ident, $ty:ty) => {
+        fn $method(self, v: $ty) -> Result<RowCells, RecordError> {
+            self.single_cell(CellSerializer.$method(v))
+        }
+    };
+}
+impl Serializer for RowSerializer {
+    type Ok = RowCells;
+    type Error = RecordError;
+    type SerializeSeq = RowCollector;
+    type SerializeTuple = RowCollector;
+    type SerializeTupleStruct = RowCollector;
+    type SerializeTupleVariant = Impossible<RowCells, RecordError>;
+    type SerializeMap = RowCollector;
+    type SerializeStruct = RowCollector;
+    type SerializeStructVariant = Impossible<RowCells, RecordError>;
+
+    serialize_single_cell!(serialize_bool, bool);
+    serialize_single_cell!(serialize_i8, i8);
+    serialize_single_cell!(serialize_i16, i16);
+    serialize_single_cell!(serialize_i32, i32);
+    serialize_single_cell!(serialize_i64, i64);
+    serialize_single_cell!(serialize_i128, i128);
+    serialize_single_cell!(serialize_u8, u8);
+    serialize_single_cell!(serialize_u16, u16);
+    serialize_single_cell!(serialize_u32, u32);
+    serialize_single_cell!(serialize_u64, u64);
+    serialize_single_cell!(serialize_u128, u128);
+    serialize_single_cell!(serialize_f32, f32);
+    serialize_single_cell!(serialize_f64, f64);
+    serialize_single_cell!(serialize_char, char);
+    serialize_single_cell!(serialize_str, &str);
+    serialize_single_cell!(serialize_bytes, &[u8]);
+    fn serialize_none(self) -> Result<RowCells, RecordError> {
+        Ok(Vec::new())
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<RowCells, RecordError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<RowCells, RecordError> {
+        Ok(Vec::new())
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<RowCells, RecordError> {
+        Ok(Vec::new())
+    }
+    fn serialize_unit_variant(
+        self,
+        name: &'static str,
+        variant_index: u32,
+        variant: &'static str,
+    ) -> Result<RowCells, RecordError> {
+        self.single_cell(CellSerializer.serialize_unit_variant(name, variant_index, variant))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<RowCells, RecordError> {
+        if name == DATE_TIME_NEWTYPE {
+            return self.single_cell(CellSerializer.serialize_newtype_struct(name, value));
+        }
+        value.serialize(self)
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<RowCells, RecordError> {
+        Err(cannot_write(&format!("the variant {}", variant)))
+    }
+    fn serialize_seq(self, len: Option<usize>) -> Result<RowCollector, RecordError> {
+        Ok(self.collect(len))
+    }
+    fn serialize_tuple(self, len: usize) -> Result<RowCollector, RecordError> {
+        Ok(self.collect(Some(len)))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<RowCollector, RecordError> {
+        Ok(self.collect(Some(len)))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, RecordError> {
+        Err(cannot_write(&format!("the variant {}", variant)))
+    }
+    fn serialize_map(self, len: Option<usize>) -> Result<RowCollector, RecordError> {
+        Ok(self.collect(len))
+    }
+    fn serialize_struct(
+        self,
+        _name: &'static str,
+        len: usize,
+    ) -> Result<RowCollector, RecordError> {
+        Ok(self.collect(Some(len)))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, RecordError> {
+        Err(cannot_write(&format!("the variant {}", variant)))
+    }
+}
+
+/// Gathers the fields of a record or the items of a sequence as cells.
+pub struct RowCollector {
+    row: RowSerializer,
+    cells: RowCells,
+    key: Option<String>,
+}
+impl RowCollector {
+    fn push<T: ?Sized + Serialize>(
+        &mut self,
+        header: Option<String>,
+        value: &T,
+    ) -> Result<(), RecordError> {
+        let index = self.cells.len();
+        let value = value
+            .serialize(CellSerializer)
+            .map_err(|e| e.at_cell(&self.row.get_cell_index(index)))?;
+        self.cells.push((header, value));
+        Ok(())
+    }
+}
+impl SerializeSeq for RowCollector {
+    type Ok = RowCells;
+    type Error = RecordError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RecordError> {
+        self.push(None, value)
+    }
+    fn end(self) -> Result<RowCells, RecordError> {
+        Ok(self.cells)
+    }
+}
+impl SerializeTuple for RowCollector {
+    type Ok = RowCells;
+    type Error = RecordError;
+
+    fn serialize_element<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RecordError> {
+        self.push(None, value)
+    }
+    fn end(self) -> Result<RowCells, RecordError> {
+        Ok(self.cells)
+    }
+}
+impl SerializeTupleStruct for RowCollector {
+    type Ok = RowCells;
+    type Error = RecordError;
+
+    fn serialize_field<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RecordError> {
+        self.push(None, value)
+    }
+    fn end(self) -> Result<RowCells, RecordError> {
+        Ok(self.cells)
+    }
+}
+impl SerializeMap for RowCollector {
+    type Ok = RowCells;
+    type Error = RecordError;
+
+    fn serialize_key<T: ?Sized + Serialize>(&mut self, key: &T) -> Result<(), RecordError> {
+        self.key = Some(key.serialize(CellSerializer)?.to_string());
+        Ok(())
+    }
+    fn serialize_value<T: ?Sized + Serialize>(&mut self, value: &T) -> Result<(), RecordError> {
+        let key = self.key.take();
+        self.push(key, value)
+    }
+    fn end(self) -> Result<RowCells, RecordError> {
+        Ok(self.cells)
+    }
+}
+impl SerializeStruct for RowCollector {
+    type Ok = RowCells;
+    type Error = RecordError;
+
+    fn serialize_field<T: ?Sized + Serialize>(
+        &mut self,
+        key: &'static str,
+        value: &T,
+    ) -> Result<(), RecordError> {
+        self.push(Some(key.to_string()), value)
+    }
+    fn end(self) -> Result<RowCells, RecordError> {
+        Ok(self.cells)
+    }
+}
+
+/// 2^53, from where doubles skip integers
+const MAX_SAFE_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// Turns a single value into the cell value written for it.
+/// Integers and floats are numbers, `ExcelDateTime` is a date and `None` or `()` an empty cell.
+/// Integers beyond 2^53 are strings of their digits, as a number would round them.
+/// Unit variants are their name, values with fields of their own fail.
+#[derive(Debug, Clone, Copy)]
+pub struct CellSerializer;
+
+macro_rules! serialize_number {
+    ($method:ident, $ty:ty) => {
+        fn $method(self, v: $ty) -> Result<CellValue, RecordError> {
+            self.serialize_f64(f64::from(v))
+        }
+    };
+}
+/// Integers a double cannot hold exactly keep their digits as text.
+macro_rules! serialize_wide_integer {
+    ($method:ident, $ty:ty) => {
+        fn $method(self, v: $ty) -> Result<CellValue, RecordError> {
+            let number = v as f64;
+            if number.abs() < MAX_SAFE_INTEGER {
+                self.serialize_f64(number)
+            } else {
+                Ok(CellValue::String(v.to_string()))
+            }
+        }
+    };
+}
+impl Serializer for CellSerializer {
+    type Ok = CellValue;
+    type Error = RecordError;
+    type SerializeSeq = Impossible<CellValue, RecordError>;
+    type SerializeTuple = Impossible<CellValue, RecordError>;
+    type SerializeTupleStruct = Impossible<CellValue, RecordError>;
+    type SerializeTupleVariant = Impossible<CellValue, RecordError>;
+    type SerializeMap = Impossible<CellValue, RecordError>;
+    type SerializeStruct = Impossible<CellValue, RecordError>;
+    type SerializeStructVariant = Impossible<CellValue, RecordError>;
+
+    fn serialize_bool(self, v: bool) -> Result<CellValue, RecordError> {
+        Ok(CellValue::Bool(v))
+    }
+    serialize_number!(serialize_i8, i8);
+    serialize_number!(serialize_i16, i16);
+    serialize_number!(serialize_i32, i32);
+    serialize_wide_integer!(serialize_i64, i64);
+    serialize_wide_integer!(serialize_i128, i128);
+    serialize_number!(serialize_u8, u8);
+    serialize_number!(serialize_u16, u16);
+    serialize_number!(serialize_u32, u32);
+    serialize_wide_integer!(serialize_u64, u64);
+    serialize_wide_integer!(serialize_u128, u128);
+    fn serialize_f32(self, v: f32) -> Result<CellValue, RecordError> {
+        // 0.1f32 is written as 0.1, not 0.10000000149011612
+        self.serialize_f64(v.to_string().parse().unwrap_or(f64::from(v)))
+    }
+    /// Excel has no NaN nor infinity.
+    fn serialize_f64(self, v: f64) -> Result<CellValue, RecordError> {
+        if !v.is_finite() {
+            return Err(cannot_write(&v.to_string()));
+        }
+        Ok(CellValue::Number(v))
+    }
+    fn serialize_char(self, v: char) -> Result<CellValue, RecordError> {
+        Ok(CellValue::String(v.to_string()))
+    }
+    fn serialize_str(self, v: &str) -> Result<CellValue, RecordError> {
+        Ok(CellValue::String(v.to_string()))
+    }
+    fn serialize_bytes(self, _v: &[u8]) -> Result<CellValue, RecordError> {
+        Err(cannot_write("bytes"))
+    }
+    fn serialize_none(self) -> Result<CellValue, RecordError> {
+        Ok(CellValue::Empty)
+    }
+    fn serialize_some<T: ?Sized + Serialize>(self, value: &T) -> Result<CellValue, RecordError> {
+        value.serialize(self)
+    }
+    fn serialize_unit(self) -> Result<CellValue, RecordError> {
+        Ok(CellValue::Empty)
+    }
+    fn serialize_unit_struct(self, _name: &'static str) -> Result<CellValue, RecordError> {
+        Ok(CellValue::Empty)
+    }
+    fn serialize_unit_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+    ) -> Result<CellValue, RecordError> {
+        Ok(CellValue::String(variant.to_string()))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        name: &'static str,
+        value: &T,
+    ) -> Result<CellValue, RecordError> {
+        let value = value.serialize(self)?;
+        if name != DATE_TIME_NEWTYPE {
+            return Ok(value);
+        }
+        value
+            .as_str()
+            .and_then(ExcelDateTime::parse_iso)
+            .map(CellValue::DateTime)
+            .ok_or_else(|| cannot_write(&format!("'{}' as a date", value)))
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _value: &T,
+    ) -> Result<CellValue, RecordError> {
+        Err(cannot_write(&format!("the variant {}", variant)))
+    }
+    fn serialize_seq(self, _len: Option<usize>) -> Result<Self::SerializeSeq, RecordError> {
+        Err(cannot_write("a sequence"))
+    }
+    fn serialize_tuple(self, _len: usize) -> Result<Self::SerializeTuple, RecordError> {
+        Err(cannot_write("a tuple"))
+    }
+    fn serialize_tuple_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleStruct, RecordError> {
+        Err(cannot_write(name))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeTupleVariant, RecordError> {
+        Err(cannot_write(&format!("the variant {}", variant)))
+    }
+    fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, RecordError> {
+        Err(cannot_write("a map"))
+    }
+    fn serialize_struct(
+        self,
+        name: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStruct, RecordError> {
+        Err(cannot_write(name))
+    }
+    fn serialize_struct_variant(
+        self,
+        _name: &'static str,
+        _variant_index: u32,
+        variant: &'static str,
+        _len: usize,
+    ) -> Result<Self::SerializeStructVariant, RecordError> {
+        Err(cannot_write(&format!("the variant {}", variant)))
+    }
+}
+fn cannot_write(what: &str) -> RecordError {
+    RecordError::new(format!("cannot write {} to a cell", what))
+}
+
+#[cfg(test)]
+mod serializer_test {
+    use std::collections::BTreeMap;
+
+    use serde::Serialize;
+
+    use crate::excel::cells::{cell_value::CellValue, date_time::ExcelDateTime};
+
+    use super::{CellSerializer, RowSerializer};
+
+    #[derive(Serialize)]
+    #[serde(rename_all = "lowercase")]
+    enum Status {
+        Open,
+    }
+    #[derive(Serialize)]
+    struct Order {
+        #[serde(rename = "Order No")]
+        number: u32,
+        #[serde(rename = "Price")]
+        price: f64,
+        #[serde(rename = "Paid")]
+        is_paid: bool,
+        #[serde(rename = "Status")]
+        status: Status,
+        #[serde(rename = "Date")]
+        date: ExcelDateTime,
+        #[serde(rename = "Note")]
+        note: Option<String>,
+    }
+    #[test]
+    fn serialize_row_test() {
+        let order = Order {
+            number: 12,
+            price: 1.5,
+            is_paid: true,
+            status: Status::Open,
+            date: ExcelDateTime::new(2023, 1, 2, 0, 0, 0),
+            note: None,
+        };
+        let cells = order.serialize(RowSerializer::new(2, 1)).unwrap();
+        assert_eq!(
+            cells,
+            vec![
+                (Some("Order No".to_string()), CellValue::Number(12.0)),
+                (Some("Price".to_string()), CellValue::Number(1.5)),
+                (Some("Paid".to_string()), CellValue::Bool(true)),
+                (
+                    Some("Status".to_string()),
+                    CellValue::String("open".to_string())
+                ),
+                (
+                    Some("Date".to_string()),
+                    CellValue::DateTime(ExcelDateTime::new(2023, 1, 2, 0, 0, 0))
+                ),
+                (Some("Note".to_string()), CellValue::Empty),
+            ]
+        );
+        let cells = ("pen", 3).serialize(RowSerializer::new(2, 1)).unwrap();
+        assert_eq!(
+            cells,
+            vec![
+                (None, CellValue::String("pen".to_string())),
+                (None, CellValue::Number(3.0)),
+            ]
+        );
+        let map = BTreeMap::from([("a", 1), ("b", 2)]);
+        let cells = map.serialize(RowSerializer::new(2, 1)).unwrap();
+        assert_eq!(cells[1], (Some("b".to_string()), CellValue::Number(2.0)));
+    }
+    #[test]
+    fn serialize_error_test() {
+        #[derive(Serialize)]
+        struct Point {
+            x: f64,
+            y: f64,
+        }
+        #[derive(Serialize)]
+        struct Shape {
+            name: String,
+            points: Vec<Point>,
+        }
+        let shape = Shape {
+            name: "line".to_string(),
+            points: vec![],
+        };
+        let error = shape.serialize(RowSerializer::new(5, 3)).unwrap_err();
+        assert_eq!(error.to_string(), "D5: cannot write a sequence to a cell");
+        let error = Point {
+            x: 0.0,
+            y: f64::NAN,
+        }
+        .serialize(RowSerializer::new(5, 3))
+        .unwrap_err();
+        assert_eq!(error.to_string(), "D5: cannot write NaN to a cell");
+    }
+    #[test]
+    fn serialize_cell_test() {
+        assert_eq!(
+            "text".serialize(CellSerializer),
+            Ok(CellValue::String("text".to_string()))
+        );
+        assert_eq!(7u64.serialize(CellSerializer), Ok(CellValue::Number(7.0)));
+        assert_eq!(
+            (-(1i64 << 53) + 1).serialize(CellSerializer),
+            Ok(CellValue::Number(-9007199254740991.0))
+        );
+        assert_eq!(
+            (1u64 << 53).serialize(CellSerializer),
+            Ok(CellValue::String("9007199254740992".to_string()))
+        );
+        assert_eq!(
+            i64::MAX.serialize(CellSerializer),
+            Ok(CellValue::String("9223372036854775807".to_string()))
+        );
+        assert_eq!(
+            u128::MAX.serialize(CellSerializer),
+            Ok(CellValue::String(u128::MAX.to_string()))
+        );
+        assert_eq!(0.1f32.serialize(CellSerializer), Ok(CellValue::Number(0.1)));
+        assert_eq!(
+            Some(false).serialize(CellSerializer),
+            Ok(CellValue::Bool(false))
+        );
+        assert_eq!(None::<i32>.serialize(CellSerializer), Ok(CellValue::Empty));
+        assert_eq!(
+            ExcelDateTime::new(2024, 2, 29, 12, 0, 0).serialize(CellSerializer),
+            Ok(CellValue::DateTime(ExcelDateTime::new(
+                2024, 2, 29, 12, 0, 0
+            )))
+        );
+        assert!(f64::INFINITY.serialize(CellSerializer).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::HashMap;
+
+use crate::excel::cells::{cell::ColumnAlphabet, cell_range::RangeArea, cell_value::CellValue};
+
+/// Where a table of a sheet is, each part left out is found from the cells.
+/// Without a header row the first row with two or more values is the header,
+/// without columns the header spans from its first to its last value.
+/// The rows end before the first blank row, or before the row holding the end marker.
+///
+///     let layout = TableLayout::new().with_header_row(2).with_end_marker("END");
+///     let table = sheet.get_table_view(&layout).unwrap();
+///
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TableLayout {
+    header_row: Option<usize>,
+    columns: Option<(usize, usize)>,
+    end_marker: Option<String>,
+}
+impl TableLayout {
+    pub fn new() -> Self {
+        TableLayout::default()
+    }
+    pub fn with_header_row(mut self, row: usize) -> Self {
+        self.header_row = Some(row);
+        self
+    }
+    /// First and last column numbers of the table, `A` being 1.
+    pub fn with_columns(mut self, first: usize, last: usize) -> Self {
+        self.columns = Some((first.min(last), first.max(last)));
+        self
+    }
+    /// Text of a cell in the row after the last one, compared after trimming.
+    pub fn with_end_marker(mut self, marker: &str) -> Self {
+        self.end_marker = Some(marker.trim().to_string());
+        self
+    }
+    pub fn get_header_row(&self) -> Option<usize> {
+        self.header_row
+    }
+    pub fn get_columns(&self) -> Option<(usize, usize)> {
+        self.columns
+    }
+    pub fn get_end_marker(&self) -> Option<&str> {
+        self.end_marker.as_deref()
+    }
+}
+
+/// Rows of a table read by the names of its header cells.
+/// Empty header cells are named by their column letter, and a name used again gets `_2`, `_3`...
+///
+///     // B2: 会社名 | 社員数
+///     // B3: A社    | 10
+///     assert_eq!(table.get_column("会社名").unwrap(), vec![&CellValue::String("A社".to_string())]);
+///     assert_eq!(table.get_row(0).unwrap().get("社員数"), Some(&CellValue::Number(10.0)));
+///
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableView {
+    header_row: usize,
+    first_column: usize,
+    headers: Vec<String>,
+    rows: Vec<Vec<CellValue>>,
+}
+impl TableView {
+    /// `header_row` and `first_column` are where `header_values` are, the rows follow them.
+    pub fn new(
+        header_row: usize,
+        first_column: usize,
+        header_values: &[CellValue],
+        rows: Vec<Vec<CellValue>>,
+    ) -> Self {
+        let mut headers: Vec<String> = Vec::with_capacity(header_values.len());
+        for (i, value) in header_values.iter().enumerate() {
+            let name = value.to_string().trim().to_string();
+            let name = if name.is_empty() {
+                ColumnAlphabet::from_number(first_column + i)
+            } else {
+                name
+            };
+            let mut unique = name.clone();
+            let mut count = 1;
+            while headers.contains(&unique) {
+                count += 1;
+                unique = format!("{}_{}", name, count);
+            }
+            headers.push(unique);
+        }
+        let rows = rows
+            .into_iter()
+            .map(|mut row| {
+                row.resize(headers.len(), CellValue::Empty);
+                row
+            })
+            .collect();
+        TableView {
+            header_row,
+            first_column,
+            headers,
+            rows,
+        }
+    }
+    pub fn get_headers(&self) -> &[String] {
+        &self.headers
+    }
+    pub fn get_header_row(&self) -> usize {
+        self.header_row
+    }
+    pub fn get_first_column(&self) -> usize {
+        self.first_column
+    }
+    /// The header row and the rows of the table.
+    pub fn get_area(&self) -> RangeArea {
+        RangeArea::from_bounds(
+            (self.header_row, self.first_column),
+            (
+                self.header_row + self.rows.len(),
+                self.first_column + self.headers.len().max(1) - 1,
+            ),
+        )
+    }
+    pub fn get_row_count(&self) -> usize {
+        self.rows.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    /// Position of the column in the table, not in the sheet.
+    pub fn get_column_index(&self, name: &str) -> Option<usize> {
+        self.headers.iter().position(|header| header == name)
+    }
+    pub fn get_column(&self, name: &str) -> Option<Vec<&CellValue>> {
+        let index = self.get_column_index(name)?;
+        Some(self.rows.iter().map(|row| &row[index]).collect())
+    }
+    /// The row at `index` of the table, `0` being the row under the header.
+    pub fn get_row(&self, index: usize) -> Option<TableRow<'_>> {
+        self.rows.get(index).map(|values| TableRow {
+            headers: &self.headers,
+            values,
+            row: self.header_row + 1 + index,
+        })
+    }
+    pub fn rows(&self) -> impl Iterator<Item = TableRow<'_>> {
+        (0..self.rows.len()).filter_map(|index| self.get_row(index))
+    }
+}
+
+/// A row of a `TableView`, its values named by the headers.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRow<'t> {
+    headers: &'t [String],
+    values: &'t [CellValue],
+    row: usize,
+}
+impl<'t> TableRow<'t> {
+    pub fn get(&self, name: &str) -> Option<&'t CellValue> {
+        let index = self.headers.iter().position(|header| header == name)?;
+        self.values.get(index)
+    }
+    /// Row number of the sheet.
+    pub fn get_row_index(&self) -> usize {
+        self.row
+    }
+    pub fn get_values(&self) -> &'t [CellValue] {
+        self.values
+    }
+    /// Header and value of each column, in column order.
+    pub fn iter(&self) -> impl Iterator<Item = (&'t str, &'t CellValue)> {
+        self.headers
+            .iter()
+            .map(|header| header.as_str())
+            .zip(self.values.iter())
+    }
+    pub fn to_map(&self) -> HashMap<String, CellValue> {
+        self.iter()
+            .map(|(header, value)| (header.to_string(), value.clone()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod table_view_test {
+    use crate::excel::cells::cell_value::CellValue;
+
+    use super::{TableLayout, TableView};
+
+    fn text(s: &str) -> CellValue {
+        CellValue::String(s.to_string())
+    }
+    #[test]
+    fn table_view_test() {
+        let headers = [
+            text("会社名"),
+            CellValue::Empty,
+            text("社員数"),
+            text("会社名"),
+        ];
+        let rows = vec![
+            vec![text("A社"), text("x"), CellValue::Number(10.0), text("a")],
+            vec![text("B社")],
+        ];
+        let table = TableView::new(2, 2, &headers, rows);
+        assert_eq!(table.get_headers(), ["会社名", "C", "社員数", "会社名_2"]);
+        assert_eq!(table.get_area().to_string(), "B2:E4");
+        assert_eq!(
+            table.get_column("会社名").unwrap(),
+            vec![&text("A社"), &text("B社")]
+        );
+        assert_eq!(
+            table.get_column("社員数").unwrap(),
+            vec![&CellValue::Number(10.0), &CellValue::Empty]
+        );
+        assert_eq!(table.get_column("住所"), None);
+
+        let row = table.get_row(0).unwrap();
+        assert_eq!(row.get_row_index(), 3);
+        assert_eq!(row.get("会社名_2"), Some(&text("a")));
+        assert_eq!(row.get("住所"), None);
+        assert_eq!(row.to_map().get("C"), Some(&text("x")));
+        assert_eq!(
+            table
+                .rows()
+                .map(|row| row.get_row_index())
+                .collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+        assert!(table.get_row(2).is_none());
+    }
+    #[test]
+    fn table_layout_test() {
+        let layout = TableLayout::new()
+            .with_columns(5, 2)
+            .with_end_marker(" END ");
+        assert_eq!(layout.get_header_row(), None);
+        assert_eq!(layout.get_columns(), Some((2, 5)));
+        assert_eq!(layout.get_end_marker(), Some("END"));
+    }
+}
@@ -1,4 +1,8 @@
+pub mod defined_name;
+pub mod relationships;
 pub mod shared_strings;
 pub mod sheet;
+pub mod styles;
+pub mod table;
 pub mod workbook;
 pub mod xl;
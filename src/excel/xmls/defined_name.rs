@@ -0,0 +1,209 @@
+use std::fmt::Display;
+
+use crate::{
+    excel::{
+        cells::cell_range::CellRange,
+        formulas::{
+            ast::{BinaryOp, Expr},
+            parser::parse,
+        },
+    },
+    xml::{escape::unescape, nodes::node::XMLNode},
+};
+
+use super::workbook::SheetError;
+
+/// Prefix of the names Excel defines itself, e.g. `_xlnm.Print_Area`.
+pub const BUILT_IN_PREFIX: &str = "_xlnm.";
+
+/// Why a defined name could not be added, changed or deleted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DefinedNameError {
+    /// The sheet the name is scoped to
+    Sheet(SheetError),
+    NotFound(String),
+    /// A table already has the name, regardless of case
+    DuplicateName(String),
+    /// Names start with a letter, `_` or `\`, go on with letters, digits, `_` and `.`,
+    /// and are not cell references such as `A1` or `R1C1`
+    InvalidName(String),
+    /// The formula of the name does not parse
+    InvalidFormula(String),
+}
+impl Display for DefinedNameError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DefinedNameError::Sheet(e) => write!(f, "{}", e),
+            DefinedNameError::NotFound(name) => write!(f, "name {:?} is not defined", name),
+            DefinedNameError::DuplicateName(name) => write!(f, "name {:?} is already used", name),
+            DefinedNameError::InvalidName(name) => {
+                write!(f, "{:?} is not a valid defined name", name)
+            }
+            DefinedNameError::InvalidFormula(formula) => {
+                write!(f, "{:?} is not a valid formula for a name", formula)
+            }
+        }
+    }
+}
+impl std::error::Error for DefinedNameError {}
+impl From<SheetError> for DefinedNameError {
+    fn from(e: SheetError) -> Self {
+        DefinedNameError::Sheet(e)
+    }
+}
+
+/// `<definedName>` of the workbook, a name of a range, a constant or a formula.
+/// Names with a `localSheetId` are only seen from the sheet at that tab position.
+///
+///     // <definedName name="TaxRate">Settings!$B$1</definedName>
+///     let name = workbook.get_defined_name("TaxRate", None).unwrap();
+///     assert_eq!(name.get_formula(), "Settings!$B$1");
+///
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct DefinedName {
+    name: String,
+    formula: String,
+    local_sheet_id: Option<usize>,
+    is_hidden: bool,
+    comment: Option<String>,
+}
+impl DefinedName {
+    /// `None` when the node has no `name`.
+    pub fn new(node: &XMLNode) -> Option<Self> {
+        Some(DefinedName {
+            name: unescape(&node.get_element_text("name")?),
+            formula: node
+                .get_text()
+                .map(|text| unescape(&text))
+                .unwrap_or_default(),
+            local_sheet_id: node
+                .search_element("localSheetId")
+                .and_then(|id| id.parse().ok()),
+            is_hidden: node
+                .search_element("hidden")
+                .is_some_and(|hidden| hidden == "1" || hidden == "true"),
+            comment: node.get_element_text("comment").map(|c| unescape(&c)),
+        })
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Formula without `=`, e.g. `Sheet1!$A$1:$C$20` or `0.1`.
+    pub fn get_formula(&self) -> &str {
+        &self.formula
+    }
+    /// Tab position of the sheet the name is scoped to, `None` for the whole workbook.
+    pub fn get_local_sheet_id(&self) -> Option<usize> {
+        self.local_sheet_id
+    }
+    pub fn is_hidden(&self) -> bool {
+        self.is_hidden
+    }
+    pub fn get_comment(&self) -> Option<&str> {
+        self.comment.as_deref()
+    }
+    /// Whether Excel defines the name itself, e.g. `_xlnm.Print_Area` or `_xlnm._FilterDatabase`.
+    pub fn is_built_in(&self) -> bool {
+        self.name.starts_with(BUILT_IN_PREFIX)
+    }
+    /// Sheet and cells the formula refers to, references without a sheet being on `sheet`.
+    /// `None` when the formula is not references to one sheet, e.g. a constant or `#REF!`.
+    ///
+    ///     let name = workbook.get_defined_name("_xlnm.Print_Titles", Some("Data")).unwrap();
+    ///     // Data!$1:$1,Data!$A:$A
+    ///     let (sheet, range) = name.get_range(None).unwrap();
+    ///     assert_eq!((sheet.as_str(), range.to_string().as_str()), ("Data", "1:1,A:A"));
+    ///
+    pub fn get_range(&self, sheet: Option<&str>) -> Option<(String, CellRange)> {
+        // parenthesized, the `,` between areas is read as a union
+        let expr = parse(&format!("({})", self.formula)).ok()?;
+        let mut references = Vec::new();
+        if !collect_references(&expr, &mut references) {
+            return None;
+        }
+        let mut sheets = references
+            .iter()
+            .map(|(target, _)| target.as_deref().or(sheet));
+        let first = sheets.next()??;
+        if !sheets.all(|target| target.is_some_and(|t| t.to_lowercase() == first.to_lowercase())) {
+            return None;
+        }
+        let areas = references
+            .iter()
+            .map(|(_, area)| area.as_str())
+            .collect::<Vec<_>>();
+        Some((first.to_string(), CellRange::new(&areas.join(","))?))
+    }
+}
+/// Pushes the sheet and area of each reference of a union, `false` for anything else.
+fn collect_references(expr: &Expr, references: &mut Vec<(Option<String>, String)>) -> bool {
+    match expr {
+        Expr::Reference(reference) => {
+            references.push((reference.sheet.clone(), reference.area.to_string()));
+            true
+        }
+        Expr::Parenthesized(expr) => collect_references(expr, references),
+        Expr::Binary {
+            op: BinaryOp::Union,
+            left,
+            right,
+        } => collect_references(left, references) && collect_references(right, references),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod defined_name_test {
+    use crate::xml::nodes::node::XMLNode;
+
+    use super::DefinedName;
+
+    fn defined_name(xml: &str) -> DefinedName {
+        let source = format!(r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>{xml}"#);
+        let node = XMLNode::from(source.as_str());
+        DefinedName::new(node.search_node("definedName").unwrap()).unwrap()
+    }
+    #[test]
+    fn new_test() {
+        let name = defined_name(
+            r#"<definedName name="_xlnm._FilterDatabase" localSheetId="1" hidden="1" comment="a &amp; b">'Term 1'!$A$1:$C$3</definedName>"#,
+        );
+        assert_eq!(name.get_name(), "_xlnm._FilterDatabase");
+        assert_eq!(name.get_formula(), "'Term 1'!$A$1:$C$3");
+        assert_eq!(name.get_local_sheet_id(), Some(1));
+        assert!(name.is_hidden());
+        assert!(name.is_built_in());
+        assert_eq!(name.get_comment(), Some("a & b"));
+
+        let name = defined_name(r#"<definedName name="TaxRate">0.1</definedName>"#);
+        assert_eq!(name.get_local_sheet_id(), None);
+        assert!(!name.is_hidden());
+        assert!(!name.is_built_in());
+        assert_eq!(name.get_comment(), None);
+    }
+    #[test]
+    fn get_range_test() {
+        let range = |formula: &str, sheet: Option<&str>| {
+            defined_name(&format!(r#"<definedName name="N">{formula}</definedName>"#))
+                .get_range(sheet)
+                .map(|(sheet, range)| (sheet, range.to_string()))
+        };
+        assert_eq!(
+            range("'Term 1'!$A$1:$C$3", None),
+            Some(("Term 1".to_string(), "A1:C3".to_string()))
+        );
+        assert_eq!(
+            range("Data!$1:$1,Data!$A:$A", None),
+            Some(("Data".to_string(), "1:1,A:A".to_string()))
+        );
+        assert_eq!(
+            range("$B$2", Some("Data")),
+            Some(("Data".to_string(), "B2".to_string()))
+        );
+        assert_eq!(range("$B$2", None), None);
+        assert_eq!(range("Data!$A$1,Summary!$A$1", None), None);
+        assert_eq!(range("0.1", None), None);
+        assert_eq!(range("#REF!", None), None);
+        assert_eq!(range("SUM(Data!$A:$A)", None), None);
+    }
+}
@@ -0,0 +1,264 @@
+use crate::xml::nodes::{node::XMLNode, node_type::NodeType};
+
+use super::xl::XL;
+
+pub const EMPTY_RELATIONSHIPS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"></Relationships>"#;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Relationship {
+    id: String,
+    rel_type: String,
+    target: String,
+    is_external: bool,
+}
+impl Relationship {
+    pub fn get_id(&self) -> &str {
+        &self.id
+    }
+    pub fn get_type(&self) -> &str {
+        &self.rel_type
+    }
+    pub fn get_target(&self) -> &str {
+        &self.target
+    }
+    pub fn is_external(&self) -> bool {
+        self.is_external
+    }
+}
+
+/// `.rels` part of a package part, e.g. `xl/_rels/workbook.xml.rels`.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Relationships<'a> {
+    node: XMLNode<'a>,
+    base_dir: String,
+    relationships: Vec<Relationship>,
+}
+impl<'a> Relationships<'a> {
+    /// `source_part` is the part owning the relationships, targets are resolved against its directory.
+    pub fn new(source: &'a str, source_part: &str) -> Self {
+        let node = XMLNode::from(source);
+        let relationships = node
+            .search_node("Relationships")
+            .unwrap_or_else(|| panic!("not exist <Relationships> for {:?}", node))
+            .search_all_nodes("Relationship")
+            .unwrap_or_default()
+            .iter()
+            .map(|relationship| Relationship {
+                id: relationship
+                    .search_element("Id")
+                    .unwrap_or_default()
+                    .to_string(),
+                rel_type: relationship
+                    .search_element("Type")
+                    .unwrap_or_default()
+                    .to_string(),
+                target: relationship
+                    .search_element("Target")
+                    .unwrap_or_default()
+                    .to_string(),
+                is_external: relationship.is_containe_key_value("TargetMode", "External"),
+            })
+            .collect();
+        let base_dir = source_part
+            .rsplit_once('/')
+            .map(|(dir, _)| dir.to_string())
+            .unwrap_or_default();
+        Relationships {
+            node,
+            base_dir,
+            relationships,
+        }
+    }
+    pub fn get_relationships(&self) -> &Vec<Relationship> {
+        &self.relationships
+    }
+    pub fn get_relationship(&self, id: &str) -> Option<&Relationship> {
+        self.relationships.iter().find(|rel| rel.id == id)
+    }
+    /// Returns the package path of the part the relationship points at.
+    ///
+    ///     // in xl/_rels/workbook.xml.rels
+    ///     // <Relationship Id="rId1" Target="worksheets/sheet1.xml"/>
+    ///     assert_eq!(rels.get_target_part("rId1"), Some("xl/worksheets/sheet1.xml".to_string()));
+    ///
+    pub fn get_target_part(&self, id: &str) -> Option<String> {
+        self.get_relationship(id)
+            .filter(|rel| !rel.is_external)
+            .map(|rel| resolve_part_path(&self.base_dir, &rel.target))
+    }
+    /// Adds a relationship of `rel_type` to the package part `part` and returns its new `Id`.
+    ///
+    ///     // in xl/_rels/workbook.xml.rels
+    ///     let id = rels.add_relationship(WORKSHEET_TYPE, "xl/worksheets/sheet4.xml");
+    ///     // <Relationship Id="rId5" Type="..." Target="worksheets/sheet4.xml"/>
+    ///
+    pub fn add_relationship(&mut self, rel_type: &str, part: &str) -> String {
+        let id = format!(
+            "rId{}",
+            self.relationships
+                .iter()
+                .filter_map(|rel| rel.id.strip_prefix("rId")?.parse::<usize>().ok())
+                .max()
+                .unwrap_or_default()
+                + 1
+        );
+        let relationship = Relationship {
+            id: id.clone(),
+            rel_type: rel_type.to_string(),
+            target: relative_part_path(&self.base_dir, part),
+            is_external: false,
+        };
+        let mut node = XMLNode::new("Relationship", NodeType::SingleElement);
+        node.set_element("Id", relationship.id.clone());
+        node.set_element("Type", relationship.rel_type.clone());
+        node.set_element("Target", relationship.target.clone());
+        self.node
+            .search_node_mut("Relationships")
+            .unwrap()
+            .add_node(node);
+        self.relationships.push(relationship);
+        id
+    }
+    pub fn remove_relationship(&mut self, id: &str) -> Option<Relationship> {
+        let position = self.relationships.iter().position(|rel| rel.id == id)?;
+        self.node
+            .search_node_mut("Relationships")
+            .unwrap()
+            .retain_nodes(|node| node.search_element("Id") != Some(id));
+        Some(self.relationships.remove(position))
+    }
+    pub fn to_xml(&self) -> String {
+        self.node.to_string()
+    }
+}
+impl<'a> XL<'a> for Relationships<'a> {
+    fn get_xml_node(&'a self) -> &'a XMLNode<'a> {
+        &self.node
+    }
+}
+
+/// Resolves a relationship target against `base_dir`.
+/// Absolute targets start at the package root, `..` moves up a directory.
+pub fn resolve_part_path(base_dir: &str, target: &str) -> String {
+    let (mut segments, target) = match target.strip_prefix('/') {
+        Some(absolute) => (Vec::new(), absolute),
+        None => (
+            base_dir
+                .split('/')
+                .filter(|segment| !segment.is_empty())
+                .collect::<Vec<_>>(),
+            target,
+        ),
+    };
+    target.split('/').for_each(|segment| match segment {
+        "" | "." => (),
+        ".." => {
+            segments.pop();
+        }
+        _ => segments.push(segment),
+    });
+    segments.join("/")
+}
+
+/// Path of the package part `part` relative to `base_dir`, the inverse of `resolve_part_path`.
+///
+///     assert_eq!(relative_part_path("xl/worksheets", "xl/drawings/drawing1.xml"), "../drawings/drawing1.xml");
+///
+pub fn relative_part_path(base_dir: &str, part: &str) -> String {
+    let base = base_dir
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .collect::<Vec<_>>();
+    let segments = part.split('/').collect::<Vec<_>>();
+    let common = base
+        .iter()
+        .zip(&segments[..segments.len() - 1])
+        .take_while(|(base, segment)| base == segment)
+        .count();
+    let mut path = vec![".."; base.len() - common];
+    path.extend(&segments[common..]);
+    path.join("/")
+}
+
+#[cfg(test)]
+mod relationships_test {
+    use super::{relative_part_path, resolve_part_path, Relationships, EMPTY_RELATIONSHIPS};
+
+    #[test]
+    fn get_target_part_test() {
+        let source = r#"
+<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet3.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="/xl/worksheets/sheet2.xml"/>
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="./worksheets/../worksheets/sheet1.xml"/>
+<Relationship Id="rId9" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/hyperlink" Target="https://example.com/" TargetMode="External"/>
+</Relationships>
+"#;
+        let rels = Relationships::new(source, "xl/workbook.xml");
+        assert_eq!(
+            rels.get_target_part("rId1"),
+            Some("xl/worksheets/sheet1.xml".to_string())
+        );
+        assert_eq!(
+            rels.get_target_part("rId2"),
+            Some("xl/worksheets/sheet2.xml".to_string())
+        );
+        assert_eq!(
+            rels.get_target_part("rId3"),
+            Some("xl/worksheets/sheet3.xml".to_string())
+        );
+        assert_eq!(rels.get_target_part("rId9"), None);
+        assert_eq!(rels.get_target_part("rId4"), None);
+        assert_eq!(
+            rels.get_relationship("rId3").unwrap().get_type(),
+            "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet"
+        );
+    }
+    #[test]
+    fn resolve_part_path_test() {
+        assert_eq!(
+            resolve_part_path("xl/worksheets", "../drawings/drawing1.xml"),
+            "xl/drawings/drawing1.xml"
+        );
+        assert_eq!(resolve_part_path("", "xl/workbook.xml"), "xl/workbook.xml");
+        assert_eq!(
+            resolve_part_path("xl", "/xl/sharedStrings.xml"),
+            "xl/sharedStrings.xml"
+        );
+    }
+    #[test]
+    fn relative_part_path_test() {
+        assert_eq!(
+            relative_part_path("xl/worksheets", "xl/drawings/drawing1.xml"),
+            "../drawings/drawing1.xml"
+        );
+        assert_eq!(
+            relative_part_path("xl", "xl/worksheets/sheet1.xml"),
+            "worksheets/sheet1.xml"
+        );
+        assert_eq!(relative_part_path("", "xl/workbook.xml"), "xl/workbook.xml");
+    }
+    #[test]
+    fn add_and_remove_relationship_test() {
+        let mut rels = Relationships::new(EMPTY_RELATIONSHIPS, "xl/workbook.xml");
+        let id = rels.add_relationship("worksheet", "xl/worksheets/sheet1.xml");
+        assert_eq!(id, "rId1");
+        let id = rels.add_relationship("worksheet", "xl/worksheets/sheet2.xml");
+        assert_eq!(id, "rId2");
+        assert_eq!(
+            rels.get_target_part("rId2"),
+            Some("xl/worksheets/sheet2.xml".to_string())
+        );
+        assert!(rels.to_xml().contains(
+            r#"<Relationship Id="rId2" Type="worksheet" Target="worksheets/sheet2.xml"/>"#
+        ));
+        assert_eq!(
+            rels.remove_relationship("rId1").map(|rel| rel.target),
+            Some("worksheets/sheet1.xml".to_string())
+        );
+        assert_eq!(rels.remove_relationship("rId1"), None);
+        assert!(!rels.to_xml().contains("rId1"));
+    }
+}
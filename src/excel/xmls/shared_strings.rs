@@ -1,16 +1,155 @@
-use super::xl::XL;
-use crate::xml::nodes::{node::XMLNode, node_type::NodeType};
+use std::{
+    cell::{Cell, Ref, RefCell},
+    collections::HashMap,
+};
 
-#[derive(Debug, PartialEq, Eq)]
+use super::{styles::Font, xl::XL};
+use crate::xml::{
+    escape::{escape, unescape},
+    nodes::{node::XMLNode, node_type::NodeType},
+};
+
+/// The shared strings part.
+///
+/// Strings added by sheet writes are kept apart from the parsed node
+/// and appended as new `<si>` when the part is written with `to_xml`.
+#[derive(Debug, PartialEq)]
 pub struct SharedStrings<'a> {
     node: XMLNode<'a>,
-    values: Vec<String>,
+    rich_texts: RefCell<Vec<RichText>>,
+    values: RefCell<Vec<String>>,
+    indexes: RefCell<HashMap<String, usize>>,
+    source_len: usize,
+    source_count: usize,
+    count: Cell<usize>,
 }
 pub trait SharedStringsInterface<'a> {
-    fn get_values(&self) -> &Vec<String>;
-    fn get_shared_string(&self, index: usize) -> &str;
-    fn add_shared_string(&mut self, value: &'a str) -> ();
+    fn get_values(&self) -> Ref<'_, Vec<String>>;
+    fn get_shared_string(&self, index: usize) -> String;
+    /// Returns the index of `value`, appending it when it is not in the table yet.
+    fn add_shared_string(&self, value: &str) -> usize;
     fn to_xml(&self) -> String;
+    /// Runs and readings of the string, a single plain run unless the table keeps them.
+    fn get_rich_text(&self, index: usize) -> Option<RichText> {
+        self.get_values()
+            .get(index)
+            .map(|value| RichText::from(value.as_str()))
+    }
+}
+
+/// A run of rich text, `font` is `None` for a run without `<rPr>`.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TextRun {
+    pub text: String,
+    pub font: Option<Font>,
+}
+/// `<rPh>` reading of the characters `start..end` of the base text.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PhoneticRun {
+    pub text: String,
+    pub start: usize,
+    pub end: usize,
+}
+/// Text of a `<si>` or an inline `<is>` as its runs and phonetic readings.
+///
+///     // <si><t>会社名</t><rPh sb="0" eb="3"><t>カイシャメイ</t></rPh></si>
+///     assert_eq!(rich_text.get_text(), "会社名");
+///     assert_eq!(rich_text.get_reading(), "カイシャメイ");
+///
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct RichText {
+    runs: Vec<TextRun>,
+    phonetic_runs: Vec<PhoneticRun>,
+}
+impl RichText {
+    pub fn get_runs(&self) -> &[TextRun] {
+        &self.runs
+    }
+    pub fn get_phonetic_runs(&self) -> &[PhoneticRun] {
+        &self.phonetic_runs
+    }
+    /// The runs concatenated.
+    pub fn get_text(&self) -> String {
+        self.runs.iter().map(|run| run.text.as_str()).collect()
+    }
+    /// The text with the characters covered by phonetic runs replaced by their readings.
+    pub fn get_reading(&self) -> String {
+        let chars = self.get_text().chars().collect::<Vec<_>>();
+        let mut phonetic_runs = self.phonetic_runs.iter().collect::<Vec<_>>();
+        phonetic_runs.sort_by_key(|run| run.start);
+        let mut reading = String::new();
+        let mut position = 0;
+        for run in phonetic_runs {
+            if run.start < position || run.end > chars.len() {
+                continue;
+            }
+            reading.extend(&chars[position..run.start]);
+            reading.push_str(&run.text);
+            position = run.end;
+        }
+        reading.extend(&chars[position..]);
+        reading
+    }
+    /// Whether some run has its own font.
+    pub fn is_rich(&self) -> bool {
+        self.runs.iter().any(|run| run.font.is_some())
+    }
+}
+impl From<&str> for RichText {
+    fn from(text: &str) -> Self {
+        RichText {
+            runs: vec![TextRun {
+                text: text.to_string(),
+                font: None,
+            }],
+            phonetic_runs: Vec::new(),
+        }
+    }
+}
+impl From<&XMLNode<'_>> for RichText {
+    fn from(node: &XMLNode) -> Self {
+        let text_of = |node: &XMLNode| {
+            node.search_node("t")
+                .and_then(|t| t.get_text())
+                .map(|text| unescape(&text))
+                .unwrap_or_default()
+        };
+        let mut runs = Vec::new();
+        if node.search_node("t").is_some() {
+            runs.push(TextRun {
+                text: text_of(node),
+                font: None,
+            });
+        }
+        node.search_all_nodes("r")
+            .unwrap_or_default()
+            .iter()
+            .for_each(|r| {
+                runs.push(TextRun {
+                    text: text_of(r),
+                    font: r.search_node("rPr").map(Font::from),
+                })
+            });
+        let phonetic_runs = node
+            .search_all_nodes("rPh")
+            .unwrap_or_default()
+            .iter()
+            .map(|rph| PhoneticRun {
+                text: text_of(rph),
+                start: parse_index(rph, "sb"),
+                end: parse_index(rph, "eb"),
+            })
+            .collect();
+        RichText {
+            runs,
+            phonetic_runs,
+        }
+    }
+}
+fn parse_index(node: &XMLNode, key: &str) -> usize {
+    node.search_element(key)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or_default()
 }
 impl<'a> SharedStrings<'a> {
     pub fn new(source: &'a str) -> Self {
@@ -18,18 +157,60 @@ impl<'a> SharedStrings<'a> {
         let sst = node
             .search_node("sst")
             .expect(format!("not exist <sst> for {:?}", node).as_str());
-        let values = if let Some(si_vec) = sst.search_all_nodes("si") {
-            si_vec
-                .iter()
-                .filter_map(|node| node.search_node("t"))
-                .filter_map(|node| node.get_child_text(0))
-                .map(|str| str.into())
-                .collect::<Vec<_>>()
-        } else {
-            Vec::new()
-        };
-        SharedStrings { node, values }
+        let rich_texts = sst
+            .search_all_nodes("si")
+            .unwrap_or_default()
+            .into_iter()
+            .map(RichText::from)
+            .collect::<Vec<_>>();
+        let values = rich_texts
+            .iter()
+            .map(|rich_text| rich_text.get_text())
+            .collect::<Vec<_>>();
+        // a written plain string must not pick up the formatting of a rich one
+        let mut indexes = HashMap::new();
+        values
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| !rich_texts[*i].is_rich())
+            .for_each(|(i, value)| {
+                indexes.entry(value.clone()).or_insert(i);
+            });
+        let count = sst
+            .search_element("count")
+            .and_then(|count| count.parse().ok())
+            .unwrap_or(values.len());
+        SharedStrings {
+            source_len: values.len(),
+            source_count: count,
+            node,
+            rich_texts: RefCell::new(rich_texts),
+            values: RefCell::new(values),
+            indexes: RefCell::new(indexes),
+            count: Cell::new(count),
+        }
+    }
+    /// Whether strings were added or used again since the part was read.
+    pub fn is_changed(&self) -> bool {
+        self.values.borrow().len() != self.source_len || self.count.get() != self.source_count
+    }
+}
+/// `<si><t>value</t></si>`, spaces at the edges or in a row need `xml:space="preserve"`.
+fn new_si_node(value: &str) -> XMLNode<'static> {
+    let mut t_node = XMLNode::new("t", NodeType::Element);
+    if value.starts_with(char::is_whitespace)
+        || value.ends_with(char::is_whitespace)
+        || value.contains("  ")
+        || value.contains(['\n', '\t'])
+    {
+        t_node.add_element("xml:space", vec!["preserve"]);
+    }
+    if !value.is_empty() {
+        t_node.add_text(escape(value));
     }
+    let mut si_node = XMLNode::new("si", NodeType::Element);
+    si_node.add_node(t_node);
+    si_node
 }
 
 impl<'a> XL<'a> for SharedStrings<'a> {
@@ -39,33 +220,38 @@ impl<'a> XL<'a> for SharedStrings<'a> {
 }
 impl<'a> SharedStringsInterface<'a> for SharedStrings<'a> {
     fn to_xml(&self) -> String {
-        self.node.to_string()
+        let mut node = self.node.clone();
+        let sst = node.search_node_mut("sst").unwrap();
+        let values = self.values.borrow();
+        values[self.source_len..]
+            .iter()
+            .for_each(|value| sst.add_node(new_si_node(value)));
+        sst.set_element("count", self.count.get().max(values.len()).to_string());
+        sst.set_element("uniqueCount", values.len().to_string());
+        sst.set_node_type(NodeType::Element);
+        node.to_string()
     }
-    fn get_values(&self) -> &Vec<String> {
-        &self.values
+    fn get_values(&self) -> Ref<'_, Vec<String>> {
+        self.values.borrow()
     }
-    fn get_shared_string(&self, index: usize) -> &str {
-        &self.values[index]
+    fn get_shared_string(&self, index: usize) -> String {
+        self.values.borrow()[index].clone()
     }
-    fn add_shared_string(&mut self, value: &'a str) -> () {
-        self.values.push(value.into());
-        let phonetic_pr = self
-            .node
-            .search_node("sst")
-            .unwrap()
-            .search_node("si")
-            .unwrap()
-            .search_node("phoneticPr")
-            .unwrap();
-        let mut sst_node = self.node.search_node("sst").unwrap().clone();
-        let mut t_node = XMLNode::new("t", NodeType::Element);
-        let mut add_node = XMLNode::new("si", NodeType::Element);
-        t_node.add_text(value);
-        add_node.add_node(t_node);
-        add_node.add_node(phonetic_pr.clone());
-        sst_node.add_node(add_node);
-        // tobe not use change_child_node because clone sst
-        self.node.change_child_node(sst_node);
+    fn get_rich_text(&self, index: usize) -> Option<RichText> {
+        self.rich_texts.borrow().get(index).cloned()
+    }
+    fn add_shared_string(&self, value: &str) -> usize {
+        self.count.set(self.count.get() + 1);
+        if let Some(index) = self.indexes.borrow().get(value) {
+            return *index;
+        }
+        let mut values = self.values.borrow_mut();
+        values.push(value.to_string());
+        self.rich_texts.borrow_mut().push(RichText::from(value));
+        self.indexes
+            .borrow_mut()
+            .insert(value.to_string(), values.len() - 1);
+        values.len() - 1
     }
 }
 #[cfg(test)]
@@ -75,7 +261,7 @@ mod shared_strings_test {
         xml::nodes::node::XMLNode,
     };
 
-    use super::SharedStrings;
+    use super::{PhoneticRun, SharedStrings};
     #[test]
     fn get_xml_node_test() {
         let source = r#"
@@ -144,7 +330,7 @@ mod shared_strings_test {
     }
     #[test]
     fn add_shared_string_test() {
-        let mut ss = SharedStrings::new(
+        let ss = SharedStrings::new(
             r#"
 <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
 <sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="938" uniqueCount="265">
@@ -172,11 +358,16 @@ mod shared_strings_test {
 </sst>
 "#,
         );
-        ss.add_shared_string("こんにちは");
-        ss.add_shared_string("こんばんわ");
+        assert_eq!(ss.add_shared_string("こんにちは"), 3);
+        assert_eq!(ss.add_shared_string(" こんばんわ"), 4);
+        assert_eq!(ss.add_shared_string("会社名"), 1);
+        assert_eq!(ss.add_shared_string("こんにちは"), 3);
+        assert_eq!(ss.add_shared_string("a&b"), 5);
+        assert_eq!(ss.get_shared_string(4), " こんばんわ");
+        assert!(ss.is_changed());
         let tobe_node = r#"
 <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
-<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="938" uniqueCount="265">
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="943" uniqueCount="6">
     <si>
         <t>詳細画面レイアウト</t>
         <rPh sb="0" eb="2">
@@ -200,15 +391,129 @@ mod shared_strings_test {
     </si>
     <si>
         <t>こんにちは</t>
-        <phoneticPr fontId="2"/>
     </si>
     <si>
-        <t>こんばんわ</t>
-        <phoneticPr fontId="2"/>
+        <t xml:space="preserve"> こんばんわ</t>
+    </si>
+    <si>
+        <t>a&amp;b</t>
     </si>
 </sst>
 "#;
         let tobe_node = XMLNode::from(tobe_node);
-        assert_eq!(ss.get_xml_node(), &tobe_node);
+        assert_eq!(XMLNode::from(ss.to_xml().as_str()), tobe_node);
+        let written = ss.to_xml();
+        assert!(written.contains(r#"<si><t xml:space="preserve"> こんばんわ</t></si>"#));
+        let ss = SharedStrings::new(&written);
+        assert_eq!(ss.get_shared_string(5), "a&b");
+        assert!(!ss.is_changed());
+    }
+    #[test]
+    fn get_rich_text_test() {
+        let ss = SharedStrings::new(
+            r#"
+<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="4" uniqueCount="4">
+    <si>
+        <t>詳細画面レイアウト</t>
+        <rPh sb="0" eb="2">
+            <t>ショウサイ</t>
+        </rPh>
+        <rPh sb="2" eb="4">
+            <t>ガメン</t>
+        </rPh>
+        <phoneticPr fontId="2"/>
+    </si>
+    <si>
+        <t>会社名</t>
+        <rPh sb="0" eb="3">
+            <t>カイシャメイ</t>
+        </rPh>
+        <phoneticPr fontId="2"/>
+    </si>
+    <si>
+        <r><t>会社</t></r>
+        <r><rPr><b/><sz val="11"/><color rgb="FFFF0000"/><rFont val="游ゴシック"/><family val="3"/></rPr><t>名</t></r>
+    </si>
+    <si>
+        <t>名</t>
+    </si>
+</sst>
+"#,
+        );
+        let rich_text = ss.get_rich_text(0).unwrap();
+        assert_eq!(rich_text.get_text(), "詳細画面レイアウト");
+        assert_eq!(rich_text.get_reading(), "ショウサイガメンレイアウト");
+        assert_eq!(
+            rich_text.get_phonetic_runs(),
+            &[
+                PhoneticRun {
+                    text: "ショウサイ".to_string(),
+                    start: 0,
+                    end: 2
+                },
+                PhoneticRun {
+                    text: "ガメン".to_string(),
+                    start: 2,
+                    end: 4
+                }
+            ]
+        );
+        assert_eq!(ss.get_rich_text(1).unwrap().get_reading(), "カイシャメイ");
+
+        let rich_text = ss.get_rich_text(2).unwrap();
+        assert_eq!(ss.get_shared_string(2), "会社名");
+        assert!(rich_text.is_rich());
+        let runs = rich_text.get_runs();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].text, "会社");
+        assert_eq!(runs[0].font, None);
+        let font = runs[1].font.as_ref().unwrap();
+        assert!(font.bold);
+        assert_eq!(font.size, Some(11.0));
+        assert_eq!(font.name, Some("游ゴシック".to_string()));
+        assert_eq!(rich_text.get_reading(), "会社名");
+
+        // plain writes do not reuse the formatted string
+        assert_eq!(ss.add_shared_string("会社名"), 1);
+        assert_eq!(ss.add_shared_string("名"), 3);
+        assert_eq!(ss.get_rich_text(9), None);
+    }
+    #[test]
+    fn rich_text_and_spaces_test() {
+        let ss = SharedStrings::new(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="2" uniqueCount="2">
+<si><r><t>bold</t></r><r><rPr><b/></rPr><t>part</t></r></si>
+<si><t>hello world</t></si>
+</sst>"#,
+        );
+        assert_eq!(ss.get_shared_string(0), "boldpart");
+        assert_eq!(ss.get_shared_string(1), "hello world");
+        assert_eq!(ss.add_shared_string("hello world"), 1);
+        assert!(ss.is_changed());
+        assert!(ss.to_xml().contains(r#"count="3" uniqueCount="2""#));
+    }
+    #[test]
+    fn whitespace_round_trip_test() {
+        let ss = SharedStrings::new(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<sst xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" count="0" uniqueCount="0"></sst>"#,
+        );
+        let values = [" lead", "trail ", "a  b", "l1\nl2", "tab\tx", " ", "a b"];
+        values.iter().for_each(|value| {
+            ss.add_shared_string(value);
+        });
+        let written = ss.to_xml();
+        let ss = SharedStrings::new(&written);
+        assert_eq!(ss.get_values().as_slice(), values);
+        for (i, value) in values.iter().enumerate() {
+            assert_eq!(ss.add_shared_string(value), i);
+        }
+        assert_eq!(ss.get_values().len(), values.len());
+        assert_eq!(
+            SharedStrings::new(&ss.to_xml()).get_values().as_slice(),
+            values
+        );
     }
 }
@@ -1,19 +1,96 @@
-use std::{cell::RefCell, fmt::Debug};
+use std::fmt::Debug;
 
-use super::shared_strings::SharedStringsInterface;
+use serde::{de::DeserializeOwned, Serialize};
+
+use super::{
+    shared_strings::{RichText, SharedStringsInterface},
+    styles::{CellFormat, NumberFormat, Styles},
+};
 use crate::{
     excel::cells::{
         cell::{CellIndex, ColumnAlphabet, ECell},
         cell_node::CellNode,
+        cell_range::{CellRange, RangeArea, RangeValues},
+        cell_value::CellValue,
+        formula::{
+            move_formula, move_references, shift_lines, shift_references, CellFormula, FormulaKind,
+        },
+        line_shift::LineShift,
+        merged_range::{MergeError, MergedRange},
+    },
+    excel::formulas::{
+        ast::{MAX_COLUMN, MAX_ROW},
+        dependency::CellAddress,
+        evaluator::{CellSource, Evaluator},
+        value::to_number,
+    },
+    excel::number_formats::{
+        format_code::{get_number_format_kind, NumberFormatKind},
+        render::format_value,
+    },
+    excel::records::{
+        deserializer::RowDeserializer,
+        record_error::RecordError,
+        serializer::RowSerializer,
+        table_view::{TableLayout, TableView},
+    },
+    xml::{
+        escape::{escape, unescape},
+        nodes::{node::XMLNode, node_type::NodeType},
     },
-    xml::nodes::{node::XMLNode, node_type::NodeType},
 };
 
-#[derive(PartialEq, Eq, Debug)]
+/// Elements a worksheet writes before `<cols>`.
+const BEFORE_COLS: [&str; 4] = ["sheetPr", "dimension", "sheetViews", "sheetFormatPr"];
+/// Widest column Excel accepts, in characters.
+const MAX_COLUMN_WIDTH: f64 = 255.0;
+
+/// Elements a worksheet writes before `<mergeCells>`.
+const BEFORE_MERGE_CELLS: [&str; 14] = [
+    "sheetPr",
+    "dimension",
+    "sheetViews",
+    "sheetFormatPr",
+    "cols",
+    "sheetData",
+    "sheetCalcPr",
+    "sheetProtection",
+    "protectedRanges",
+    "scenarios",
+    "autoFilter",
+    "sortState",
+    "dataConsolidate",
+    "customSheetViews",
+];
+
+/// What `clear_range` removes from the cells.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ClearMode {
+    /// Values and formulas, keeping the styles
+    Contents,
+    /// Styles and merges, keeping the values
+    Formats,
+    All,
+}
+
+/// Cells and merges taken from an area to be pasted elsewhere.
+struct CopiedCells<'a> {
+    area: RangeArea,
+    cells: Vec<(usize, usize, XMLNode<'a>)>,
+    merged_ranges: Vec<MergedRange>,
+}
+
+#[derive(PartialEq, Debug)]
 pub struct Sheet<'a, S: SharedStringsInterface<'a>> {
     sheet_name: String,
     node: XMLNode<'a>,
-    shared_strings: RefCell<&'a S>,
+    shared_strings: &'a S,
+    styles: Option<&'a Styles<'a>>,
+    is_date1904: bool,
+    is_formula_changed: bool,
+    is_full_calc_needed: bool,
+    changed_cells: Vec<String>,
+    is_merged_value_spread: bool,
 }
 impl<'a, S: SharedStringsInterface<'a>> Sheet<'a, S> {
     pub fn new(sheet_name: &str, source: &'a str, shared_strings: &'a S) -> Self {
@@ -21,13 +98,33 @@ impl<'a, S: SharedStringsInterface<'a>> Sheet<'a, S> {
         Sheet {
             sheet_name: sheet_name.to_string(),
             node,
-            shared_strings: RefCell::new(shared_strings),
+            shared_strings,
+            styles: None,
+            is_date1904: false,
+            is_formula_changed: false,
+            is_full_calc_needed: false,
+            changed_cells: Vec::new(),
+            is_merged_value_spread: false,
         }
     }
+    pub fn with_styles(mut self, styles: &'a Styles<'a>) -> Self {
+        self.styles = Some(styles);
+        self
+    }
+    pub fn with_date1904(mut self, is_date1904: bool) -> Self {
+        self.is_date1904 = is_date1904;
+        self
+    }
+    /// Cells inside a merge read as its top-left cell, as Excel shows them.
+    /// Without it only the top-left cell has the value and the others are empty.
+    pub fn with_merged_values(mut self, is_merged_value_spread: bool) -> Self {
+        self.is_merged_value_spread = is_merged_value_spread;
+        self
+    }
     pub fn get_sheet_name(&self) -> &str {
         &self.sheet_name
     }
-    fn get_sheet_data_node(&self) -> &XMLNode {
+    fn get_sheet_data_node(&self) -> &XMLNode<'a> {
         self.node
             .search_node("worksheet")
             .expect(format!("not found worksheet\n{:?}", &self.node).as_str())
@@ -41,6 +138,775 @@ impl<'a, S: SharedStringsInterface<'a>> Sheet<'a, S> {
             .search_node_mut("sheetData")
             .unwrap()
     }
+    /// Returns the resolved format of the cell, `None` without a styles part.
+    /// A cell that is not written has the default format of cellXfs 0.
+    pub fn get_cell_format(&self, cell_index: &str) -> Option<CellFormat> {
+        let styles = self.styles?;
+        let index = CellIndex::new(cell_index);
+        let style_index = self
+            .get_sheet_data_node()
+            .search_child_by_id("r", index.get_value())
+            .map(|c_node| self.with_cell_node(c_node, |c_node| c_node.get_style_index()))
+            .unwrap_or_default();
+        styles.get_cell_format(style_index)
+    }
+    /// Text of the cell as Excel shows it with its number format.
+    ///
+    ///     // A1 is 1234.5 formatted with `#,##0.00`
+    ///     assert_eq!(sheet.get_formatted_cell("A1"), "1,234.50");
+    ///
+    pub fn get_formatted_cell(&self, cell_index: &str) -> String {
+        let code = self
+            .get_cell_format(cell_index)
+            .map(|format| format.number_format.code)
+            .unwrap_or_else(|| "General".to_string());
+        format_value(&self.get_cell_value(cell_index), &code, self.is_date1904)
+    }
+    /// Runs and phonetic readings of a shared or inline string cell.
+    pub fn get_rich_text(&self, cell_index: &str) -> Option<RichText> {
+        let index = CellIndex::new(cell_index);
+        let c_node = self
+            .get_sheet_data_node()
+            .search_child_by_id("r", index.get_value())?;
+        match c_node.search_element("t") {
+            Some("s") => c_node
+                .search_node("v")
+                .and_then(|v_node| v_node.get_text())
+                .and_then(|text| text.trim().parse().ok())
+                .and_then(|index| self.shared_strings.get_rich_text(index)),
+            Some("inlineStr") => c_node.search_node("is").map(RichText::from),
+            _ => None,
+        }
+    }
+    /// Formula of the cell, a shared formula child gets the formula of its master moved to the cell.
+    ///
+    ///     // <c r="B3"><f t="shared" si="0"/></c> following <c r="B2"><f t="shared" ref="B2:B3" si="0">A2*2</f></c>
+    ///     assert_eq!(sheet.get_formula("B3").unwrap().text, "A3*2");
+    ///
+    pub fn get_formula(&self, cell_index: &str) -> Option<CellFormula> {
+        let index = CellIndex::new(cell_index);
+        let c_node = self
+            .get_sheet_data_node()
+            .search_child_by_id("r", index.get_value())?;
+        let formula = self.with_cell_node(c_node, |c_node| c_node.get_formula())?;
+        if !formula.is_shared_child() {
+            return Some(formula);
+        }
+        let FormulaKind::Shared { index: si, .. } = formula.kind else {
+            return Some(formula);
+        };
+        let (master_index, master) = self.get_shared_formula_master(si)?;
+        Some(CellFormula {
+            text: move_formula(&master.text, &master_index, cell_index),
+            kind: formula.kind,
+        })
+    }
+    /// Writes the formula to the cell and drops its cached value.
+    /// Cells sharing a formula written by this cell keep it as their own normal formula.
+    /// The workbook saved with this sheet is calculated again when it is opened.
+    pub fn set_formula(&mut self, cell_index: &'a str, formula: &str) {
+        let index = CellIndex::new(cell_index);
+        self.detach_shared_formula(cell_index);
+        self.update_dimension(&index);
+        let c_node = self.get_or_create_cell_node(&index);
+        c_node.remove_node("f");
+        c_node.remove_node("v");
+        c_node.remove_node("is");
+        c_node.remove_element("t");
+        let mut f_node = XMLNode::new("f", NodeType::Element);
+        f_node.add_text(escape(&CellFormula::new(formula).text));
+        c_node.add_node(f_node);
+        c_node.set_node_type(NodeType::Element);
+        self.is_formula_changed = true;
+        self.changed_cells.push(cell_index.to_string());
+    }
+    /// Whether formula cells were written, overwritten, cleared or moved, leaving cached values
+    /// and the calculation chain of the workbook to be built again.
+    pub fn is_formula_changed(&self) -> bool {
+        self.is_formula_changed
+    }
+    /// Leaves the workbook saved with this sheet to be calculated again when it is opened,
+    /// for cached values that could not be calculated here.
+    pub fn set_full_calc_needed(&mut self) {
+        self.is_full_calc_needed = true;
+    }
+    /// Whether Excel has to calculate the workbook again when it is opened.
+    pub fn is_full_calc_needed(&self) -> bool {
+        self.is_full_calc_needed || self.is_formula_changed
+    }
+    /// Calculates the cell from its formula and the cells it reads, instead of the cached value.
+    /// A cell without a formula is its value, references to other sheets are `#REF!`.
+    ///
+    ///     // A1 = 1, A2 = 2, A3 = SUM(A1:A2)*10
+    ///     assert_eq!(sheet.calculate_cell("A3"), CellValue::Number(30.0));
+    ///
+    pub fn calculate_cell(&self, cell_index: &str) -> CellValue {
+        Evaluator::new(self).evaluate_cell(self.get_sheet_name(), cell_index)
+    }
+    /// Cells written by `set_cell` and `set_formula` since the sheet was read or last recalculated.
+    pub fn get_changed_cells(&self) -> Vec<CellAddress> {
+        self.changed_cells
+            .iter()
+            .map(|cell_index| CellAddress::from_index(&self.sheet_name, cell_index))
+            .collect()
+    }
+    pub fn clear_changed_cells(&mut self) {
+        self.changed_cells.clear();
+    }
+    /// Writes the calculated value of a formula cell into its `<v>`, keeping the formula.
+    /// A cell without a formula is left as it is.
+    ///
+    ///     sheet.set_formula_value("B1", &CellValue::String("ok".to_string()));
+    ///     // <c r="B1" t="str"><f>IF(A1>0,"ok","ng")</f><v>ok</v></c>
+    ///
+    pub fn set_formula_value(&mut self, cell_index: &str, value: &CellValue) {
+        let index = CellIndex::new(cell_index);
+        let Some(c_node) = self
+            .get_sheet_data_node_mut()
+            .search_child_by_id_mut("r", index.get_value())
+        else {
+            return;
+        };
+        if c_node.search_node("f").is_none() {
+            return;
+        }
+        c_node.remove_node("v");
+        c_node.remove_node("is");
+        c_node.remove_element("t");
+        let (cell_type, text) = match value {
+            CellValue::Empty => return,
+            CellValue::Number(number) => (None, number.to_string()),
+            CellValue::Bool(b) => (Some("b"), if *b { "1" } else { "0" }.to_string()),
+            CellValue::Error(kind) => (Some("e"), kind.as_str().to_string()),
+            CellValue::DateTime(_) | CellValue::Duration(_) => {
+                (None, to_number(value).unwrap_or_default().to_string())
+            }
+            CellValue::String(text) | CellValue::InlineString(text) => (Some("str"), escape(text)),
+        };
+        if let Some(cell_type) = cell_type {
+            c_node.set_element("t", cell_type);
+        }
+        let mut v_node = XMLNode::new("v", NodeType::Element);
+        v_node.add_text(text);
+        c_node.add_node(v_node);
+    }
+    /// Values of each area of the range, keeping where it starts rather than padding from `A1`.
+    /// Whole columns and rows end at the last used row and column.
+    ///
+    ///     let blocks = sheet.get_range(&CellRange::new("C2:D3").unwrap());
+    ///     assert_eq!(blocks[0].get_origin(), "C2");
+    ///     assert_eq!(blocks[0].get_values()[0][0], sheet.get_cell_value("C2"));
+    ///
+    pub fn get_range(&self, range: &CellRange) -> Vec<RangeValues> {
+        range
+            .get_areas()
+            .iter()
+            .map(|area| self.get_area_values(area))
+            .collect()
+    }
+    /// Reads the rows of `range` below `header_row` as records, each header cell naming the field of the cells under it.
+    /// Cells past the used range and rows without any value are skipped, errors tell the cell they happened at.
+    ///
+    ///     #[derive(Deserialize)]
+    ///     struct Item {
+    ///         #[serde(rename = "Name")]
+    ///         name: String,
+    ///         #[serde(rename = "Unit Price")]
+    ///         price: f64,
+    ///     }
+    ///     let items: Vec<Item> = sheet.deserialize_rows(1, &RangeArea::new("A:B").unwrap())?;
+    ///
+    pub fn deserialize_rows<T: DeserializeOwned>(
+        &self,
+        header_row: usize,
+        range: &RangeArea,
+    ) -> Result<Vec<T>, RecordError> {
+        let (max_row, max_column) = self.get_used_range(&self.sheet_name).unwrap_or_default();
+        let (first_row, last_row) = (
+            range.get_first_row().max(header_row + 1),
+            range.get_last_row().min(max_row),
+        );
+        let (first_column, last_column) = (
+            range.get_first_column(),
+            range.get_last_column().min(max_column),
+        );
+        if first_row > last_row || first_column > last_column {
+            return Ok(Vec::new());
+        }
+        let headers = self
+            .get_area_values(&RangeArea::from_bounds(
+                (header_row, first_column),
+                (header_row, last_column),
+            ))
+            .into_values()
+            .remove(0)
+            .into_iter()
+            .map(|header| Some(header.to_string().trim().to_string()).filter(|h| !h.is_empty()))
+            .collect::<Vec<_>>();
+        let rows = self.get_area_values(&RangeArea::from_bounds(
+            (first_row, first_column),
+            (last_row, last_column),
+        ));
+        rows.get_values()
+            .iter()
+            .enumerate()
+            .filter(|(_, values)| values.iter().any(|value| !value.is_empty()))
+            .map(|(i, values)| {
+                let row = first_row + i;
+                T::deserialize(
+                    RowDeserializer::new(&headers, values, row, first_column)
+                        .with_date1904(self.is_date1904),
+                )
+                .map_err(|e| e.at_row(row))
+            })
+            .collect()
+    }
+    /// The table the layout points to, its missing parts found from the cells.
+    /// `None` when no header row is found.
+    ///
+    ///     // A1: 詳細画面レイアウト
+    ///     // B2: 会社名 | 社員数
+    ///     let table = sheet.get_table_view(&TableLayout::new()).unwrap();
+    ///     assert_eq!(table.get_header_row(), 2);
+    ///
+    pub fn get_table_view(&self, layout: &TableLayout) -> Option<TableView> {
+        let (max_row, max_column) = self.get_used_range(&self.sheet_name).unwrap_or_default();
+        if max_row == 0 {
+            return None;
+        }
+        let (first_column, last_column) = layout.get_columns().unwrap_or((1, max_column));
+        let values = self.get_area_values(&RangeArea::from_bounds(
+            (1, first_column),
+            (max_row, last_column),
+        ));
+        let values = values.get_values();
+        let count_values = |row: &[CellValue]| row.iter().filter(|value| !is_blank(value)).count();
+        let header_row = match layout.get_header_row() {
+            Some(header_row) => header_row,
+            None => {
+                values
+                    .iter()
+                    .position(|row| count_values(row) >= 2)
+                    .or_else(|| values.iter().position(|row| count_values(row) > 0))?
+                    + 1
+            }
+        };
+        let header = values.get(header_row.checked_sub(1)?)?;
+        let (first, last) = match layout.get_columns() {
+            Some(_) => (0, header.len() - 1),
+            None => (
+                header.iter().position(|value| !is_blank(value))?,
+                header.iter().rposition(|value| !is_blank(value))?,
+            ),
+        };
+        let rows = values[header_row..]
+            .iter()
+            .map(|row| &row[first..=last])
+            .take_while(|row| {
+                count_values(row) > 0
+                    && layout.get_end_marker().is_none_or(|marker| {
+                        !row.iter().any(|value| value.to_string().trim() == marker)
+                    })
+            })
+            .map(|row| row.to_vec())
+            .collect();
+        Some(TableView::new(
+            header_row,
+            first_column + first,
+            &header[first..=last],
+            rows,
+        ))
+    }
+    /// Writes the records from `anchor`, a header row of their field names above a row for each record.
+    /// Strings go to the shared strings, numbers and booleans are written as they are,
+    /// dates as serial numbers and `None` as an empty cell. Nothing is written when a record fails.
+    /// Date cells not already shown as dates take the first date style of `<cellXfs>`;
+    /// without one in the styles part they stay serial numbers until the caller styles them.
+    /// Returns the area written, `None` without records.
+    ///
+    ///     let area = sheet.serialize_rows("B2", &items)?;
+    ///     // B2: Name | Unit Price
+    ///     // B3: pen  | 1.5
+    ///     sheet.set_cell_style(&area.resize(1, area.get_column_count()).unwrap(), 1);
+    ///     sheet.fit_column_widths(&area);
+    ///
+    pub fn serialize_rows<T: Serialize>(
+        &mut self,
+        anchor: &str,
+        records: &[T],
+    ) -> Result<Option<RangeArea>, RecordError> {
+        if records.is_empty() {
+            return Ok(None);
+        }
+        let anchor = CellIndex::new(anchor);
+        let (header_row, first_column) = (anchor.get_row_index(), anchor.get_column_index());
+        let mut headers: Vec<Option<String>> = Vec::new();
+        let mut rows = Vec::with_capacity(records.len());
+        for (i, record) in records.iter().enumerate() {
+            let row = header_row + 1 + i;
+            let cells = record
+                .serialize(RowSerializer::new(row, first_column))
+                .map_err(|e| e.at_row(row))?;
+            let mut values = vec![CellValue::Empty; headers.len()];
+            for (position, (header, value)) in cells.into_iter().enumerate() {
+                let position = match header {
+                    Some(header) => headers
+                        .iter()
+                        .position(|h| h.as_ref() == Some(&header))
+                        .unwrap_or_else(|| {
+                            headers.push(Some(header));
+                            headers.len() - 1
+                        }),
+                    None => position,
+                };
+                if headers.len() <= position {
+                    headers.resize(position + 1, None);
+                }
+                if values.len() <= position {
+                    values.resize(position + 1, CellValue::Empty);
+                }
+                values[position] = value;
+            }
+            rows.push(values);
+        }
+        for (i, header) in headers.iter().enumerate() {
+            if let Some(header) = header {
+                let value = CellValue::String(header.clone());
+                self.write_cell_value(&to_cell_index(header_row, first_column + i), &value);
+            }
+        }
+        for (i, values) in rows.iter().enumerate() {
+            for j in 0..headers.len() {
+                let value = values.get(j).unwrap_or(&CellValue::Empty);
+                let cell_index = to_cell_index(header_row + 1 + i, first_column + j);
+                self.write_cell_value(&cell_index, value);
+                self.apply_date_style(&cell_index, value);
+            }
+        }
+        Ok(Some(RangeArea::from_bounds(
+            (header_row, first_column),
+            (
+                header_row + records.len(),
+                first_column + headers.len().max(1) - 1,
+            ),
+        )))
+    }
+    /// Gives a date cell the first date style of `<cellXfs>`, unless its own style shows dates.
+    fn apply_date_style(&mut self, cell_index: &str, value: &CellValue) {
+        let Some(styles) = self.styles else {
+            return;
+        };
+        let is_date = |format: &NumberFormat| {
+            matches!(
+                get_number_format_kind(&format.code),
+                NumberFormatKind::Date | NumberFormatKind::DateTime
+            )
+        };
+        if value.as_date_time().is_none()
+            || self
+                .get_sheet_data_node()
+                .search_child_by_id("r", cell_index)
+                .and_then(|c_node| {
+                    let style_index =
+                        self.with_cell_node(c_node, |cell_node| cell_node.get_style_index());
+                    styles.get_number_format(style_index)
+                })
+                .is_some_and(|format| is_date(&format))
+        {
+            return;
+        }
+        if let Some(style_index) = styles.find_style_index(is_date) {
+            self.get_or_create_cell_node(&CellIndex::new(cell_index))
+                .set_element("s", style_index.to_string());
+        }
+    }
+    /// Gives the cells of the area the style at `style_index` of `<cellXfs>` in the styles, creating empty cells.
+    /// Whole columns and rows end at the last used row and column.
+    pub fn set_cell_style(&mut self, area: &RangeArea, style_index: usize) {
+        for (row, column) in self.clip_to_used_range(area).positions() {
+            let index = to_cell_index(row, column);
+            let index = CellIndex::new(&index);
+            self.update_dimension(&index);
+            self.get_or_create_cell_node(&index)
+                .set_element("s", style_index.to_string());
+        }
+    }
+    /// Widens or narrows each column of the area to the longest text shown in it, as formatted by the styles.
+    /// Columns without text in the area are left as they are.
+    pub fn fit_column_widths(&mut self, area: &RangeArea) {
+        let area = self.clip_to_used_range(area);
+        for column in area.get_first_column()..=area.get_last_column() {
+            let width = (area.get_first_row()..=area.get_last_row())
+                .flat_map(|row| {
+                    let text = self.get_formatted_cell(&to_cell_index(row, column));
+                    text.lines().map(get_text_width).collect::<Vec<_>>()
+                })
+                .max()
+                .unwrap_or_default();
+            if width > 0 {
+                self.set_column_width(column, (width as f64 + 2.0).min(MAX_COLUMN_WIDTH));
+            }
+        }
+    }
+    /// Sets the width of the column in characters, splitting a `<col>` shared with other columns.
+    ///
+    ///     sheet.set_column_width(2, 12.5);
+    ///     // <cols><col min="1" max="1" width="5"/><col min="2" max="2" width="12.5" customWidth="1"/>...
+    ///
+    pub fn set_column_width(&mut self, column: usize, width: f64) {
+        let worksheet = self.node.search_node_mut("worksheet").unwrap();
+        if worksheet.search_node("cols").is_none() {
+            let position = worksheet
+                .get_child_nodes()
+                .unwrap_or_default()
+                .iter()
+                .rposition(|child| BEFORE_COLS.contains(&child.get_value()))
+                .map(|position| position + 1)
+                .unwrap_or_default();
+            worksheet.insert_node(position, XMLNode::new("cols", NodeType::Element));
+        }
+        let cols = worksheet.search_node_mut("cols").unwrap();
+        let span = |col: &XMLNode| -> Option<(usize, usize)> {
+            Some((
+                col.search_element("min")?.parse().ok()?,
+                col.search_element("max")?.parse().ok()?,
+            ))
+        };
+        let mut new_cols = Vec::new();
+        let mut target = None;
+        for col in cols.search_all_nodes("col").unwrap_or_default() {
+            match span(col) {
+                Some((min, max)) if min <= column && column <= max => {
+                    for (min, max) in [(min, column - 1), (column + 1, max)] {
+                        if min <= max {
+                            let mut part = col.clone();
+                            part.set_element("min", min.to_string());
+                            part.set_element("max", max.to_string());
+                            new_cols.push(part);
+                        }
+                    }
+                    target = Some(col.clone());
+                }
+                _ => new_cols.push(col.clone()),
+            }
+        }
+        let mut target = target.unwrap_or_else(|| XMLNode::new("col", NodeType::SingleElement));
+        target.set_element("min", column.to_string());
+        target.set_element("max", column.to_string());
+        target.set_element("width", width.to_string());
+        target.set_element("customWidth", "1");
+        new_cols.push(target);
+        new_cols.sort_by_key(|col| span(col).map(|(min, _)| min).unwrap_or_default());
+        cols.retain_nodes(|_| false);
+        new_cols.into_iter().for_each(|col| cols.add_node(col));
+    }
+    /// Ranges of `<mergeCells>` in the order they are written.
+    pub fn get_merged_ranges(&self) -> Vec<MergedRange> {
+        self.node
+            .search_node("worksheet")
+            .and_then(|worksheet| worksheet.search_node("mergeCells"))
+            .and_then(|merge_cells| merge_cells.search_all_nodes("mergeCell"))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|merge_cell| merge_cell.search_element("ref"))
+            .filter_map(MergedRange::new)
+            .collect()
+    }
+    /// The merge the cell belongs to.
+    ///
+    ///     // <mergeCell ref="B2:E2"/>
+    ///     assert_eq!(sheet.get_merged_range("C2").unwrap().to_string(), "B2:E2");
+    ///
+    pub fn get_merged_range(&self, cell_index: &str) -> Option<MergedRange> {
+        self.get_merged_ranges()
+            .into_iter()
+            .find(|range| range.contains(cell_index))
+    }
+    /// Merges the cells of `range`, which must not overlap an existing merge.
+    /// Values of the cells other than the top-left one are kept as Excel leaves them hidden.
+    pub fn add_merged_range(&mut self, range: &str) -> Result<MergedRange, MergeError> {
+        let range = MergedRange::new(range).ok_or(MergeError::InvalidRange(range.to_string()))?;
+        if range.is_single_cell() {
+            return Err(MergeError::SingleCell(range));
+        }
+        let mut ranges = self.get_merged_ranges();
+        if let Some(existing) = ranges.iter().find(|existing| existing.overlaps(&range)) {
+            return Err(MergeError::Overlap {
+                range,
+                existing: *existing,
+            });
+        }
+        ranges.push(range);
+        self.write_merged_ranges(&ranges);
+        Ok(range)
+    }
+    /// Copies the values, styles, formulas and merges of `source` as copy and paste does,
+    /// moving the relative references of the formulas with the cells.
+    /// Empty cells of `source` clear the destination. A `destination` that is a multiple
+    /// of `source` in size is filled with copies, otherwise one copy starts at its top-left cell.
+    /// Returns the pasted area, `None` without writing when it would leave the sheet.
+    ///
+    ///     // template in A1:C2, filled in down to row 10
+    ///     let destination = RangeArea::new("A3:C10").unwrap();
+    ///     sheet.copy_range(&RangeArea::new("A1:C2").unwrap(), &destination);
+    ///
+    pub fn copy_range(&mut self, source: &RangeArea, destination: &RangeArea) -> Option<RangeArea> {
+        let copied = self.copy_cells(source);
+        self.paste_cells(copied, destination, true)
+    }
+    /// `copy_range` from another sheet of the workbook.
+    /// References without a sheet name refer to this sheet after pasting, as they do in Excel.
+    pub fn copy_range_from(
+        &mut self,
+        sheet: &Sheet<'a, S>,
+        source: &RangeArea,
+        destination: &RangeArea,
+    ) -> Option<RangeArea> {
+        let copied = sheet.copy_cells(source);
+        self.paste_cells(copied, destination, true)
+    }
+    /// Moves the cells of `source` to start at `destination` as cut and paste does.
+    /// The formulas keep referring to the same cells, and references of this sheet
+    /// to the moved cells follow them. Other sheets follow with `move_formula_references`.
+    pub fn move_range(&mut self, source: &RangeArea, destination: &str) -> Option<RangeArea> {
+        let destination = CellIndex::new(destination);
+        let rows = destination.get_row_index() as isize - source.get_first_row() as isize;
+        let columns = destination.get_column_index() as isize - source.get_first_column() as isize;
+        let moved = source.offset(rows, columns)?;
+        let copied = self.copy_cells(source);
+        self.clear_range(source, ClearMode::All);
+        self.paste_cells(copied, &moved, false)?;
+        let sheet_name = self.sheet_name.clone();
+        self.move_formula_references(&sheet_name, source, rows, columns);
+        Some(moved)
+    }
+    /// Moves the references to cells of `area` on `sheet_name` by `rows` and `columns`,
+    /// after the cells are moved with `move_range`.
+    pub fn move_formula_references(
+        &mut self,
+        sheet_name: &str,
+        area: &RangeArea,
+        rows: isize,
+        columns: isize,
+    ) {
+        let own = self.sheet_name.clone();
+        self.rewrite_formulas(sheet_name, |text| {
+            move_references(text, &own, sheet_name, area, rows, columns)
+        });
+    }
+    /// Clears the cells of `area`. Clearing formats also unmerges the merges it overlaps.
+    pub fn clear_range(&mut self, area: &RangeArea, mode: ClearMode) {
+        if mode != ClearMode::Formats {
+            let masters = self
+                .get_cell_positions(|c_node| c_node.search_node("f").is_some())
+                .into_iter()
+                .filter(|(row, column)| area.contains_position(*row, *column))
+                .map(|(row, column)| to_cell_index(row, column))
+                .collect::<Vec<_>>();
+            for master in masters.iter() {
+                self.detach_shared_formula(master);
+            }
+            self.is_formula_changed |= !masters.is_empty();
+        }
+        let mut cleared = Vec::new();
+        for row in self
+            .get_sheet_data_node_mut()
+            .search_all_nodes_mut("row")
+            .unwrap_or_default()
+        {
+            let is_in_area =
+                |c_node: &XMLNode| c_node.search_element("r").is_some_and(|r| area.contains(r));
+            for c_node in row.search_all_nodes_mut("c").unwrap_or_default() {
+                if !is_in_area(c_node) {
+                    continue;
+                }
+                match mode {
+                    ClearMode::Contents | ClearMode::All => {
+                        c_node.remove_node("f");
+                        c_node.remove_node("v");
+                        c_node.remove_node("is");
+                        c_node.remove_element("t");
+                        cleared.push(c_node.search_element("r").unwrap().to_string());
+                    }
+                    ClearMode::Formats => c_node.remove_element("s"),
+                }
+            }
+            row.retain_nodes(|c_node| {
+                !is_in_area(c_node)
+                    || mode == ClearMode::Contents && c_node.search_element("s").is_some()
+                    || mode == ClearMode::Formats
+            });
+        }
+        self.changed_cells.extend(cleared);
+        if mode != ClearMode::Contents {
+            let merged_ranges = self
+                .get_merged_ranges()
+                .into_iter()
+                .filter(|range| RangeArea::from(*range).intersect(area).is_none())
+                .collect::<Vec<_>>();
+            if merged_ranges.len() != self.get_merged_range_count() {
+                self.write_merged_ranges(&merged_ranges);
+            }
+        }
+    }
+    /// Inserts `count` empty rows before row `at`, moving the rows below it down.
+    pub fn insert_rows(&mut self, at: usize, count: usize) {
+        self.shift_lines(LineShift::InsertRows { at, count });
+    }
+    /// Deletes `count` rows from row `at`, moving the rows below it up.
+    pub fn delete_rows(&mut self, at: usize, count: usize) {
+        self.shift_lines(LineShift::DeleteRows { at, count });
+    }
+    /// Inserts `count` empty columns before column `at`, moving the columns right of it.
+    pub fn insert_columns(&mut self, at: usize, count: usize) {
+        self.shift_lines(LineShift::InsertColumns { at, count });
+    }
+    /// Deletes `count` columns from column `at`, moving the columns right of it left.
+    pub fn delete_columns(&mut self, at: usize, count: usize) {
+        self.shift_lines(LineShift::DeleteColumns { at, count });
+    }
+    /// Moves the cells with their merges, `<dimension>`, `<cols>`, hyperlinks,
+    /// conditional formats and data validations, and the references of this sheet to them.
+    /// Other sheets follow with `shift_formula_references`.
+    pub fn shift_lines(&mut self, shift: LineShift) {
+        let (LineShift::InsertRows { count, .. }
+        | LineShift::DeleteRows { count, .. }
+        | LineShift::InsertColumns { count, .. }
+        | LineShift::DeleteColumns { count, .. }) = shift;
+        if count == 0 {
+            return;
+        }
+        // the calculation chain lists the formula cells by their old addresses
+        self.is_formula_changed |= !self
+            .get_cell_positions(|c_node| c_node.search_node("f").is_some())
+            .is_empty();
+        self.unshare_formulas();
+        self.shift_cells(shift);
+        let merged_ranges = self
+            .get_merged_ranges()
+            .into_iter()
+            .filter_map(|range| shift.move_area(&RangeArea::from(range)))
+            .map(MergedRange::from)
+            .filter(|range| !range.is_single_cell())
+            .collect::<Vec<_>>();
+        if !merged_ranges.is_empty() || self.get_merged_range_count() > 0 {
+            self.write_merged_ranges(&merged_ranges);
+        }
+        let worksheet = self.node.search_node_mut("worksheet").unwrap();
+        if let Some(dimension) = worksheet.search_node_mut("dimension") {
+            let range = dimension
+                .search_element("ref")
+                .and_then(RangeArea::new)
+                .and_then(|area| shift.move_area(&area))
+                .map(|area| area.to_string())
+                .unwrap_or("A1".to_string());
+            dimension.set_element("ref", range);
+        }
+        if !shift.is_row() {
+            shift_nodes(worksheet, "cols", "col", |col| {
+                let min = col.search_element("min")?.parse().ok()?;
+                let max = col.search_element("max")?.parse().ok()?;
+                let (min, max) = shift.move_span(min, max)?;
+                col.set_element("min", min.to_string());
+                col.set_element("max", max.to_string());
+                Some(())
+            });
+        }
+        shift_nodes(worksheet, "hyperlinks", "hyperlink", |hyperlink| {
+            let area = shift.move_area(&RangeArea::new(hyperlink.search_element("ref")?)?)?;
+            hyperlink.set_element("ref", area.to_string());
+            Some(())
+        });
+        worksheet.retain_nodes(|node| {
+            node.get_value() != "conditionalFormatting"
+                || node
+                    .get_element_text("sqref")
+                    .and_then(|sqref| shift_sqref(&sqref, shift))
+                    .is_some()
+        });
+        for conditional in worksheet
+            .search_all_nodes_mut("conditionalFormatting")
+            .unwrap_or_default()
+        {
+            let sqref = conditional
+                .get_element_text("sqref")
+                .and_then(|sqref| shift_sqref(&sqref, shift));
+            conditional.set_element("sqref", sqref.unwrap_or_default());
+        }
+        shift_nodes(
+            worksheet,
+            "dataValidations",
+            "dataValidation",
+            |validation| {
+                let sqref = shift_sqref(&validation.get_element_text("sqref")?, shift)?;
+                validation.set_element("sqref", sqref);
+                Some(())
+            },
+        );
+        let sheet_name = self.sheet_name.clone();
+        self.shift_formula_references(&sheet_name, shift);
+    }
+    /// Moves the references to `sheet_name` in the formulas of this sheet,
+    /// after lines of that sheet are inserted or deleted.
+    pub fn shift_formula_references(&mut self, sheet_name: &str, shift: LineShift) {
+        let own = self.sheet_name.clone();
+        let f = |text: &str| shift_lines(text, &own, sheet_name, shift);
+        self.rewrite_formulas(sheet_name, f);
+        self.rewrite_rule_formulas(f);
+    }
+    /// Rewrites the formulas of conditional formats and data validations with `f`.
+    fn rewrite_rule_formulas(&mut self, f: impl Fn(&str) -> String) {
+        let worksheet = self.node.search_node_mut("worksheet").unwrap();
+        let rewrite_text = |node: &mut XMLNode| {
+            let Some(text) = node.get_text().map(|text| unescape(&text)) else {
+                return;
+            };
+            let rewritten = f(&text);
+            if rewritten != text {
+                node.change_text(escape(&rewritten));
+            }
+        };
+        for child in worksheet.get_child_nodes_mut().unwrap_or_default() {
+            let (items, formulas): (&str, &[&str]) = match child.get_value() {
+                "conditionalFormatting" => ("cfRule", &["formula"]),
+                "dataValidations" => ("dataValidation", &["formula1", "formula2"]),
+                _ => continue,
+            };
+            for item in child.search_all_nodes_mut(items).unwrap_or_default() {
+                item.get_child_nodes_mut()
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|node| formulas.contains(&node.get_value()))
+                    .for_each(rewrite_text);
+            }
+        }
+    }
+    /// Removes the merge written as `range`, returning whether there was one.
+    pub fn remove_merged_range(&mut self, range: &str) -> bool {
+        let Some(range) = MergedRange::new(range) else {
+            return false;
+        };
+        let mut ranges = self.get_merged_ranges();
+        let count = ranges.len();
+        ranges.retain(|existing| *existing != range);
+        if ranges.len() == count {
+            return false;
+        }
+        self.write_merged_ranges(&ranges);
+        true
+    }
+    /// Relationship ids of the tables of the sheet, in the order of `<tableParts>`.
+    pub fn get_table_part_ids(&self) -> Vec<String> {
+        self.node
+            .search_node("worksheet")
+            .and_then(|worksheet| worksheet.search_node("tableParts"))
+            .and_then(|table_parts| table_parts.search_all_nodes("tablePart"))
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|table_part| table_part.search_element("r:id"))
+            .map(|id| id.to_string())
+            .collect()
+    }
     pub fn get_all_row_index(&self) -> Vec<usize> {
         let rows = self.get_sheet_data_node().search_all_nodes("row");
         if let Some(rows) = rows {
@@ -58,13 +924,28 @@ impl<'a, S: SharedStringsInterface<'a>> Sheet<'a, S> {
             .get_sheet_data_node()
             .search_child_by_id("r", index.get_value());
         if let Some(c_node) = c_node {
-            let shared_strings = self.shared_strings.borrow().get_values();
-            let c_node = CellNode::new(c_node, shared_strings);
-            c_node.get_v_text()
+            self.with_cell_node(c_node, |c_node| c_node.get_v_text())
         } else {
             None
         }
     }
+    fn with_cell_node<T>(&self, c_node: &XMLNode, f: impl FnOnce(&CellNode) -> T) -> T {
+        let shared_strings = self.shared_strings.get_values();
+        f(&CellNode::new(c_node, &shared_strings))
+    }
+    /// Numbers shown as dates or times by the styles part become DateTime or Duration.
+    fn get_cell_node_value(&self, c_node: &XMLNode) -> CellValue {
+        let (value, style_index) = self.with_cell_node(c_node, |cell_node| {
+            (cell_node.get_value(), cell_node.get_style_index())
+        });
+        match self
+            .styles
+            .and_then(|styles| styles.get_number_format(style_index))
+        {
+            Some(number_format) => value.with_number_format(&number_format.code, self.is_date1904),
+            None => value,
+        }
+    }
     fn get_max_column_index(&self) -> usize {
         self.get_all_cell()
             .iter()
@@ -78,7 +959,10 @@ impl<'a, S: SharedStringsInterface<'a>> Sheet<'a, S> {
 }
 impl<'a, S: SharedStringsInterface<'a>> WorkSheet<'a> for Sheet<'a, S> {
     fn get_cell(&self, cell_index: &str) -> Option<String> {
-        self.get_cell_v(cell_index)
+        match self.get_merged_top_left(cell_index) {
+            Some(top_left) => self.get_cell_v(&top_left),
+            None => self.get_cell_v(cell_index),
+        }
     }
     fn get_column_range(&self, start: &str, end: &str) -> Vec<Vec<Option<String>>> {
         let start = ColumnAlphabet::new(start);
@@ -115,13 +999,18 @@ impl<'a, S: SharedStringsInterface<'a>> WorkSheet<'a> for Sheet<'a, S> {
                 let column_index = cell_index.get_column_index();
                 let nones_range = (result.len() + 1)..column_index;
                 nones_range.for_each(|_| result.push(None));
-                let shared_strings = self.shared_strings.borrow().get_values();
-                let c_node = CellNode::new(c_node, shared_strings);
-                result.push(c_node.get_v_text());
+                result.push(self.with_cell_node(c_node, |c_node| c_node.get_v_text()));
             }
+            self.spread_merged_values(row_index, &mut result, None, |top_left| {
+                self.get_cell_v(top_left)
+            });
             result
         } else {
-            vec![None]
+            let mut result = vec![None];
+            self.spread_merged_values(row_index, &mut result, None, |top_left| {
+                self.get_cell_v(top_left)
+            });
+            result
         }
     }
     fn get_column(&self, s: &str) -> Vec<Option<String>> {
@@ -172,26 +1061,787 @@ impl<'a, S: SharedStringsInterface<'a>> WorkSheet<'a> for Sheet<'a, S> {
         }
         result
     }
+    fn get_cell_value(&self, cell_index: &str) -> CellValue {
+        match self.get_merged_top_left(cell_index) {
+            Some(top_left) => self.get_unmerged_cell_value(&top_left),
+            None => self.get_unmerged_cell_value(cell_index),
+        }
+    }
+    fn get_row_values(&self, row_index: usize) -> Vec<CellValue> {
+        let row_node = self
+            .get_sheet_data_node()
+            .search_child_by_id("r", row_index.to_string().as_str());
+        let mut result = vec![];
+        if let Some(c_nodes) = row_node.and_then(|row_node| row_node.search_all_nodes("c")) {
+            for c_node in c_nodes.iter() {
+                let Some(index) = c_node.search_element("r") else {
+                    continue;
+                };
+                let column_index = CellIndex::new(index).get_column_index();
+                ((result.len() + 1)..column_index).for_each(|_| result.push(CellValue::Empty));
+                result.push(self.get_cell_node_value(c_node));
+            }
+        }
+        self.spread_merged_values(row_index, &mut result, CellValue::Empty, |top_left| {
+            self.get_unmerged_cell_value(top_left)
+        });
+        result
+    }
+    fn get_column_values(&self, s: &str) -> Vec<CellValue> {
+        let column_index = ColumnAlphabet::new(s).to_number();
+        self.get_all_row_index()
+            .iter()
+            .map(|row_index| {
+                self.get_row_values(*row_index)
+                    .get(column_index - 1)
+                    .cloned()
+                    .unwrap_or(CellValue::Empty)
+            })
+            .collect()
+    }
+    fn get_all_cell_values(&self) -> Vec<Vec<CellValue>> {
+        let rows = self
+            .get_all_row_index()
+            .iter()
+            .map(|i| self.get_row_values(*i))
+            .collect::<Vec<_>>();
+        let max_len = rows.iter().map(|row| row.len()).max().unwrap_or_default();
+        rows.into_iter()
+            .map(|mut row| {
+                row.resize(max_len, CellValue::Empty);
+                row
+            })
+            .collect()
+    }
+    fn get_column_range_values(&self, start: &str, end: &str) -> Vec<Vec<CellValue>> {
+        let start = ColumnAlphabet::new(start).to_number();
+        let end = ColumnAlphabet::new(end).to_number();
+        self.get_all_cell_values()
+            .into_iter()
+            .map(|mut row| {
+                row.resize(row.len().max(end), CellValue::Empty);
+                row.drain((start - 1)..end).collect()
+            })
+            .collect()
+    }
+    /// Writes the value to the cell as a shared string, creating its `<row>` and `<c>` in sorted position when missing.
+    /// The row `spans` and the sheet `<dimension>` are widened to cover the cell.
     fn set_cell(&mut self, cell: &'a ECell<'a>) -> () {
-        let index = cell.get_index();
-        let value = cell.get_value();
-        let maybe_child = self
-            .get_sheet_data_node_mut()
-            .search_child_by_id_mut("r", index.get_value());
-        if let Some(cell) = maybe_child {
-            if let Some(v_node) = cell.search_node_mut("v") {
-                v_node.change_text(value);
+        let value = CellValue::String(cell.get_value().to_string());
+        self.write_cell_value(cell.get_index().get_value(), &value);
+    }
+}
+impl<'a, S: SharedStringsInterface<'a>> CellSource for Sheet<'a, S> {
+    fn get_value_at(&self, sheet: &str, row: usize, column: usize) -> Option<CellValue> {
+        self.is_sheet(sheet)
+            .then(|| self.get_unmerged_cell_value(&to_cell_index(row, column)))
+    }
+    fn get_formula_at(&self, sheet: &str, row: usize, column: usize) -> Option<String> {
+        if !self.is_sheet(sheet) {
+            return None;
+        }
+        self.get_formula(&to_cell_index(row, column))
+            .map(|formula| formula.text)
+    }
+    fn get_used_range(&self, sheet: &str) -> Option<(usize, usize)> {
+        if !self.is_sheet(sheet) {
+            return None;
+        }
+        let used_range = self
+            .get_cell_positions(|_| true)
+            .into_iter()
+            .fold((0, 0), |(max_row, max_column), (row, column)| {
+                (max_row.max(row), max_column.max(column))
+            });
+        Some(used_range)
+    }
+    fn get_sheet_names(&self) -> Vec<String> {
+        vec![self.sheet_name.clone()]
+    }
+    fn get_formula_cells(&self, sheet: &str) -> Vec<(usize, usize)> {
+        if !self.is_sheet(sheet) {
+            return Vec::new();
+        }
+        self.get_cell_positions(|c_node| c_node.search_node("f").is_some())
+    }
+    fn is_date1904(&self) -> bool {
+        self.is_date1904
+    }
+}
+/// Whether `formula` has references to rows or columns of `sheet`, whole sheet spans aside.
+fn refers_to_sheet(formula: &str, formula_sheet: &str, sheet: &str) -> bool {
+    let every_row = LineShift::DeleteRows {
+        at: 1,
+        count: MAX_ROW,
+    };
+    let every_column = LineShift::DeleteColumns {
+        at: 1,
+        count: MAX_COLUMN,
+    };
+    [every_row, every_column]
+        .into_iter()
+        .any(|every_line| shift_lines(formula, formula_sheet, sheet, every_line) != formula)
+}
+/// Moves the space separated areas of a `sqref`, `None` when all of them are deleted.
+fn shift_sqref(sqref: &str, shift: LineShift) -> Option<String> {
+    let areas = sqref
+        .split_whitespace()
+        .filter_map(RangeArea::new)
+        .filter_map(|area| shift.move_area(&area))
+        .map(|area| area.to_string())
+        .collect::<Vec<_>>();
+    (!areas.is_empty()).then(|| areas.join(" "))
+}
+/// Applies `f` to each `item` of the `list` element, dropping those it gives `None`
+/// and the list when none is left. A `count` of the list is kept up to date.
+fn shift_nodes<'a>(
+    worksheet: &mut XMLNode<'a>,
+    list: &str,
+    item: &str,
+    mut f: impl FnMut(&mut XMLNode<'a>) -> Option<()>,
+) {
+    let Some(list_node) = worksheet.search_node_mut(list) else {
+        return;
+    };
+    let mut kept = Vec::new();
+    for (i, node) in list_node
+        .search_all_nodes_mut(item)
+        .unwrap_or_default()
+        .into_iter()
+        .enumerate()
+    {
+        if f(node).is_some() {
+            kept.push(i);
+        }
+    }
+    let mut i = 0;
+    list_node.retain_nodes(|node| {
+        if node.get_value() != item {
+            return true;
+        }
+        i += 1;
+        kept.contains(&(i - 1))
+    });
+    if kept.is_empty() {
+        worksheet.remove_node(list);
+        return;
+    }
+    if list_node.search_element("count").is_some() {
+        list_node.set_element("count", kept.len().to_string());
+    }
+}
+fn to_cell_index(row: usize, column: usize) -> String {
+    format!("{}{}", ColumnAlphabet::from_number(column), row)
+}
+/// Empty cells and cells of spaces only.
+fn is_blank(value: &CellValue) -> bool {
+    value.is_empty() || value.as_str().is_some_and(|text| text.trim().is_empty())
+}
+/// Characters the text takes in a column, counting full-width ones as two.
+fn get_text_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if (c as u32) < 0x1100 { 1 } else { 2 })
+        .sum()
+}
+impl<'a, S: SharedStringsInterface<'a>> Sheet<'a, S> {
+    /// Sheet names in formulas ignore case.
+    fn is_sheet(&self, sheet: &str) -> bool {
+        sheet.to_lowercase() == self.sheet_name.to_lowercase()
+    }
+    /// Row and column of the written cells `f` accepts, row by row.
+    fn get_cell_positions(&self, f: impl Fn(&XMLNode) -> bool) -> Vec<(usize, usize)> {
+        self.get_sheet_data_node()
+            .search_all_nodes("row")
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|row| row.search_all_nodes("c").unwrap_or_default())
+            .filter(|c_node| f(c_node))
+            .filter_map(|c_node| c_node.search_element("r"))
+            .map(|r| {
+                let index = CellIndex::new(r);
+                (index.get_row_index(), index.get_column_index())
+            })
+            .collect()
+    }
+    /// Returns the index of the cell writing the text of shared formula `si` and its formula.
+    fn get_shared_formula_master(&self, si: usize) -> Option<(String, CellFormula)> {
+        self.get_sheet_data_node()
+            .search_all_nodes("row")?
+            .into_iter()
+            .filter_map(|row| row.search_all_nodes("c"))
+            .flatten()
+            .find_map(|c_node| {
+                let formula = self.with_cell_node(c_node, |c_node| c_node.get_formula())?;
+                let is_master = matches!(formula.kind, FormulaKind::Shared { index, .. } if index == si)
+                    && !formula.is_shared_child();
+                if !is_master {
+                    return None;
+                }
+                Some((c_node.search_element("r")?.to_string(), formula))
+            })
+    }
+    /// When the cell writes a shared formula, gives every cell sharing it its own normal formula.
+    fn detach_shared_formula(&mut self, cell_index: &str) {
+        let Some(CellFormula {
+            kind: FormulaKind::Shared { index: si, .. },
+            ..
+        }) = self.get_formula(cell_index)
+        else {
+            return;
+        };
+        let Some((master_index, _)) = self.get_shared_formula_master(si) else {
+            return;
+        };
+        if master_index != cell_index {
+            return;
+        }
+        let children = self
+            .get_sheet_data_node()
+            .search_all_nodes("row")
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|row| row.search_all_nodes("c"))
+            .flatten()
+            .filter_map(|c_node| c_node.search_element("r"))
+            .filter(|r| *r != cell_index)
+            .filter_map(|r| Some((r.to_string(), self.get_formula(r)?)))
+            .filter(|(_, formula)| {
+                matches!(formula.kind, FormulaKind::Shared { index, .. } if index == si)
+            })
+            .collect::<Vec<_>>();
+        for (r, formula) in children {
+            let Some(f_node) = self
+                .get_sheet_data_node_mut()
+                .search_child_by_id_mut("r", &r)
+                .and_then(|c_node| c_node.search_node_mut("f"))
+            else {
+                continue;
+            };
+            let mut normal = XMLNode::new("f", NodeType::Element);
+            normal.add_text(escape(&formula.text));
+            *f_node = normal;
+        }
+    }
+    fn get_or_create_cell_node(&mut self, index: &CellIndex) -> &mut XMLNode<'a> {
+        let row_index = index.get_row_index();
+        let column_index = index.get_column_index();
+        let row = find_or_insert_child(
+            self.get_sheet_data_node_mut(),
+            row_index,
+            |row| {
+                row.search_element("r")
+                    .and_then(|r| r.parse().ok())
+                    .unwrap_or_default()
+            },
+            || {
+                let mut row = XMLNode::new("row", NodeType::Element);
+                row.set_element("r", row_index.to_string());
+                row.set_element("spans", format!("{}:{}", column_index, column_index));
+                row
+            },
+        );
+        row.set_node_type(NodeType::Element);
+        if let Some((start, end)) = row
+            .search_element("spans")
+            .and_then(|spans| spans.split_once(':'))
+            .and_then(|(start, end)| {
+                Some((start.parse::<usize>().ok()?, end.parse::<usize>().ok()?))
+            })
+        {
+            let spans = format!("{}:{}", start.min(column_index), end.max(column_index));
+            row.set_element("spans", spans);
+        }
+        find_or_insert_child(
+            row,
+            column_index,
+            |c| {
+                c.search_element("r")
+                    .map(|r| CellIndex::new(r).get_column_index())
+                    .unwrap_or_default()
+            },
+            || {
+                let mut c = XMLNode::new("c", NodeType::Element);
+                c.set_element("r", index.get_value().to_string());
+                c
+            },
+        )
+    }
+    fn update_dimension(&mut self, index: &CellIndex) {
+        let is_empty = self.get_sheet_data_node().get_child_nodes().is_none();
+        let Some(dimension) = self
+            .node
+            .search_node_mut("worksheet")
+            .and_then(|worksheet| worksheet.search_node_mut("dimension"))
+        else {
+            return;
+        };
+        let mut columns = vec![index.get_column_index()];
+        let mut rows = vec![index.get_row_index()];
+        if !is_empty {
+            dimension
+                .search_element("ref")
+                .unwrap_or_default()
+                .split(':')
+                .filter(|cell| !cell.is_empty())
+                .map(CellIndex::new)
+                .for_each(|cell| {
+                    columns.push(cell.get_column_index());
+                    rows.push(cell.get_row_index());
+                });
+        }
+        let start = format!(
+            "{}{}",
+            ColumnAlphabet::from_number(*columns.iter().min().unwrap()),
+            rows.iter().min().unwrap()
+        );
+        let end = format!(
+            "{}{}",
+            ColumnAlphabet::from_number(*columns.iter().max().unwrap()),
+            rows.iter().max().unwrap()
+        );
+        let range = if start == end {
+            start
+        } else {
+            format!("{}:{}", start, end)
+        };
+        dimension.set_element("ref", range);
+    }
+    /// Writes the value to the cell, creating its `<row>` and `<c>` in sorted position when missing,
+    /// and widens the row `spans` and the sheet `<dimension>` to cover it.
+    /// Strings are shared strings, dates their serial numbers and an empty value clears the cell.
+    fn write_cell_value(&mut self, cell_index: &str, value: &CellValue) {
+        if value.is_empty()
+            && self
+                .get_sheet_data_node()
+                .search_child_by_id("r", cell_index)
+                .is_none()
+        {
+            return;
+        }
+        let index = CellIndex::new(cell_index);
+        self.changed_cells.push(cell_index.to_string());
+        let (cell_type, text) = match value {
+            CellValue::Empty => (None, None),
+            CellValue::Number(number) => (None, Some(number.to_string())),
+            CellValue::Bool(b) => (Some("b"), Some(if *b { "1" } else { "0" }.to_string())),
+            CellValue::Error(kind) => (Some("e"), Some(kind.as_str().to_string())),
+            CellValue::DateTime(date_time) => (
+                None,
+                Some(date_time.to_serial(self.is_date1904).to_string()),
+            ),
+            CellValue::Duration(duration) => (None, Some(duration.to_serial().to_string())),
+            CellValue::String(text) | CellValue::InlineString(text) => (
+                Some("s"),
+                Some(self.shared_strings.add_shared_string(text).to_string()),
+            ),
+        };
+        self.update_dimension(&index);
+        self.detach_shared_formula(cell_index);
+        self.is_formula_changed |= self
+            .get_sheet_data_node()
+            .search_child_by_id("r", cell_index)
+            .is_some_and(|c_node| c_node.search_node("f").is_some());
+        let c_node = self.get_or_create_cell_node(&index);
+        c_node.remove_node("f");
+        c_node.remove_node("is");
+        c_node.remove_node("v");
+        match cell_type {
+            Some(cell_type) => c_node.set_element("t", cell_type),
+            None => c_node.remove_element("t"),
+        }
+        if let Some(text) = text {
+            let mut v_node = XMLNode::new("v", NodeType::Element);
+            v_node.add_text(text);
+            c_node.add_node(v_node);
+            c_node.set_node_type(NodeType::Element);
+        }
+    }
+    /// The area ending at the last used row and column when it is whole columns or rows.
+    fn clip_to_used_range(&self, area: &RangeArea) -> RangeArea {
+        let (max_row, max_column) = self.get_used_range(&self.sheet_name).unwrap_or_default();
+        RangeArea::from_bounds(
+            (area.get_first_row(), area.get_first_column()),
+            (
+                if area.is_whole_columns() {
+                    max_row.max(area.get_first_row())
+                } else {
+                    area.get_last_row()
+                },
+                if area.is_whole_rows() {
+                    max_column.max(area.get_first_column())
+                } else {
+                    area.get_last_column()
+                },
+            ),
+        )
+    }
+    fn get_area_values(&self, area: &RangeArea) -> RangeValues {
+        let area = self.clip_to_used_range(area);
+        let mut values =
+            vec![vec![CellValue::Empty; area.get_column_count()]; area.get_row_count()];
+        let rows = self.get_sheet_data_node().search_all_nodes("row");
+        for c_node in rows
+            .unwrap_or_default()
+            .iter()
+            .flat_map(|row| row.search_all_nodes("c").unwrap_or_default())
+        {
+            let Some(index) = c_node.search_element("r") else {
+                continue;
+            };
+            let index = CellIndex::new(index);
+            let (row, column) = (index.get_row_index(), index.get_column_index());
+            if area.contains_position(row, column) {
+                values[row - area.get_first_row()][column - area.get_first_column()] =
+                    self.get_cell_node_value(c_node);
+            }
+        }
+        if self.is_merged_value_spread {
+            for merged in self.get_merged_ranges() {
+                let top_left = (merged.get_first_row(), merged.get_first_column());
+                let Some(shared) = RangeArea::from(merged).intersect(&area) else {
+                    continue;
+                };
+                let value = self.get_unmerged_cell_value(&merged.get_top_left());
+                shared
+                    .positions()
+                    .filter(|position| *position != top_left)
+                    .for_each(|(row, column)| {
+                        values[row - area.get_first_row()][column - area.get_first_column()] =
+                            value.clone()
+                    });
+            }
+        }
+        RangeValues::new(area, values)
+    }
+    /// Writes `f` of each formula of the cells, giving cells sharing a formula their own
+    /// when the shared one refers to `sheet_name`, as `f` may change them differently.
+    /// Cached values of rewritten formulas are left for Excel to calculate again.
+    fn rewrite_formulas(&mut self, sheet_name: &str, f: impl Fn(&str) -> String) {
+        let own = self.sheet_name.clone();
+        let cells = self.get_cell_positions(|c_node| c_node.search_node("f").is_some());
+        let formulas = cells
+            .iter()
+            .map(|(row, column)| to_cell_index(*row, *column))
+            .filter_map(|r| Some((self.get_formula(&r)?, r)))
+            .collect::<Vec<_>>();
+        let is_unshared = formulas.iter().any(|(formula, _)| {
+            matches!(formula.kind, FormulaKind::Shared { .. })
+                && refers_to_sheet(&formula.text, &own, sheet_name)
+        });
+        if is_unshared {
+            self.unshare_formulas();
+        }
+        for (formula, r) in formulas {
+            let text = f(&formula.text);
+            if text == formula.text || formula.is_shared_child() && !is_unshared {
+                continue;
+            }
+            if let Some(f_node) = self
+                .get_sheet_data_node_mut()
+                .search_child_by_id_mut("r", &r)
+                .and_then(|c_node| c_node.search_node_mut("f"))
+            {
+                f_node.change_text(escape(&text));
+            }
+            self.is_formula_changed = true;
+        }
+    }
+    /// Cells of the area with shared formulas written out as normal ones, and the merges inside it.
+    fn copy_cells(&self, area: &RangeArea) -> CopiedCells<'a> {
+        let cells = self
+            .get_sheet_data_node()
+            .search_all_nodes("row")
+            .unwrap_or_default()
+            .into_iter()
+            .flat_map(|row| row.search_all_nodes("c").unwrap_or_default())
+            .filter_map(|c_node| {
+                let index = CellIndex::new(c_node.search_element("r")?);
+                let (row, column) = (index.get_row_index(), index.get_column_index());
+                if !area.contains_position(row, column) {
+                    return None;
+                }
+                let mut c_node = c_node.clone();
+                let formula = self.get_formula(index.get_value());
+                if let (Some(f_node), Some(formula)) = (c_node.search_node_mut("f"), formula) {
+                    if let FormulaKind::Shared { .. } = formula.kind {
+                        let mut normal = XMLNode::new("f", NodeType::Element);
+                        normal.add_text(escape(&formula.text));
+                        *f_node = normal;
+                    }
+                }
+                Some((row, column, c_node))
+            })
+            .collect();
+        let merged_ranges = self
+            .get_merged_ranges()
+            .into_iter()
+            .filter(|range| area.contains_area(&RangeArea::from(*range)))
+            .collect();
+        CopiedCells {
+            area: *area,
+            cells,
+            merged_ranges,
+        }
+    }
+    /// Writes the copied cells over `destination`, moving the relative references of
+    /// their formulas when `is_relative`.
+    fn paste_cells(
+        &mut self,
+        copied: CopiedCells<'a>,
+        destination: &RangeArea,
+        is_relative: bool,
+    ) -> Option<RangeArea> {
+        let source = copied.area;
+        let repeat = |size: usize, source_size: usize| {
+            if size.is_multiple_of(source_size) {
+                size / source_size
             } else {
-                let mut v_child = XMLNode::new("v", NodeType::Element);
-                v_child.add_text(value);
-                cell.add_node(v_child);
-                cell.add_element("t", vec!["str"]);
-                cell.add_element("r", vec![index.get_value()]);
-                cell.set_node_type(NodeType::Element);
+                1
+            }
+        };
+        let row_repeat = repeat(destination.get_row_count(), source.get_row_count());
+        let column_repeat = repeat(destination.get_column_count(), source.get_column_count());
+        let pasted = RangeArea::new(&destination.get_top_left())?.resize(
+            source.get_row_count() * row_repeat,
+            source.get_column_count() * column_repeat,
+        )?;
+        self.clear_range(&pasted, ClearMode::All);
+        let mut merged_ranges = self.get_merged_ranges();
+        for (row_copy, column_copy) in (0..row_repeat)
+            .flat_map(|row_copy| (0..column_repeat).map(move |column_copy| (row_copy, column_copy)))
+        {
+            let rows = (pasted.get_first_row() + row_copy * source.get_row_count()) as isize
+                - source.get_first_row() as isize;
+            let columns = (pasted.get_first_column() + column_copy * source.get_column_count())
+                as isize
+                - source.get_first_column() as isize;
+            for (row, column, c_node) in copied.cells.iter() {
+                let r = to_cell_index(
+                    (*row as isize + rows) as usize,
+                    (*column as isize + columns) as usize,
+                );
+                self.paste_cell(c_node.clone(), &r, rows, columns, is_relative);
+            }
+            merged_ranges.extend(
+                copied
+                    .merged_ranges
+                    .iter()
+                    .filter_map(|range| range.get_area().offset(rows, columns))
+                    .map(MergedRange::from),
+            );
+        }
+        if !copied.merged_ranges.is_empty() {
+            self.write_merged_ranges(&merged_ranges);
+        }
+        Some(pasted)
+    }
+    fn paste_cell(
+        &mut self,
+        mut c_node: XMLNode<'a>,
+        r: &str,
+        rows: isize,
+        columns: isize,
+        is_relative: bool,
+    ) {
+        c_node.set_element("r", r.to_string());
+        if let Some(f_node) = c_node.search_node_mut("f") {
+            if is_relative {
+                let text = f_node
+                    .get_text()
+                    .map(|text| unescape(&text))
+                    .unwrap_or_default();
+                f_node.change_text(escape(&shift_references(&text, rows, columns)));
+            }
+            if let Some(range) = f_node
+                .search_element("ref")
+                .and_then(RangeArea::new)
+                .and_then(|area| area.offset(rows, columns))
+            {
+                f_node.set_element("ref", range.to_string());
+            }
+            self.is_formula_changed = true;
+        }
+        let index = CellIndex::new(r);
+        self.update_dimension(&index);
+        *self.get_or_create_cell_node(&index) = c_node;
+        self.changed_cells.push(r.to_string());
+    }
+    fn get_merged_range_count(&self) -> usize {
+        self.node
+            .search_node("worksheet")
+            .and_then(|worksheet| worksheet.search_node("mergeCells"))
+            .map(|merge_cells| merge_cells.get_child_nodes().unwrap_or_default().len())
+            .unwrap_or_default()
+    }
+    /// Gives every cell sharing a formula its own normal formula.
+    fn unshare_formulas(&mut self) {
+        let shared = self
+            .get_cell_positions(|c_node| {
+                c_node
+                    .search_node("f")
+                    .is_some_and(|f_node| f_node.search_element("t") == Some("shared"))
+            })
+            .into_iter()
+            .map(|(row, column)| to_cell_index(row, column))
+            .filter_map(|r| Some((self.get_formula(&r)?.text, r)))
+            .collect::<Vec<_>>();
+        for (text, r) in shared {
+            let Some(f_node) = self
+                .get_sheet_data_node_mut()
+                .search_child_by_id_mut("r", &r)
+                .and_then(|c_node| c_node.search_node_mut("f"))
+            else {
+                continue;
+            };
+            let mut normal = XMLNode::new("f", NodeType::Element);
+            normal.add_text(escape(&text));
+            *f_node = normal;
+        }
+    }
+    /// Renumbers `<row r>` and `<c r>`, dropping the deleted ones.
+    fn shift_cells(&mut self, shift: LineShift) {
+        let sheet_data = self.get_sheet_data_node_mut();
+        if shift.is_row() {
+            sheet_data.retain_nodes(|row| {
+                row.search_element("r")
+                    .and_then(|r| r.parse().ok())
+                    .is_none_or(|r| shift.move_line(r).is_some())
+            });
+        }
+        for row in sheet_data.search_all_nodes_mut("row").unwrap_or_default() {
+            if let Some(r) = row
+                .search_element("r")
+                .and_then(|r| r.parse().ok())
+                .and_then(|r| shift.move_line(r))
+            {
+                row.set_element("r", r.to_string());
             }
+            if !shift.is_row() {
+                row.retain_nodes(|c_node| {
+                    c_node
+                        .search_element("r")
+                        .is_none_or(|r| shift.move_cell(r).is_some())
+                });
+                let spans = row
+                    .search_element("spans")
+                    .and_then(|spans| spans.split_once(':'))
+                    .and_then(|(first, last)| Some((first.parse().ok()?, last.parse().ok()?)))
+                    .and_then(|(first, last)| shift.move_span(first, last));
+                match spans {
+                    Some((first, last)) => row.set_element("spans", format!("{}:{}", first, last)),
+                    None => row.remove_element("spans"),
+                }
+            }
+            for c_node in row.search_all_nodes_mut("c").unwrap_or_default() {
+                let Some(r) = c_node.search_element("r").and_then(|r| shift.move_cell(r)) else {
+                    continue;
+                };
+                c_node.set_element("r", r);
+                if let Some(f_node) = c_node.search_node_mut("f") {
+                    if let Some(range) = f_node
+                        .search_element("ref")
+                        .and_then(RangeArea::new)
+                        .and_then(|area| shift.move_area(&area))
+                    {
+                        f_node.set_element("ref", range.to_string());
+                    }
+                }
+            }
+        }
+    }
+    fn get_unmerged_cell_value(&self, cell_index: &str) -> CellValue {
+        let index = CellIndex::new(cell_index);
+        self.get_sheet_data_node()
+            .search_child_by_id("r", index.get_value())
+            .map(|c_node| self.get_cell_node_value(c_node))
+            .unwrap_or(CellValue::Empty)
+    }
+    /// The top-left cell of the merge containing the cell, when merged values are spread.
+    fn get_merged_top_left(&self, cell_index: &str) -> Option<String> {
+        if !self.is_merged_value_spread {
+            return None;
+        }
+        self.get_merged_range(cell_index)
+            .map(|range| range.get_top_left())
+            .filter(|top_left| top_left != cell_index)
+    }
+    /// Fills the merged columns of the row with the value of their top-left cell.
+    fn spread_merged_values<T: Clone>(
+        &self,
+        row_index: usize,
+        row: &mut Vec<T>,
+        empty: T,
+        get_top_left: impl Fn(&str) -> T,
+    ) {
+        if !self.is_merged_value_spread {
+            return;
+        }
+        for range in self.get_merged_ranges() {
+            if !(range.get_first_row()..=range.get_last_row()).contains(&row_index) {
+                continue;
+            }
+            if row.len() < range.get_last_column() {
+                row.resize(range.get_last_column(), empty.clone());
+            }
+            let top_left = range.get_top_left();
+            (range.get_first_column()..=range.get_last_column())
+                .filter(|column| {
+                    !(row_index == range.get_first_row() && *column == range.get_first_column())
+                })
+                .for_each(|column| row[column - 1] = get_top_left(&top_left));
+        }
+    }
+    /// Rewrites `<mergeCells>` with `ranges`, removing it when there are none.
+    fn write_merged_ranges(&mut self, ranges: &[MergedRange]) {
+        let worksheet = self.node.search_node_mut("worksheet").unwrap();
+        if ranges.is_empty() {
+            worksheet.remove_node("mergeCells");
+            return;
+        }
+        let mut merge_cells = XMLNode::new("mergeCells", NodeType::Element);
+        merge_cells.set_element("count", ranges.len().to_string());
+        for range in ranges {
+            let mut merge_cell = XMLNode::new("mergeCell", NodeType::SingleElement);
+            merge_cell.set_element("ref", range.to_string());
+            merge_cells.add_node(merge_cell);
+        }
+        if worksheet.search_node("mergeCells").is_some() {
+            worksheet.change_child_node(merge_cells);
             return;
         }
+        let position = worksheet
+            .get_child_nodes()
+            .unwrap_or_default()
+            .iter()
+            .rposition(|child| BEFORE_MERGE_CELLS.contains(&child.get_value()))
+            .map(|position| position + 1)
+            .unwrap_or_default();
+        worksheet.insert_node(position, merge_cells);
+    }
+}
+/// Returns the child whose key is `target`, inserting the one made by `create`
+/// before the first child with a larger key when there is none.
+fn find_or_insert_child<'a, 'b>(
+    parent: &'b mut XMLNode<'a>,
+    target: usize,
+    key: impl Fn(&XMLNode) -> usize,
+    create: impl FnOnce() -> XMLNode<'a>,
+) -> &'b mut XMLNode<'a> {
+    let keys = parent
+        .get_child_nodes()
+        .unwrap_or_default()
+        .into_iter()
+        .map(key)
+        .collect::<Vec<_>>();
+    let position = keys
+        .iter()
+        .position(|key| *key >= target)
+        .unwrap_or(keys.len());
+    if keys.get(position) != Some(&target) {
+        parent.insert_node(position, create());
+        parent.set_node_type(NodeType::Element);
     }
+    parent.get_child_nodes_mut().unwrap().swap_remove(position)
 }
 pub trait WorkSheet<'a> {
     fn get_cell(&self, cell_index: &str) -> Option<String>;
@@ -199,16 +1849,33 @@ pub trait WorkSheet<'a> {
     fn get_column(&self, s: &str) -> Vec<Option<String>>;
     fn get_all_cell(&self) -> Vec<Vec<Option<String>>>;
     fn get_column_range(&self, start: &str, end: &str) -> Vec<Vec<Option<String>>>;
+    fn get_cell_value(&self, cell_index: &str) -> CellValue;
+    fn get_row_values(&self, u: usize) -> Vec<CellValue>;
+    fn get_column_values(&self, s: &str) -> Vec<CellValue>;
+    fn get_all_cell_values(&self) -> Vec<Vec<CellValue>>;
+    fn get_column_range_values(&self, start: &str, end: &str) -> Vec<Vec<CellValue>>;
     fn set_cell(&mut self, cell: &'a ECell<'a>) -> ();
 }
 
 #[cfg(test)]
 mod xml_sheet_test {
+    use serde::{Deserialize, Serialize};
+
     use crate::excel::{
-        cells::cell::ECell,
+        cells::{
+            cell::ECell,
+            cell_range::{CellRange, RangeArea},
+            cell_value::{CellErrorKind, CellValue},
+            date_time::{ExcelDateTime, ExcelDuration},
+            formula::{CellFormula, FormulaKind},
+            line_shift::LineShift,
+            merged_range::{MergeError, MergedRange},
+        },
+        records::table_view::TableLayout,
         xmls::{
             shared_strings::SharedStringsInterface,
-            sheet::{Sheet, WorkSheet},
+            sheet::{ClearMode, Sheet, WorkSheet},
+            styles::Styles,
         },
     };
 
@@ -442,6 +2109,433 @@ mod xml_sheet_test {
         );
     }
     #[test]
+    fn get_cell_value_test() {
+        let mock = SharedStringsMock::new();
+        mock.add_shared_string("zero");
+        mock.add_shared_string("one");
+        mock.add_shared_string("two");
+        mock.add_shared_string("three");
+        mock.add_shared_string("four");
+        let sheet = Sheet::new("test", SOURCE1, &mock);
+        assert_eq!(
+            sheet.get_cell_value("B2"),
+            CellValue::String("zero".to_string())
+        );
+        assert_eq!(sheet.get_cell_value("F6"), CellValue::Number(50.0));
+        assert_eq!(sheet.get_cell_value("C2"), CellValue::Empty);
+        assert_eq!(sheet.get_cell_value("XX3"), CellValue::Empty);
+        assert_eq!(
+            sheet.get_row_values(3),
+            vec![
+                CellValue::Empty,
+                CellValue::Empty,
+                CellValue::String("three".to_string()),
+                CellValue::Empty,
+                CellValue::String("four".to_string()),
+                CellValue::Number(50.0),
+                CellValue::Empty,
+                CellValue::String("shared_value".to_string()),
+            ]
+        );
+        assert_eq!(sheet.get_row_values(10), vec![]);
+    }
+    #[test]
+    /// expect CellValue
+    /// | |A|B|C|D|E|F|G|
+    /// |-|-|-|-|-|-|-|-|
+    /// |1|a| | | | | | |
+    /// |2| |b| | | | | |
+    /// |3| | |c| | | | |
+    /// |4| | | |d| | | |
+    /// |5|あ| | | |e| |0|
+    fn get_all_cell_values_test() {
+        let shareds = SharedStringsMock::new();
+        shareds.add_shared_string("あ");
+        let sheet = Sheet::new("test", SOURCE2, &shareds);
+        let all = sheet.get_all_cell_values();
+        assert_eq!(all.len(), 5);
+        assert!(all.iter().all(|row| row.len() == 7));
+        assert_eq!(all[4][6], CellValue::Number(0.0));
+        assert_eq!(
+            sheet.get_column_values("A"),
+            vec![
+                CellValue::String("a".to_string()),
+                CellValue::Empty,
+                CellValue::Empty,
+                CellValue::Empty,
+                CellValue::String("あ".to_string())
+            ]
+        );
+        assert_eq!(
+            sheet.get_column_range_values("F", "H"),
+            vec![
+                vec![CellValue::Empty, CellValue::Empty, CellValue::Empty],
+                vec![CellValue::Empty, CellValue::Empty, CellValue::Empty],
+                vec![CellValue::Empty, CellValue::Empty, CellValue::Empty],
+                vec![CellValue::Empty, CellValue::Empty, CellValue::Empty],
+                vec![CellValue::Empty, CellValue::Number(0.0), CellValue::Empty],
+            ]
+        );
+    }
+    #[test]
+    fn deserialize_rows_test() {
+        #[derive(Debug, PartialEq, Deserialize)]
+        struct Item {
+            #[serde(rename = "Name")]
+            name: String,
+            #[serde(rename = "Unit Price")]
+            price: f64,
+            #[serde(rename = "Stock")]
+            stock: Option<u32>,
+        }
+        let shareds = SharedStringsMock::new();
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData>
+<row r="1"><c r="A1" t="inlineStr"><is><t>Items</t></is></c></row>
+<row r="2"><c r="B2" t="inlineStr"><is><t>Name</t></is></c><c r="C2" t="inlineStr"><is><t>Unit Price</t></is></c><c r="D2" t="inlineStr"><is><t>Stock</t></is></c></row>
+<row r="3"><c r="B3" t="inlineStr"><is><t>pen</t></is></c><c r="C3"><v>1.5</v></c><c r="D3"><v>10</v></c></row>
+<row r="5"><c r="B5" t="inlineStr"><is><t>ink</t></is></c><c r="C5" t="inlineStr"><is><t>3</t></is></c></row>
+<row r="6"><c r="B6" t="inlineStr"><is><t>pad</t></is></c><c r="C6" t="inlineStr"><is><t>abc</t></is></c></row>
+</sheetData></worksheet>"#;
+        let sheet = Sheet::new("test", source, &shareds);
+        let items: Vec<Item> = sheet
+            .deserialize_rows(2, &RangeArea::new("B1:D5").unwrap())
+            .unwrap();
+        assert_eq!(
+            items,
+            vec![
+                Item {
+                    name: "pen".to_string(),
+                    price: 1.5,
+                    stock: Some(10)
+                },
+                Item {
+                    name: "ink".to_string(),
+                    price: 3.0,
+                    stock: None
+                },
+            ]
+        );
+        let error = sheet
+            .deserialize_rows::<Item>(2, &RangeArea::new("B:D").unwrap())
+            .unwrap_err();
+        assert_eq!(error.to_string(), "C6: expected number, got 'abc'");
+    }
+    #[test]
+    fn get_table_view_test() {
+        let shareds = SharedStringsMock::new();
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData>
+<row r="1"><c r="A1" t="inlineStr"><is><t>詳細画面レイアウト</t></is></c></row>
+<row r="2"><c r="B2" t="inlineStr"><is><t>会社名</t></is></c><c r="C2" t="inlineStr"><is><t>社員数</t></is></c><c r="E2" t="inlineStr"><is><t>会社名</t></is></c></row>
+<row r="3"><c r="B3" t="inlineStr"><is><t>A社</t></is></c><c r="C3"><v>10</v></c></row>
+<row r="4"><c r="B4" t="inlineStr"><is><t>B社</t></is></c><c r="C4"><v>20</v></c><c r="E4" t="inlineStr"><is><t>b</t></is></c></row>
+<row r="5"><c r="B5" t="inlineStr"><is><t>END</t></is></c></row>
+<row r="6"><c r="D6" t="inlineStr"><is><t> </t></is></c></row>
+<row r="7"><c r="B7" t="inlineStr"><is><t>C社</t></is></c></row>
+</sheetData></worksheet>"#;
+        let sheet = Sheet::new("test", source, &shareds);
+
+        let table = sheet.get_table_view(&TableLayout::new()).unwrap();
+        assert_eq!(table.get_headers(), ["会社名", "社員数", "D", "会社名_2"]);
+        assert_eq!(table.get_area().to_string(), "B2:E5");
+        assert_eq!(
+            table.get_column("社員数").unwrap(),
+            vec![
+                &CellValue::Number(10.0),
+                &CellValue::Number(20.0),
+                &CellValue::Empty
+            ]
+        );
+        assert_eq!(
+            table.get_row(1).unwrap().get("会社名_2"),
+            Some(&CellValue::InlineString("b".to_string()))
+        );
+
+        let table = sheet
+            .get_table_view(&TableLayout::new().with_end_marker("END"))
+            .unwrap();
+        assert_eq!(table.get_row_count(), 2);
+
+        let table = sheet
+            .get_table_view(&TableLayout::new().with_header_row(1).with_columns(1, 2))
+            .unwrap();
+        assert_eq!(table.get_headers(), ["詳細画面レイアウト", "B"]);
+        assert_eq!(
+            table.get_column("B").unwrap(),
+            vec![
+                &CellValue::InlineString("会社名".to_string()),
+                &CellValue::InlineString("A社".to_string()),
+                &CellValue::InlineString("B社".to_string()),
+                &CellValue::InlineString("END".to_string()),
+            ]
+        );
+        assert_eq!(
+            sheet.get_table_view(&TableLayout::new().with_header_row(9)),
+            None
+        );
+    }
+    #[test]
+    fn serialize_rows_test() {
+        #[derive(Serialize)]
+        struct Item {
+            #[serde(rename = "Name")]
+            name: String,
+            #[serde(rename = "Unit Price")]
+            price: f64,
+            #[serde(rename = "Stock")]
+            stock: Option<u32>,
+            #[serde(rename = "Since")]
+            since: ExcelDateTime,
+        }
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<dimension ref="C3:D3"/>
+<sheetFormatPr defaultRowHeight="15"/>
+<cols><col min="2" max="4" width="9" style="2"/></cols>
+<sheetData>
+<row r="3"><c r="C3"><v>100</v></c><c r="D3"><v>7</v></c></row>
+</sheetData>
+</worksheet>"#;
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", source, &shareds);
+        let items = vec![
+            Item {
+                name: "pen".to_string(),
+                price: 1.5,
+                stock: None,
+                since: ExcelDateTime::new(2023, 1, 2, 0, 0, 0),
+            },
+            Item {
+                name: "notebook".to_string(),
+                price: 12.0,
+                stock: Some(3),
+                since: ExcelDateTime::new(2023, 1, 3, 0, 0, 0),
+            },
+        ];
+        let area = sheet.serialize_rows("B2", &items).unwrap().unwrap();
+        assert_eq!(area.to_string(), "B2:E4");
+        assert_eq!(
+            sheet.get_row_values(2)[1..],
+            [
+                CellValue::String("Name".to_string()),
+                CellValue::String("Unit Price".to_string()),
+                CellValue::String("Stock".to_string()),
+                CellValue::String("Since".to_string()),
+            ]
+        );
+        assert_eq!(sheet.get_cell_value("C3"), CellValue::Number(1.5));
+        assert_eq!(sheet.get_cell_value("D3"), CellValue::Empty);
+        assert_eq!(sheet.get_cell_value("D4"), CellValue::Number(3.0));
+        assert_eq!(sheet.get_cell_value("E4"), CellValue::Number(44929.0));
+        assert_eq!(shareds.get_shared_string(4), "pen");
+
+        sheet.set_cell_style(&area.resize(1, area.get_column_count()).unwrap(), 1);
+        sheet.fit_column_widths(&area);
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="B2:E4"/>"#));
+        assert!(xml.contains(r#"<c r="B2" t="s" s="1"><v>0</v></c>"#));
+        assert!(xml.contains(r#"<c r="D3"></c>"#));
+        assert!(xml.contains(concat!(
+            r#"<sheetFormatPr defaultRowHeight="15"/><cols>"#,
+            r#"<col min="2" max="2" width="10" style="2" customWidth="1"/>"#,
+            r#"<col min="3" max="3" width="12" style="2" customWidth="1"/>"#,
+            r#"<col min="4" max="4" width="7" style="2" customWidth="1"/>"#,
+            r#"<col min="5" max="5" width="7" customWidth="1"/></cols>"#
+        )));
+
+        let error = sheet.serialize_rows("A10", &[(1.0, f64::NAN)]).unwrap_err();
+        assert_eq!(error.to_string(), "B11: cannot write NaN to a cell");
+        assert_eq!(sheet.get_cell_value("A11"), CellValue::Empty);
+        assert_eq!(sheet.serialize_rows::<Item>("A1", &[]), Ok(None));
+
+        let styles = Styles::new(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<cellXfs count="3"><xf numFmtId="0" xfId="0"/><xf numFmtId="4" xfId="0"/><xf numFmtId="14" xfId="0"/></cellXfs>
+</styleSheet>"#,
+        );
+        let source = source.replace(r#"<c r="D3">"#, r#"<c r="D3" s="1">"#);
+        let mut sheet = Sheet::new("test", &source, &shareds).with_styles(&styles);
+        let date = ExcelDateTime::new(2023, 1, 2, 0, 0, 0);
+        sheet.serialize_rows("B2", &[(date, date, 2.0)]).unwrap();
+        assert_eq!(sheet.get_cell_value("B3"), CellValue::DateTime(date));
+        assert_eq!(sheet.get_cell_value("C3"), CellValue::DateTime(date));
+        assert_eq!(sheet.get_cell_value("D3"), CellValue::Number(2.0));
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<c r="D3" s="1"><v>2</v></c>"#));
+    }
+    #[test]
+    fn get_range_test() {
+        let shareds = SharedStringsMock::new();
+        shareds.add_shared_string("あ");
+        let sheet = Sheet::new("test", SOURCE2, &shareds);
+        let blocks = sheet.get_range(&CellRange::new("C3:D4,E:E,5:5").unwrap());
+        assert_eq!(blocks[0].get_origin(), "C3");
+        assert_eq!(
+            blocks[0].get_values(),
+            &vec![
+                vec![CellValue::String("c".to_string()), CellValue::Empty],
+                vec![CellValue::Empty, CellValue::String("d".to_string())],
+            ]
+        );
+        assert_eq!(
+            blocks[0].get_value("D4"),
+            Some(&CellValue::String("d".to_string()))
+        );
+        assert_eq!(blocks[0].get_value("B4"), None);
+        assert_eq!(blocks[1].get_area().to_string(), "E1:E5");
+        assert_eq!(
+            blocks[1].get_values()[4],
+            vec![CellValue::String("e".to_string())]
+        );
+        assert_eq!(blocks[2].get_area().to_string(), "A5:G5");
+        assert_eq!(
+            blocks[2].get_values()[0][0],
+            CellValue::String("あ".to_string())
+        );
+
+        let sheet = Sheet::new("test", MERGE_SHEET, &shareds).with_merged_values(true);
+        let blocks = sheet.get_range(&CellRange::new("B1:C2").unwrap());
+        assert_eq!(
+            blocks[0].clone().into_values(),
+            vec![
+                vec![CellValue::Number(1.0), CellValue::Number(3.0)],
+                vec![CellValue::Number(5.0), CellValue::Number(5.0)],
+            ]
+        );
+    }
+    #[test]
+    fn get_cell_format_test() {
+        let styles = Styles::new(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<numFmts count="1"><numFmt numFmtId="176" formatCode="0.0"/></numFmts>
+<fonts count="2"><font><sz val="11"/><name val="游ゴシック"/></font><font><b/><sz val="11"/><name val="游ゴシック"/></font></fonts>
+<fills count="1"><fill><patternFill patternType="none"/></fill></fills>
+<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>
+<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>
+<cellXfs count="2"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="176" fontId="1" fillId="0" borderId="0" xfId="0"/></cellXfs>
+</styleSheet>"#,
+        );
+        let shareds = SharedStringsMock::new();
+        let source = SOURCE2.replace(r#"<c r="B2">"#, r#"<c r="B2" s="1">"#);
+        let sheet = Sheet::new("test", &source, &shareds);
+        assert_eq!(sheet.get_cell_format("B2"), None);
+
+        let sheet = sheet.with_styles(&styles);
+        let format = sheet.get_cell_format("B2").unwrap();
+        assert!(format.font.bold);
+        assert_eq!(format.number_format.code, "0.0");
+        let format = sheet.get_cell_format("A1").unwrap();
+        assert!(!format.font.bold);
+        assert_eq!(format.number_format.code, "General");
+        assert_eq!(sheet.get_cell_format("Z99"), sheet.get_cell_format("A1"));
+    }
+    #[test]
+    fn get_cell_value_date_test() {
+        let styles = Styles::new(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<numFmts count="1"><numFmt numFmtId="176" formatCode="yyyy/mm/dd"/></numFmts>
+<fonts count="1"><font><sz val="11"/><name val="游ゴシック"/></font></fonts>
+<fills count="1"><fill><patternFill patternType="none"/></fill></fills>
+<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>
+<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>
+<cellXfs count="4"><xf numFmtId="0" xfId="0"/><xf numFmtId="176" xfId="0"/><xf numFmtId="20" xfId="0"/><xf numFmtId="46" xfId="0"/></cellXfs>
+</styleSheet>"#,
+        );
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<sheetData>
+<row r="1">
+<c r="A1" s="1"><v>44927</v></c>
+<c r="B1" s="2"><v>0.375</v></c>
+<c r="C1" s="3"><v>1.5</v></c>
+<c r="D1"><v>44927</v></c>
+</row>
+</sheetData>
+</worksheet>"#;
+        let shareds = SharedStringsMock::new();
+        let sheet = Sheet::new("test", source, &shareds);
+        assert_eq!(sheet.get_cell_value("A1"), CellValue::Number(44927.0));
+
+        let sheet = sheet.with_styles(&styles);
+        assert_eq!(
+            sheet.get_row_values(1),
+            vec![
+                CellValue::DateTime(ExcelDateTime::new(2023, 1, 1, 0, 0, 0)),
+                CellValue::DateTime(ExcelDateTime::new(1899, 12, 31, 9, 0, 0)),
+                CellValue::Duration(ExcelDuration::from_milliseconds(36 * 3_600_000)),
+                CellValue::Number(44927.0),
+            ]
+        );
+        let sheet = sheet.with_date1904(true);
+        assert_eq!(
+            sheet.get_cell_value("A1"),
+            CellValue::DateTime(ExcelDateTime::new(2027, 1, 2, 0, 0, 0))
+        );
+    }
+    #[test]
+    fn get_formatted_cell_test() {
+        let styles = Styles::new(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<numFmts count="1"><numFmt numFmtId="176" formatCode="yyyy&quot;年&quot;m&quot;月&quot;d&quot;日&quot;"/></numFmts>
+<cellXfs count="4"><xf numFmtId="0" xfId="0"/><xf numFmtId="4" xfId="0"/><xf numFmtId="176" xfId="0"/><xf numFmtId="10" xfId="0"/></cellXfs>
+</styleSheet>"#,
+        );
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<sheetData>
+<row r="1">
+<c r="A1" s="1"><v>1234.5</v></c>
+<c r="B1" s="2"><v>44927</v></c>
+<c r="C1" s="3"><v>0.125</v></c>
+<c r="D1" t="b"><v>1</v></c>
+<c r="E1"><v>0.1</v></c>
+</row>
+</sheetData>
+</worksheet>"#;
+        let shareds = SharedStringsMock::new();
+        let sheet = Sheet::new("test", source, &shareds);
+        assert_eq!(sheet.get_formatted_cell("A1"), "1234.5");
+
+        let sheet = sheet.with_styles(&styles);
+        assert_eq!(sheet.get_formatted_cell("A1"), "1,234.50");
+        assert_eq!(sheet.get_formatted_cell("B1"), "2023年1月1日");
+        assert_eq!(sheet.get_formatted_cell("C1"), "12.50%");
+        assert_eq!(sheet.get_formatted_cell("D1"), "TRUE");
+        assert_eq!(sheet.get_formatted_cell("E1"), "0.1");
+        assert_eq!(sheet.get_formatted_cell("Z9"), "");
+    }
+    #[test]
+    fn get_rich_text_test() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<sheetData>
+<row r="1">
+<c r="A1" t="s"><v>0</v></c>
+<c r="B1" t="inlineStr"><is><r><t>会社</t></r><r><rPr><b/><sz val="11"/><rFont val="游ゴシック"/></rPr><t>名</t></r><rPh sb="0" eb="2"><t>カイシャ</t></rPh></is></c>
+<c r="C1"><v>1</v></c>
+</row>
+</sheetData>
+</worksheet>"#;
+        let shareds = SharedStringsMock::new();
+        shareds.add_shared_string("plain");
+        let sheet = Sheet::new("test", source, &shareds);
+        assert_eq!(sheet.get_rich_text("A1").unwrap().get_text(), "plain");
+        let rich_text = sheet.get_rich_text("B1").unwrap();
+        assert_eq!(rich_text.get_text(), "会社名");
+        assert_eq!(rich_text.get_reading(), "カイシャ名");
+        let font = rich_text.get_runs()[1].font.as_ref().unwrap();
+        assert!(font.bold);
+        assert_eq!(font.name, Some("游ゴシック".to_string()));
+        assert_eq!(rich_text.get_runs()[0].font, None);
+        assert_eq!(sheet.get_rich_text("C1"), None);
+        assert_eq!(sheet.get_rich_text("D1"), None);
+    }
+    #[test]
     fn get_max_column_index_test() {
         let mut shareds = SharedStringsMock::new();
         shareds.add_shared_string("あ");
@@ -466,31 +2560,574 @@ mod xml_sheet_test {
             ]
         )
     }
+    #[test]
+    fn set_cell_create_test() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<dimension ref="B2:C3"/>
+<sheetData>
+<row r="2" spans="2:3"><c r="B2"><v>1</v></c><c r="C2" s="1"/></row>
+<row r="3" spans="2:3"><c r="C3"><v>2</v></c></row>
+</sheetData>
+</worksheet>"#;
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", source, &shareds);
+        let cells = [
+            ECell::new("a", "A3"),
+            ECell::new("b", "E1"),
+            ECell::new("c", "D5"),
+            ECell::new("d", "C2"),
+            ECell::new("e", "AA4"),
+        ];
+        cells.iter().for_each(|cell| sheet.set_cell(cell));
+        assert_eq!(sheet.get_cell("A3"), Some("a".to_string()));
+        assert_eq!(sheet.get_cell("E1"), Some("b".to_string()));
+        assert_eq!(sheet.get_cell("D5"), Some("c".to_string()));
+        assert_eq!(sheet.get_cell("C2"), Some("d".to_string()));
+        assert_eq!(sheet.get_cell("B2"), Some("1".to_string()));
+        assert_eq!(sheet.get_all_row_index(), vec![1, 2, 3, 4, 5]);
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="A1:AA5"/>"#));
+        assert!(xml.contains(
+            r#"<row r="3" spans="1:3"><c r="A3" t="s"><v>0</v></c><c r="C3"><v>2</v></c></row>"#
+        ));
+        assert!(xml.contains(r#"<c r="C2" s="1" t="s"><v>3</v></c>"#));
+        assert!(xml.contains(r#"<row r="1" spans="5:5"><c r="E1" t="s"><v>1</v></c></row>"#));
+        assert!(xml.contains(r#"<row r="4" spans="27:27"><c r="AA4" t="s"><v>4</v></c></row>"#));
+        assert_eq!(shareds.get_shared_string(2), "c");
+    }
+    #[test]
+    fn set_cell_empty_sheet_test() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<dimension ref="A1"/>
+<sheetData/>
+</worksheet>"#;
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", source, &shareds);
+        let cell = ECell::new("x", "C4");
+        sheet.set_cell(&cell);
+        assert_eq!(sheet.get_cell("C4"), Some("x".to_string()));
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="C4"/>"#));
+        assert!(xml.contains(r#"<sheetData><row r="4" spans="3:3"><c r="C4" t="s"><v>0</v></c>"#));
+    }
+    const FORMULA_SHEET: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<dimension ref="A1:D3"/>
+<sheetData>
+<row r="1" spans="1:4"><c r="A1"><v>1</v></c><c r="B1"><f t="shared" ref="B1:B3" si="0">A1*2</f><v>2</v></c><c r="C1"><f t="array" ref="C1:C3">A1:A3*B1:B3</f><v>2</v></c><c r="D1" t="str"><f>$E$3&amp;G4</f><v>x</v></c></row>
+<row r="2" spans="1:2"><c r="A2"><v>2</v></c><c r="B2"><f t="shared" si="0"/><v>4</v></c></row>
+<row r="3" spans="1:2"><c r="A3"><v>3</v></c><c r="B3"><f t="shared" si="0"/><v>6</v></c></row>
+</sheetData>
+</worksheet>"#;
+    #[test]
+    fn get_formula_test() {
+        let shareds = SharedStringsMock::new();
+        let sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        assert_eq!(sheet.get_formula("D1"), Some(CellFormula::new("$E$3&G4")));
+        assert_eq!(sheet.get_formula("A1"), None);
+        assert_eq!(
+            sheet.get_formula("B1").unwrap().kind,
+            FormulaKind::Shared {
+                index: 0,
+                range: Some("B1:B3".to_string())
+            }
+        );
+        let child = sheet.get_formula("B3").unwrap();
+        assert_eq!(child.text, "A3*2");
+        assert_eq!(
+            child.kind,
+            FormulaKind::Shared {
+                index: 0,
+                range: None
+            }
+        );
+        assert_eq!(
+            sheet.get_formula("C1").unwrap().kind,
+            FormulaKind::Array {
+                range: "C1:C3".to_string()
+            }
+        );
+    }
+    #[test]
+    fn set_formula_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        assert!(!sheet.is_formula_changed());
+        sheet.set_formula("D1", "=A1&\"<\"");
+        sheet.set_formula("E2", "SUM(A1:A3)");
+        assert!(sheet.is_formula_changed());
+        assert_eq!(sheet.get_formula("D1"), Some(CellFormula::new("A1&\"<\"")));
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<c r="D1"><f>A1&amp;&quot;&lt;&quot;</f></c>"#));
+        assert!(xml.contains(r#"<c r="E2"><f>SUM(A1:A3)</f></c>"#));
+        assert!(xml.contains(r#"<dimension ref="A1:E3"/>"#));
+    }
+    #[test]
+    fn set_shared_formula_master_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        sheet.set_formula("B1", "A1*10");
+        assert_eq!(sheet.get_formula("B1"), Some(CellFormula::new("A1*10")));
+        assert_eq!(sheet.get_formula("B2"), Some(CellFormula::new("A2*2")));
+        assert_eq!(sheet.get_formula("B3"), Some(CellFormula::new("A3*2")));
+        let cell = ECell::new("x", "B2");
+        sheet.set_cell(&cell);
+        assert_eq!(sheet.get_formula("B2"), None);
+        assert_eq!(sheet.get_formula("B3"), Some(CellFormula::new("A3*2")));
+    }
+    #[test]
+    fn calculate_cell_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        assert_eq!(sheet.calculate_cell("B3"), CellValue::Number(6.0));
+        assert_eq!(sheet.calculate_cell("A2"), CellValue::Number(2.0));
+        assert_eq!(sheet.calculate_cell("D1"), CellValue::String(String::new()));
+        sheet.set_formula("B1", "=SUM(A:A)+TEST!B3");
+        assert_eq!(sheet.calculate_cell("B1"), CellValue::Number(12.0));
+        sheet.set_formula("E1", "Other!A1");
+        assert_eq!(
+            sheet.calculate_cell("E1"),
+            CellValue::Error(CellErrorKind::Ref)
+        );
+    }
+    #[test]
+    fn set_formula_value_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        sheet.set_formula_value("B2", &CellValue::Number(2.5));
+        sheet.set_formula_value("D1", &CellValue::Error(CellErrorKind::NA));
+        sheet.set_formula_value("B3", &CellValue::String("a&b".to_string()));
+        sheet.set_formula_value("A1", &CellValue::Number(100.0));
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<c r="B2"><f t="shared" si="0"/><v>2.5</v></c>"#));
+        assert!(xml.contains(r#"<c r="D1" t="e"><f>$E$3&amp;G4</f><v>#N/A</v></c>"#));
+        assert!(xml.contains(r#"<c r="B3" t="str"><f t="shared" si="0"/><v>a&amp;b</v></c>"#));
+        assert!(xml.contains(r#"<c r="A1"><v>1</v></c>"#));
+        assert!(sheet.get_changed_cells().is_empty());
+        let cell = ECell::new("x", "A2");
+        sheet.set_cell(&cell);
+        sheet.set_formula("C2", "A2");
+        assert_eq!(
+            sheet
+                .get_changed_cells()
+                .iter()
+                .map(|cell| cell.to_string())
+                .collect::<Vec<_>>(),
+            vec!["test!A2", "test!C2"]
+        );
+    }
+    #[test]
+    fn formula_changed_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        let cell = ECell::new("x", "A1");
+        sheet.set_cell(&cell);
+        assert!(!sheet.is_formula_changed());
+        let cell = ECell::new("x", "D1");
+        sheet.set_cell(&cell);
+        assert!(sheet.is_formula_changed());
+
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        sheet.clear_range(&RangeArea::new("A1:A3").unwrap(), ClearMode::All);
+        assert!(!sheet.is_formula_changed());
+        sheet.clear_range(&RangeArea::new("C1").unwrap(), ClearMode::Formats);
+        assert!(!sheet.is_formula_changed());
+        sheet.clear_range(&RangeArea::new("C1").unwrap(), ClearMode::Contents);
+        assert!(sheet.is_formula_changed());
+
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        sheet.move_range(&RangeArea::new("D1").unwrap(), "E1");
+        assert!(sheet.is_formula_changed());
+
+        let mut sheet = Sheet::new("test", FORMULA_SHEET, &shareds);
+        sheet.insert_rows(1, 1);
+        assert!(sheet.is_formula_changed());
+    }
+    #[test]
+    fn formula_whitespace_test() {
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<sheetData>
+<row r="1"><c r="A1"><v>1</v></c><c r="B1" t="str"><f>"a  b"&amp;A1&amp;" "</f><v>a  b1 </v></c></row>
+</sheetData>
+</worksheet>"#;
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", source, &shareds);
+        assert_eq!(
+            sheet.get_formula("B1"),
+            Some(CellFormula::new(r#""a  b"&A1&" ""#))
+        );
+        sheet.insert_rows(1, 1);
+        assert_eq!(
+            sheet.get_formula("B2"),
+            Some(CellFormula::new(r#""a  b"&A2&" ""#))
+        );
+        assert!(sheet
+            .to_xml()
+            .contains(r#"<f>&quot;a  b&quot;&amp;A2&amp;&quot; &quot;</f>"#));
+    }
+    const MERGE_SHEET: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<dimension ref="A1:C3"/>
+<sheetData>
+<row r="1" spans="1:3"><c r="A1"><v>1</v></c><c r="C1"><v>3</v></c></row>
+<row r="2" spans="1:3"><c r="A2"><v>4</v></c><c r="B2"><v>5</v></c></row>
+</sheetData>
+<mergeCells count="2"><mergeCell ref="A1:B1"/><mergeCell ref="B2:C3"/></mergeCells>
+<pageMargins left="0.7" right="0.7" top="0.75" bottom="0.75" header="0.3" footer="0.3"/>
+</worksheet>"#;
+    #[test]
+    fn get_merged_range_test() {
+        let shareds = SharedStringsMock::new();
+        let sheet = Sheet::new("test", MERGE_SHEET, &shareds);
+        assert_eq!(
+            sheet
+                .get_merged_ranges()
+                .iter()
+                .map(|range| range.to_string())
+                .collect::<Vec<_>>(),
+            vec!["A1:B1", "B2:C3"]
+        );
+        assert_eq!(sheet.get_merged_range("C3").unwrap().to_string(), "B2:C3");
+        assert_eq!(sheet.get_merged_range("A2"), None);
+        assert_eq!(sheet.get_cell("B1"), None);
+        assert_eq!(sheet.get_cell_value("C2"), CellValue::Empty);
+    }
+    #[test]
+    fn with_merged_values_test() {
+        let shareds = SharedStringsMock::new();
+        let sheet = Sheet::new("test", MERGE_SHEET, &shareds).with_merged_values(true);
+        assert_eq!(sheet.get_cell("B1"), Some("1".to_string()));
+        assert_eq!(sheet.get_cell_value("C3"), CellValue::Number(5.0));
+        assert_eq!(sheet.get_cell_value("A2"), CellValue::Number(4.0));
+        assert_eq!(
+            sheet.get_row(1),
+            vec![
+                Some("1".to_string()),
+                Some("1".to_string()),
+                Some("3".to_string())
+            ]
+        );
+        assert_eq!(
+            sheet.get_row_values(3),
+            vec![
+                CellValue::Empty,
+                CellValue::Number(5.0),
+                CellValue::Number(5.0)
+            ]
+        );
+        assert_eq!(sheet.calculate_cell("C3"), CellValue::Empty);
+    }
+    #[test]
+    fn add_merged_range_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", MERGE_SHEET, &shareds);
+        assert_eq!(
+            sheet.add_merged_range("C1:D2"),
+            Err(MergeError::Overlap {
+                range: MergedRange::new("C1:D2").unwrap(),
+                existing: MergedRange::new("B2:C3").unwrap()
+            })
+        );
+        assert_eq!(
+            sheet.add_merged_range("D4"),
+            Err(MergeError::SingleCell(MergedRange::new("D4").unwrap()))
+        );
+        assert_eq!(
+            sheet.add_merged_range("4D:E5"),
+            Err(MergeError::InvalidRange("4D:E5".to_string()))
+        );
+        assert_eq!(
+            sheet.add_merged_range("$E$5:D4").unwrap().to_string(),
+            "D4:E5"
+        );
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<mergeCells count="3"><mergeCell ref="A1:B1"/><mergeCell ref="B2:C3"/><mergeCell ref="D4:E5"/></mergeCells><pageMargins"#));
+        assert!(!sheet.remove_merged_range("A1:C1"));
+        assert!(sheet.remove_merged_range("A1:B1"));
+        assert!(sheet.remove_merged_range("B2:C3"));
+        assert!(sheet.remove_merged_range("D4:E5"));
+        assert!(!sheet.to_xml().contains("mergeCells"));
+
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData/><phoneticPr fontId="1"/></worksheet>"#;
+        let mut sheet = Sheet::new("test", source, &shareds);
+        sheet.add_merged_range("A1:A2").unwrap();
+        assert!(sheet.to_xml().contains(
+            r#"<sheetData/><mergeCells count="1"><mergeCell ref="A1:A2"/></mergeCells><phoneticPr"#
+        ));
+    }
+    const SHIFT_SHEET: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<dimension ref="A1:D4"/>
+<cols><col min="1" max="1" width="5"/><col min="2" max="3" width="9"/><col min="4" max="4" width="12"/></cols>
+<sheetData>
+<row r="1" spans="1:4"><c r="A1"><v>1</v></c><c r="B1"><v>2</v></c><c r="D1"><f>SUM(A1:B1)</f><v>3</v></c></row>
+<row r="2" spans="1:4"><c r="A2"><v>3</v></c><c r="B2"><f t="shared" ref="B2:B4" si="0">A2*$A$1</f><v>3</v></c></row>
+<row r="3" spans="1:2"><c r="A3"><v>4</v></c><c r="B3"><f t="shared" si="0"/><v>4</v></c></row>
+<row r="4" spans="1:4"><c r="A4"><f>SUM(A1:A3)</f><v>8</v></c><c r="B4"><f t="shared" si="0"/><v>8</v></c><c r="D4"><f>Other!A2+D1</f><v>0</v></c></row>
+</sheetData>
+<mergeCells count="2"><mergeCell ref="C2:D3"/><mergeCell ref="A5:B5"/></mergeCells>
+<conditionalFormatting sqref="A1:A4 D4"><cfRule type="expression" priority="1"><formula>$A1&gt;$A$2</formula></cfRule></conditionalFormatting>
+<conditionalFormatting sqref="C3"><cfRule type="cellIs" priority="2" operator="greaterThan"><formula>0</formula></cfRule></conditionalFormatting>
+<dataValidations count="1"><dataValidation type="list" sqref="B2:B4"><formula1>$A$1:$A$3</formula1></dataValidation></dataValidations>
+<hyperlinks><hyperlink ref="A3" r:id="rId1"/></hyperlinks>
+</worksheet>"#;
+    #[test]
+    fn insert_rows_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", SHIFT_SHEET, &shareds);
+        sheet.insert_rows(2, 1);
+        assert_eq!(sheet.get_cell_value("A3"), CellValue::Number(3.0));
+        assert_eq!(sheet.get_cell_value("A2"), CellValue::Empty);
+        assert_eq!(sheet.get_formula("B3"), Some(CellFormula::new("A3*$A$1")));
+        assert_eq!(sheet.get_formula("B5"), Some(CellFormula::new("A5*$A$1")));
+        assert_eq!(
+            sheet.get_formula("A5"),
+            Some(CellFormula::new("SUM(A1:A4)"))
+        );
+        assert_eq!(
+            sheet.get_formula("D5"),
+            Some(CellFormula::new("Other!A2+D1"))
+        );
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="A1:D5"/>"#));
+        assert!(xml.contains(r#"<row r="5" spans="1:4"><c r="A5"><f>SUM(A1:A4)</f><v>8</v></c>"#));
+        assert!(xml.contains(
+            r#"<mergeCells count="2"><mergeCell ref="C3:D4"/><mergeCell ref="A6:B6"/></mergeCells>"#
+        ));
+        assert!(xml.contains(r#"<conditionalFormatting sqref="A1:A5 D5"><cfRule type="expression" priority="1"><formula>$A1&gt;$A$3</formula>"#));
+        assert!(xml.contains(r#"<conditionalFormatting sqref="C4">"#));
+        assert!(xml.contains(
+            r#"<dataValidation type="list" sqref="B3:B5"><formula1>$A$1:$A$4</formula1>"#
+        ));
+        assert!(xml.contains(r#"<hyperlink ref="A4" r:id="rId1"/>"#));
+    }
+    #[test]
+    fn delete_columns_test() {
+        let shareds = SharedStringsMock::new();
+        let mut sheet = Sheet::new("test", SHIFT_SHEET, &shareds);
+        sheet.delete_columns(2, 2);
+        assert_eq!(
+            sheet.get_formula("B1"),
+            Some(CellFormula::new("SUM(A1:A1)"))
+        );
+        assert_eq!(
+            sheet.get_formula("B4"),
+            Some(CellFormula::new("Other!A2+B1"))
+        );
+        assert_eq!(sheet.get_row_values(2), vec![CellValue::Number(3.0)]);
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="A1:B4"/>"#));
+        assert!(xml.contains(
+            r#"<cols><col min="1" max="1" width="5"/><col min="2" max="2" width="12"/></cols>"#
+        ));
+        assert!(xml.contains(r#"<row r="3" spans="1:1"><c r="A3"><v>4</v></c></row>"#));
+        assert!(xml.contains(r#"<mergeCells count="1"><mergeCell ref="B2:B3"/></mergeCells>"#));
+        assert!(xml.contains(r#"<conditionalFormatting sqref="A1:A4 B4">"#));
+        assert!(!xml.contains(r#"<conditionalFormatting sqref="C3">"#));
+        assert!(!xml.contains("dataValidation"));
+        assert!(xml.contains(r#"<hyperlink ref="A3" r:id="rId1"/>"#));
+
+        sheet.delete_rows(1, 4);
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="A1"/>"#));
+        assert!(!xml.contains("<row"));
+        assert!(!xml.contains("mergeCells"));
+        assert!(!xml.contains("hyperlinks"));
+    }
+    #[test]
+    fn shift_formula_references_test() {
+        let shareds = SharedStringsMock::new();
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData>
+<row r="1"><c r="A1"><f>test!B2+SUM('test'!A:A)+B2+test!A1:A2</f></c><c r="C1"><f t="shared" ref="C1:C3" si="0">test!A1</f></c></row>
+<row r="2"><c r="C2"><f t="shared" si="0"/></c></row>
+<row r="3"><c r="C3"><f t="shared" si="0"/></c></row>
+</sheetData></worksheet>"#;
+        let mut sheet = Sheet::new("Other", source, &shareds);
+        sheet.shift_formula_references("Data", LineShift::InsertRows { at: 2, count: 1 });
+        assert!(!sheet.is_formula_changed());
+        sheet.shift_formula_references("Test", LineShift::InsertRows { at: 2, count: 1 });
+        assert!(sheet.is_formula_changed());
+        assert_eq!(
+            sheet.get_formula("A1"),
+            Some(CellFormula::new("test!B3+SUM('test'!A:A)+B2+test!A1:A3"))
+        );
+        assert_eq!(sheet.get_formula("C2"), Some(CellFormula::new("test!A3")));
+        assert_eq!(sheet.get_formula("C3"), Some(CellFormula::new("test!A4")));
+        sheet.shift_formula_references("test", LineShift::DeleteRows { at: 3, count: 1 });
+        assert_eq!(
+            sheet.get_formula("A1"),
+            Some(CellFormula::new("test!#REF!+SUM('test'!A:A)+B2+test!A1:A2"))
+        );
+    }
+    const COPY_SHEET: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><dimension ref="A1:C2"/><sheetData>
+<row r="1" spans="1:3"><c r="A1" s="1" t="s"><v>0</v></c><c r="B1" s="2"><v>10</v></c><c r="C1" s="3"><f>B1*$A$3+SUM(B$1:B1)</f><v>10</v></c></row>
+<row r="2" spans="1:3"><c r="A2" s="1"/><c r="B2"><f t="shared" ref="B2:C2" si="0">B1+1</f><v>11</v></c><c r="C2"><f t="shared" si="0"/><v>11</v></c></row>
+</sheetData><mergeCells count="1"><mergeCell ref="A1:A2"/></mergeCells></worksheet>"#;
+    #[test]
+    fn copy_range_test() {
+        let shareds = SharedStringsMock::new();
+        shareds.add_shared_string("title");
+        let mut sheet = Sheet::new("test", COPY_SHEET, &shareds);
+        let template = RangeArea::new("A1:C2").unwrap();
+        let pasted = sheet.copy_range(&template, &RangeArea::new("A3:C6").unwrap());
+        assert_eq!(pasted, RangeArea::new("A3:C6"));
+        assert_eq!(
+            sheet.get_cell_value("A5"),
+            CellValue::String("title".to_string())
+        );
+        assert_eq!(
+            sheet.get_formula("C3"),
+            Some(CellFormula::new("B3*$A$3+SUM(B$1:B3)"))
+        );
+        assert_eq!(
+            sheet.get_formula("C5"),
+            Some(CellFormula::new("B5*$A$3+SUM(B$1:B5)"))
+        );
+        assert_eq!(sheet.get_formula("B4"), Some(CellFormula::new("B3+1")));
+        assert_eq!(sheet.get_formula("C6"), Some(CellFormula::new("C5+1")));
+        assert!(sheet.is_formula_changed());
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="A1:C6"/>"#));
+        assert!(xml.contains(r#"<c r="A3" s="1" t="s"><v>0</v></c><c r="B3" s="2"><v>10</v></c>"#));
+        assert!(xml.contains(r#"<c r="C4"><f>C3+1</f><v>11</v></c>"#));
+        assert!(xml.contains(r#"<mergeCells count="3"><mergeCell ref="A1:A2"/><mergeCell ref="A3:A4"/><mergeCell ref="A5:A6"/></mergeCells>"#));
+
+        let pasted = sheet.copy_range(&template, &RangeArea::new("E5:F7").unwrap());
+        assert_eq!(pasted, RangeArea::new("E5:G6"));
+        assert_eq!(sheet.get_formula("F6"), Some(CellFormula::new("F5+1")));
+        assert_eq!(
+            sheet.copy_range(&template, &RangeArea::new("XFD1").unwrap()),
+            None
+        );
+        sheet.copy_range(
+            &RangeArea::new("D1:D2").unwrap(),
+            &RangeArea::new("B1:B2").unwrap(),
+        );
+        assert_eq!(sheet.get_cell_value("B1"), CellValue::Empty);
+        assert_eq!(sheet.get_formula("B2"), None);
+    }
+    #[test]
+    fn copy_range_from_test() {
+        let shareds = SharedStringsMock::new();
+        shareds.add_shared_string("title");
+        let template = Sheet::new("Template", COPY_SHEET, &shareds);
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><dimension ref="A1"/><sheetData/></worksheet>"#;
+        let mut sheet = Sheet::new("Report", source, &shareds);
+        sheet.copy_range_from(
+            &template,
+            &RangeArea::new("A1:C2").unwrap(),
+            &RangeArea::new("B2").unwrap(),
+        );
+        assert_eq!(
+            sheet.get_cell_value("B2"),
+            CellValue::String("title".to_string())
+        );
+        assert_eq!(
+            sheet.get_formula("D2"),
+            Some(CellFormula::new("C2*$A$3+SUM(C$1:C2)"))
+        );
+        assert_eq!(sheet.get_merged_range("B3").unwrap().to_string(), "B2:B3");
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<dimension ref="B2:D3"/>"#));
+        assert!(xml.contains(r#"<c r="C2" s="2"><v>10</v></c>"#));
+    }
+    #[test]
+    fn clear_range_test() {
+        let shareds = SharedStringsMock::new();
+        shareds.add_shared_string("title");
+        let mut sheet = Sheet::new("test", COPY_SHEET, &shareds);
+        sheet.clear_range(&RangeArea::new("A1:C1").unwrap(), ClearMode::Contents);
+        assert_eq!(sheet.get_cell_value("A1"), CellValue::Empty);
+        assert_eq!(sheet.get_formula("C1"), None);
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<c r="A1" s="1">"#));
+        assert!(xml.contains(r#"<c r="C1" s="3">"#));
+        assert!(xml.contains("mergeCells"));
+        sheet.clear_range(&RangeArea::new("A2:B2").unwrap(), ClearMode::Formats);
+        assert_eq!(sheet.get_formula("B2").unwrap().text, "B1+1");
+        let xml = sheet.to_xml();
+        assert!(xml.contains(r#"<c r="A2"/>"#));
+        assert!(!xml.contains("mergeCells"));
+        sheet.clear_range(&RangeArea::new("B2").unwrap(), ClearMode::All);
+        assert_eq!(sheet.get_formula("C2"), Some(CellFormula::new("C1+1")));
+        assert!(!sheet.to_xml().contains(r#"<c r="B2""#));
+        assert_eq!(sheet.get_changed_cells().len(), 4);
+    }
+    #[test]
+    fn move_range_test() {
+        let shareds = SharedStringsMock::new();
+        let source = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet><sheetData>
+<row r="1"><c r="A1"><v>1</v></c><c r="B1"><f>SUM(A1:A2)</f><v>3</v></c><c r="C1"><f>B1+$A$1</f><v>4</v></c></row>
+<row r="2"><c r="A2"><v>2</v></c><c r="B2"><f>A1*2+A1:B1</f><v>2</v></c></row>
+</sheetData></worksheet>"#;
+        let mut sheet = Sheet::new("test", source, &shareds);
+        let moved = sheet.move_range(&RangeArea::new("A1:A2").unwrap(), "D3");
+        assert_eq!(moved, RangeArea::new("D3:D4"));
+        assert_eq!(sheet.get_cell_value("D4"), CellValue::Number(2.0));
+        assert_eq!(sheet.get_cell_value("A1"), CellValue::Empty);
+        assert_eq!(
+            sheet.get_formula("B1"),
+            Some(CellFormula::new("SUM(D3:D4)"))
+        );
+        assert_eq!(sheet.get_formula("C1"), Some(CellFormula::new("B1+$D$3")));
+        assert_eq!(
+            sheet.get_formula("B2"),
+            Some(CellFormula::new("D3*2+A1:B1"))
+        );
+        sheet.move_range(&RangeArea::new("B1").unwrap(), "E1");
+        assert_eq!(
+            sheet.get_formula("E1"),
+            Some(CellFormula::new("SUM(D3:D4)"))
+        );
+        assert_eq!(sheet.get_formula("C1"), Some(CellFormula::new("E1+$D$3")));
+        assert_eq!(
+            sheet.move_range(&RangeArea::new("E1").unwrap(), "XFD1"),
+            RangeArea::new("XFD1")
+        );
+        assert_eq!(
+            sheet.move_range(&RangeArea::new("XFD1:XFD2").unwrap(), "XFD1048576"),
+            None
+        );
+    }
 }
 
 mod mock_shared_strings {
+    use std::cell::{Ref, RefCell};
+
     use crate::excel::xmls::shared_strings::SharedStringsInterface;
 
     pub struct SharedStringsMock {
-        values: Vec<String>,
+        values: RefCell<Vec<String>>,
     }
     impl SharedStringsMock {
         pub fn new() -> Self {
-            SharedStringsMock { values: Vec::new() }
+            SharedStringsMock {
+                values: RefCell::new(Vec::new()),
+            }
         }
     }
     impl<'a> SharedStringsInterface<'a> for SharedStringsMock {
         fn to_xml(&self) -> String {
             "".to_string()
         }
-        fn get_values(&self) -> &Vec<String> {
-            &self.values
+        fn get_values(&self) -> Ref<'_, Vec<String>> {
+            self.values.borrow()
         }
-        fn get_shared_string(&self, index: usize) -> &str {
-            self.values[index].as_str()
+        fn get_shared_string(&self, index: usize) -> String {
+            self.values.borrow()[index].clone()
         }
-        fn add_shared_string(&mut self, value: &'a str) -> () {
-            self.values.push(value.to_string())
+        fn add_shared_string(&self, value: &str) -> usize {
+            let mut values = self.values.borrow_mut();
+            values.push(value.to_string());
+            values.len() - 1
         }
     }
 }
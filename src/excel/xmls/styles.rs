@@ -0,0 +1,579 @@
+use std::collections::HashMap;
+
+use super::xl::XL;
+use crate::xml::{escape::unescape, nodes::node::XMLNode};
+
+/// Styles part used when a package has no `xl/styles.xml`, the same defaults Excel writes.
+pub const EMPTY_STYLES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<fonts count="1"><font><sz val="11"/><color theme="1"/><name val="Calibri"/><family val="2"/><scheme val="minor"/></font></fonts>
+<fills count="2"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill></fills>
+<borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders>
+<cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs>
+<cellXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/></cellXfs>
+<cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles>
+</styleSheet>"#;
+
+/// Format code of a built-in number format id, ids without a fixed code are `None`.
+///
+///     assert_eq!(builtin_number_format_code(14), Some("mm-dd-yy"));
+///
+pub fn builtin_number_format_code(id: u32) -> Option<&'static str> {
+    let code = match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        12 => "# ?/?",
+        13 => "# ??/??",
+        14 => "mm-dd-yy",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "m/d/yy h:mm",
+        // 27 to 36 and 50 to 58 depend on the locale, these are the ja-JP ones
+        27 | 36 | 50 | 57 => r#"[$-411]ge.m.d"#,
+        28 | 29 | 51 | 54 | 58 => r#"[$-411]ggge"年"m"月"d"日""#,
+        30 => "m/d/yy",
+        31 => r#"yyyy"年"m"月"d"日""#,
+        32 => r#"h"時"mm"分""#,
+        33 => r#"h"時"mm"分"ss"秒""#,
+        34 | 52 | 55 => r#"yyyy"年"m"月""#,
+        35 | 53 | 56 => r#"m"月"d"日""#,
+        37 => "#,##0 ;(#,##0)",
+        38 => "#,##0 ;[Red](#,##0)",
+        39 => "#,##0.00;(#,##0.00)",
+        40 => "#,##0.00;[Red](#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mmss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    };
+    Some(code)
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Color {
+    pub rgb: Option<String>,
+    pub theme: Option<u32>,
+    pub indexed: Option<u32>,
+    pub tint: Option<f64>,
+    pub auto: bool,
+}
+impl From<&XMLNode<'_>> for Color {
+    fn from(node: &XMLNode) -> Self {
+        Color {
+            rgb: node.search_element("rgb").map(|rgb| rgb.to_string()),
+            theme: parse_element(node, "theme"),
+            indexed: parse_element(node, "indexed"),
+            tint: parse_element(node, "tint"),
+            auto: is_true(node.search_element("auto")),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Font {
+    pub name: Option<String>,
+    pub size: Option<f64>,
+    pub bold: bool,
+    pub italic: bool,
+    pub strike: bool,
+    /// `single`, `double`, ... `None` when not underlined
+    pub underline: Option<String>,
+    pub color: Option<Color>,
+}
+impl From<&XMLNode<'_>> for Font {
+    /// Reads `<font>` of the styles part, and `<rPr>` of rich text runs.
+    fn from(node: &XMLNode) -> Self {
+        let name = node
+            .search_node("name")
+            .or_else(|| node.search_node("rFont"))
+            .and_then(|name| name.get_element_text("val"));
+        Font {
+            name,
+            size: node
+                .search_node("sz")
+                .and_then(|sz| parse_element(sz, "val")),
+            bold: is_on(node, "b"),
+            italic: is_on(node, "i"),
+            strike: is_on(node, "strike"),
+            underline: node.search_node("u").and_then(|u| {
+                match u.search_element("val").unwrap_or("single") {
+                    "none" => None,
+                    val => Some(val.to_string()),
+                }
+            }),
+            color: node.search_node("color").map(Color::from),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Fill {
+    /// `none`, `solid`, `gray125`, ... `None` for gradient fills
+    pub pattern_type: Option<String>,
+    pub fg_color: Option<Color>,
+    pub bg_color: Option<Color>,
+}
+impl From<&XMLNode<'_>> for Fill {
+    fn from(node: &XMLNode) -> Self {
+        match node.search_node("patternFill") {
+            Some(pattern) => Fill {
+                pattern_type: Some(
+                    pattern
+                        .search_element("patternType")
+                        .unwrap_or("none")
+                        .to_string(),
+                ),
+                fg_color: pattern.search_node("fgColor").map(Color::from),
+                bg_color: pattern.search_node("bgColor").map(Color::from),
+            },
+            None => Fill::default(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct BorderSide {
+    /// `thin`, `medium`, `dashed`, ... `None` when there is no line
+    pub style: Option<String>,
+    pub color: Option<Color>,
+}
+impl From<&XMLNode<'_>> for BorderSide {
+    fn from(node: &XMLNode) -> Self {
+        BorderSide {
+            style: node.search_element("style").map(|style| style.to_string()),
+            color: node.search_node("color").map(Color::from),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Border {
+    pub left: BorderSide,
+    pub right: BorderSide,
+    pub top: BorderSide,
+    pub bottom: BorderSide,
+    pub diagonal: BorderSide,
+}
+impl From<&XMLNode<'_>> for Border {
+    fn from(node: &XMLNode) -> Self {
+        let side = |key: &str| {
+            node.search_node(key)
+                .map(BorderSide::from)
+                .unwrap_or_default()
+        };
+        Border {
+            // strict documents name the left and right sides start and end
+            left: node
+                .search_node("left")
+                .or_else(|| node.search_node("start"))
+                .map(BorderSide::from)
+                .unwrap_or_default(),
+            right: node
+                .search_node("right")
+                .or_else(|| node.search_node("end"))
+                .map(BorderSide::from)
+                .unwrap_or_default(),
+            top: side("top"),
+            bottom: side("bottom"),
+            diagonal: side("diagonal"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Alignment {
+    pub horizontal: Option<String>,
+    pub vertical: Option<String>,
+    pub wrap_text: bool,
+    pub shrink_to_fit: bool,
+    pub indent: u32,
+    pub text_rotation: u32,
+}
+impl From<&XMLNode<'_>> for Alignment {
+    fn from(node: &XMLNode) -> Self {
+        Alignment {
+            horizontal: node.search_element("horizontal").map(|h| h.to_string()),
+            vertical: node.search_element("vertical").map(|v| v.to_string()),
+            wrap_text: is_true(node.search_element("wrapText")),
+            shrink_to_fit: is_true(node.search_element("shrinkToFit")),
+            indent: parse_element(node, "indent").unwrap_or_default(),
+            text_rotation: parse_element(node, "textRotation").unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct NumberFormat {
+    pub id: u32,
+    pub code: String,
+}
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            id: 0,
+            code: "General".to_string(),
+        }
+    }
+}
+
+/// Fully resolved format of a cell.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct CellFormat {
+    pub number_format: NumberFormat,
+    pub font: Font,
+    pub fill: Fill,
+    pub border: Border,
+    pub alignment: Alignment,
+}
+
+/// `<xf>` of cellXfs or cellStyleXfs, ids not written are `None`.
+#[derive(Debug, PartialEq, Clone, Default)]
+struct Xf {
+    num_fmt_id: Option<u32>,
+    font_id: Option<usize>,
+    fill_id: Option<usize>,
+    border_id: Option<usize>,
+    xf_id: Option<usize>,
+    alignment: Option<Alignment>,
+}
+impl From<&XMLNode<'_>> for Xf {
+    fn from(node: &XMLNode) -> Self {
+        Xf {
+            num_fmt_id: parse_element(node, "numFmtId"),
+            font_id: parse_element(node, "fontId"),
+            fill_id: parse_element(node, "fillId"),
+            border_id: parse_element(node, "borderId"),
+            xf_id: parse_element(node, "xfId"),
+            alignment: node.search_node("alignment").map(Alignment::from),
+        }
+    }
+}
+
+/// `xl/styles.xml`, the `s` attribute of `<c>` is an index into its cellXfs.
+#[derive(Debug, PartialEq)]
+pub struct Styles<'a> {
+    node: XMLNode<'a>,
+    num_fmts: HashMap<u32, String>,
+    fonts: Vec<Font>,
+    fills: Vec<Fill>,
+    borders: Vec<Border>,
+    cell_style_xfs: Vec<Xf>,
+    cell_xfs: Vec<Xf>,
+}
+impl<'a> Styles<'a> {
+    pub fn new(source: &'a str) -> Self {
+        let node = XMLNode::from(source);
+        let style_sheet = node
+            .search_node("styleSheet")
+            .unwrap_or_else(|| panic!("not exist <styleSheet> for {:?}", node));
+        let num_fmts = children(style_sheet, "numFmts", "numFmt")
+            .iter()
+            .filter_map(|num_fmt| {
+                let id = parse_element(num_fmt, "numFmtId")?;
+                let code = num_fmt.get_element_text("formatCode")?;
+                Some((id, unescape(&code)))
+            })
+            .collect();
+        let fonts = children(style_sheet, "fonts", "font")
+            .into_iter()
+            .map(Font::from)
+            .collect();
+        let fills = children(style_sheet, "fills", "fill")
+            .into_iter()
+            .map(Fill::from)
+            .collect();
+        let borders = children(style_sheet, "borders", "border")
+            .into_iter()
+            .map(Border::from)
+            .collect();
+        let cell_style_xfs = children(style_sheet, "cellStyleXfs", "xf")
+            .into_iter()
+            .map(Xf::from)
+            .collect();
+        let cell_xfs = children(style_sheet, "cellXfs", "xf")
+            .into_iter()
+            .map(Xf::from)
+            .collect();
+        Styles {
+            node,
+            num_fmts,
+            fonts,
+            fills,
+            borders,
+            cell_style_xfs,
+            cell_xfs,
+        }
+    }
+    /// Returns the number format of `style_index`, falling back to the built-in formats.
+    pub fn get_number_format(&self, style_index: usize) -> Option<NumberFormat> {
+        let id = self.resolve(style_index, |xf| xf.num_fmt_id)?;
+        Some(self.number_format_by_id(id))
+    }
+    /// Returns the first index of `<cellXfs>` whose number format matches.
+    ///
+    ///     let index = styles.find_style_index(|format| format.code == "yyyy/mm/dd");
+    ///
+    pub fn find_style_index(&self, is_match: impl Fn(&NumberFormat) -> bool) -> Option<usize> {
+        (0..self.cell_xfs.len()).find(|&index| {
+            self.get_number_format(index)
+                .is_some_and(|format| is_match(&format))
+        })
+    }
+    pub fn number_format_by_id(&self, id: u32) -> NumberFormat {
+        let code = self
+            .num_fmts
+            .get(&id)
+            .cloned()
+            .or_else(|| builtin_number_format_code(id).map(|code| code.to_string()))
+            .unwrap_or_else(|| "General".to_string());
+        NumberFormat { id, code }
+    }
+    /// Resolves the `s` attribute of a cell into its format.
+    /// Ids a cellXfs `<xf>` does not write are taken from the cell style it refers to by `xfId`.
+    ///
+    ///     // <c r="B2" s="15" t="s">
+    ///     let format = styles.get_cell_format(15).unwrap();
+    ///     assert_eq!(format.font.bold, true);
+    ///
+    pub fn get_cell_format(&self, style_index: usize) -> Option<CellFormat> {
+        let xf = self.cell_xfs.get(style_index)?;
+        let number_format = self.get_number_format(style_index).unwrap_or_default();
+        let font = self
+            .resolve(style_index, |xf| xf.font_id)
+            .and_then(|id| self.fonts.get(id))
+            .cloned()
+            .unwrap_or_default();
+        let fill = self
+            .resolve(style_index, |xf| xf.fill_id)
+            .and_then(|id| self.fills.get(id))
+            .cloned()
+            .unwrap_or_default();
+        let border = self
+            .resolve(style_index, |xf| xf.border_id)
+            .and_then(|id| self.borders.get(id))
+            .cloned()
+            .unwrap_or_default();
+        let alignment = xf
+            .alignment
+            .clone()
+            .or_else(|| {
+                self.get_style_xf(xf)
+                    .and_then(|style| style.alignment.clone())
+            })
+            .unwrap_or_default();
+        Some(CellFormat {
+            number_format,
+            font,
+            fill,
+            border,
+            alignment,
+        })
+    }
+    fn get_style_xf(&self, xf: &Xf) -> Option<&Xf> {
+        self.cell_style_xfs.get(xf.xf_id.unwrap_or_default())
+    }
+    fn resolve<T>(&self, style_index: usize, get: impl Fn(&Xf) -> Option<T>) -> Option<T> {
+        let xf = self.cell_xfs.get(style_index)?;
+        get(xf).or_else(|| self.get_style_xf(xf).and_then(&get))
+    }
+}
+impl<'a> XL<'a> for Styles<'a> {
+    fn get_xml_node(&'a self) -> &'a XMLNode<'a> {
+        &self.node
+    }
+}
+
+fn children<'b>(node: &'b XMLNode, list: &str, item: &str) -> Vec<&'b XMLNode<'b>> {
+    node.search_node(list)
+        .and_then(|list| list.search_all_nodes(item))
+        .unwrap_or_default()
+}
+fn parse_element<T: std::str::FromStr>(node: &XMLNode, key: &str) -> Option<T> {
+    node.search_element(key)?.parse().ok()
+}
+fn is_true(value: Option<&str>) -> bool {
+    matches!(value, Some("1") | Some("true"))
+}
+/// `<b/>` is on, `<b val="0"/>` is off
+fn is_on(node: &XMLNode, key: &str) -> bool {
+    node.search_node(key)
+        .map(|child| !matches!(child.search_element("val"), Some("0") | Some("false")))
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod styles_test {
+    use super::{Color, Styles, EMPTY_STYLES};
+
+    const SOURCE: &str = r#"
+<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:mc="http://schemas.openxmlformats.org/markup-compatibility/2006" mc:Ignorable="x14ac x16r2 xr" xmlns:x14ac="http://schemas.microsoft.com/office/spreadsheetml/2009/9/ac">
+<numFmts count="2">
+<numFmt numFmtId="176" formatCode="yyyy/mm/dd"/>
+<numFmt numFmtId="177" formatCode="&quot;¥&quot;#,##0;[Red]&quot;¥&quot;\-#,##0"/>
+</numFmts>
+<fonts count="3" x14ac:knownFonts="1">
+<font>
+<sz val="11"/>
+<color theme="1"/>
+<name val="游ゴシック"/>
+<family val="2"/>
+<charset val="128"/>
+<scheme val="minor"/>
+</font>
+<font>
+<b/>
+<sz val="14"/>
+<color rgb="FFFF0000"/>
+<name val="Calibri Light"/>
+</font>
+<font>
+<i/>
+<u/>
+<sz val="9"/>
+<color theme="4" tint="-0.249977111117893"/>
+<name val="Meiryo UI"/>
+</font>
+</fonts>
+<fills count="3">
+<fill>
+<patternFill patternType="none"/>
+</fill>
+<fill>
+<patternFill patternType="gray125"/>
+</fill>
+<fill>
+<patternFill patternType="solid">
+<fgColor rgb="FFFFFF00"/>
+<bgColor indexed="64"/>
+</patternFill>
+</fill>
+</fills>
+<borders count="2">
+<border>
+<left/>
+<right/>
+<top/>
+<bottom/>
+<diagonal/>
+</border>
+<border>
+<left style="thin">
+<color indexed="64"/>
+</left>
+<right style="thin">
+<color indexed="64"/>
+</right>
+<top/>
+<bottom style="medium">
+<color auto="1"/>
+</bottom>
+<diagonal/>
+</border>
+</borders>
+<cellStyleXfs count="2">
+<xf numFmtId="0" fontId="0" fillId="0" borderId="0">
+<alignment vertical="center"/>
+</xf>
+<xf numFmtId="0" fontId="1" fillId="2" borderId="1" applyAlignment="0"/>
+</cellStyleXfs>
+<cellXfs count="4">
+<xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0">
+<alignment vertical="center"/>
+</xf>
+<xf numFmtId="176" fontId="0" fillId="0" borderId="0" xfId="0" applyNumberFormat="1"/>
+<xf numFmtId="177" fontId="1" fillId="2" borderId="1" xfId="1" applyFont="1" applyFill="1" applyBorder="1" applyAlignment="1">
+<alignment horizontal="center" vertical="top" wrapText="1" indent="1"/>
+</xf>
+<xf numFmtId="14" xfId="1"/>
+</cellXfs>
+<cellStyles count="2">
+<cellStyle name="標準" xfId="0" builtinId="0"/>
+<cellStyle name="見出し 1" xfId="1" builtinId="16"/>
+</cellStyles>
+</styleSheet>
+"#;
+    #[test]
+    fn get_number_format_test() {
+        let styles = Styles::new(SOURCE);
+        let format = styles.get_number_format(1).unwrap();
+        assert_eq!(format.id, 176);
+        assert_eq!(format.code, "yyyy/mm/dd");
+        assert_eq!(
+            styles.get_number_format(2).unwrap().code,
+            r##""¥"#,##0;[Red]"¥"\-#,##0"##
+        );
+        assert_eq!(styles.get_number_format(3).unwrap().code, "mm-dd-yy");
+        assert_eq!(styles.get_number_format(0).unwrap().code, "General");
+        assert_eq!(styles.get_number_format(4), None);
+        assert_eq!(
+            styles.find_style_index(|format| format.code.contains("mm")),
+            Some(1)
+        );
+        assert_eq!(styles.find_style_index(|format| format.code == "@"), None);
+    }
+    #[test]
+    fn get_cell_format_test() {
+        let styles = Styles::new(SOURCE);
+        let format = styles.get_cell_format(2).unwrap();
+        assert_eq!(format.font.name, Some("Calibri Light".to_string()));
+        assert_eq!(format.font.size, Some(14.0));
+        assert!(format.font.bold);
+        assert!(!format.font.italic);
+        assert_eq!(format.font.color.unwrap().rgb, Some("FFFF0000".to_string()));
+        assert_eq!(format.fill.pattern_type, Some("solid".to_string()));
+        assert_eq!(
+            format.fill.fg_color.unwrap().rgb,
+            Some("FFFFFF00".to_string())
+        );
+        assert_eq!(format.border.left.style, Some("thin".to_string()));
+        assert_eq!(format.border.top.style, None);
+        assert_eq!(
+            format.border.bottom.color,
+            Some(Color {
+                auto: true,
+                ..Color::default()
+            })
+        );
+        assert_eq!(format.alignment.horizontal, Some("center".to_string()));
+        assert_eq!(format.alignment.vertical, Some("top".to_string()));
+        assert!(format.alignment.wrap_text);
+        assert_eq!(format.alignment.indent, 1);
+
+        let format = styles.get_cell_format(0).unwrap();
+        assert_eq!(format.font.name, Some("游ゴシック".to_string()));
+        assert_eq!(format.font.color.unwrap().theme, Some(1));
+        assert_eq!(format.fill.pattern_type, Some("none".to_string()));
+        assert_eq!(format.alignment.vertical, Some("center".to_string()));
+        assert_eq!(styles.get_cell_format(10), None);
+    }
+    #[test]
+    fn get_cell_format_from_cell_style_test() {
+        let styles = Styles::new(SOURCE);
+        let format = styles.get_cell_format(3).unwrap();
+        assert_eq!(format.number_format.code, "mm-dd-yy");
+        assert!(format.font.bold);
+        assert_eq!(format.border.right.style, Some("thin".to_string()));
+    }
+    #[test]
+    fn empty_styles_test() {
+        let styles = Styles::new(EMPTY_STYLES);
+        let format = styles.get_cell_format(0).unwrap();
+        assert_eq!(format.font.name, Some("Calibri".to_string()));
+        assert_eq!(format.font.size, Some(11.0));
+        assert_eq!(format.number_format.code, "General");
+    }
+}
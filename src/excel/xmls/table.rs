@@ -0,0 +1,582 @@
+use std::fmt::Display;
+
+use crate::{
+    excel::{
+        cells::{cell_range::RangeArea, line_shift::LineShift},
+        formulas::ast::CellReference,
+    },
+    xml::{
+        escape::{escape, unescape},
+        nodes::{node::XMLNode, node_type::NodeType},
+    },
+};
+
+use super::workbook::SheetError;
+
+pub const TABLE_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/table";
+pub const TABLE_CONTENT_TYPE: &str =
+    "application/vnd.openxmlformats-officedocument.spreadsheetml.table+xml";
+/// Style Excel gives a table made with Insert > Table.
+const DEFAULT_TABLE_STYLE: &str = "TableStyleMedium2";
+/// Elements a table writes after `<tableColumns>`.
+const AFTER_TABLE_COLUMNS: [&str; 2] = ["tableStyleInfo", "extLst"];
+const EMPTY_TABLE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"></table>"#;
+
+/// Why a table could not be made or resized.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum TableError {
+    Sheet(SheetError),
+    NotFound(String),
+    /// Table names are unique among the tables and the defined names, regardless of case
+    DuplicateName(String),
+    /// Names start with a letter, `_` or `\`, go on with letters, digits, `_` and `.`,
+    /// and are not cell references such as `A1` or `R1C1`
+    InvalidName(String),
+    /// Tables have a header row and a row of data at least, and keep their header row when resized
+    InvalidRange(String),
+    /// Header cells are unique text, the cell is the first one that is not
+    InvalidHeader(String),
+    /// Tables of a sheet do not overlap, the name is the table in the way
+    Overlap(String),
+}
+impl Display for TableError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TableError::Sheet(e) => write!(f, "{}", e),
+            TableError::NotFound(name) => write!(f, "table {:?} is not found", name),
+            TableError::DuplicateName(name) => write!(f, "name {:?} is already used", name),
+            TableError::InvalidName(name) => write!(f, "{:?} is not a valid table name", name),
+            TableError::InvalidRange(range) => {
+                write!(f, "{} is not a valid range for the table", range)
+            }
+            TableError::InvalidHeader(cell) => {
+                write!(f, "header cell {} is empty or used twice", cell)
+            }
+            TableError::Overlap(name) => write!(f, "the range overlaps table {:?}", name),
+        }
+    }
+}
+impl std::error::Error for TableError {}
+impl From<SheetError> for TableError {
+    fn from(e: SheetError) -> Self {
+        TableError::Sheet(e)
+    }
+}
+
+/// Column of a table, with the function or label of its totals row cell.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TableColumn {
+    id: usize,
+    name: String,
+    totals_row_function: Option<String>,
+    totals_row_label: Option<String>,
+    calculated_formula: Option<String>,
+}
+impl TableColumn {
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// e.g. `sum`, `average` or `custom`.
+    pub fn get_totals_row_function(&self) -> Option<&str> {
+        self.totals_row_function.as_deref()
+    }
+    pub fn get_totals_row_label(&self) -> Option<&str> {
+        self.totals_row_label.as_deref()
+    }
+    /// Formula every data cell of the column has, e.g. `Table1[[#This Row],[Price]]*2`.
+    pub fn get_calculated_formula(&self) -> Option<&str> {
+        self.calculated_formula.as_deref()
+    }
+}
+
+/// `<tableStyleInfo>` of a table.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TableStyle {
+    name: Option<String>,
+    is_first_column_shown: bool,
+    is_last_column_shown: bool,
+    is_row_stripes_shown: bool,
+    is_column_stripes_shown: bool,
+}
+impl TableStyle {
+    pub fn get_name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+    pub fn is_first_column_shown(&self) -> bool {
+        self.is_first_column_shown
+    }
+    pub fn is_last_column_shown(&self) -> bool {
+        self.is_last_column_shown
+    }
+    pub fn is_row_stripes_shown(&self) -> bool {
+        self.is_row_stripes_shown
+    }
+    pub fn is_column_stripes_shown(&self) -> bool {
+        self.is_column_stripes_shown
+    }
+}
+
+/// Table (list object) of a sheet, read from its part `xl/tables/tableN.xml`.
+///
+///     let table = package.get_table("job_info").unwrap();
+///     assert_eq!(table.get_area().to_string(), "B2:F20");
+///     assert_eq!(table.get_columns()[0].get_name(), "会社名");
+///
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Table {
+    part: String,
+    sheet_name: String,
+    id: usize,
+    name: String,
+    display_name: String,
+    area: RangeArea,
+    header_row_count: usize,
+    totals_row_count: usize,
+    columns: Vec<TableColumn>,
+    style: Option<TableStyle>,
+    auto_filter: Option<RangeArea>,
+}
+impl Table {
+    /// `None` when the source has no `<table>` with a valid `ref`.
+    pub fn new(source: &str, part: &str, sheet_name: &str) -> Option<Self> {
+        let node = XMLNode::from(source);
+        let table = node.search_node("table")?;
+        let text = |node: &XMLNode, key: &str| node.get_element_text(key).map(|v| unescape(&v));
+        let flag = |node: &XMLNode, key: &str, default: bool| {
+            node.search_element(key)
+                .map(|v| v == "1" || v == "true")
+                .unwrap_or(default)
+        };
+        let count = |key: &str, default: usize| {
+            table
+                .search_element(key)
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(default)
+        };
+        let columns = table
+            .search_node("tableColumns")
+            .and_then(|columns| columns.search_all_nodes("tableColumn"))
+            .unwrap_or_default()
+            .into_iter()
+            .map(|column| TableColumn {
+                id: column
+                    .search_element("id")
+                    .and_then(|id| id.parse().ok())
+                    .unwrap_or_default(),
+                name: text(column, "name").unwrap_or_default(),
+                totals_row_function: text(column, "totalsRowFunction"),
+                totals_row_label: text(column, "totalsRowLabel"),
+                calculated_formula: column
+                    .search_node("calculatedColumnFormula")
+                    .and_then(|formula| formula.get_text())
+                    .map(|formula| unescape(&formula)),
+            })
+            .collect();
+        let style = table.search_node("tableStyleInfo").map(|style| TableStyle {
+            name: text(style, "name"),
+            is_first_column_shown: flag(style, "showFirstColumn", false),
+            is_last_column_shown: flag(style, "showLastColumn", false),
+            is_row_stripes_shown: flag(style, "showRowStripes", false),
+            is_column_stripes_shown: flag(style, "showColumnStripes", false),
+        });
+        let name = text(table, "name").unwrap_or_default();
+        Some(Table {
+            part: part.to_string(),
+            sheet_name: sheet_name.to_string(),
+            id: count("id", 0),
+            display_name: text(table, "displayName").unwrap_or_else(|| name.clone()),
+            name,
+            area: RangeArea::new(table.search_element("ref")?)?,
+            header_row_count: count("headerRowCount", 1),
+            totals_row_count: count("totalsRowCount", 0),
+            columns,
+            style,
+            auto_filter: table
+                .search_node("autoFilter")
+                .and_then(|filter| RangeArea::new(filter.search_element("ref")?)),
+        })
+    }
+    pub fn get_part(&self) -> &str {
+        &self.part
+    }
+    pub fn get_sheet_name(&self) -> &str {
+        &self.sheet_name
+    }
+    pub fn get_id(&self) -> usize {
+        self.id
+    }
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    /// Name formulas use, the same as `get_name` for tables Excel writes.
+    pub fn get_display_name(&self) -> &str {
+        &self.display_name
+    }
+    /// Header row, data and totals row.
+    pub fn get_area(&self) -> RangeArea {
+        self.area
+    }
+    pub fn has_header_row(&self) -> bool {
+        self.header_row_count > 0
+    }
+    pub fn has_totals_row(&self) -> bool {
+        self.totals_row_count > 0
+    }
+    /// Rows between the header row and the totals row, `None` when there are none.
+    pub fn get_data_area(&self) -> Option<RangeArea> {
+        let first_row = self.area.get_first_row() + self.header_row_count;
+        let last_row = self
+            .area
+            .get_last_row()
+            .checked_sub(self.totals_row_count)?;
+        (first_row <= last_row).then(|| {
+            RangeArea::from_bounds(
+                (first_row, self.area.get_first_column()),
+                (last_row, self.area.get_last_column()),
+            )
+        })
+    }
+    pub fn get_columns(&self) -> &[TableColumn] {
+        &self.columns
+    }
+    /// The column named `name`, ignoring case as Excel does.
+    pub fn get_column(&self, name: &str) -> Option<&TableColumn> {
+        self.columns
+            .iter()
+            .find(|column| column.name.to_lowercase() == name.to_lowercase())
+    }
+    pub fn get_style(&self) -> Option<&TableStyle> {
+        self.style.as_ref()
+    }
+    pub fn get_auto_filter(&self) -> Option<&RangeArea> {
+        self.auto_filter.as_ref()
+    }
+    /// Whether the table is called `name`, ignoring case.
+    pub fn is_named(&self, name: &str) -> bool {
+        let name = name.to_lowercase();
+        self.name.to_lowercase() == name || self.display_name.to_lowercase() == name
+    }
+}
+
+/// Source of a new table part with a header row of `column_names` and the default style.
+///
+///     let source = new_table_xml(3, "Orders", &RangeArea::new("B2:C10").unwrap(), &names);
+///     // <table id="3" name="Orders" displayName="Orders" ref="B2:C10" totalsRowShown="0">
+///
+pub fn new_table_xml(id: usize, name: &str, area: &RangeArea, column_names: &[String]) -> String {
+    let mut node = XMLNode::from(EMPTY_TABLE);
+    let table = node.search_node_mut("table").unwrap();
+    table.set_element("id", id.to_string());
+    table.set_element("name", escape(name));
+    table.set_element("displayName", escape(name));
+    table.set_element("ref", area.to_string());
+    table.set_element("totalsRowShown", "0");
+    let mut auto_filter = XMLNode::new("autoFilter", NodeType::SingleElement);
+    auto_filter.set_element("ref", area.to_string());
+    table.add_node(auto_filter);
+    table.add_node(XMLNode::new("tableColumns", NodeType::Element));
+    let mut style = XMLNode::new("tableStyleInfo", NodeType::SingleElement);
+    style.set_element("name", DEFAULT_TABLE_STYLE);
+    style.set_element("showFirstColumn", "0");
+    style.set_element("showLastColumn", "0");
+    style.set_element("showRowStripes", "1");
+    style.set_element("showColumnStripes", "0");
+    table.add_node(style);
+    write_table_columns(table, column_names);
+    node.to_string()
+}
+/// Moves the table to `area` keeping its other settings, `column_names` being the header cells of the area.
+/// Columns keep their totals and formulas by name, new columns are added and missing ones removed.
+/// The sort state is dropped, as the sorted rows change.
+pub fn resize_table_xml(source: &str, area: &RangeArea, column_names: &[String]) -> String {
+    let mut node = XMLNode::from(source);
+    let Some(table) = node.search_node_mut("table") else {
+        return source.to_string();
+    };
+    let totals_row_count = table
+        .search_element("totalsRowCount")
+        .and_then(|count| count.parse::<usize>().ok())
+        .unwrap_or_default();
+    table.set_element("ref", area.to_string());
+    if table.search_node("autoFilter").is_some() {
+        let filter_area = RangeArea::from_bounds(
+            (area.get_first_row(), area.get_first_column()),
+            (
+                area.get_last_row() - totals_row_count,
+                area.get_last_column(),
+            ),
+        );
+        // filters of removed columns would point outside the table
+        let mut auto_filter = XMLNode::new("autoFilter", NodeType::SingleElement);
+        auto_filter.set_element("ref", filter_area.to_string());
+        table.change_child_node(auto_filter);
+    }
+    table.remove_node("sortState");
+    write_table_columns(table, column_names);
+    node.to_string()
+}
+/// Moves the table with the lines inserted or deleted in its sheet.
+/// Columns inserted inside the table are new columns named `ColumnN`, deleted ones are removed
+/// with their filters and sort state, as `resize_table_xml` does.
+/// A table whose cells are all deleted is left as it is.
+pub fn shift_table_xml(source: &str, shift: LineShift) -> String {
+    let Some(table) = Table::new(source, "", "") else {
+        return source.to_string();
+    };
+    let area = table.get_area();
+    let Some(moved) = shift.move_area(&area) else {
+        return source.to_string();
+    };
+    if !shift.is_row() && moved.get_column_count() != area.get_column_count() {
+        let mut column_names = vec![None; moved.get_column_count()];
+        for (i, column) in table.get_columns().iter().enumerate() {
+            if let Some(new_column) = shift.move_line(area.get_first_column() + i) {
+                column_names[new_column - moved.get_first_column()] =
+                    Some(column.get_name().to_string());
+            }
+        }
+        let mut number = 0;
+        let column_names = column_names
+            .into_iter()
+            .map(|name| {
+                name.unwrap_or_else(|| loop {
+                    number += 1;
+                    let name = format!("Column{}", number);
+                    if table.get_column(&name).is_none() {
+                        break name;
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        return resize_table_xml(source, &moved, &column_names);
+    }
+    let mut node = XMLNode::from(source);
+    let Some(table) = node.search_node_mut("table") else {
+        return source.to_string();
+    };
+    table.set_element("ref", moved.to_string());
+    let move_ref = |node: &mut XMLNode| {
+        if let Some(area) = node
+            .search_element("ref")
+            .and_then(RangeArea::new)
+            .and_then(|area| shift.move_area(&area))
+        {
+            node.set_element("ref", area.to_string());
+        }
+    };
+    if let Some(auto_filter) = table.search_node_mut("autoFilter") {
+        move_ref(auto_filter);
+    }
+    if let Some(sort_state) = table.search_node_mut("sortState") {
+        move_ref(sort_state);
+        for condition in sort_state
+            .search_all_nodes_mut("sortCondition")
+            .unwrap_or_default()
+        {
+            move_ref(condition);
+        }
+    }
+    node.to_string()
+}
+/// Rewrites `<tableColumns>` to `column_names`, keeping the columns already named so.
+fn write_table_columns<'a>(table: &mut XMLNode<'a>, column_names: &[String]) {
+    let old_columns = table
+        .search_node("tableColumns")
+        .and_then(|columns| columns.search_all_nodes("tableColumn"))
+        .unwrap_or_default()
+        .into_iter()
+        .cloned()
+        .collect::<Vec<_>>();
+    let mut next_id = old_columns
+        .iter()
+        .filter_map(|column| column.search_element("id")?.parse::<usize>().ok())
+        .max()
+        .unwrap_or_default()
+        + 1;
+    let mut columns = XMLNode::new("tableColumns", NodeType::Element);
+    columns.set_element("count", column_names.len().to_string());
+    for name in column_names {
+        let old_column = old_columns.iter().find(|column| {
+            column
+                .get_element_text("name")
+                .map(|n| unescape(&n).to_lowercase())
+                == Some(name.to_lowercase())
+        });
+        let column = match old_column {
+            Some(column) => column.clone(),
+            None => {
+                let mut column = XMLNode::new("tableColumn", NodeType::SingleElement);
+                column.set_element("id", next_id.to_string());
+                column.set_element("name", escape(name));
+                next_id += 1;
+                column
+            }
+        };
+        columns.add_node(column);
+    }
+    if table.search_node("tableColumns").is_some() {
+        table.change_child_node(columns);
+        return;
+    }
+    let position = table
+        .get_child_nodes()
+        .unwrap_or_default()
+        .iter()
+        .position(|child| AFTER_TABLE_COLUMNS.contains(&child.get_value()))
+        .unwrap_or(table.get_child_nodes().map(|c| c.len()).unwrap_or_default());
+    table.insert_node(position, columns);
+    table.set_node_type(NodeType::Element);
+}
+/// Whether Excel accepts `name` for a table or a defined name.
+pub fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    let Some(first) = chars.next() else {
+        return false;
+    };
+    (first.is_alphabetic() || first == '_' || first == '\\')
+        && chars.all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '\\')
+        && name.chars().count() <= 255
+        && !["r", "c"].contains(&name.to_lowercase().as_str())
+        && CellReference::new(name).is_none()
+        && !is_r1c1_reference(name)
+}
+/// `R1C1`, `R2C`, `RC3`...
+fn is_r1c1_reference(name: &str) -> bool {
+    let name = name.to_uppercase();
+    let Some(rest) = name.strip_prefix('R') else {
+        return false;
+    };
+    let rest = rest.trim_start_matches(|c: char| c.is_ascii_digit());
+    rest.strip_prefix('C')
+        .is_some_and(|rest| rest.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod table_test {
+    use crate::excel::cells::{cell_range::RangeArea, line_shift::LineShift};
+
+    use super::{is_valid_name, new_table_xml, resize_table_xml, shift_table_xml, Table};
+
+    const TABLE: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" id="2" name="job_info" displayName="job_info" ref="B2:D6" totalsRowCount="1">
+<autoFilter ref="B2:D5"><filterColumn colId="2"><filters><filter val="1"/></filters></filterColumn></autoFilter>
+<sortState ref="B3:D5"><sortCondition ref="B3:B5"/></sortState>
+<tableColumns count="3">
+<tableColumn id="1" name="会社名" totalsRowLabel="合計"/>
+<tableColumn id="2" name="Price &amp; Tax" totalsRowFunction="sum"/>
+<tableColumn id="3" name="Double"><calculatedColumnFormula>job_info[[#This Row],[Price &amp; Tax]]*2</calculatedColumnFormula></tableColumn>
+</tableColumns>
+<tableStyleInfo name="TableStyleLight9" showFirstColumn="0" showLastColumn="0" showRowStripes="1" showColumnStripes="0"/>
+</table>"#;
+    #[test]
+    fn table_new_test() {
+        let table = Table::new(TABLE, "xl/tables/table2.xml", "Jobs").unwrap();
+        assert_eq!(table.get_id(), 2);
+        assert_eq!(table.get_name(), "job_info");
+        assert!(table.is_named("JOB_INFO"));
+        assert_eq!(table.get_area().to_string(), "B2:D6");
+        assert_eq!(table.get_data_area().unwrap().to_string(), "B3:D5");
+        assert!(table.has_header_row());
+        assert!(table.has_totals_row());
+        assert_eq!(table.get_auto_filter().unwrap().to_string(), "B2:D5");
+        let columns = table.get_columns();
+        assert_eq!(columns.len(), 3);
+        assert_eq!(columns[0].get_totals_row_label(), Some("合計"));
+        assert_eq!(columns[1].get_name(), "Price & Tax");
+        assert_eq!(columns[1].get_totals_row_function(), Some("sum"));
+        assert_eq!(
+            table.get_column("double").unwrap().get_calculated_formula(),
+            Some("job_info[[#This Row],[Price & Tax]]*2")
+        );
+        let style = table.get_style().unwrap();
+        assert_eq!(style.get_name(), Some("TableStyleLight9"));
+        assert!(style.is_row_stripes_shown());
+        assert!(!style.is_column_stripes_shown());
+    }
+    #[test]
+    fn new_table_xml_test() {
+        let names = vec!["Name".to_string(), "A & B".to_string()];
+        let source = new_table_xml(3, "Orders", &RangeArea::new("B2:C10").unwrap(), &names);
+        assert!(source.contains(concat!(
+            r#"<table xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" "#,
+            r#"id="3" name="Orders" displayName="Orders" ref="B2:C10" totalsRowShown="0">"#,
+            r#"<autoFilter ref="B2:C10"/>"#,
+            r#"<tableColumns count="2"><tableColumn id="1" name="Name"/>"#,
+            r#"<tableColumn id="2" name="A &amp; B"/></tableColumns>"#,
+            r#"<tableStyleInfo name="TableStyleMedium2" showFirstColumn="0" showLastColumn="0" "#,
+            r#"showRowStripes="1" showColumnStripes="0"/></table>"#
+        )));
+        let table = Table::new(&source, "xl/tables/table3.xml", "Sheet1").unwrap();
+        assert_eq!(table.get_columns()[1].get_name(), "A & B");
+        assert!(!table.has_totals_row());
+    }
+    #[test]
+    fn resize_table_xml_test() {
+        let names = vec![
+            "会社名".to_string(),
+            "Double".to_string(),
+            "Note".to_string(),
+        ];
+        let source = resize_table_xml(TABLE, &RangeArea::new("B2:D9").unwrap(), &names);
+        let table = Table::new(&source, "xl/tables/table2.xml", "Jobs").unwrap();
+        assert_eq!(table.get_area().to_string(), "B2:D9");
+        assert_eq!(table.get_auto_filter().unwrap().to_string(), "B2:D8");
+        let columns = table.get_columns();
+        assert_eq!(
+            columns
+                .iter()
+                .map(|column| (column.get_id(), column.get_name()))
+                .collect::<Vec<_>>(),
+            vec![(1, "会社名"), (3, "Double"), (4, "Note")]
+        );
+        assert!(columns[1].get_calculated_formula().is_some());
+        assert!(!source.contains("sortState"));
+        assert!(!source.contains("filterColumn"));
+        assert!(source.contains(r#"<tableColumns count="3">"#));
+    }
+    #[test]
+    fn shift_table_xml_test() {
+        let source = shift_table_xml(TABLE, LineShift::InsertRows { at: 4, count: 2 });
+        assert!(source.contains(r#"ref="B2:D8""#));
+        assert!(source.contains(r#"<autoFilter ref="B2:D7"><filterColumn colId="2">"#));
+        assert!(source.contains(r#"<sortState ref="B3:D7"><sortCondition ref="B3:B7"/>"#));
+        let source = shift_table_xml(TABLE, LineShift::DeleteColumns { at: 1, count: 1 });
+        assert!(source.contains(r#"ref="A2:C6""#));
+        assert!(source.contains(r#"<tableColumns count="3">"#));
+
+        let source = shift_table_xml(TABLE, LineShift::InsertColumns { at: 3, count: 1 });
+        let table = Table::new(&source, "xl/tables/table2.xml", "Jobs").unwrap();
+        assert_eq!(table.get_area().to_string(), "B2:E6");
+        assert_eq!(
+            table
+                .get_columns()
+                .iter()
+                .map(|column| column.get_name())
+                .collect::<Vec<_>>(),
+            vec!["会社名", "Column1", "Price & Tax", "Double"]
+        );
+        let source = shift_table_xml(TABLE, LineShift::DeleteColumns { at: 3, count: 1 });
+        let table = Table::new(&source, "xl/tables/table2.xml", "Jobs").unwrap();
+        assert_eq!(table.get_area().to_string(), "B2:C6");
+        assert_eq!(table.get_columns()[1].get_name(), "Double");
+
+        let source = shift_table_xml(TABLE, LineShift::DeleteRows { at: 2, count: 5 });
+        assert_eq!(source, TABLE);
+    }
+    #[test]
+    fn is_valid_name_test() {
+        assert!(is_valid_name("job_info"));
+        assert!(is_valid_name("_売上.2024"));
+        assert!(!is_valid_name("2024"));
+        assert!(!is_valid_name("job info"));
+        assert!(!is_valid_name("A1"));
+        assert!(!is_valid_name("R1C1"));
+        assert!(!is_valid_name("c"));
+        assert!(!is_valid_name(""));
+    }
+}
@@ -1,26 +1,599 @@
-use crate::xml::nodes::node::XMLNode;
+use std::fmt::Display;
+
+use crate::{
+    excel::{
+        cells::{
+            cell_range::CellRange,
+            formula::{remove_sheet, rename_sheet, shift_lines},
+            line_shift::LineShift,
+        },
+        formulas::parser::parse,
+    },
+    xml::{
+        escape::{escape, unescape},
+        nodes::{node::XMLNode, node_type::NodeType},
+    },
+};
 
 use self::sheet_map::*;
 
-use super::xl::XL;
+use super::{
+    defined_name::{DefinedName, DefinedNameError},
+    relationships::Relationships,
+    table::is_valid_name,
+    xl::XL,
+};
+
+pub const WORKBOOK_PART: &str = "xl/workbook.xml";
+pub const WORKSHEET_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet";
+pub const CALC_CHAIN_TYPE: &str =
+    "http://schemas.openxmlformats.org/officeDocument/2006/relationships/calcChain";
+/// Characters Excel does not allow in sheet names.
+const INVALID_SHEET_NAME_CHARS: [char; 7] = ['\\', '/', '?', '*', ':', '[', ']'];
+const MAX_SHEET_NAME_LENGTH: usize = 31;
+/// Children of `<workbook>` that come before `<definedNames>` in the schema.
+const BEFORE_DEFINED_NAMES: [&str; 8] = [
+    "fileVersion",
+    "fileSharing",
+    "workbookPr",
+    "workbookProtection",
+    "bookViews",
+    "sheets",
+    "functionGroups",
+    "externalReferences",
+];
+/// Children of `<workbook>` that come before `<calcPr>` in the schema.
+const BEFORE_CALC_PR: [&str; 9] = [
+    "fileVersion",
+    "fileSharing",
+    "workbookPr",
+    "workbookProtection",
+    "bookViews",
+    "sheets",
+    "functionGroups",
+    "externalReferences",
+    "definedNames",
+];
+
+/// Why a sheet could not be added, renamed, moved or deleted.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SheetError {
+    NotFound(String),
+    /// Sheet names are unique regardless of case
+    DuplicateName(String),
+    /// Names are 1 to 31 characters without `\ / ? * : [ ]`, and do not start or end with `'`
+    InvalidName(String),
+    /// A workbook keeps one visible sheet at least
+    LastVisibleSheet(String),
+}
+impl Display for SheetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SheetError::NotFound(name) => write!(f, "sheet {:?} is not found", name),
+            SheetError::DuplicateName(name) => write!(f, "sheet {:?} already exists", name),
+            SheetError::InvalidName(name) => write!(f, "{:?} is not a valid sheet name", name),
+            SheetError::LastVisibleSheet(name) => {
+                write!(f, "{:?} is the last visible sheet", name)
+            }
+        }
+    }
+}
+impl std::error::Error for SheetError {}
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct WorkBook<'a> {
     node: XMLNode<'a>,
+    relationships: Relationships<'a>,
     sheet_map: SheetMap,
 }
 impl<'a> WorkBook<'a> {
-    pub fn new(source: &'a str) -> Self {
+    /// `rels_source` is `xl/_rels/workbook.xml.rels`, which maps each `<sheet r:id>` to its part.
+    pub fn new(source: &'a str, rels_source: &'a str) -> Self {
         let node = XMLNode::from(source);
+        let relationships = Relationships::new(rels_source, WORKBOOK_PART);
         let workbook_node = node.search_node("workbook").unwrap();
         let sheets_node = workbook_node.search_node("sheets").unwrap();
-        let sheet_map = SheetMap::from(sheets_node);
-        WorkBook { node, sheet_map }
+        let sheet_map = SheetMap::new_with_relationships(sheets_node, &relationships);
+        WorkBook {
+            node,
+            relationships,
+            sheet_map,
+        }
+    }
+    /// `<workbookPr date1904="1"/>` counts serial dates from 1904-01-01 instead of 1900-01-01.
+    pub fn is_date1904(&self) -> bool {
+        self.node
+            .search_node("workbook")
+            .and_then(|workbook| workbook.search_node("workbookPr"))
+            .and_then(|workbook_pr| workbook_pr.search_element("date1904"))
+            .map(|date1904| date1904 == "1" || date1904 == "true")
+            .unwrap_or(false)
+    }
+    /// Returns the workbook with `<calcPr fullCalcOnLoad="1"/>`, so that formulas written
+    /// without their cached values are calculated when the file is opened.
+    pub fn to_xml_with_full_calc(&self) -> String {
+        let mut node = self.node.clone();
+        let workbook = node.search_node_mut("workbook").unwrap();
+        if let Some(calc_pr) = workbook.search_node_mut("calcPr") {
+            calc_pr.set_element("fullCalcOnLoad", "1");
+            return node.to_string();
+        }
+        let position = workbook
+            .get_child_nodes()
+            .unwrap_or_default()
+            .iter()
+            .rposition(|child| BEFORE_CALC_PR.contains(&child.get_value()))
+            .map(|position| position + 1)
+            .unwrap_or_default();
+        let mut calc_pr = XMLNode::new("calcPr", NodeType::SingleElement);
+        calc_pr.set_element("fullCalcOnLoad", "1");
+        workbook.insert_node(position, calc_pr);
+        node.to_string()
     }
+    pub fn get_relationships(&self) -> &Relationships<'a> {
+        &self.relationships
+    }
+    /// Removes the relationships of `rel_type`, and returns the parts they pointed at.
+    pub fn remove_relationships(&mut self, rel_type: &str) -> Vec<String> {
+        let ids = self
+            .relationships
+            .get_relationships()
+            .iter()
+            .filter(|rel| rel.get_type() == rel_type)
+            .map(|rel| rel.get_id().to_string())
+            .collect::<Vec<_>>();
+        let parts = ids
+            .iter()
+            .filter_map(|id| self.relationships.get_target_part(id))
+            .collect();
+        ids.iter().for_each(|id| {
+            self.relationships.remove_relationship(id);
+        });
+        self.refresh_sheet_map();
+        parts
+    }
+    /// Returns the package path of the sheet part, e.g. `xl/worksheets/sheet3.xml`.
     pub fn get_excel_sheet_name(&self, sheet_name: &str) -> &str {
         let sheet_name = UserDefineSheetName::new(sheet_name);
         self.sheet_map.get_excel_sheet_name(sheet_name).unwrap()
     }
+    /// Returns the sheet name shown in Excel for the sheet part, the inverse of `get_excel_sheet_name`.
+    pub fn get_user_sheet_name(&self, excel_sheet_name: &str) -> Option<&str> {
+        self.sheet_map
+            .get_user_sheet_name(&ExcelDefineSheetName::new(excel_sheet_name))
+            .map(|sheet_name| sheet_name.get_sheet_name())
+    }
+    /// Sheet names in the order of the tabs.
+    pub fn get_sheet_names(&self) -> Vec<String> {
+        self.get_sheets_node()
+            .search_all_nodes("sheet")
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|sheet| sheet.get_element_text("name"))
+            .map(|name| unescape(&name))
+            .collect()
+    }
+    /// Position of the tab of the sheet, the name is compared regardless of case.
+    pub fn get_sheet_index(&self, sheet_name: &str) -> Option<usize> {
+        self.get_sheet_names()
+            .iter()
+            .position(|name| name.to_lowercase() == sheet_name.to_lowercase())
+    }
+    /// Whether a defined name of any scope is called `name`, regardless of case.
+    pub fn is_name_defined(&self, name: &str) -> bool {
+        self.get_defined_names()
+            .iter()
+            .any(|defined| defined.get_name().to_lowercase() == name.to_lowercase())
+    }
+    /// Names of the workbook in the order they are written, those scoped to a sheet included.
+    pub fn get_defined_names(&self) -> Vec<DefinedName> {
+        self.node
+            .search_node("workbook")
+            .and_then(|workbook| workbook.search_node("definedNames"))
+            .and_then(|defined_names| defined_names.search_all_nodes("definedName"))
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(DefinedName::new)
+            .collect()
+    }
+    /// The name seen from the sheet `sheet`, its own name first and then the one of the workbook.
+    /// Without a sheet only names of the workbook are found. Names are compared regardless of case.
+    ///
+    ///     // <definedName name="Total" localSheetId="1">Summary!$B$9</definedName>
+    ///     assert!(workbook.get_defined_name("Total", None).is_none());
+    ///     assert!(workbook.get_defined_name("Total", Some("Summary")).is_some());
+    ///
+    pub fn get_defined_name(&self, name: &str, sheet: Option<&str>) -> Option<DefinedName> {
+        let local_sheet_id = match sheet {
+            Some(sheet) => Some(self.get_sheet_index(sheet)?),
+            None => None,
+        };
+        let names = self.get_defined_names();
+        let find = |local_sheet_id: Option<usize>| {
+            names.iter().find(|defined| {
+                defined.get_local_sheet_id() == local_sheet_id
+                    && defined.get_name().to_lowercase() == name.to_lowercase()
+            })
+        };
+        local_sheet_id
+            .and_then(|id| find(Some(id)))
+            .or_else(|| find(None))
+            .cloned()
+    }
+    /// Sheet and cells of the name seen from the sheet `sheet`, see `get_defined_name`.
+    /// References without a sheet are on the sheet the name is scoped to, or else on `sheet`.
+    /// `None` when the name is not defined or is not a range, e.g. a constant.
+    ///
+    ///     // <definedName name="TaxRate">Settings!$B$1</definedName>
+    ///     let (sheet, range) = workbook.get_named_range("TaxRate", None).unwrap();
+    ///     assert_eq!((sheet.as_str(), range.to_string().as_str()), ("Settings", "B1"));
+    ///
+    pub fn get_named_range(&self, name: &str, sheet: Option<&str>) -> Option<(String, CellRange)> {
+        let defined = self.get_defined_name(name, sheet)?;
+        let scope = defined
+            .get_local_sheet_id()
+            .and_then(|id| self.get_sheet_names().get(id).cloned());
+        defined.get_range(scope.as_deref().or(sheet))
+    }
+    /// Defines the name for the workbook, or for the sheet `sheet` only, or changes the formula
+    /// of the name already defined there. `formula` is written without `=`, e.g. `Data!$A$1:$A$10`.
+    ///
+    ///     workbook.set_defined_name("TaxRate", "Settings!$B$1", None)?;
+    ///     workbook.set_defined_name("_xlnm.Print_Area", "Data!$A$1:$F$40", Some("Data"))?;
+    ///
+    pub fn set_defined_name(
+        &mut self,
+        name: &str,
+        formula: &str,
+        sheet: Option<&str>,
+    ) -> Result<(), DefinedNameError> {
+        if !is_valid_name(name) {
+            return Err(DefinedNameError::InvalidName(name.to_string()));
+        }
+        let formula = formula.strip_prefix('=').unwrap_or(formula);
+        if formula.trim().is_empty() || parse(&format!("({})", formula)).is_err() {
+            return Err(DefinedNameError::InvalidFormula(formula.to_string()));
+        }
+        let local_sheet_id = sheet
+            .map(|sheet| self.find_sheet_index(sheet))
+            .transpose()?;
+        let workbook = self.node.search_node_mut("workbook").unwrap();
+        if workbook.search_node("definedNames").is_none() {
+            let position = workbook
+                .get_child_nodes()
+                .unwrap_or_default()
+                .iter()
+                .rposition(|child| BEFORE_DEFINED_NAMES.contains(&child.get_value()))
+                .map(|position| position + 1)
+                .unwrap_or_default();
+            workbook.insert_node(position, XMLNode::new("definedNames", NodeType::Element));
+        }
+        let defined_names = workbook.search_node_mut("definedNames").unwrap();
+        if let Some(defined) = defined_names
+            .search_all_nodes_mut("definedName")
+            .unwrap_or_default()
+            .into_iter()
+            .find(|defined| is_same_name(defined, name, local_sheet_id))
+        {
+            defined.change_text(escape(formula));
+            return Ok(());
+        }
+        let mut defined = XMLNode::new("definedName", NodeType::Element);
+        defined.set_element("name", escape(name));
+        if let Some(id) = local_sheet_id {
+            defined.set_element("localSheetId", id.to_string());
+        }
+        defined.change_text(escape(formula));
+        defined_names.add_node(defined);
+        Ok(())
+    }
+    /// Deletes the name of the workbook, or the one of the sheet `sheet`.
+    pub fn remove_defined_name(
+        &mut self,
+        name: &str,
+        sheet: Option<&str>,
+    ) -> Result<(), DefinedNameError> {
+        let local_sheet_id = sheet
+            .map(|sheet| self.find_sheet_index(sheet))
+            .transpose()?;
+        let workbook = self.node.search_node_mut("workbook").unwrap();
+        let Some(defined_names) = workbook.search_node_mut("definedNames") else {
+            return Err(DefinedNameError::NotFound(name.to_string()));
+        };
+        let count = defined_names.get_child_nodes().unwrap_or_default().len();
+        defined_names.retain_nodes(|defined| !is_same_name(defined, name, local_sheet_id));
+        let rest = defined_names.get_child_nodes().unwrap_or_default().len();
+        if rest == count {
+            return Err(DefinedNameError::NotFound(name.to_string()));
+        }
+        if rest == 0 {
+            workbook.remove_node("definedNames");
+        }
+        Ok(())
+    }
+    /// Registers the sheet written in the part `part` after the last tab, and returns its new `sheetId`.
+    ///
+    ///     let sheet_id = workbook.add_sheet("Summary", "xl/worksheets/sheet4.xml")?;
+    ///     assert_eq!(workbook.get_excel_sheet_name("Summary"), "xl/worksheets/sheet4.xml");
+    ///
+    pub fn add_sheet(&mut self, sheet_name: &str, part: &str) -> Result<usize, SheetError> {
+        self.check_new_sheet_name(sheet_name, None)?;
+        let r_id = self.relationships.add_relationship(WORKSHEET_TYPE, part);
+        let sheet_id = self
+            .get_sheets_node()
+            .search_all_nodes("sheet")
+            .unwrap_or_default()
+            .iter()
+            .filter_map(|sheet| sheet.search_element("sheetId")?.parse::<usize>().ok())
+            .max()
+            .unwrap_or_default()
+            + 1;
+        let mut sheet = XMLNode::new("sheet", NodeType::SingleElement);
+        sheet.set_element("name", escape(sheet_name));
+        sheet.set_element("sheetId", sheet_id.to_string());
+        sheet.set_element("r:id", r_id);
+        self.get_sheets_node_mut().add_node(sheet);
+        self.refresh_sheet_map();
+        Ok(sheet_id)
+    }
+    /// Unregisters the sheet and its relationship, and returns the part it is written in.
+    /// Names scoped to the sheet are removed, and references to it in the other names become `#REF!`.
+    pub fn remove_sheet(&mut self, sheet_name: &str) -> Result<String, SheetError> {
+        let position = self.find_sheet_index(sheet_name)?;
+        let sheets = self
+            .get_sheets_node()
+            .search_all_nodes("sheet")
+            .unwrap_or_default();
+        let is_visible = |sheet: &XMLNode| {
+            sheet
+                .search_element("state")
+                .is_none_or(|state| state == "visible")
+        };
+        if is_visible(sheets[position]) && sheets.iter().filter(|s| is_visible(s)).count() == 1 {
+            return Err(SheetError::LastVisibleSheet(sheet_name.to_string()));
+        }
+        let r_id = sheets[position].search_element("r:id").map(String::from);
+        let name = self.get_sheet_names().remove(position);
+        let part = self.get_excel_sheet_name(&name).to_string();
+        if let Some(r_id) = r_id {
+            self.relationships.remove_relationship(&r_id);
+        }
+        let mut index = 0;
+        self.get_sheets_node_mut().retain_nodes(|_| {
+            index += 1;
+            index - 1 != position
+        });
+        self.move_sheet_indexes(|index| match index {
+            index if index < position => Some(index),
+            index if index > position => Some(index - 1),
+            _ => None,
+        });
+        self.rewrite_defined_names(|text| remove_sheet(text, &name));
+        self.refresh_sheet_map();
+        Ok(part)
+    }
+    /// Renames the sheet, also in the defined names referring to it.
+    pub fn rename_sheet(&mut self, sheet_name: &str, new_name: &str) -> Result<(), SheetError> {
+        let position = self.find_sheet_index(sheet_name)?;
+        self.check_new_sheet_name(new_name, Some(position))?;
+        let old_name = self.get_sheet_names().remove(position);
+        if let Some(sheet) = self
+            .get_sheets_node_mut()
+            .get_child_nodes_mut()
+            .unwrap_or_default()
+            .into_iter()
+            .nth(position)
+        {
+            sheet.set_element("name", escape(new_name));
+        }
+        self.rewrite_defined_names(|text| rename_sheet(text, &old_name, new_name));
+        self.refresh_sheet_map();
+        Ok(())
+    }
+    /// Moves the references to the sheet in the defined names after lines of it are inserted
+    /// or deleted, `false` when no name refers to the moved lines.
+    pub fn shift_lines(&mut self, sheet_name: &str, shift: LineShift) -> bool {
+        // defined names write the sheet of every reference, so no sheet is their own
+        self.rewrite_defined_names(|text| shift_lines(text, "", sheet_name, shift))
+    }
+    /// Moves the tab of the sheet to `index`, the last position when it is beyond the tabs.
+    /// Names scoped to sheets and the active tab follow the sheets.
+    ///
+    ///     // Data, Summary, Notes
+    ///     workbook.move_sheet("Notes", 0)?;
+    ///     assert_eq!(workbook.get_sheet_names(), vec!["Notes", "Data", "Summary"]);
+    ///
+    pub fn move_sheet(&mut self, sheet_name: &str, index: usize) -> Result<(), SheetError> {
+        let from = self.find_sheet_index(sheet_name)?;
+        let to = index.min(self.get_sheet_names().len() - 1);
+        if from == to {
+            return Ok(());
+        }
+        let sheets = self.get_sheets_node_mut();
+        let sheet = sheets.get_child_nodes().unwrap_or_default()[from].clone();
+        let mut position = 0;
+        sheets.retain_nodes(|_| {
+            position += 1;
+            position - 1 != from
+        });
+        sheets.insert_node(to, sheet);
+        self.move_sheet_indexes(|index| match index {
+            index if index == from => Some(to),
+            index if from < to && (from..=to).contains(&index) => Some(index - 1),
+            index if to < from && (to..from).contains(&index) => Some(index + 1),
+            index => Some(index),
+        });
+        Ok(())
+    }
+    /// Copies the names scoped to the sheet `sheet_name` to the sheet `copy_name`,
+    /// with their references to the sheet moved to the copy, e.g. its print area.
+    pub fn copy_local_names(
+        &mut self,
+        sheet_name: &str,
+        copy_name: &str,
+    ) -> Result<(), SheetError> {
+        let from = self.find_sheet_index(sheet_name)?;
+        let to = self.find_sheet_index(copy_name)?;
+        let (from_name, to_name) = {
+            let names = self.get_sheet_names();
+            (names[from].clone(), names[to].clone())
+        };
+        let Some(defined_names) = self
+            .node
+            .search_node_mut("workbook")
+            .unwrap()
+            .search_node_mut("definedNames")
+        else {
+            return Ok(());
+        };
+        let copies = defined_names
+            .search_all_nodes("definedName")
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|name| name.search_element("localSheetId") == Some(&from.to_string()))
+            .map(|name| {
+                let mut copy = name.clone();
+                copy.set_element("localSheetId", to.to_string());
+                let text = unescape(&name.get_text().unwrap_or_default());
+                copy.change_text(escape(&rename_sheet(&text, &from_name, &to_name)));
+                copy
+            })
+            .collect::<Vec<_>>();
+        copies
+            .into_iter()
+            .for_each(|copy| defined_names.add_node(copy));
+        Ok(())
+    }
+    pub fn to_xml(&self) -> String {
+        self.node.to_string()
+    }
+    fn get_sheets_node(&self) -> &XMLNode<'a> {
+        self.node
+            .search_node("workbook")
+            .unwrap()
+            .search_node("sheets")
+            .unwrap()
+    }
+    fn get_sheets_node_mut(&mut self) -> &mut XMLNode<'a> {
+        self.node
+            .search_node_mut("workbook")
+            .unwrap()
+            .search_node_mut("sheets")
+            .unwrap()
+    }
+    fn find_sheet_index(&self, sheet_name: &str) -> Result<usize, SheetError> {
+        self.get_sheet_index(sheet_name)
+            .ok_or_else(|| SheetError::NotFound(sheet_name.to_string()))
+    }
+    /// `except` is the sheet being renamed, which may take its own name in another case.
+    fn check_new_sheet_name(&self, name: &str, except: Option<usize>) -> Result<(), SheetError> {
+        let is_valid = !name.is_empty()
+            && name.chars().count() <= MAX_SHEET_NAME_LENGTH
+            && !name.contains(INVALID_SHEET_NAME_CHARS)
+            && !name.starts_with('\'')
+            && !name.ends_with('\'');
+        if !is_valid {
+            return Err(SheetError::InvalidName(name.to_string()));
+        }
+        match self.get_sheet_index(name) {
+            Some(index) if Some(index) != except => {
+                Err(SheetError::DuplicateName(name.to_string()))
+            }
+            _ => Ok(()),
+        }
+    }
+    /// Renumbers the tab positions kept in the workbook after tabs are moved or removed.
+    /// Names scoped to a sheet `f` gives `None` are removed, the active and first tabs stay on the tabs.
+    fn move_sheet_indexes(&mut self, f: impl Fn(usize) -> Option<usize>) {
+        let last = self.get_sheet_names().len().saturating_sub(1);
+        let workbook = self.node.search_node_mut("workbook").unwrap();
+        if let Some(defined_names) = workbook.search_node_mut("definedNames") {
+            defined_names.retain_nodes(|name| {
+                let local_id = name
+                    .search_element("localSheetId")
+                    .and_then(|id| id.parse::<usize>().ok());
+                local_id.is_none_or(|id| f(id).is_some())
+            });
+            for name in defined_names
+                .search_all_nodes_mut("definedName")
+                .unwrap_or_default()
+            {
+                if let Some(moved) = name
+                    .search_element("localSheetId")
+                    .and_then(|id| f(id.parse::<usize>().ok()?))
+                {
+                    name.set_element("localSheetId", moved.to_string());
+                }
+            }
+            if defined_names
+                .search_all_nodes("definedName")
+                .unwrap_or_default()
+                .is_empty()
+            {
+                workbook.remove_node("definedNames");
+            }
+        }
+        let Some(views) = workbook.search_node_mut("bookViews") else {
+            return;
+        };
+        for view in views
+            .search_all_nodes_mut("workbookView")
+            .unwrap_or_default()
+        {
+            for key in ["activeTab", "firstSheet"] {
+                let Some(index) = view
+                    .search_element(key)
+                    .and_then(|index| index.parse::<usize>().ok())
+                else {
+                    continue;
+                };
+                let moved = f(index).unwrap_or(index).min(last);
+                view.set_element(key, moved.to_string());
+            }
+        }
+    }
+    /// Whether any name changed.
+    fn rewrite_defined_names(&mut self, f: impl Fn(&str) -> String) -> bool {
+        let Some(defined_names) = self
+            .node
+            .search_node_mut("workbook")
+            .unwrap()
+            .search_node_mut("definedNames")
+        else {
+            return false;
+        };
+        let mut is_changed = false;
+        for name in defined_names
+            .search_all_nodes_mut("definedName")
+            .unwrap_or_default()
+        {
+            let Some(text) = name.get_text().map(|text| unescape(&text)) else {
+                continue;
+            };
+            let rewritten = f(&text);
+            if rewritten != text {
+                name.change_text(escape(&rewritten));
+                is_changed = true;
+            }
+        }
+        is_changed
+    }
+    fn refresh_sheet_map(&mut self) {
+        let workbook_node = self.node.search_node("workbook").unwrap();
+        let sheets_node = workbook_node.search_node("sheets").unwrap();
+        self.sheet_map = SheetMap::new_with_relationships(sheets_node, &self.relationships);
+    }
+}
+/// Whether the `<definedName>` is `name` scoped to the sheet at `local_sheet_id`.
+fn is_same_name(defined: &XMLNode, name: &str, local_sheet_id: Option<usize>) -> bool {
+    defined
+        .get_element_text("name")
+        .is_some_and(|defined| unescape(&defined).to_lowercase() == name.to_lowercase())
+        && defined
+            .search_element("localSheetId")
+            .and_then(|id| id.parse::<usize>().ok())
+            == local_sheet_id
 }
 impl<'a> XL<'a> for WorkBook<'a> {
     fn get_xml_node(&'a self) -> &'a XMLNode<'a> {
@@ -29,9 +602,24 @@ impl<'a> XL<'a> for WorkBook<'a> {
 }
 #[cfg(test)]
 mod workbook_test {
-    use crate::{excel::xmls::xl::XL, xml::nodes::node::XMLNode};
+    use crate::{
+        excel::{
+            cells::line_shift::LineShift,
+            xmls::{relationships::EMPTY_RELATIONSHIPS, xl::XL},
+        },
+        xml::nodes::node::XMLNode,
+    };
 
-    use super::WorkBook;
+    use super::{DefinedNameError, SheetError, WorkBook};
+    const RELS: &str = r#"
+<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+<Relationship Id="rId3" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet3.xml"/>
+<Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="/xl/worksheets/sheet1.xml"/>
+<Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet2.xml"/>
+<Relationship Id="rId4" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/theme" Target="theme/theme1.xml"/>
+</Relationships>
+"#;
     #[test]
     fn new_test() {
         let source = r#"
@@ -62,10 +650,278 @@ mod workbook_test {
                 </workbook>
         "#;
         let node = XMLNode::from(source);
-        let workbook = WorkBook::new(source);
+        let workbook = WorkBook::new(source, EMPTY_RELATIONSHIPS);
         assert_eq!(workbook.get_xml_node(), &node);
     }
     #[test]
+    fn is_date1904_test() {
+        let source = |workbook_pr: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+{}
+<sheets>
+<sheet name="term1" sheetId="1" r:id="rId1"/>
+</sheets>
+</workbook>"#,
+                workbook_pr
+            )
+        };
+        let date1904 = source(r#"<workbookPr date1904="1" defaultThemeVersion="166925"/>"#);
+        assert!(WorkBook::new(&date1904, RELS).is_date1904());
+        let date1900 = source(r#"<workbookPr defaultThemeVersion="166925"/>"#);
+        assert!(!WorkBook::new(&date1900, RELS).is_date1904());
+        let no_workbook_pr = source("");
+        assert!(!WorkBook::new(&no_workbook_pr, RELS).is_date1904());
+    }
+    #[test]
+    fn to_xml_with_full_calc_test() {
+        let source = |calc_pr: &str| {
+            format!(
+                r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main">
+<sheets>
+<sheet name="term1" sheetId="1" r:id="rId1"/>
+</sheets>
+{}
+<extLst></extLst>
+</workbook>"#,
+                calc_pr
+            )
+        };
+        let with_calc_pr = source(r#"<calcPr calcId="191029"/>"#);
+        let xml = WorkBook::new(&with_calc_pr, RELS).to_xml_with_full_calc();
+        assert!(xml.contains(r#"<calcPr calcId="191029" fullCalcOnLoad="1"/>"#));
+        let without_calc_pr = source("");
+        let xml = WorkBook::new(&without_calc_pr, RELS).to_xml_with_full_calc();
+        assert!(xml.contains(r#"</sheets><calcPr fullCalcOnLoad="1"/><extLst>"#));
+    }
+    const EDIT_WORKBOOK: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships">
+<bookViews><workbookView activeTab="2"/></bookViews>
+<sheets>
+<sheet name="term1" sheetId="1" r:id="rId1"/>
+<sheet name="term2" sheetId="2" state="hidden" r:id="rId2"/>
+<sheet name="table" sheetId="3" r:id="rId3"/>
+</sheets>
+<definedNames>
+<definedName name="_xlnm.Print_Area" localSheetId="0">term1!$A$1:$C$3</definedName>
+<definedName name="Total" localSheetId="2">table!$B$9</definedName>
+<definedName name="Terms">term1!$A:$A</definedName>
+</definedNames>
+</workbook>"#;
+    #[test]
+    fn add_sheet_test() {
+        let mut workbook = WorkBook::new(EDIT_WORKBOOK, RELS);
+        assert_eq!(
+            workbook.add_sheet("Sales & Costs", "xl/worksheets/sheet4.xml"),
+            Ok(4)
+        );
+        assert_eq!(
+            workbook.get_sheet_names(),
+            vec!["term1", "term2", "table", "Sales & Costs"]
+        );
+        assert_eq!(
+            workbook.get_excel_sheet_name("Sales & Costs"),
+            "xl/worksheets/sheet4.xml"
+        );
+        assert!(workbook
+            .to_xml()
+            .contains(r#"<sheet name="Sales &amp; Costs" sheetId="4" r:id="rId5"/>"#));
+        assert!(workbook
+            .get_relationships()
+            .to_xml()
+            .contains(r#"Id="rId5" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet4.xml""#));
+        assert_eq!(
+            workbook.add_sheet("TABLE", "xl/worksheets/sheet5.xml"),
+            Err(SheetError::DuplicateName("TABLE".to_string()))
+        );
+        assert_eq!(
+            workbook.add_sheet("a/b", "xl/worksheets/sheet5.xml"),
+            Err(SheetError::InvalidName("a/b".to_string()))
+        );
+    }
+    #[test]
+    fn remove_sheet_test() {
+        let mut workbook = WorkBook::new(EDIT_WORKBOOK, RELS);
+        assert_eq!(
+            workbook.remove_sheet("Term1"),
+            Ok("xl/worksheets/sheet2.xml".to_string())
+        );
+        assert_eq!(workbook.get_sheet_names(), vec!["term2", "table"]);
+        assert_eq!(workbook.get_relationships().get_relationship("rId1"), None);
+        let xml = workbook.to_xml();
+        assert!(xml.contains(r#"<workbookView activeTab="1"/>"#));
+        assert!(!xml.contains("_xlnm.Print_Area"));
+        assert!(
+            xml.contains(r#"<definedName name="Total" localSheetId="1">table!$B$9</definedName>"#)
+        );
+        assert!(xml.contains(r#"<definedName name="Terms">#REF!</definedName>"#));
+        // term2 is hidden
+        assert_eq!(
+            workbook.remove_sheet("table"),
+            Err(SheetError::LastVisibleSheet("table".to_string()))
+        );
+        assert_eq!(
+            workbook.remove_sheet("term1"),
+            Err(SheetError::NotFound("term1".to_string()))
+        );
+    }
+    #[test]
+    fn shift_lines_test() {
+        let mut workbook = WorkBook::new(EDIT_WORKBOOK, RELS);
+        assert!(workbook.shift_lines("term1", LineShift::InsertRows { at: 2, count: 2 }));
+        let xml = workbook.to_xml();
+        assert!(xml.contains(
+            r#"<definedName name="_xlnm.Print_Area" localSheetId="0">term1!$A$1:$C$5</definedName>"#
+        ));
+        assert!(xml.contains(r#"<definedName name="Terms">term1!$A:$A</definedName>"#));
+        assert!(xml.contains(r#"table!$B$9</definedName>"#));
+        assert!(!workbook.shift_lines("term2", LineShift::DeleteRows { at: 1, count: 1 }));
+        assert!(workbook.shift_lines("table", LineShift::DeleteRows { at: 9, count: 1 }));
+        assert!(workbook
+            .to_xml()
+            .contains(r#"<definedName name="Total" localSheetId="2">table!#REF!</definedName>"#));
+    }
+    #[test]
+    fn rename_and_move_sheet_test() {
+        let mut workbook = WorkBook::new(EDIT_WORKBOOK, RELS);
+        workbook.rename_sheet("term1", "Term 1").unwrap();
+        assert_eq!(
+            workbook.get_excel_sheet_name("Term 1"),
+            "xl/worksheets/sheet2.xml"
+        );
+        assert!(workbook
+            .to_xml()
+            .contains(r#"<definedName name="Terms">'Term 1'!$A:$A</definedName>"#));
+        assert_eq!(workbook.rename_sheet("term 1", "TERM 1"), Ok(()));
+        assert_eq!(
+            workbook.rename_sheet("table", "term2"),
+            Err(SheetError::DuplicateName("term2".to_string()))
+        );
+
+        workbook.move_sheet("table", 0).unwrap();
+        assert_eq!(workbook.get_sheet_names(), vec!["table", "TERM 1", "term2"]);
+        let xml = workbook.to_xml();
+        assert!(xml.contains(r#"<workbookView activeTab="0"/>"#));
+        assert!(xml.contains(r#"name="_xlnm.Print_Area" localSheetId="1""#));
+        assert!(xml.contains(r#"name="Total" localSheetId="0""#));
+        workbook.move_sheet("table", 9).unwrap();
+        assert_eq!(workbook.get_sheet_names(), vec!["TERM 1", "term2", "table"]);
+
+        workbook
+            .add_sheet("copy", "xl/worksheets/sheet4.xml")
+            .unwrap();
+        workbook.copy_local_names("TERM 1", "copy").unwrap();
+        assert!(workbook.to_xml().contains(
+            r#"<definedName name="_xlnm.Print_Area" localSheetId="3">copy!$A$1:$C$3</definedName>"#
+        ));
+    }
+    #[test]
+    fn get_defined_name_test() {
+        let workbook = WorkBook::new(EDIT_WORKBOOK, RELS);
+        let names = workbook.get_defined_names();
+        assert_eq!(
+            names.iter().map(|name| name.get_name()).collect::<Vec<_>>(),
+            vec!["_xlnm.Print_Area", "Total", "Terms"]
+        );
+        assert_eq!(names[1].get_local_sheet_id(), Some(2));
+        assert!(workbook.get_defined_name("total", None).is_none());
+        assert_eq!(
+            workbook
+                .get_defined_name("total", Some("Table"))
+                .unwrap()
+                .get_formula(),
+            "table!$B$9"
+        );
+        // names of the workbook are seen from every sheet
+        assert!(workbook.get_defined_name("Terms", Some("term2")).is_some());
+        assert!(workbook
+            .get_defined_name("Terms", Some("nothing"))
+            .is_none());
+
+        let range = |name: &str, sheet: Option<&str>| {
+            workbook
+                .get_named_range(name, sheet)
+                .map(|(sheet, range)| (sheet, range.to_string()))
+        };
+        assert_eq!(
+            range("terms", None),
+            Some(("term1".to_string(), "A:A".to_string()))
+        );
+        assert_eq!(
+            range("_xlnm.Print_Area", Some("term1")),
+            Some(("term1".to_string(), "A1:C3".to_string()))
+        );
+        assert_eq!(range("_xlnm.Print_Area", Some("term2")), None);
+    }
+    #[test]
+    fn set_and_remove_defined_name_test() {
+        let mut workbook = WorkBook::new(EDIT_WORKBOOK, RELS);
+        workbook
+            .set_defined_name("TaxRate", "=term2!$B$1", None)
+            .unwrap();
+        workbook
+            .set_defined_name("Total", "table!$B$10", Some("table"))
+            .unwrap();
+        workbook
+            .set_defined_name("Total", "\"a<b\"", Some("term2"))
+            .unwrap();
+        let xml = workbook.to_xml();
+        assert!(xml.contains(r#"<definedName name="TaxRate">term2!$B$1</definedName>"#));
+        assert!(
+            xml.contains(r#"<definedName name="Total" localSheetId="2">table!$B$10</definedName>"#)
+        );
+        assert!(xml.contains(
+            r#"<definedName name="Total" localSheetId="1">&quot;a&lt;b&quot;</definedName>"#
+        ));
+        assert_eq!(
+            workbook
+                .get_defined_name("Total", Some("term2"))
+                .unwrap()
+                .get_formula(),
+            "\"a<b\""
+        );
+        assert_eq!(
+            workbook.set_defined_name("A1", "1", None),
+            Err(DefinedNameError::InvalidName("A1".to_string()))
+        );
+        assert_eq!(
+            workbook.set_defined_name("Rate", "SUM(", None),
+            Err(DefinedNameError::InvalidFormula("SUM(".to_string()))
+        );
+        assert_eq!(
+            workbook.set_defined_name("Rate", "1", Some("nothing")),
+            Err(DefinedNameError::Sheet(SheetError::NotFound(
+                "nothing".to_string()
+            )))
+        );
+
+        workbook
+            .remove_defined_name("total", Some("term2"))
+            .unwrap();
+        assert_eq!(
+            workbook.remove_defined_name("Total", None),
+            Err(DefinedNameError::NotFound("Total".to_string()))
+        );
+        for (name, sheet) in [
+            ("_xlnm.Print_Area", Some("term1")),
+            ("Total", Some("table")),
+            ("Terms", None),
+            ("TaxRate", None),
+        ] {
+            workbook.remove_defined_name(name, sheet).unwrap();
+        }
+        assert!(!workbook.to_xml().contains("definedNames"));
+
+        // added after the sheets when the workbook has no names
+        workbook.set_defined_name("Rate", "0.1", None).unwrap();
+        assert!(workbook.to_xml().contains(
+            r#"</sheets><definedNames><definedName name="Rate">0.1</definedName></definedNames>"#
+        ));
+        assert!(workbook.is_name_defined("RATE"));
+    }
+    #[test]
     fn get_excel_sheet_name_test() {
         let source = r#"
                 <?xml version="1.0" encoding="UTF-8" standalone="yes"?>
@@ -94,17 +950,35 @@ mod workbook_test {
                     </extLst>
                 </workbook>
         "#;
-        let workbook = WorkBook::new(source);
-        assert_eq!(workbook.get_excel_sheet_name("term1"), "sheet1");
-        assert_eq!(workbook.get_excel_sheet_name("term2"), "sheet2");
-        assert_eq!(workbook.get_excel_sheet_name("table"), "sheet3");
+        let workbook = WorkBook::new(source, RELS);
+        assert_eq!(
+            workbook.get_excel_sheet_name("term1"),
+            "xl/worksheets/sheet2.xml"
+        );
+        assert_eq!(
+            workbook.get_excel_sheet_name("term2"),
+            "xl/worksheets/sheet1.xml"
+        );
+        assert_eq!(
+            workbook.get_excel_sheet_name("table"),
+            "xl/worksheets/sheet3.xml"
+        );
+        // without relationships the part is guessed from sheetId
+        let workbook = WorkBook::new(source, EMPTY_RELATIONSHIPS);
+        assert_eq!(
+            workbook.get_excel_sheet_name("table"),
+            "xl/worksheets/sheet3.xml"
+        );
     }
 }
 
 mod sheet_map {
     use std::collections::HashMap;
 
-    use crate::xml::nodes::node::XMLNode;
+    use crate::{
+        excel::xmls::relationships::Relationships,
+        xml::{escape::unescape, nodes::node::XMLNode},
+    };
 
     #[derive(PartialEq, Eq, Debug)]
     pub struct SheetMap(HashMap<ExcelDefineSheetName, UserDefineSheetName>);
@@ -131,16 +1005,27 @@ mod sheet_map {
         }
     }
 
-    impl<'a> From<&XMLNode<'a>> for SheetMap {
-        fn from(sheets_node: &XMLNode<'a>) -> Self {
+    impl SheetMap {
+        /// Maps each `<sheet>` to the part its `r:id` points at.
+        /// Sheets missing from the relationships fall back to `sheet{sheetId}.xml`.
+        pub fn new_with_relationships(
+            sheets_node: &XMLNode,
+            relationships: &Relationships,
+        ) -> Self {
             let mut sheet_names = SheetMap::new();
             let sheets = sheets_node
                 .search_all_nodes("sheet")
                 .expect(format!("invalid node {:?}", sheets_node).as_str());
             sheets.iter().for_each(|sheet| {
-                let e_sheet_id = sheet.search_element("sheetId").unwrap();
-                let u_sheet = UserDefineSheetName::new(sheet.search_element("name").unwrap());
-                let e_sheet = ExcelDefineSheetName::from(e_sheet_id);
+                let u_sheet =
+                    UserDefineSheetName::new(unescape(&sheet.get_element_text("name").unwrap()));
+                let e_sheet = sheet
+                    .search_element("r:id")
+                    .and_then(|id| relationships.get_target_part(id))
+                    .map(ExcelDefineSheetName::new)
+                    .unwrap_or_else(|| {
+                        ExcelDefineSheetName::from(sheet.search_element("sheetId").unwrap())
+                    });
                 sheet_names.set(e_sheet, u_sheet);
             });
             sheet_names
@@ -171,12 +1056,12 @@ mod sheet_map {
     }
     impl From<&str> for ExcelDefineSheetName {
         fn from(id: &str) -> Self {
-            ExcelDefineSheetName::new(format!("sheet{}", id))
+            ExcelDefineSheetName::new(format!("xl/worksheets/sheet{}.xml", id))
         }
     }
     impl From<u8> for ExcelDefineSheetName {
         fn from(id: u8) -> Self {
-            ExcelDefineSheetName::new(format!("sheet{}", id))
+            ExcelDefineSheetName::new(format!("xl/worksheets/sheet{}.xml", id))
         }
     }
     impl SheetName for ExcelDefineSheetName {
@@ -209,11 +1094,11 @@ mod sheet_map {
             let sheet5 = ExcelDefineSheetName::from(5);
             assert_eq!(
                 sheet_nams.get_excel_sheet_name(UserDefineSheetName::new("user1")),
-                Some("sheet1")
+                Some("xl/worksheets/sheet1.xml")
             );
             assert_eq!(
                 sheet_nams.get_excel_sheet_name(UserDefineSheetName::new("user4")),
-                Some("sheet4")
+                Some("xl/worksheets/sheet4.xml")
             );
             assert_eq!(sheet_nams.get_excel_sheet_name(user5), None);
             assert_eq!(
@@ -233,23 +1118,40 @@ mod sheet_map {
                     <sheet name="term1" sheetId="1" state="hidden" r:id="rId1"/>
                     <sheet name="term2" sheetId="2" state="hidden" r:id="rId2"/>
                     <sheet name="テーブル一覧" sheetId="8" r:id="rId3"/>
+                    <sheet name="求人情報テーブル(job_info)" sheetId="5" r:id="rId4"/>
                 </sheets>
+        "#;
+            let rels = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+                <Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships">
+                    <Relationship Id="rId1" Target="worksheets/sheet1.xml"/>
+                    <Relationship Id="rId2" Target="worksheets/sheet2.xml"/>
+                    <Relationship Id="rId3" Target="worksheets/sheet3.xml"/>
+                </Relationships>
         "#;
             let source_node = XMLNode::from(source);
+            let relationships = Relationships::new(rels, "xl/workbook.xml");
             let mut tobe = SheetMap::new();
             tobe.set(
-                ExcelDefineSheetName::new("sheet1"),
+                ExcelDefineSheetName::new("xl/worksheets/sheet1.xml"),
                 UserDefineSheetName::new("term1"),
             );
             tobe.set(
-                ExcelDefineSheetName::new("sheet2"),
+                ExcelDefineSheetName::new("xl/worksheets/sheet2.xml"),
                 UserDefineSheetName::new("term2"),
             );
             tobe.set(
-                ExcelDefineSheetName::new("sheet8"),
+                ExcelDefineSheetName::new("xl/worksheets/sheet3.xml"),
                 UserDefineSheetName::new("テーブル一覧"),
             );
-            assert_eq!(SheetMap::from(&source_node), tobe);
+            // rId4 is missing from the relationships
+            tobe.set(
+                ExcelDefineSheetName::new("xl/worksheets/sheet5.xml"),
+                UserDefineSheetName::new("求人情報テーブル(job_info)"),
+            );
+            assert_eq!(
+                SheetMap::new_with_relationships(&source_node, &relationships),
+                tobe
+            );
         }
     }
 }
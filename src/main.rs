@@ -1,15 +1,16 @@
 use crate::excel::cells::cell::ECell;
 use crate::excel::excel::Excel;
-use crate::excel::file_operator::XLSXFile;
+use crate::excel::file_operator::XLSXPackage;
 use crate::excel::xmls::sheet::WorkSheet;
 
 mod drawio;
 mod excel;
 mod html;
 mod xml;
+mod zip;
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let mut xlsx = XLSXFile::open("test_buf.xlsx");
+    let mut xlsx = XLSXPackage::open("test_buf.xlsx");
     let mut excel = Excel::new(&mut xlsx);
     excel.read_sheet("term1");
     let mut sheet1 = excel.get_sheet("term1");
@@ -1,2 +1,3 @@
+pub mod escape;
 pub mod nodes;
 pub mod tokens;
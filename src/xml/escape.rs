@@ -0,0 +1,83 @@
+/// Replaces the predefined entities and character references with their characters.
+///
+///     assert_eq!(unescape("$E$3&amp;G4"), "$E$3&G4");
+///
+pub fn unescape(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut result = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let replaced = rest.find(';').and_then(|end| {
+            let entity = &rest[1..end];
+            let c = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity
+                    .strip_prefix("#x")
+                    .map(|hex| u32::from_str_radix(hex, 16))
+                    .or_else(|| entity.strip_prefix('#').map(|dec| dec.parse::<u32>()))
+                    .and_then(|code| code.ok())
+                    .and_then(char::from_u32),
+            };
+            c.map(|c| (c, end))
+        });
+        match replaced {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+/// Replaces the characters that can not appear as they are in text or attribute values.
+///
+///     assert_eq!(escape("a<b & c"), "a&lt;b &amp; c");
+///
+pub fn escape(s: &str) -> String {
+    s.chars()
+        .fold(String::with_capacity(s.len()), |mut acc, c| {
+            match c {
+                '&' => acc.push_str("&amp;"),
+                '<' => acc.push_str("&lt;"),
+                '>' => acc.push_str("&gt;"),
+                '"' => acc.push_str("&quot;"),
+                _ => acc.push(c),
+            }
+            acc
+        })
+}
+
+#[cfg(test)]
+mod escape_test {
+    use super::{escape, unescape};
+
+    #[test]
+    fn unescape_test() {
+        assert_eq!(unescape("$E$3&amp;G4"), "$E$3&G4");
+        assert_eq!(
+            unescape("&lt;a&gt; &quot;b&quot; &apos;c&apos;"),
+            "<a> \"b\" 'c'"
+        );
+        assert_eq!(unescape("&#12354;&#x3042;"), "ああ");
+        assert_eq!(unescape("a & b &unknown;"), "a & b &unknown;");
+    }
+    #[test]
+    fn escape_test() {
+        assert_eq!(escape(r#"a<b & "c""#), "a&lt;b &amp; &quot;c&quot;");
+        assert_eq!(unescape(&escape("<&>'")), "<&>'");
+        assert_eq!(escape("会社名"), "会社名");
+    }
+}
@@ -162,7 +162,7 @@ fn start_or_single_token_to_node<'a>(token: Token<'a>) -> XMLNode<'a> {
         element.key_push(source.get(start_index..source.len()).unwrap());
         element.empty_push();
     }
-    let mut node = XMLNode::new(&source.get(node_char_range).unwrap(), node_type);
+    let mut node = XMLNode::new(source.get(node_char_range).unwrap(), node_type);
     let mut key_values = element.key_values();
     key_values.iter_mut().for_each(|(key, values)| {
         node.add_element(*key, values.drain(..).collect());
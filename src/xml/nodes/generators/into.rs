@@ -6,9 +6,7 @@ impl<'a> XMLNode<'a> {
             NodeType::Character => format!("{}", self.get_value()),
             NodeType::Element => {
                 if let Some(children) = self.get_children() {
-                    let result = children.iter().fold(String::new(), |acc, cur| {
-                        format!("{}{}", acc, cur.to_string())
-                    });
+                    let result = children_to_string(&children);
                     format!(
                         "<{}>{}</{}>",
                         self.get_node_value().to_string(),
@@ -25,9 +23,7 @@ impl<'a> XMLNode<'a> {
             }
             NodeType::SingleElement => {
                 if let Some(children) = self.get_children() {
-                    let result = children.iter().fold(String::new(), |acc, cur| {
-                        format!("{}{}", acc, cur.to_string())
-                    });
+                    let result = children_to_string(&children);
                     if self.get_value().chars().nth(0).unwrap() == '?' {
                         let str = self.get_node_value().to_string();
                         let remove_last_question = str.get(..(str.len() - 2)).unwrap();
@@ -42,6 +38,21 @@ impl<'a> XMLNode<'a> {
         }
     }
 }
+/// Character data is split at whitespace by the tokenizer,
+/// so adjacent character nodes are joined by a space again.
+fn children_to_string(children: &[&XMLNode]) -> String {
+    let mut result = String::new();
+    let mut is_prev_character = false;
+    for child in children {
+        let is_character = child.get_node_type() == NodeType::Character;
+        if is_character && is_prev_character {
+            result.push(' ');
+        }
+        result.push_str(&child.to_string());
+        is_prev_character = is_character;
+    }
+    result
+}
 impl<'a> Into<String> for XMLNode<'a> {
     fn into(self) -> String {
         self.to_string()
@@ -70,6 +81,12 @@ mod xml_into_str_test {
         assert_eq!(expect, data);
     }
     #[test]
+    fn to_string_keeps_spaces_test() {
+        let data = r#"<si><t>hello world rust</t><t>a</t></si>"#;
+        let root_node = XMLNode::from(data);
+        assert_eq!(root_node.to_string(), data);
+    }
+    #[test]
 
     fn into_test_2() {
         let data = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use super::{node_type::NodeType, node_value::NodeValue};
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct XMLNode<'a> {
@@ -7,7 +9,7 @@ pub struct XMLNode<'a> {
 }
 
 impl<'a> XMLNode<'a> {
-    pub fn new(s: &'a str, node_type: NodeType) -> Self {
+    pub fn new(s: impl Into<Cow<'a, str>>, node_type: NodeType) -> Self {
         XMLNode {
             value: NodeValue::new(s),
             children: None,
@@ -119,6 +121,14 @@ impl<'a> XMLNode<'a> {
                 .collect::<Vec<_>>()
         })
     }
+    /// Returns the character data of the node.
+    /// Values such as `<t>` and `<f>` are read as they are written, the character data of
+    /// other elements is split at whitespace by the tokenizer, so the parts are joined by a space.
+    pub fn get_text(&self) -> Option<String> {
+        self.get_all_texts()
+            .filter(|texts| !texts.is_empty())
+            .map(|texts| texts.join(" "))
+    }
 
     #[allow(dead_code)]
     pub fn search_node_mut(&mut self, search_value: &str) -> Option<&mut XMLNode<'a>> {
@@ -164,7 +174,7 @@ impl<'a> XMLNode<'a> {
         None
     }
     #[allow(dead_code)]
-    pub fn search_all_nodes(&self, search_value: &str) -> Option<Vec<&XMLNode>> {
+    pub fn search_all_nodes(&self, search_value: &str) -> Option<Vec<&XMLNode<'a>>> {
         if self.has_nodes() {
             return Some(
                 self.get_child_nodes()
@@ -182,6 +192,12 @@ impl<'a> XMLNode<'a> {
     pub fn search_element(&self, key: &str) -> Option<&str> {
         self.value.search_element(key)
     }
+    /// Returns the whole attribute value, `search_element` only returns the part before the first whitespace.
+    pub fn get_element_text(&self, key: &str) -> Option<String> {
+        self.value
+            .search_all_element(key)
+            .map(|values| values.join(" "))
+    }
     #[allow(dead_code)]
     pub fn nth_child_node(&self, n: usize) -> Option<&XMLNode> {
         if self.has_nodes() {
@@ -196,18 +212,48 @@ impl<'a> XMLNode<'a> {
         }
         self.children = Some(Box::new(vec![child]));
     }
-    pub fn add_text(&mut self, s: &'a str) {
-        if self.has_children() {
-            s.split(' ').for_each(|s| {
-                self.children
-                    .as_mut()
-                    .unwrap()
-                    .push(XMLNode::new(s, NodeType::Character))
-            });
+    pub fn add_text(&mut self, s: impl Into<Cow<'a, str>>) {
+        let s = s.into();
+        if let Some(children) = self.children.as_mut() {
+            children.extend(
+                split_text(s)
+                    .into_iter()
+                    .map(|s| XMLNode::new(s, NodeType::Character)),
+            );
             return;
         }
         self.children = Some(Box::new(vec![XMLNode::new(s, NodeType::Character)]));
     }
+    /// Removes every child node named `name`.
+    pub fn remove_node(&mut self, name: &str) {
+        if let Some(children) = self.children.as_mut() {
+            children.retain(|child| {
+                child.node_type == NodeType::Character || child.get_value() != name
+            });
+        }
+    }
+    /// Keeps the child nodes `f` accepts, text is always kept.
+    pub fn retain_nodes(&mut self, mut f: impl FnMut(&XMLNode<'a>) -> bool) {
+        if let Some(children) = self.children.as_mut() {
+            children.retain(|child| child.node_type == NodeType::Character || f(child));
+        }
+    }
+    /// Inserts `child` before the `index`th child node, or appends it when there are fewer nodes.
+    pub fn insert_node(&mut self, index: usize, child: XMLNode<'a>) {
+        let Some(children) = self.children.as_mut() else {
+            return self.add_node(child);
+        };
+        let position = children
+            .iter()
+            .enumerate()
+            .filter(|(_, node)| {
+                node.node_type == NodeType::SingleElement || node.node_type == NodeType::Element
+            })
+            .nth(index)
+            .map(|(i, _)| i)
+            .unwrap_or(children.len());
+        children.insert(position, child);
+    }
     pub fn change_child_node(&mut self, new_node: XMLNode<'a>) {
         if self.has_children() {
             for (i, child) in &mut self.children.as_mut().unwrap().iter().enumerate() {
@@ -220,35 +266,41 @@ impl<'a> XMLNode<'a> {
         }
         self.add_node(new_node)
     }
-    pub fn change_text(&mut self, s: &'a str) {
-        fn _push_text_node<'a>(node: &mut XMLNode<'a>, s: &'a str) {
-            let _ = s.split(' ').filter(|s| s.len() > 0).for_each(|splited| {
-                node.children
-                    .as_mut()
-                    .unwrap()
-                    .push(XMLNode::new(splited, NodeType::Character));
-            });
-        }
-        // todo not use clone
-        if self.has_children() {
-            let new_child = self
-                .children
-                .as_ref()
-                .unwrap()
-                .iter()
-                .filter(|node| node.node_type != NodeType::Character)
-                .map(|node| node.clone())
-                .collect::<Vec<_>>();
-            self.children = Some(Box::new(new_child));
-            _push_text_node(self, s)
+    pub fn change_text(&mut self, s: impl Into<Cow<'a, str>>) {
+        let mut children = self
+            .children
+            .take()
+            .map(|children| {
+                children
+                    .into_iter()
+                    .filter(|node| node.node_type != NodeType::Character)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        let s = s.into();
+        if !s.is_empty() {
+            children.extend(
+                split_text(s)
+                    .into_iter()
+                    .map(|s| XMLNode::new(s, NodeType::Character)),
+            );
         }
-        self.children = Some(Box::new(vec![]));
-        _push_text_node(self, s);
+        self.children = Some(Box::new(children));
     }
     #[allow(dead_code)]
     pub fn add_element(&mut self, key: &'a str, values: Vec<&'a str>) {
         self.value.add_element(key, values)
     }
+    /// Sets `key` to a single value, replacing the old values.
+    ///
+    ///     node.set_element("ref", format!("A1:{}", last));
+    ///
+    pub fn set_element(&mut self, key: &'a str, value: impl Into<Cow<'a, str>>) {
+        self.value.set_element(key, value.into())
+    }
+    pub fn remove_element(&mut self, key: &str) {
+        self.value.remove_element(key)
+    }
     #[allow(dead_code)]
     pub fn element_all(&self, key: &str, value: &str) -> Option<Vec<&XMLNode>> {
         if self.has_nodes() {
@@ -363,6 +415,15 @@ impl<'a> XMLNode<'a> {
     }
 }
 
+/// The tokenizer keeps character data split at spaces, so written text is split the same way.
+/// Empty parts are kept, joining the parts by a space gives back the text with all its spaces.
+fn split_text(s: Cow<'_, str>) -> Vec<Cow<'_, str>> {
+    match s {
+        Cow::Borrowed(s) => s.split(' ').map(Cow::Borrowed).collect(),
+        Cow::Owned(s) => s.split(' ').map(|s| Cow::Owned(s.to_string())).collect(),
+    }
+}
+
 #[cfg(test)]
 pub mod xml_node_test {
 
@@ -596,6 +657,20 @@ pub mod xml_node_test {
         assert_eq!(node, tobe_node);
     }
     #[test]
+    fn text_whitespace_round_trip_test() {
+        for text in [" lead", "trail ", "a  b", "l1\nl2", "tab\tx", "  "] {
+            let mut node = XMLNode::new("t", NodeType::Element);
+            node.change_text(text);
+            assert_eq!(node.get_text().as_deref(), Some(text));
+            let source = format!("<si>{}</si>", node.to_string());
+            let read = XMLNode::from(source.as_str());
+            assert_eq!(
+                read.search_node("t").unwrap().get_text().as_deref(),
+                Some(text)
+            );
+        }
+    }
+    #[test]
     fn search_all_child_test() {
         let data = r#"
         <xml>
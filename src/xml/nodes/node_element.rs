@@ -1,11 +1,18 @@
+use std::borrow::Cow;
+
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct NodeElement<'a>(Vec<(&'a str, Vec<&'a str>)>);
+pub struct NodeElement<'a>(Vec<(&'a str, Vec<Cow<'a, str>>)>);
 impl<'a> NodeElement<'a> {
     pub fn new(key: &'a str, values: Vec<&'a str>) -> Self {
-        NodeElement(vec![(key, values)])
+        NodeElement(vec![(key, to_cows(values))])
     }
     pub fn with_element(element: Vec<(&'a str, Vec<&'a str>)>) -> Self {
-        NodeElement(element)
+        NodeElement(
+            element
+                .into_iter()
+                .map(|(key, values)| (key, to_cows(values)))
+                .collect(),
+        )
     }
     fn index_of(&self, key: &str) -> Option<usize> {
         let mut index = None;
@@ -16,31 +23,44 @@ impl<'a> NodeElement<'a> {
         });
         index
     }
+    /// Replaces the values of `key` with one value that may be owned, e.g. a computed `ref`.
+    pub fn set(&mut self, key: &'a str, value: Cow<'a, str>) {
+        match self.index_of(key) {
+            Some(index) => self.0[index] = (key, vec![value]),
+            None => self.0.push((key, vec![value])),
+        }
+    }
+    pub fn remove(&mut self, key: &str) {
+        self.0.retain(|(e_key, _)| *e_key != key)
+    }
+}
+fn to_cows(values: Vec<&str>) -> Vec<Cow<'_, str>> {
+    values.into_iter().map(Cow::Borrowed).collect()
 }
 
 pub trait ElementsInterface<'a> {
     fn add(&mut self, key: &'a str, values: Vec<&'a str>) -> ();
     fn contains_key(&self, key: &str) -> bool;
     fn to_string(&self) -> String;
-    fn search_all(&self, key: &str) -> Option<&Vec<&'a str>>;
-    fn search(&self, key: &str) -> Option<&'a str>;
+    fn search_all(&self, key: &str) -> Option<Vec<&str>>;
+    fn search(&self, key: &str) -> Option<&str>;
     fn is_containe_key_value(&self, key: &str, value: &str) -> bool;
     fn change(&mut self, key: &'a str, values: Vec<&'a str>) -> ();
 }
 
 impl<'a> ElementsInterface<'a> for NodeElement<'a> {
-    fn search(&self, key: &str) -> Option<&'a str> {
+    fn search(&self, key: &str) -> Option<&str> {
         if let Some(values) = self.search_all(key) {
-            values.iter().map(|s| *s).next()
+            values.into_iter().next()
         } else {
             None
         }
     }
-    fn search_all(&self, key: &str) -> Option<&Vec<&'a str>> {
+    fn search_all(&self, key: &str) -> Option<Vec<&str>> {
         self.0
             .iter()
             .find(|(e_key, _values)| *e_key == key)
-            .map(|(_key, values)| values)
+            .map(|(_key, values)| values.iter().map(|value| value.as_ref()).collect())
     }
     fn contains_key(&self, key: &str) -> bool {
         self.0.iter().any(|(e_key, _values)| key == *e_key)
@@ -58,18 +78,20 @@ impl<'a> ElementsInterface<'a> for NodeElement<'a> {
                 .iter_mut()
                 .filter(|(e_key, _)| *e_key == key)
                 .for_each(|(_key, mut_values)| {
-                    values.iter().for_each(|value| mut_values.push(*value))
+                    values
+                        .iter()
+                        .for_each(|value| mut_values.push(Cow::Borrowed(*value)))
                 });
             return;
         }
-        self.0.push((key, values))
+        self.0.push((key, to_cows(values)))
     }
     fn change(&mut self, key: &'a str, values: Vec<&'a str>) -> () {
         if self.contains_key(key) {
             let index = self.index_of(key).unwrap();
-            self.0[index] = (key, values);
+            self.0[index] = (key, to_cows(values));
         } else {
-            self.0.push((key, values))
+            self.0.push((key, to_cows(values)))
         }
     }
     fn to_string(&self) -> String {
@@ -80,7 +102,7 @@ impl<'a> ElementsInterface<'a> for NodeElement<'a> {
         with_last_empty
     }
 }
-fn taple_to_string(taple: &(&str, Vec<&str>)) -> String {
+fn taple_to_string(taple: &(&str, Vec<Cow<'_, str>>)) -> String {
     if taple.1.len() == 0 {
         taple.0.to_string()
     } else {
@@ -92,8 +114,11 @@ fn taple_to_string(taple: &(&str, Vec<&str>)) -> String {
 mod node_tests {
     use crate::xml::nodes::node_element::ElementsInterface;
     impl<'a> NodeElement<'a> {
-        pub fn get(&self) -> Vec<(&'a str, Vec<&'a str>)> {
-            self.0.clone()
+        pub fn get(&self) -> Vec<(&str, Vec<&str>)> {
+            self.0
+                .iter()
+                .map(|(key, values)| (*key, values.iter().map(|v| v.as_ref()).collect()))
+                .collect()
         }
     }
     use super::NodeElement;
@@ -101,7 +126,7 @@ mod node_tests {
     fn change_test() {
         let mut element = NodeElement::new("test", vec!["value"]);
         element.change("test", vec!["value2", "value3"]);
-        assert_eq!(element.search_all("test"), Some(&vec!["value2", "value3"]));
+        assert_eq!(element.search_all("test"), Some(vec!["value2", "value3"]));
     }
     #[test]
     fn is_containe_key_value_test() {
@@ -125,8 +150,8 @@ mod node_tests {
     fn search_all_test() {
         let mut element = NodeElement::new("test", vec!["value"]);
         element.add("test2", vec!["value2", "value3"]);
-        assert_eq!(element.search_all("test"), Some(&vec!["value"]));
-        assert_eq!(element.search_all("test2"), Some(&vec!["value2", "value3"]));
+        assert_eq!(element.search_all("test"), Some(vec!["value"]));
+        assert_eq!(element.search_all("test2"), Some(vec!["value2", "value3"]));
         assert_eq!(element.search_all("test3"), None);
     }
     #[test]
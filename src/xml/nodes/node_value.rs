@@ -1,15 +1,17 @@
+use std::borrow::Cow;
+
 use super::node_element::{ElementsInterface, NodeElement};
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct NodeValue<'a> {
-    value: &'a str,
+    value: Cow<'a, str>,
     element: Option<NodeElement<'a>>,
 }
 
 impl<'a> NodeValue<'a> {
-    pub fn new(s: &'a str) -> Self {
+    pub fn new(s: impl Into<Cow<'a, str>>) -> Self {
         NodeValue {
-            value: s,
+            value: s.into(),
             element: None,
         }
     }
@@ -33,10 +35,10 @@ impl<'a> NodeValue<'a> {
             self.value.to_string()
         }
     }
-    pub fn change_value(&mut self, value: &'a str) {
-        self.value = value
+    pub fn change_value(&mut self, value: impl Into<Cow<'a, str>>) {
+        self.value = value.into()
     }
-    pub fn search_all_element(&self, key: &str) -> Option<&Vec<&str>> {
+    pub fn search_all_element(&self, key: &str) -> Option<Vec<&str>> {
         if self.element.is_some() {
             self.element.as_ref().unwrap().search_all(key)
         } else {
@@ -57,6 +59,21 @@ impl<'a> NodeValue<'a> {
             self.element = Some(NodeElement::new(key, values));
         }
     }
+    pub fn set_element(&mut self, key: &'a str, value: Cow<'a, str>) {
+        match self.element.as_mut() {
+            Some(element) => element.set(key, value),
+            None => {
+                let mut element = NodeElement::with_element(vec![]);
+                element.set(key, value);
+                self.element = Some(element);
+            }
+        }
+    }
+    pub fn remove_element(&mut self, key: &str) {
+        if let Some(element) = self.element.as_mut() {
+            element.remove(key)
+        }
+    }
 }
 impl<'a> Into<String> for NodeValue<'a> {
     fn into(self) -> String {
@@ -82,7 +99,7 @@ mod node_value_test {
         node.add_element("class", vec!["big"]);
         node.add_element("class", vec!["big2"]);
         assert_eq!(node.search_element("class"), Some("big"));
-        assert_eq!(node.search_all_element("class"), Some(&vec!["big", "big2"]));
+        assert_eq!(node.search_all_element("class"), Some(vec!["big", "big2"]));
         assert_eq!(node.search_element("non"), None);
     }
     #[test]
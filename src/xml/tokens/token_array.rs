@@ -1,8 +1,30 @@
 use super::token::{Token, TokenType};
 
+/// Local names of elements whose character data is a value, kept as one token with its whitespace.
+const RAW_TEXT_ELEMENTS: [&str; 16] = [
+    "t",
+    "v",
+    "f",
+    "formula",
+    "formula1",
+    "formula2",
+    "definedName",
+    "calculatedColumnFormula",
+    "totalsRowFormula",
+    "oddHeader",
+    "oddFooter",
+    "evenHeader",
+    "evenFooter",
+    "firstHeader",
+    "firstFooter",
+    "lpstr",
+];
+
 enum StateMachine {
     CharBlank,
     CharChar,
+    /// character data of an element in `RAW_TEXT_ELEMENTS` or with `xml:space="preserve"`
+    RawChar,
     StartStart,
     EndChar,
     StartChar,
@@ -45,6 +67,18 @@ pub fn create_token_array<'a>(source: &'a str) -> Vec<Token<'a>> {
                 }
             }
         },
+        StateMachine::RawChar => {
+            if c == 60 {
+                if start_index < i {
+                    vec.push(Token::with_type(
+                        source.get(start_index..i).unwrap(),
+                        TokenType::Character,
+                    ));
+                }
+                state = StateMachine::StartStart;
+                start_index = i + 1;
+            }
+        }
         StateMachine::StartStart => match c {
             47 => {
                 state = StateMachine::EndChar;
@@ -81,11 +115,14 @@ pub fn create_token_array<'a>(source: &'a str) -> Vec<Token<'a>> {
                 state = StateMachine::StartSlash;
             }
             62 => {
-                state = StateMachine::CharBlank;
-                vec.push(Token::with_type(
-                    source.get(start_index..i).unwrap(),
-                    TokenType::StartToken,
-                ))
+                let tag = source.get(start_index..i).unwrap();
+                state = if is_raw_text_element(tag) {
+                    StateMachine::RawChar
+                } else {
+                    StateMachine::CharBlank
+                };
+                vec.push(Token::with_type(tag, TokenType::StartToken));
+                start_index = i + 1;
             }
             _ => (),
         },
@@ -107,6 +144,13 @@ pub fn create_token_array<'a>(source: &'a str) -> Vec<Token<'a>> {
 
     vec
 }
+fn is_raw_text_element(tag: &str) -> bool {
+    let name = tag.split_ascii_whitespace().next().unwrap_or_default();
+    let local_name = name.rsplit(':').next().unwrap_or(name);
+    RAW_TEXT_ELEMENTS.contains(&local_name)
+        || tag.contains(r#"xml:space="preserve""#)
+        || tag.contains("xml:space='preserve'")
+}
 
 #[cfg(test)]
 mod p_token_array_test {
@@ -176,6 +220,35 @@ mod p_token_array_test {
             ]
         );
     }
+    #[test]
+    fn raw_text_test() {
+        let source = "<si><t xml:space=\"preserve\"> a  b\n\tc </t><x:t>d  e</x:t></si><c><f>\"a  b\"&amp;A2</f><v> </v></c><p> p  q </p>";
+        assert_eq!(
+            create_token_array(source),
+            vec![
+                Token::with_type("si", TokenType::StartToken),
+                Token::with_type(r#"t xml:space="preserve""#, TokenType::StartToken),
+                Token::with_type(" a  b\n\tc ", TokenType::Character),
+                Token::with_type("t", TokenType::EndToken),
+                Token::with_type("x:t", TokenType::StartToken),
+                Token::with_type("d  e", TokenType::Character),
+                Token::with_type("x:t", TokenType::EndToken),
+                Token::with_type("si", TokenType::EndToken),
+                Token::with_type("c", TokenType::StartToken),
+                Token::with_type("f", TokenType::StartToken),
+                Token::with_type("\"a  b\"&amp;A2", TokenType::Character),
+                Token::with_type("f", TokenType::EndToken),
+                Token::with_type("v", TokenType::StartToken),
+                Token::with_type(" ", TokenType::Character),
+                Token::with_type("v", TokenType::EndToken),
+                Token::with_type("c", TokenType::EndToken),
+                Token::with_type("p", TokenType::StartToken),
+                Token::with_type("p", TokenType::Character),
+                Token::with_type("q", TokenType::Character),
+                Token::with_type("p", TokenType::EndToken),
+            ]
+        );
+    }
 }
 
 //#[derive(Debug)]
@@ -0,0 +1,6 @@
+pub mod archive;
+pub mod crc32;
+pub mod deflate;
+pub mod error;
+pub mod inflate;
+pub mod writer;
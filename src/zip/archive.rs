@@ -0,0 +1,215 @@
+use super::{crc32::crc32, error::ZipError, inflate::inflate};
+
+pub(super) const LOCAL_FILE_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+pub(super) const CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0201_4b50;
+pub(super) const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x0605_4b50;
+const END_OF_CENTRAL_DIRECTORY_LEN: usize = 22;
+const LOCAL_FILE_HEADER_LEN: usize = 30;
+const CENTRAL_DIRECTORY_LEN: usize = 46;
+pub(super) const STORED: u16 = 0;
+pub(super) const DEFLATED: u16 = 8;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ZipEntry {
+    name: String,
+    method: u16,
+    flags: u16,
+    crc32: u32,
+    compressed_size: usize,
+    local_header_offset: usize,
+}
+impl ZipEntry {
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+    fn is_encrypted(&self) -> bool {
+        self.flags & 1 == 1
+    }
+}
+
+/// Read-only view of a zip archive held in memory.
+///
+/// Entries are listed from the central directory, and each entry is inflated
+/// on demand by `read`.
+#[derive(Debug)]
+pub struct ZipArchive<'a> {
+    source: &'a [u8],
+    entries: Vec<ZipEntry>,
+}
+impl<'a> ZipArchive<'a> {
+    pub fn new(source: &'a [u8]) -> Result<Self, ZipError> {
+        let eocd = find_end_of_central_directory(source)?;
+        let entry_len = read_u16(source, eocd + 10)? as usize;
+        let cd_size = read_u32(source, eocd + 12)? as usize;
+        let cd_offset = read_u32(source, eocd + 16)? as usize;
+        if entry_len == 0xFFFF || cd_size == 0xFFFF_FFFF || cd_offset == 0xFFFF_FFFF {
+            return Err(ZipError::UnsupportedZip64);
+        }
+        let mut entries = Vec::with_capacity(entry_len);
+        let mut offset = cd_offset;
+        for _ in 0..entry_len {
+            expect_signature(source, offset, CENTRAL_DIRECTORY_SIGNATURE)?;
+            let name_len = read_u16(source, offset + 28)? as usize;
+            let extra_len = read_u16(source, offset + 30)? as usize;
+            let comment_len = read_u16(source, offset + 32)? as usize;
+            let name_start = offset + CENTRAL_DIRECTORY_LEN;
+            let name = source
+                .get(name_start..name_start + name_len)
+                .ok_or(ZipError::UnexpectedEof)?;
+            entries.push(ZipEntry {
+                name: String::from_utf8_lossy(name).into_owned(),
+                flags: read_u16(source, offset + 8)?,
+                method: read_u16(source, offset + 10)?,
+                crc32: read_u32(source, offset + 16)?,
+                compressed_size: read_u32(source, offset + 20)? as usize,
+                local_header_offset: read_u32(source, offset + 42)? as usize,
+            });
+            offset = name_start + name_len + extra_len + comment_len;
+        }
+        Ok(ZipArchive { source, entries })
+    }
+    pub fn get_entries(&self) -> &Vec<ZipEntry> {
+        &self.entries
+    }
+    pub fn get_names(&self) -> Vec<&str> {
+        self.entries.iter().map(|entry| entry.get_name()).collect()
+    }
+    pub fn read(&self, name: &str) -> Result<Vec<u8>, ZipError> {
+        let entry = self
+            .entries
+            .iter()
+            .find(|entry| entry.name == name)
+            .ok_or_else(|| ZipError::NotFoundEntry(name.to_string()))?;
+        self.read_entry(entry)
+    }
+    pub fn read_entry(&self, entry: &ZipEntry) -> Result<Vec<u8>, ZipError> {
+        if entry.is_encrypted() {
+            return Err(ZipError::Encrypted(entry.name.clone()));
+        }
+        let offset = entry.local_header_offset;
+        expect_signature(self.source, offset, LOCAL_FILE_HEADER_SIGNATURE)?;
+        // sizes in the local header may be zero when a data descriptor is used,
+        // so only the name and extra lengths are taken from it
+        let name_len = read_u16(self.source, offset + 26)? as usize;
+        let extra_len = read_u16(self.source, offset + 28)? as usize;
+        let data_start = offset + LOCAL_FILE_HEADER_LEN + name_len + extra_len;
+        let compressed = self
+            .source
+            .get(data_start..data_start + entry.compressed_size)
+            .ok_or(ZipError::UnexpectedEof)?;
+        let data = match entry.method {
+            STORED => compressed.to_vec(),
+            DEFLATED => inflate(compressed)?,
+            method => return Err(ZipError::UnsupportedMethod(method)),
+        };
+        let actual = crc32(&data);
+        if actual != entry.crc32 {
+            return Err(ZipError::CrcMismatch {
+                name: entry.name.clone(),
+                expect: entry.crc32,
+                actual,
+            });
+        }
+        Ok(data)
+    }
+}
+
+fn find_end_of_central_directory(source: &[u8]) -> Result<usize, ZipError> {
+    if source.len() < END_OF_CENTRAL_DIRECTORY_LEN {
+        return Err(ZipError::NotFoundEndOfCentralDirectory);
+    }
+    // the record is followed by a comment of at most u16::MAX bytes
+    let last = source.len() - END_OF_CENTRAL_DIRECTORY_LEN;
+    let first = last.saturating_sub(u16::MAX as usize);
+    (first..=last)
+        .rev()
+        .find(|offset| read_u32(source, *offset) == Ok(END_OF_CENTRAL_DIRECTORY_SIGNATURE))
+        .ok_or(ZipError::NotFoundEndOfCentralDirectory)
+}
+fn expect_signature(source: &[u8], offset: usize, expect: u32) -> Result<(), ZipError> {
+    if read_u32(source, offset)? != expect {
+        return Err(ZipError::InvalidSignature { offset, expect });
+    }
+    Ok(())
+}
+fn read_u16(source: &[u8], offset: usize) -> Result<u16, ZipError> {
+    source
+        .get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or(ZipError::UnexpectedEof)
+}
+fn read_u32(source: &[u8], offset: usize) -> Result<u32, ZipError> {
+    source
+        .get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+        .ok_or(ZipError::UnexpectedEof)
+}
+
+#[cfg(test)]
+mod zip_archive_test {
+    use crate::zip::error::ZipError;
+
+    use super::ZipArchive;
+
+    // python: zipfile with "[Content_Types].xml" stored and "xl/workbook.xml" deflated
+    const ARCHIVE: [u8; 279] = [
+        0x50, 0x4b, 0x03, 0x04, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x00, 0xc7,
+        0x1c, 0x17, 0x3c, 0x08, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00,
+        0x5b, 0x43, 0x6f, 0x6e, 0x74, 0x65, 0x6e, 0x74, 0x5f, 0x54, 0x79, 0x70, 0x65, 0x73, 0x5d,
+        0x2e, 0x78, 0x6d, 0x6c, 0x3c, 0x54, 0x79, 0x70, 0x65, 0x73, 0x2f, 0x3e, 0x50, 0x4b, 0x03,
+        0x04, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x21, 0x00, 0xce, 0x5e, 0xad, 0x80,
+        0x1d, 0x00, 0x00, 0x00, 0x3e, 0x00, 0x00, 0x00, 0x0f, 0x00, 0x00, 0x00, 0x78, 0x6c, 0x2f,
+        0x77, 0x6f, 0x72, 0x6b, 0x62, 0x6f, 0x6f, 0x6b, 0x2e, 0x78, 0x6d, 0x6c, 0xb3, 0x29, 0xcf,
+        0x2f, 0xca, 0x4e, 0xca, 0xcf, 0xcf, 0xb6, 0xb3, 0x29, 0xce, 0x48, 0x4d, 0x2d, 0x29, 0x86,
+        0xd2, 0xfa, 0x98, 0xb4, 0x3e, 0x4c, 0x81, 0x3e, 0x5c, 0x0f, 0x00, 0x50, 0x4b, 0x01, 0x02,
+        0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x21, 0x00, 0xc7, 0x1c, 0x17,
+        0x3c, 0x08, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x00, 0x00,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, 0x00, 0x00, 0x00, 0x00, 0x5b, 0x43, 0x6f,
+        0x6e, 0x74, 0x65, 0x6e, 0x74, 0x5f, 0x54, 0x79, 0x70, 0x65, 0x73, 0x5d, 0x2e, 0x78, 0x6d,
+        0x6c, 0x50, 0x4b, 0x01, 0x02, 0x14, 0x03, 0x14, 0x00, 0x00, 0x00, 0x08, 0x00, 0x00, 0x00,
+        0x21, 0x00, 0xce, 0x5e, 0xad, 0x80, 0x1d, 0x00, 0x00, 0x00, 0x3e, 0x00, 0x00, 0x00, 0x0f,
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80, 0x01, 0x39, 0x00,
+        0x00, 0x00, 0x78, 0x6c, 0x2f, 0x77, 0x6f, 0x72, 0x6b, 0x62, 0x6f, 0x6f, 0x6b, 0x2e, 0x78,
+        0x6d, 0x6c, 0x50, 0x4b, 0x05, 0x06, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x02, 0x00, 0x7e,
+        0x00, 0x00, 0x00, 0x83, 0x00, 0x00, 0x00, 0x00, 0x00,
+    ];
+    #[test]
+    fn get_names_test() {
+        let archive = ZipArchive::new(&ARCHIVE).unwrap();
+        assert_eq!(
+            archive.get_names(),
+            vec!["[Content_Types].xml", "xl/workbook.xml"]
+        );
+    }
+    #[test]
+    fn read_test() {
+        let archive = ZipArchive::new(&ARCHIVE).unwrap();
+        assert_eq!(archive.read("[Content_Types].xml").unwrap(), b"<Types/>");
+        assert_eq!(
+            archive.read("xl/workbook.xml").unwrap(),
+            b"<workbook><sheets><sheet/><sheet/><sheet/></sheets></workbook>"
+        );
+        assert_eq!(
+            archive.read("xl/styles.xml"),
+            Err(ZipError::NotFoundEntry("xl/styles.xml".to_string()))
+        );
+    }
+    #[test]
+    fn crc_mismatch_test() {
+        let mut broken = ARCHIVE;
+        // last byte of the stored "[Content_Types].xml" data
+        broken[56] = b'!';
+        let archive = ZipArchive::new(&broken).unwrap();
+        assert!(matches!(
+            archive.read("[Content_Types].xml"),
+            Err(ZipError::CrcMismatch { .. })
+        ));
+    }
+    #[test]
+    fn not_zip_test() {
+        assert_eq!(
+            ZipArchive::new(b"<workbook/>").unwrap_err(),
+            ZipError::NotFoundEndOfCentralDirectory
+        );
+    }
+}
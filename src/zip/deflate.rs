@@ -0,0 +1,189 @@
+use super::inflate::{DIST_BASE, DIST_EXTRA, LENGTH_BASE, LENGTH_EXTRA};
+
+const WINDOW_SIZE: usize = 32768;
+const MIN_MATCH: usize = 3;
+const MAX_MATCH: usize = 258;
+const MAX_CHAIN: usize = 64;
+const HASH_BITS: u32 = 15;
+const NO_POSITION: usize = usize::MAX;
+
+/// Compresses data into a single raw DEFLATE (RFC 1951) block
+/// using LZ77 matching and the fixed huffman codes.
+///
+///     let compressed = deflate(b"abcabcabc");
+///     assert_eq!(inflate(&compressed).unwrap(), b"abcabcabc");
+///
+pub fn deflate(source: &[u8]) -> Vec<u8> {
+    let mut writer = BitWriter::new(source.len() / 2);
+    // final block with fixed huffman codes
+    writer.write_bits(1, 1);
+    writer.write_bits(1, 2);
+    let mut matcher = Matcher::new(source.len());
+    let mut pos = 0;
+    while pos < source.len() {
+        match matcher.longest_match(source, pos) {
+            Some((len, distance)) => {
+                write_match(&mut writer, len, distance);
+                (pos..pos + len).for_each(|i| matcher.insert(source, i));
+                pos += len;
+            }
+            None => {
+                write_literal(&mut writer, source[pos] as u16);
+                matcher.insert(source, pos);
+                pos += 1;
+            }
+        }
+    }
+    write_literal(&mut writer, 256);
+    writer.finish()
+}
+
+struct BitWriter {
+    out: Vec<u8>,
+    bit_buf: u32,
+    bit_count: u32,
+}
+impl BitWriter {
+    fn new(capacity: usize) -> Self {
+        BitWriter {
+            out: Vec::with_capacity(capacity),
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+    fn write_bits(&mut self, value: u32, len: u32) {
+        self.bit_buf |= value << self.bit_count;
+        self.bit_count += len;
+        while self.bit_count >= 8 {
+            self.out.push(self.bit_buf as u8);
+            self.bit_buf >>= 8;
+            self.bit_count -= 8;
+        }
+    }
+    /// huffman codes are packed starting from the most significant bit
+    fn write_code(&mut self, code: u32, len: u32) {
+        let reversed = (0..len).fold(0, |acc, i| (acc << 1) | ((code >> i) & 1));
+        self.write_bits(reversed, len)
+    }
+    fn finish(mut self) -> Vec<u8> {
+        if self.bit_count > 0 {
+            self.out.push(self.bit_buf as u8);
+        }
+        self.out
+    }
+}
+
+/// hash chains of every position inserted so far, keyed by the next three bytes
+struct Matcher {
+    head: Vec<usize>,
+    prev: Vec<usize>,
+}
+impl Matcher {
+    fn new(len: usize) -> Self {
+        Matcher {
+            head: vec![NO_POSITION; 1 << HASH_BITS],
+            prev: vec![NO_POSITION; len],
+        }
+    }
+    fn hash(source: &[u8], pos: usize) -> Option<usize> {
+        let bytes = source.get(pos..pos + MIN_MATCH)?;
+        let key = (bytes[0] as u32) << 16 | (bytes[1] as u32) << 8 | bytes[2] as u32;
+        Some((key.wrapping_mul(2_654_435_761) >> (32 - HASH_BITS)) as usize)
+    }
+    fn insert(&mut self, source: &[u8], pos: usize) {
+        if let Some(hash) = Matcher::hash(source, pos) {
+            self.prev[pos] = self.head[hash];
+            self.head[hash] = pos;
+        }
+    }
+    fn longest_match(&self, source: &[u8], pos: usize) -> Option<(usize, usize)> {
+        let hash = Matcher::hash(source, pos)?;
+        let max_len = MAX_MATCH.min(source.len() - pos);
+        let mut best: Option<(usize, usize)> = None;
+        let mut candidate = self.head[hash];
+        for _ in 0..MAX_CHAIN {
+            if candidate == NO_POSITION || pos - candidate > WINDOW_SIZE {
+                break;
+            }
+            let len = source[candidate..]
+                .iter()
+                .zip(&source[pos..pos + max_len])
+                .take_while(|(a, b)| a == b)
+                .count();
+            if len >= MIN_MATCH && best.is_none_or(|(best_len, _)| len > best_len) {
+                best = Some((len, pos - candidate));
+                if len == max_len {
+                    break;
+                }
+            }
+            candidate = self.prev[candidate];
+        }
+        best
+    }
+}
+
+fn write_literal(writer: &mut BitWriter, symbol: u16) {
+    let symbol = symbol as u32;
+    match symbol {
+        0..=143 => writer.write_code(0x30 + symbol, 8),
+        144..=255 => writer.write_code(0x190 + symbol - 144, 9),
+        256..=279 => writer.write_code(symbol - 256, 7),
+        _ => writer.write_code(0xC0 + symbol - 280, 8),
+    }
+}
+fn write_match(writer: &mut BitWriter, len: usize, distance: usize) {
+    let len_index = LENGTH_BASE
+        .iter()
+        .rposition(|base| *base as usize <= len)
+        .unwrap();
+    write_literal(writer, 257 + len_index as u16);
+    writer.write_bits(
+        (len - LENGTH_BASE[len_index] as usize) as u32,
+        LENGTH_EXTRA[len_index] as u32,
+    );
+    let dist_index = DIST_BASE
+        .iter()
+        .rposition(|base| *base as usize <= distance)
+        .unwrap();
+    writer.write_code(dist_index as u32, 5);
+    writer.write_bits(
+        (distance - DIST_BASE[dist_index] as usize) as u32,
+        DIST_EXTRA[dist_index] as u32,
+    );
+}
+
+#[cfg(test)]
+mod deflate_test {
+    use crate::zip::inflate::inflate;
+
+    use super::deflate;
+
+    #[test]
+    fn deflate_empty_test() {
+        assert_eq!(inflate(&deflate(b"")).unwrap(), b"");
+    }
+    #[test]
+    fn deflate_round_trip_test() {
+        let source = (1..=500)
+            .map(|i| format!(r#"<c r="A{}" t="s"><v>{}</v></c>"#, i, i % 7))
+            .collect::<String>();
+        let compressed = deflate(source.as_bytes());
+        assert!(compressed.len() < source.len() / 4);
+        assert_eq!(inflate(&compressed).unwrap(), source.as_bytes());
+    }
+    #[test]
+    fn deflate_long_run_test() {
+        let source = "あ".repeat(100_000);
+        assert_eq!(
+            inflate(&deflate(source.as_bytes())).unwrap(),
+            source.as_bytes()
+        );
+    }
+    #[test]
+    fn deflate_binary_test() {
+        let source = (0..70_000_u32)
+            .map(|i| (i.wrapping_mul(7919) >> 3) as u8)
+            .collect::<Vec<_>>();
+        assert_eq!(inflate(&deflate(&source)).unwrap(), source);
+    }
+}
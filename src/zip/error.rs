@@ -0,0 +1,51 @@
+use std::fmt::Display;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum ZipError {
+    NotFoundEndOfCentralDirectory,
+    InvalidSignature {
+        offset: usize,
+        expect: u32,
+    },
+    UnexpectedEof,
+    UnsupportedMethod(u16),
+    UnsupportedZip64,
+    Encrypted(String),
+    InvalidDeflate(&'static str),
+    CrcMismatch {
+        name: String,
+        expect: u32,
+        actual: u32,
+    },
+    NotFoundEntry(String),
+}
+impl Display for ZipError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZipError::NotFoundEndOfCentralDirectory => {
+                write!(f, "not found end of central directory record")
+            }
+            ZipError::InvalidSignature { offset, expect } => {
+                write!(f, "expect signature {:#010x} at offset {}", expect, offset)
+            }
+            ZipError::UnexpectedEof => write!(f, "unexpected end of archive"),
+            ZipError::UnsupportedMethod(method) => {
+                write!(f, "compression method {} is not supported", method)
+            }
+            ZipError::UnsupportedZip64 => write!(f, "zip64 archive is not supported"),
+            ZipError::Encrypted(name) => write!(f, "{} is encrypted", name),
+            ZipError::InvalidDeflate(reason) => write!(f, "invalid deflate stream: {}", reason),
+            ZipError::CrcMismatch {
+                name,
+                expect,
+                actual,
+            } => write!(
+                f,
+                "crc mismatch for {}: expect {:#010x} actual {:#010x}",
+                name, expect, actual
+            ),
+            ZipError::NotFoundEntry(name) => write!(f, "{} is not found in archive", name),
+        }
+    }
+}
+impl std::error::Error for ZipError {}
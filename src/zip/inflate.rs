@@ -0,0 +1,297 @@
+use super::error::ZipError;
+
+const MAX_BITS: usize = 15;
+const MAX_LIT_CODES: usize = 286;
+const MAX_DIST_CODES: usize = 30;
+const FIXED_LIT_CODES: usize = 288;
+pub(super) const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+pub(super) const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+pub(super) const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+pub(super) const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+/// order of code length code lengths in dynamic block header
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+/// Decompresses raw DEFLATE (RFC 1951) data.
+///
+///     let data = inflate(&[0x4b, 0x4c, 0x4a, 0x06, 0x00]).unwrap();
+///     assert_eq!(data, b"abc");
+///
+pub fn inflate(source: &[u8]) -> Result<Vec<u8>, ZipError> {
+    let mut reader = BitReader::new(source);
+    let mut out = Vec::with_capacity(source.len() * 4);
+    loop {
+        let is_last = reader.bits(1)? == 1;
+        match reader.bits(2)? {
+            0 => stored_block(&mut reader, &mut out)?,
+            1 => {
+                let (lit, dist) = fixed_tables();
+                compressed_block(&mut reader, &mut out, &lit, &dist)?
+            }
+            2 => {
+                let (lit, dist) = dynamic_tables(&mut reader)?;
+                compressed_block(&mut reader, &mut out, &lit, &dist)?
+            }
+            _ => return Err(ZipError::InvalidDeflate("invalid block type")),
+        }
+        if is_last {
+            return Ok(out);
+        }
+    }
+}
+
+struct BitReader<'a> {
+    source: &'a [u8],
+    pos: usize,
+    bit_buf: u32,
+    bit_count: u32,
+}
+impl<'a> BitReader<'a> {
+    fn new(source: &'a [u8]) -> Self {
+        BitReader {
+            source,
+            pos: 0,
+            bit_buf: 0,
+            bit_count: 0,
+        }
+    }
+    fn bits(&mut self, need: u32) -> Result<u32, ZipError> {
+        while self.bit_count < need {
+            let byte = *self.source.get(self.pos).ok_or(ZipError::UnexpectedEof)?;
+            self.pos += 1;
+            self.bit_buf |= (byte as u32) << self.bit_count;
+            self.bit_count += 8;
+        }
+        let value = self.bit_buf & ((1_u64 << need) - 1) as u32;
+        self.bit_buf >>= need;
+        self.bit_count -= need;
+        Ok(value)
+    }
+    fn align_to_byte(&mut self) {
+        self.bit_buf = 0;
+        self.bit_count = 0;
+    }
+    fn take_bytes(&mut self, len: usize) -> Result<&'a [u8], ZipError> {
+        let bytes = self
+            .source
+            .get(self.pos..self.pos + len)
+            .ok_or(ZipError::UnexpectedEof)?;
+        self.pos += len;
+        Ok(bytes)
+    }
+}
+
+/// canonical huffman table, counts of each code length and symbols sorted by code
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+impl Huffman {
+    fn new(lengths: &[u8]) -> Result<Self, ZipError> {
+        let mut counts = [0_u16; MAX_BITS + 1];
+        lengths.iter().for_each(|len| counts[*len as usize] += 1);
+        let mut left: i32 = 1;
+        for count in counts.iter().skip(1) {
+            left <<= 1;
+            left -= *count as i32;
+            if left < 0 {
+                return Err(ZipError::InvalidDeflate("over-subscribed code lengths"));
+            }
+        }
+        let mut offsets = [0_u16; MAX_BITS + 1];
+        for len in 1..MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0_u16; lengths.len()];
+        lengths
+            .iter()
+            .enumerate()
+            .filter(|(_, len)| **len != 0)
+            .for_each(|(symbol, len)| {
+                symbols[offsets[*len as usize] as usize] = symbol as u16;
+                offsets[*len as usize] += 1;
+            });
+        Ok(Huffman { counts, symbols })
+    }
+    fn decode(&self, reader: &mut BitReader) -> Result<u16, ZipError> {
+        let mut code: i32 = 0;
+        let mut first: i32 = 0;
+        let mut index: i32 = 0;
+        for len in 1..=MAX_BITS {
+            code |= reader.bits(1)? as i32;
+            let count = self.counts[len] as i32;
+            if code - count < first {
+                return Ok(self.symbols[(index + (code - first)) as usize]);
+            }
+            index += count;
+            first += count;
+            first <<= 1;
+            code <<= 1;
+        }
+        Err(ZipError::InvalidDeflate("ran out of codes"))
+    }
+}
+
+fn stored_block(reader: &mut BitReader, out: &mut Vec<u8>) -> Result<(), ZipError> {
+    reader.align_to_byte();
+    let header = reader.take_bytes(4)?;
+    let len = u16::from_le_bytes([header[0], header[1]]);
+    let nlen = u16::from_le_bytes([header[2], header[3]]);
+    if len != !nlen {
+        return Err(ZipError::InvalidDeflate("stored block length mismatch"));
+    }
+    out.extend_from_slice(reader.take_bytes(len as usize)?);
+    Ok(())
+}
+
+fn fixed_tables() -> (Huffman, Huffman) {
+    let mut lengths = [0_u8; FIXED_LIT_CODES];
+    lengths.iter_mut().enumerate().for_each(|(symbol, len)| {
+        *len = match symbol {
+            0..=143 => 8,
+            144..=255 => 9,
+            256..=279 => 7,
+            _ => 8,
+        }
+    });
+    // fixed tables are complete, so they never fail to build
+    let lit = Huffman::new(&lengths).unwrap();
+    let dist = Huffman::new(&[5_u8; MAX_DIST_CODES]).unwrap();
+    (lit, dist)
+}
+
+fn dynamic_tables(reader: &mut BitReader) -> Result<(Huffman, Huffman), ZipError> {
+    let lit_len = reader.bits(5)? as usize + 257;
+    let dist_len = reader.bits(5)? as usize + 1;
+    let code_len = reader.bits(4)? as usize + 4;
+    if lit_len > MAX_LIT_CODES || dist_len > MAX_DIST_CODES {
+        return Err(ZipError::InvalidDeflate(
+            "too many length or distance codes",
+        ));
+    }
+    let mut code_lengths = [0_u8; 19];
+    for index in CODE_LENGTH_ORDER.iter().take(code_len) {
+        code_lengths[*index] = reader.bits(3)? as u8;
+    }
+    let code_huffman = Huffman::new(&code_lengths)?;
+    let mut lengths = Vec::with_capacity(lit_len + dist_len);
+    while lengths.len() < lit_len + dist_len {
+        let symbol = code_huffman.decode(reader)?;
+        let (value, repeat) = match symbol {
+            0..=15 => (symbol as u8, 1),
+            16 => {
+                let prev = *lengths
+                    .last()
+                    .ok_or(ZipError::InvalidDeflate("repeat with no first length"))?;
+                (prev, 3 + reader.bits(2)? as usize)
+            }
+            17 => (0, 3 + reader.bits(3)? as usize),
+            _ => (0, 11 + reader.bits(7)? as usize),
+        };
+        if lengths.len() + repeat > lit_len + dist_len {
+            return Err(ZipError::InvalidDeflate("too many code lengths"));
+        }
+        lengths.extend(std::iter::repeat_n(value, repeat));
+    }
+    if lengths[256] == 0 {
+        return Err(ZipError::InvalidDeflate("missing end-of-block code"));
+    }
+    let lit = Huffman::new(&lengths[..lit_len])?;
+    let dist = Huffman::new(&lengths[lit_len..])?;
+    Ok((lit, dist))
+}
+
+fn compressed_block(
+    reader: &mut BitReader,
+    out: &mut Vec<u8>,
+    lit: &Huffman,
+    dist: &Huffman,
+) -> Result<(), ZipError> {
+    loop {
+        let symbol = lit.decode(reader)? as usize;
+        match symbol {
+            0..=255 => out.push(symbol as u8),
+            256 => return Ok(()),
+            _ => {
+                let symbol = symbol - 257;
+                if symbol >= LENGTH_BASE.len() {
+                    return Err(ZipError::InvalidDeflate("invalid length symbol"));
+                }
+                let len = LENGTH_BASE[symbol] as usize
+                    + reader.bits(LENGTH_EXTRA[symbol] as u32)? as usize;
+                let symbol = dist.decode(reader)? as usize;
+                if symbol >= DIST_BASE.len() {
+                    return Err(ZipError::InvalidDeflate("invalid distance symbol"));
+                }
+                let distance =
+                    DIST_BASE[symbol] as usize + reader.bits(DIST_EXTRA[symbol] as u32)? as usize;
+                if distance > out.len() {
+                    return Err(ZipError::InvalidDeflate("distance too far back"));
+                }
+                let start = out.len() - distance;
+                // copy byte by byte because the match may overlap the output
+                (0..len).for_each(|i| out.push(out[start + i]));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod inflate_test {
+    use super::inflate;
+
+    #[test]
+    fn inflate_fixed_huffman_test() {
+        // python: zlib.compressobj(9, zlib.DEFLATED, -15).compress(b"abc")
+        assert_eq!(inflate(&[0x4b, 0x4c, 0x4a, 0x06, 0x00]).unwrap(), b"abc");
+    }
+    #[test]
+    fn inflate_stored_test() {
+        let source = [0x01, 0x03, 0x00, 0xfc, 0xff, b'x', b'm', b'l'];
+        assert_eq!(inflate(&source).unwrap(), b"xml");
+    }
+    #[test]
+    fn inflate_overlapping_match_test() {
+        // python: raw deflate of "<row><c>あいう</c></row>" * 20
+        let source = [
+            0xb3, 0x29, 0xca, 0x2f, 0xb7, 0xb3, 0x49, 0xb6, 0x7b, 0xdc, 0xd8, 0xf4, 0xb8, 0xb1,
+            0xe5, 0x71, 0x63, 0x9b, 0x8d, 0x7e, 0xb2, 0x9d, 0x8d, 0x3e, 0x58, 0x74, 0x54, 0x6a,
+            0x64, 0x4b, 0x01, 0x00,
+        ];
+        let tobe = "<row><c>あいう</c></row>".repeat(20);
+        assert_eq!(inflate(&source).unwrap(), tobe.as_bytes());
+    }
+    #[test]
+    fn inflate_dynamic_huffman_test() {
+        // python: raw deflate of "<c r=\"A{i}\"><v>{i*i}</v></c>" for i in 1..=10
+        let source = [
+            0x5d, 0xce, 0xcb, 0x0d, 0xc0, 0x30, 0x08, 0x03, 0xd0, 0x55, 0xaa, 0x2c, 0x10, 0xc8,
+            0x87, 0x26, 0x12, 0x45, 0xca, 0x2c, 0xd9, 0xa0, 0x87, 0xcc, 0x5f, 0x09, 0xd4, 0x8b,
+            0xaf, 0x0f, 0x64, 0x5b, 0xf7, 0xf5, 0x3e, 0x69, 0x71, 0x32, 0x3d, 0xc6, 0x9a, 0x8f,
+            0x69, 0xde, 0xa6, 0xa1, 0xc5, 0xb5, 0x81, 0x56, 0xd7, 0x09, 0xda, 0x22, 0x41, 0x80,
+            0xbb, 0x73, 0xe9, 0xc0, 0xe2, 0x5c, 0xf1, 0xfb, 0x8e, 0x42, 0xcc, 0x1e, 0xce, 0x82,
+            0x43, 0xa6, 0xf3, 0xc0, 0xd5, 0x4c, 0x31, 0x85, 0xe8, 0x3f, 0x7c,
+        ];
+        let tobe = (1..=10)
+            .map(|i| format!(r#"<c r="A{}"><v>{}</v></c>"#, i, i * i))
+            .collect::<String>();
+        assert_eq!(inflate(&source).unwrap(), tobe.as_bytes());
+    }
+    #[test]
+    fn inflate_invalid_test() {
+        assert!(inflate(&[0x07]).is_err());
+        assert!(inflate(&[0x4b, 0x4c]).is_err());
+    }
+}
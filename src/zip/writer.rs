@@ -0,0 +1,198 @@
+use std::io::{self, Write};
+
+use super::{
+    archive::{
+        CENTRAL_DIRECTORY_SIGNATURE, DEFLATED, END_OF_CENTRAL_DIRECTORY_SIGNATURE,
+        LOCAL_FILE_HEADER_SIGNATURE, STORED,
+    },
+    crc32::crc32,
+    deflate::deflate,
+};
+
+const VERSION_NEEDED: u16 = 20;
+/// 1980-01-01 00:00:00, the smallest date a zip entry can carry
+const DOS_DATE: u16 = 0x0021;
+const DOS_TIME: u16 = 0;
+/// `0xFFFF` in the entry count tells readers to look for a zip64 record
+const MAX_ENTRIES: usize = 0xFFFE;
+
+struct CentralEntry {
+    name: String,
+    method: u16,
+    crc32: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+    local_header_offset: u32,
+}
+
+/// Streams a zip archive into any `Write`.
+///
+///     let mut writer = ZipWriter::new(Vec::new());
+///     writer.add_entry("xl/workbook.xml", b"<workbook/>").unwrap();
+///     let bytes = writer.finish().unwrap();
+///
+pub struct ZipWriter<W: Write> {
+    writer: W,
+    offset: usize,
+    entries: Vec<CentralEntry>,
+}
+impl<W: Write> ZipWriter<W> {
+    pub fn new(writer: W) -> Self {
+        ZipWriter {
+            writer,
+            offset: 0,
+            entries: Vec::new(),
+        }
+    }
+    /// Writes the entry deflated, or stored when deflate does not make it smaller.
+    /// Fails for names over 65535 bytes and past 65534 entries, which need zip64.
+    pub fn add_entry(&mut self, name: &str, data: &[u8]) -> io::Result<()> {
+        let name_len = to_u16(name.len())?;
+        if self.entries.len() >= MAX_ENTRIES {
+            return Err(zip64_error());
+        }
+        let compressed = deflate(data);
+        let (method, body) = if compressed.len() < data.len() {
+            (DEFLATED, compressed.as_slice())
+        } else {
+            (STORED, data)
+        };
+        let entry = CentralEntry {
+            name: name.to_string(),
+            method,
+            crc32: crc32(data),
+            compressed_size: to_u32(body.len())?,
+            uncompressed_size: to_u32(data.len())?,
+            local_header_offset: to_u32(self.offset)?,
+        };
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&LOCAL_FILE_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+        // general purpose flag, bit 11 marks the name as utf-8
+        header.extend_from_slice(&0x0800_u16.to_le_bytes());
+        header.extend_from_slice(&entry.method.to_le_bytes());
+        header.extend_from_slice(&DOS_TIME.to_le_bytes());
+        header.extend_from_slice(&DOS_DATE.to_le_bytes());
+        header.extend_from_slice(&entry.crc32.to_le_bytes());
+        header.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        header.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+        header.extend_from_slice(&name_len.to_le_bytes());
+        header.extend_from_slice(&0_u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.write(&header)?;
+        self.write(body)?;
+        self.entries.push(entry);
+        Ok(())
+    }
+    /// Writes the central directory and returns the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let cd_offset = to_u32(self.offset)?;
+        let mut directory = Vec::new();
+        for entry in self.entries.iter() {
+            directory.extend_from_slice(&CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+            // version made by, upper byte 3 is unix
+            directory.extend_from_slice(&(0x0300 | VERSION_NEEDED).to_le_bytes());
+            directory.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+            directory.extend_from_slice(&0x0800_u16.to_le_bytes());
+            directory.extend_from_slice(&entry.method.to_le_bytes());
+            directory.extend_from_slice(&DOS_TIME.to_le_bytes());
+            directory.extend_from_slice(&DOS_DATE.to_le_bytes());
+            directory.extend_from_slice(&entry.crc32.to_le_bytes());
+            directory.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            directory.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+            directory.extend_from_slice(&to_u16(entry.name.len())?.to_le_bytes());
+            // extra field, comment, disk number and internal attributes
+            directory.extend_from_slice(&[0; 8]);
+            // external attributes, -rw-r--r--
+            directory.extend_from_slice(&(0o100644_u32 << 16).to_le_bytes());
+            directory.extend_from_slice(&entry.local_header_offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let entry_len = to_u16(self.entries.len())?;
+        let mut end = Vec::with_capacity(22);
+        end.extend_from_slice(&END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+        end.extend_from_slice(&[0; 4]);
+        end.extend_from_slice(&entry_len.to_le_bytes());
+        end.extend_from_slice(&entry_len.to_le_bytes());
+        end.extend_from_slice(&to_u32(directory.len())?.to_le_bytes());
+        end.extend_from_slice(&cd_offset.to_le_bytes());
+        end.extend_from_slice(&0_u16.to_le_bytes());
+        self.write(&directory)?;
+        self.write(&end)?;
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+    fn write(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.writer.write_all(bytes)?;
+        self.offset += bytes.len();
+        Ok(())
+    }
+}
+fn to_u32(len: usize) -> io::Result<u32> {
+    u32::try_from(len).map_err(|_| zip64_error())
+}
+fn to_u16(len: usize) -> io::Result<u16> {
+    u16::try_from(len).map_err(|_| zip64_error())
+}
+fn zip64_error() -> io::Error {
+    io::Error::other("zip64 archive is not supported")
+}
+
+#[cfg(test)]
+mod zip_writer_test {
+    use crate::zip::archive::{ZipArchive, STORED};
+
+    use super::{CentralEntry, ZipWriter, MAX_ENTRIES};
+
+    #[test]
+    fn round_trip_test() {
+        let sheet = r#"<row r="1"><c r="A1"><v>1</v></c></row>"#.repeat(100);
+        let mut writer = ZipWriter::new(Vec::new());
+        writer
+            .add_entry("[Content_Types].xml", b"<Types/>")
+            .unwrap();
+        writer
+            .add_entry("xl/worksheets/sheet1.xml", sheet.as_bytes())
+            .unwrap();
+        writer
+            .add_entry("xl/テーブル.xml", "<t>会社名</t>".as_bytes())
+            .unwrap();
+        let bytes = writer.finish().unwrap();
+
+        let archive = ZipArchive::new(&bytes).unwrap();
+        assert_eq!(
+            archive.get_names(),
+            vec![
+                "[Content_Types].xml",
+                "xl/worksheets/sheet1.xml",
+                "xl/テーブル.xml"
+            ]
+        );
+        assert_eq!(archive.read("[Content_Types].xml").unwrap(), b"<Types/>");
+        assert_eq!(
+            archive.read("xl/worksheets/sheet1.xml").unwrap(),
+            sheet.as_bytes()
+        );
+        assert_eq!(
+            archive.read("xl/テーブル.xml").unwrap(),
+            "<t>会社名</t>".as_bytes()
+        );
+    }
+    #[test]
+    fn zip64_limit_test() {
+        let mut writer = ZipWriter::new(Vec::new());
+        assert!(writer.add_entry(&"a".repeat(65536), b"").is_err());
+        writer.add_entry(&"a".repeat(65535), b"").unwrap();
+        let entry = |i: usize| CentralEntry {
+            name: i.to_string(),
+            method: STORED,
+            crc32: 0,
+            compressed_size: 0,
+            uncompressed_size: 0,
+            local_header_offset: 0,
+        };
+        writer.entries.extend((1..MAX_ENTRIES).map(entry));
+        assert!(writer.add_entry("a", b"").is_err());
+        assert_eq!(writer.entries.len(), MAX_ENTRIES);
+    }
+}